@@ -1,4 +1,6 @@
 pub mod collections;
+pub mod db;
+pub mod export;
 pub mod generate;
 pub mod history;
 pub mod import;
@@ -1,6 +1,6 @@
-use crate::{GlobalArgs, Subcommand};
+use crate::{util::load_database, GlobalArgs, Subcommand};
 use clap::Parser;
-use slumber_core::db::Database;
+use slumber_config::Config;
 use std::{path::PathBuf, process::ExitCode};
 
 /// View and modify request collection metadata
@@ -29,7 +29,7 @@ enum CollectionsSubcommand {
 
 impl Subcommand for CollectionsCommand {
     async fn execute(self, _global: GlobalArgs) -> anyhow::Result<ExitCode> {
-        let database = Database::load()?;
+        let database = load_database(&Config::load()?)?;
         match self.subcommand {
             CollectionsSubcommand::List => {
                 for path in database.collections()? {
@@ -0,0 +1,107 @@
+use crate::{util::load_database, GlobalArgs, Subcommand};
+use anyhow::bail;
+use clap::{Parser, ValueHint};
+use slumber_config::Config;
+use slumber_core::db::{
+    read_key_file, Database, PassphrasePrompter, RekeyVacuumError,
+    TerminalPassphrasePrompter, KEY_ENV_VAR,
+};
+use std::{env, path::PathBuf, process::ExitCode};
+
+/// Environment variable to provide the new passphrase when rekeying
+const NEW_KEY_ENV_VAR: &str = "SLUMBER_DB_NEW_KEY";
+
+/// Manage the history database
+#[derive(Clone, Debug, Parser)]
+pub struct DbCommand {
+    #[command(subcommand)]
+    subcommand: DbSubcommand,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+enum DbSubcommand {
+    /// Encrypt the database with a new passphrase, or decrypt it
+    ///
+    /// The current passphrase is loaded the same as for any other command.
+    /// The new passphrase is read from `--new-key-file`, then the
+    /// `SLUMBER_DB_NEW_KEY` environment variable, then a prompt. This can
+    /// also be used to encrypt an unencrypted database.
+    Rekey {
+        /// Read the new passphrase from this file
+        #[clap(long, value_hint = ValueHint::FilePath)]
+        new_key_file: Option<PathBuf>,
+        /// Remove encryption, storing all data in plain text
+        #[clap(long, conflicts_with = "new_key_file")]
+        decrypt: bool,
+    },
+    /// Rebuild the database file, overwriting unused pages
+    ///
+    /// Rekeying does this automatically. If it fails there, run this to make
+    /// sure no data from before the rekey remains on disk.
+    Vacuum,
+}
+
+impl Subcommand for DbCommand {
+    async fn execute(self, _global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        let config = Config::load()?;
+        let database = load_database(&config)?;
+        match self.subcommand {
+            DbSubcommand::Rekey {
+                new_key_file,
+                decrypt,
+            } => {
+                let passphrase = if decrypt {
+                    None
+                } else if let Some(path) = &new_key_file {
+                    Some(read_key_file(path)?)
+                } else if let Ok(passphrase) = env::var(NEW_KEY_ENV_VAR) {
+                    Some(passphrase)
+                } else {
+                    Some(
+                        TerminalPassphrasePrompter
+                            .prompt_passphrase("New passphrase", true)?,
+                    )
+                };
+                if passphrase.as_deref() == Some("") {
+                    bail!("Passphrase cannot be empty");
+                }
+
+                // If only the vacuum failed, the rekey still went through
+                let vacuum_error = match database.rekey(passphrase.as_deref()) {
+                    Ok(()) => None,
+                    Err(error) if error.is::<RekeyVacuumError>() => Some(error),
+                    Err(error) => return Err(error),
+                };
+                let path = Database::path();
+                if decrypt {
+                    println!("Decrypted {}", path.display());
+                    if config.encryption.enabled {
+                        eprintln!(
+                            "`encryption.enabled` is set in the config, so \
+                            the database will be encrypted again the next \
+                            time it's opened"
+                        );
+                    }
+                } else {
+                    println!("Encrypted {} with new key", path.display());
+                    eprintln!(
+                        "Update `{KEY_ENV_VAR}` or `encryption.key_file` if \
+                        you use either to provide the key"
+                    );
+                }
+                if let Some(error) = vacuum_error {
+                    eprintln!(
+                        "{error:#}\nRun `slumber db vacuum` to retry; until \
+                        then, old data may be recoverable from the file"
+                    );
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+            DbSubcommand::Vacuum => {
+                database.vacuum()?;
+                println!("Vacuumed {}", Database::path().display());
+            }
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
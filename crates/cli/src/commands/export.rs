@@ -0,0 +1,140 @@
+use crate::{GlobalArgs, Subcommand};
+use anyhow::{bail, Context};
+use clap::{Parser, ValueEnum};
+use indexmap::IndexSet;
+use slumber_core::collection::{Collection, CollectionFile};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+/// Convert the current Slumber collection into an external format
+///
+/// Any Slumber features that can't be represented in the output format are
+/// listed on stderr.
+#[derive(Clone, Debug, Parser)]
+pub struct ExportCommand {
+    /// Output format
+    format: Format,
+    /// Destination for the exported file [default: stdout]
+    output_file: Option<PathBuf>,
+    /// Also write profiles as environments to this file, in the
+    /// `http-client.env.json` format. Only supported for the `http-file`
+    /// format
+    #[clap(long)]
+    env_file: Option<PathBuf>,
+    /// Also write each profile as a Postman environment file in this
+    /// directory. Only supported for the `postman` format
+    #[clap(long)]
+    env_dir: Option<PathBuf>,
+    /// Name of the exported collection or API. Only used for the `openapi`
+    /// and `postman` formats [default: name of the collection's directory]
+    #[clap(long)]
+    name: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum Format {
+    /// JetBrains HTTP Client / VS Code REST Client `.http` file
+    HttpFile,
+    /// OpenAPI v3.0 skeleton. Written as YAML, unless the output file has a
+    /// `.json` extension
+    Openapi,
+    /// Postman collection v2.1
+    Postman,
+}
+
+impl Subcommand for ExportCommand {
+    async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        if self.env_file.is_some() && !matches!(self.format, Format::HttpFile) {
+            bail!("`--env-file` is only supported for the `http-file` format");
+        }
+        if self.env_dir.is_some() && !matches!(self.format, Format::Postman) {
+            bail!("`--env-dir` is only supported for the `postman` format");
+        }
+
+        let collection_path = CollectionFile::try_path(None, global.file)?;
+        let collection = Collection::load(&collection_path)?;
+        let name = self
+            .name
+            .unwrap_or_else(|| collection_name(&collection_path));
+
+        // Each format can produce multiple exports, so merge their reports
+        let mut unsupported = IndexSet::new();
+        let output = match self.format {
+            Format::HttpFile => {
+                if let Some(path) = self.env_file {
+                    let env = collection.to_http_client_env();
+                    unsupported.extend(env.unsupported);
+                    write_json(&path, &env.output)?;
+                }
+                let export = collection.to_http_file();
+                unsupported.extend(export.unsupported);
+                export.output
+            }
+            Format::Openapi => {
+                let export = collection.to_openapi(&name);
+                unsupported.extend(export.unsupported);
+                let is_json = self.output_file.as_ref().is_some_and(|path| {
+                    path.extension().is_some_and(|ext| ext == "json")
+                });
+                if is_json {
+                    serde_json::to_string_pretty(&export.output)?
+                } else {
+                    serde_yaml::to_string(&export.output)?
+                }
+            }
+            Format::Postman => {
+                if let Some(directory) = self.env_dir {
+                    let environments = collection.to_postman_environments();
+                    unsupported.extend(environments.unsupported);
+                    fs::create_dir_all(&directory).context(format!(
+                        "Error creating environment directory {directory:?}"
+                    ))?;
+                    for (profile_id, environment) in environments.output {
+                        let path = directory.join(format!(
+                            "{profile_id}.postman_environment.json"
+                        ));
+                        write_json(&path, &environment)?;
+                    }
+                }
+                let export = collection.to_postman(&name);
+                unsupported.extend(export.unsupported);
+                serde_json::to_string_pretty(&export.output)?
+            }
+        };
+
+        match self.output_file {
+            Some(path) => fs::write(&path, output)
+                .context(format!("Error writing output file {path:?}"))?,
+            None => print!("{output}"),
+        }
+
+        if !unsupported.is_empty() {
+            eprintln!("Some features couldn't be exported:");
+            for message in unsupported {
+                eprintln!("- {message}");
+            }
+        }
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Get a default name for the collection, from the directory it's in
+fn collection_name(collection_path: &Path) -> String {
+    collection_path
+        .canonicalize()
+        .ok()
+        .as_deref()
+        .and_then(Path::parent)
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Slumber".into())
+}
+
+fn write_json(path: &Path, value: &serde_json::Value) -> anyhow::Result<()> {
+    let content = serde_json::to_string_pretty(value)?;
+    fs::write(path, content).context(format!("Error writing file {path:?}"))
+}
@@ -1,25 +1,56 @@
 use crate::{commands::request::BuildRequestCommand, GlobalArgs, Subcommand};
 use clap::{Parser, ValueEnum};
-use slumber_core::template::TemplateError;
+use slumber_core::{http::generate::GenerateFormat, template::TemplateError};
 use std::process::ExitCode;
 
 /// Render a request and generate an equivalent for a third-party client
 #[derive(Clone, Debug, Parser)]
 #[clap(visible_alias = "gen")]
 pub struct GenerateCommand {
-    format: GenerateFormat,
+    format: Format,
     #[clap(flatten)]
     build_request: BuildRequestCommand,
     /// Execute triggered sub-requests. By default, if a request dependency is
     /// triggered (e.g. if it is expired), an error will be thrown instead
     #[clap(long)]
     execute_triggers: bool,
+    /// Include sensitive values (e.g. the `Authorization` header) verbatim.
+    /// By default they're redacted according to the `redaction` config
+    #[clap(long)]
+    no_redact: bool,
 }
 
 /// Third-party client to generate for
-#[derive(Clone, Debug, ValueEnum)]
-pub enum GenerateFormat {
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum Format {
+    /// cURL command
     Curl,
+    /// HTTPie command
+    Httpie,
+    /// wget command
+    Wget,
+    /// Python script, using `requests`
+    Python,
+    /// JavaScript, using `fetch`
+    Javascript,
+    /// Go program, using `net/http`
+    Go,
+    /// Rust program, using `reqwest` and `tokio`
+    Rust,
+}
+
+impl From<Format> for GenerateFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Curl => Self::Curl,
+            Format::Httpie => Self::Httpie,
+            Format::Wget => Self::Wget,
+            Format::Python => Self::Python,
+            Format::Javascript => Self::Javascript,
+            Format::Go => Self::Go,
+            Format::Rust => Self::Rust,
+        }
+    }
 }
 
 impl Subcommand for GenerateCommand {
@@ -41,7 +72,14 @@ impl Subcommand for GenerateCommand {
                     error
                 }
             })?;
-        println!("{}", ticket.record().to_curl()?);
+        let record = ticket.record();
+        let format = self.format.into();
+        let output = if self.no_redact {
+            record.generate(format)?
+        } else {
+            record.redacted().generate(format)?
+        };
+        println!("{output}");
         Ok(ExitCode::SUCCESS)
     }
 }
@@ -1,24 +1,40 @@
 use crate::{
     completions::{complete_profile, complete_recipe},
-    util::HeaderDisplay,
+    util::{load_database, print_diff, HeaderDisplay},
     GlobalArgs, Subcommand,
 };
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
 use clap::{Parser, ValueHint};
 use clap_complete::ArgValueCompleter;
 use dialoguer::console::Style;
+use itertools::Itertools;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use slumber_config::Config;
 use slumber_core::{
-    collection::{CollectionFile, ProfileId, RecipeId},
-    db::Database,
-    http::{Exchange, ExchangeSummary, RequestId},
+    collection::{
+        Collection, CollectionFile, ProfileId, ProtobufSchema, RecipeId,
+        SelectorMode,
+    },
+    db::{CollectionDatabase, HistoryFilter, PruneReport, StatusFilter},
+    http::{
+        diff::ExchangeDiff, har::Har, query::Query, Exchange,
+        ExchangeAnnotation, ExchangeSummary, RequestId,
+    },
     util::{format_byte_size, format_duration, format_time, MaybeStr},
 };
-use std::process::ExitCode;
-use tracing::warn;
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
-/// View request collection history (unstable)
+/// View and query request history
 #[derive(Clone, Debug, Parser)]
-#[command(hide = true)] // Hidden because unstable
 pub struct HistoryCommand {
     #[command(subcommand)]
     subcommand: HistorySubcommand,
@@ -26,55 +42,289 @@ pub struct HistoryCommand {
 
 #[derive(Clone, Debug, clap::Subcommand)]
 enum HistorySubcommand {
-    /// List all requests for a recipe/profile combination
+    /// List requests in history, newest first
     #[command(visible_alias = "ls")]
     List {
-        /// Recipe to query for
-        #[clap(add = ArgValueCompleter::new(complete_recipe))]
-        recipe: RecipeId,
-
-        /// Profile to query for. If omitted, query for requests with no
-        /// profile
-        #[clap(
-            long = "profile",
-            short,
-            add = ArgValueCompleter::new(complete_profile),
-        )]
-        profile: Option<ProfileId>,
+        #[clap(flatten)]
+        filter: FilterArgs,
+
+        #[clap(flatten)]
+        output: ListOutputArgs,
+    },
+
+    /// Search history across all recipes and profiles
+    ///
+    /// The query is matched against the URL, headers and bodies of each
+    /// request and response. Every word in the query must appear somewhere in
+    /// the exchange. Words are matched whole, not as substrings.
+    Search {
+        /// Text to search for. If omitted, match all requests
+        query: Option<String>,
+
+        #[clap(flatten)]
+        filter: FilterArgs,
+
+        #[clap(flatten)]
+        output: ListOutputArgs,
+    },
+
+    /// Export requests and responses to a file
+    ///
+    /// Export specific requests by ID, or every request matching the given
+    /// filters. HAR files can be loaded into browser dev tools and many other
+    /// HTTP tools.
+    Export {
+        /// IDs of the requests to export. If omitted, export all requests
+        /// that match the filters
+        #[clap(value_hint = ValueHint::Other)]
+        requests: Vec<RequestId>,
+
+        #[clap(flatten)]
+        filter: FilterArgs,
+
+        /// Output format
+        #[clap(long, value_enum, default_value_t = ExportFormat::Har)]
+        format: ExportFormat,
+
+        /// File to write to. If omitted, write to stdout
+        #[clap(long, short, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+
+    /// Delete old requests according to the `retention` config
+    ///
+    /// This applies to the history of all collections, not just the current
+    /// one. After deleting, the database is vacuumed to return the freed space
+    /// to the filesystem.
+    Prune {
+        /// Show what would be deleted, without deleting anything
+        #[clap(long)]
+        dry_run: bool,
+
+        /// Skip vacuuming the database after deleting. Vacuuming can be slow
+        /// for large databases
+        #[clap(long)]
+        no_vacuum: bool,
+    },
+
+    /// Compare the responses of two requests
+    ///
+    /// Output is a unified diff of each response's status, headers and body.
+    /// Bodies of a known content type are compared structurally, so
+    /// formatting and key order are ignored. The requests can be from
+    /// different recipes and profiles.
+    Diff {
+        /// ID of the old request/response
+        #[clap(value_hint = ValueHint::Other)]
+        old: RequestId,
+
+        /// ID of the new request/response
+        #[clap(value_hint = ValueHint::Other)]
+        new: RequestId,
+    },
+
+    /// Pin a request, so it's never deleted by pruning
+    Pin {
+        /// ID of the request to pin
+        #[clap(value_hint = ValueHint::Other)]
+        request: RequestId,
     },
 
-    /// Print an entire request/response
+    /// Unpin a request
+    Unpin {
+        /// ID of the request to unpin
+        #[clap(value_hint = ValueHint::Other)]
+        request: RequestId,
+    },
+
+    /// Set or clear the label of a request
+    ///
+    /// Labels can be used to filter history, and chained requests can select
+    /// the most recent response with a particular label.
+    Label {
+        /// ID of the request to label
+        #[clap(value_hint = ValueHint::Other)]
+        request: RequestId,
+
+        /// Label to apply. If omitted, remove the existing label
+        label: Option<String>,
+    },
+
+    /// Set or clear the note attached to a request
+    Note {
+        /// ID of the request to annotate
+        #[clap(value_hint = ValueHint::Other)]
+        request: RequestId,
+
+        /// Free-form note text. If omitted, remove the existing note
+        note: Option<String>,
+    },
+
+    /// Print an entire request/response, or just part of the response
     Get {
         // Disable completion for this arg. We could load all the request IDs
-        // from the DB, but that's not worth the effort since this is an
-        // unstable command still and people will rarely be typing an ID by
-        // hand, they'll typically just copy paste
+        // from the DB, but that's not worth the effort since people will
+        // rarely be typing an ID by hand, they'll typically just copy paste
         /// ID of the request/response to print
         #[clap(value_hint = ValueHint::Other)]
         request: RequestId,
+
+        /// Print only the response body
+        #[clap(long, conflicts_with_all = ["headers", "query", "format"])]
+        body: bool,
+
+        /// Print only the response headers
+        #[clap(long, conflicts_with_all = ["query", "format"])]
+        headers: bool,
+
+        /// Print only the part of the response body selected by a JSONPath
+        /// (e.g. `$.id`) or XPath query
+        #[clap(long, short, conflicts_with = "format")]
+        query: Option<Query>,
+
+        /// Output format
+        #[clap(long, value_enum, default_value_t)]
+        format: OutputFormat,
     },
 }
 
 impl Subcommand for HistoryCommand {
     async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
-        warn!(
-            "`history` command is unstable; \
-            it may change or be removed at any time"
-        );
-        let collection_path = CollectionFile::try_path(None, global.file)?;
-        let database = Database::load()?.into_collection(&collection_path)?;
+        let config = Config::load()?;
+        let database = load_database(&config)?;
+        let collection_path =
+            || CollectionFile::try_path(None, global.file.clone());
+        // Most subcommands are scoped to a single collection
+        let collection_database =
+            || database.clone().into_collection(&collection_path()?);
 
         match self.subcommand {
-            HistorySubcommand::List { recipe, profile } => {
+            HistorySubcommand::List { filter, output } => {
+                let filter = HistoryFilter {
+                    limit: output.limit,
+                    ..filter.into()
+                };
                 let exchanges =
-                    database.get_all_requests(profile.as_ref(), &recipe)?;
-                Self::print_list(exchanges);
+                    collection_database()?.search_requests(&filter)?;
+                Self::print_summaries(&exchanges, output.format);
+            }
+            HistorySubcommand::Search {
+                query,
+                filter,
+                output,
+            } => {
+                let filter = HistoryFilter {
+                    query,
+                    limit: output.limit,
+                    ..filter.into()
+                };
+                let exchanges =
+                    collection_database()?.search_requests(&filter)?;
+                Self::print_summaries(&exchanges, output.format);
+            }
+            HistorySubcommand::Export {
+                requests,
+                filter,
+                format,
+                output,
+            } => {
+                let database = collection_database()?;
+                // Specific IDs take precedence over filters
+                let requests = if requests.is_empty() {
+                    database
+                        .search_requests(&filter.into())?
+                        .into_iter()
+                        .map(|exchange| exchange.id)
+                        // Export oldest first, like a browser would
+                        .rev()
+                        .collect()
+                } else {
+                    requests
+                };
+                let exchanges = requests
+                    .into_iter()
+                    .map(|request| {
+                        database.get_request(request)?.ok_or_else(|| {
+                            anyhow!("Request `{request}` not found")
+                        })
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                Self::export(&exchanges, format, output.as_deref())?;
+            }
+            HistorySubcommand::Prune { dry_run, no_vacuum } => {
+                let report =
+                    database.prune(&config.retention, dry_run, !no_vacuum)?;
+                Self::print_prune_report(&report, dry_run);
+            }
+            HistorySubcommand::Diff { old, new } => {
+                let database = collection_database()?;
+                let [old, new] = [old, new].map(|request| {
+                    database
+                        .get_request(request)?
+                        .ok_or_else(|| anyhow!("Request `{request}` not found"))
+                });
+                Self::print_diff(&old?, &new?);
+            }
+            HistorySubcommand::Pin { request } => {
+                Self::annotate(
+                    &collection_database()?,
+                    request,
+                    |annotation| annotation.pinned = true,
+                )?;
+            }
+            HistorySubcommand::Unpin { request } => {
+                Self::annotate(
+                    &collection_database()?,
+                    request,
+                    |annotation| annotation.pinned = false,
+                )?;
+            }
+            HistorySubcommand::Label { request, label } => {
+                Self::annotate(
+                    &collection_database()?,
+                    request,
+                    |annotation| annotation.label = label,
+                )?;
             }
-            HistorySubcommand::Get { request } => {
+            HistorySubcommand::Note { request, note } => {
+                Self::annotate(
+                    &collection_database()?,
+                    request,
+                    |annotation| annotation.note = note,
+                )?;
+            }
+            HistorySubcommand::Get {
+                request,
+                body,
+                headers,
+                query,
+                format,
+            } => {
+                let database = collection_database()?;
                 let exchange = database
                     .get_request(request)?
                     .ok_or_else(|| anyhow!("Request `{request}` not found"))?;
-                Self::print_detail(exchange);
+                if body {
+                    Self::print_body(&exchange)?;
+                } else if headers {
+                    print!("{}", HeaderDisplay(&exchange.response.headers));
+                } else if let Some(query) = query {
+                    // Load the collection to get the recipe's protobuf
+                    // schema, if any. The recipe may not exist anymore
+                    let collection = Collection::load(&collection_path()?)?;
+                    let protobuf = collection
+                        .recipes
+                        .get_recipe(&exchange.request.recipe_id)
+                        .and_then(|recipe| recipe.protobuf.as_ref());
+                    println!(
+                        "{}",
+                        Self::query_body(&exchange, &query, protobuf)?
+                    );
+                } else {
+                    let annotation =
+                        database.get_annotation(request)?.unwrap_or_default();
+                    Self::print_detail(exchange, &annotation, format);
+                }
             }
         }
         Ok(ExitCode::SUCCESS)
@@ -82,18 +332,180 @@ impl Subcommand for HistoryCommand {
 }
 
 impl HistoryCommand {
-    fn print_list(exchanges: Vec<ExchangeSummary>) {
-        for exchange in exchanges {
+    /// Modify the annotation of a single request
+    fn annotate(
+        database: &CollectionDatabase,
+        request: RequestId,
+        f: impl FnOnce(&mut ExchangeAnnotation),
+    ) -> anyhow::Result<()> {
+        let mut annotation = database
+            .get_annotation(request)?
+            .ok_or_else(|| anyhow!("Request `{request}` not found"))?;
+        f(&mut annotation);
+        database.set_annotation(request, &annotation)
+    }
+
+    /// Print a list of exchanges in the given format
+    fn print_summaries(exchanges: &[ExchangeSummary], format: OutputFormat) {
+        let entries = exchanges.iter().map(HistoryEntry::from).collect_vec();
+        match format {
+            OutputFormat::Table => {
+                let rows = entries.iter().map(|entry| {
+                    [
+                        entry.id.to_string(),
+                        entry.recipe.to_string(),
+                        entry
+                            .profile
+                            .map(ProfileId::to_string)
+                            .unwrap_or_else(|| "-".into()),
+                        entry.status.to_string(),
+                        format_time(&entry.start_time).to_string(),
+                        format_duration(&(entry.end_time - entry.start_time)),
+                        if entry.pinned { "*" } else { "" }.to_owned(),
+                        entry.label.unwrap_or_default().to_owned(),
+                    ]
+                });
+                print_table(
+                    [
+                        "ID", "RECIPE", "PROFILE", "STATUS", "TIME",
+                        "DURATION", "PINNED", "LABEL",
+                    ],
+                    rows,
+                );
+            }
+            OutputFormat::Json | OutputFormat::Yaml => {
+                println!("{}", format.serialize(&entries))
+            }
+        }
+    }
+
+    /// Write exchanges to a file (or stdout) in the given format
+    fn export(
+        exchanges: &[Exchange],
+        format: ExportFormat,
+        output: Option<&Path>,
+    ) -> anyhow::Result<()> {
+        let content = match format {
+            ExportFormat::Har => Har::from_exchanges(exchanges).to_json(),
+        };
+        if let Some(path) = output {
+            fs::write(path, content)
+                .with_context(|| format!("Error writing to {path:?}"))?;
+            eprintln!(
+                "Exported {} request(s) to {}",
+                exchanges.len(),
+                path.display()
+            );
+        } else {
+            println!("{content}");
+        }
+        Ok(())
+    }
+
+    fn print_prune_report(report: &PruneReport, dry_run: bool) {
+        let verb = if dry_run { "Would delete" } else { "Deleted" };
+        println!(
+            "{verb} {} request(s) ({})",
+            report.deleted,
+            format_byte_size(report.deleted_size as usize)
+        );
+        if let Some(reclaimed_size) = report.reclaimed_size {
             println!(
-                "{} {} {}",
+                "Reclaimed {} of disk space",
+                format_byte_size(reclaimed_size as usize)
+            );
+        }
+    }
+
+    fn print_diff(old: &Exchange, new: &Exchange) {
+        let label = |exchange: &Exchange| {
+            format!(
+                "{} ({} {})",
                 exchange.id,
-                exchange.status,
+                exchange.request.recipe_id,
                 format_time(&exchange.start_time)
-            );
+            )
+        };
+        let diff = ExchangeDiff::new(old, new);
+        print_diff(&diff.to_unified(&label(old), &label(new)));
+    }
+
+    /// Write the response body to stdout. Binary bodies are written as raw
+    /// bytes, so they can be piped to a file
+    fn print_body(exchange: &Exchange) -> anyhow::Result<()> {
+        Self::write_body(exchange, &mut io::stdout())
+            .context("Error writing to stdout")
+    }
+
+    /// Write the response body to a writer. Text bodies are written as
+    /// decoded text, everything else as raw bytes
+    fn write_body(
+        exchange: &Exchange,
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        let body = &exchange.response.body;
+        if let Some(text) = body.text() {
+            writer.write_all(text.as_bytes())
+        } else {
+            writer.write_all(body.bytes())
         }
     }
 
-    fn print_detail(exchange: Exchange) {
+    /// Apply a query to the response body, and stringify the result
+    fn query_body(
+        exchange: &Exchange,
+        query: &Query,
+        protobuf: Option<&ProtobufSchema>,
+    ) -> anyhow::Result<String> {
+        let response = &exchange.response;
+        let content_type = response.content_type().ok_or_else(|| {
+            anyhow!("Response has no content type; body cannot be queried")
+        })?;
+        let body = content_type
+            .parse_content_with_schema(response.body.bytes(), protobuf)
+            .context("Error parsing response body")?;
+        query
+            .query_to_string(SelectorMode::Auto, &*body)
+            .with_context(|| format!("Error querying body with `{query}`"))
+    }
+
+    fn print_detail(
+        exchange: Exchange,
+        annotation: &ExchangeAnnotation,
+        format: OutputFormat,
+    ) {
+        if let OutputFormat::Json | OutputFormat::Yaml = format {
+            let summary = ExchangeSummary {
+                annotation: annotation.clone(),
+                ..ExchangeSummary::from(&exchange)
+            };
+            let output = ExchangeDetail {
+                summary: (&summary).into(),
+                request: MessageDetail {
+                    method: Some(exchange.request.method.to_string()),
+                    url: Some(exchange.request.url.as_str()),
+                    status: None,
+                    headers: headers_to_map(&exchange.request.headers),
+                    body: exchange
+                        .request
+                        .body
+                        .as_deref()
+                        .map(String::from_utf8_lossy),
+                },
+                response: MessageDetail {
+                    method: None,
+                    url: None,
+                    status: Some(exchange.response.status.as_u16()),
+                    headers: headers_to_map(&exchange.response.headers),
+                    body: Some(String::from_utf8_lossy(
+                        exchange.response.body.bytes(),
+                    )),
+                },
+            };
+            println!("{}", format.serialize(&output));
+            return;
+        }
+
         let header_style = Style::new().bold().underlined();
         let subheader_style = Style::new().bold();
 
@@ -129,6 +541,17 @@ impl HistoryCommand {
             subheader_style.apply_to("Duration:"),
             format_duration(&exchange.duration())
         );
+        println!(
+            "{} {}",
+            subheader_style.apply_to("Pinned:"),
+            annotation.pinned
+        );
+        if let Some(label) = &annotation.label {
+            println!("{} {label}", subheader_style.apply_to("Label:"));
+        }
+        if let Some(note) = &annotation.note {
+            println!("{} {note}", subheader_style.apply_to("Note:"));
+        }
         println!();
 
         // Response
@@ -152,3 +575,416 @@ impl HistoryCommand {
         );
     }
 }
+
+/// Filters for selecting requests from history, shared between subcommands
+#[derive(Clone, Debug, clap::Args)]
+struct FilterArgs {
+    /// Only include requests for recipes matching this glob pattern (e.g.
+    /// `user_*`). Supports `*`, `?` and `[...]` wildcards
+    #[clap(
+        long = "recipe",
+        short,
+        add = ArgValueCompleter::new(complete_recipe),
+    )]
+    recipe: Option<String>,
+
+    /// Only include requests for this profile. If neither this nor
+    /// `--no-profile` is given, requests for any profile are included
+    #[clap(
+        long = "profile",
+        short,
+        add = ArgValueCompleter::new(complete_profile),
+        conflicts_with = "no_profile",
+    )]
+    profile: Option<ProfileId>,
+
+    /// Only include requests made without a profile
+    #[clap(long)]
+    no_profile: bool,
+
+    /// Only include responses with this status code (e.g. `404`) or status
+    /// class (e.g. `5xx`)
+    #[clap(long, short)]
+    status: Option<StatusFilter>,
+
+    /// Only include requests started at or after this time. Accepts a date
+    /// (`2024-10-13`, local midnight) or an RFC 3339 timestamp
+    #[clap(long, value_parser = parse_time)]
+    since: Option<DateTime<Utc>>,
+
+    /// Only include requests started before this time. Accepts a date
+    /// (`2024-10-13`, local midnight) or an RFC 3339 timestamp
+    #[clap(long, value_parser = parse_time)]
+    until: Option<DateTime<Utc>>,
+
+    /// Only include pinned requests
+    #[clap(long)]
+    pinned: bool,
+
+    /// Only include requests with this label
+    #[clap(long)]
+    label: Option<String>,
+}
+
+impl From<FilterArgs> for HistoryFilter {
+    fn from(args: FilterArgs) -> Self {
+        Self {
+            recipe_glob: args.recipe,
+            profile_id: if args.no_profile {
+                Some(None)
+            } else {
+                args.profile.map(Some)
+            },
+            status: args.status,
+            since: args.since,
+            until: args.until,
+            pinned: args.pinned,
+            label: args.label,
+            ..Default::default()
+        }
+    }
+}
+
+/// Output options for subcommands that print a list of requests
+#[derive(Clone, Debug, clap::Args)]
+struct ListOutputArgs {
+    /// Maximum number of results to print
+    #[clap(long, short = 'n')]
+    limit: Option<usize>,
+
+    /// Output format
+    #[clap(long, value_enum, default_value_t)]
+    format: OutputFormat,
+}
+
+/// Format for printing history data
+#[derive(Copy, Clone, Debug, Default, clap::ValueEnum)]
+enum OutputFormat {
+    /// Human-readable output
+    #[default]
+    Table,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Serialize a value in this format. Panics for [OutputFormat::Table],
+    /// which needs custom handling for each kind of output
+    fn serialize<T: Serialize>(self, value: &T) -> String {
+        // Panics are intentional, they indicate a wonky bug
+        match self {
+            Self::Table => unreachable!("Table output is printed manually"),
+            Self::Json => {
+                serde_json::to_string_pretty(value).expect("Error serializing")
+            }
+            Self::Yaml => {
+                serde_yaml::to_string(value).expect("Error serializing")
+            }
+        }
+    }
+}
+
+/// Serializable version of [ExchangeSummary], for structured output
+#[derive(Debug, Serialize)]
+struct HistoryEntry<'a> {
+    id: RequestId,
+    recipe: &'a RecipeId,
+    profile: Option<&'a ProfileId>,
+    status: u16,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    pinned: bool,
+    label: Option<&'a str>,
+    note: Option<&'a str>,
+}
+
+impl<'a> From<&'a ExchangeSummary> for HistoryEntry<'a> {
+    fn from(exchange: &'a ExchangeSummary) -> Self {
+        Self {
+            id: exchange.id,
+            recipe: &exchange.recipe_id,
+            profile: exchange.profile_id.as_ref(),
+            status: exchange.status.as_u16(),
+            start_time: exchange.start_time,
+            end_time: exchange.end_time,
+            pinned: exchange.annotation.pinned,
+            label: exchange.annotation.label.as_deref(),
+            note: exchange.annotation.note.as_deref(),
+        }
+    }
+}
+
+/// An entire exchange, for structured output from `history get`
+#[derive(Debug, Serialize)]
+struct ExchangeDetail<'a> {
+    #[serde(flatten)]
+    summary: HistoryEntry<'a>,
+    request: MessageDetail<'a>,
+    response: MessageDetail<'a>,
+}
+
+/// One half of an exchange. Bodies are included as text, with invalid UTF-8
+/// replaced
+#[derive(Debug, Serialize)]
+struct MessageDetail<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    headers: BTreeMap<&'a str, String>,
+    body: Option<Cow<'a, str>>,
+}
+
+/// Convert headers to a map for serialization. Repeated headers are joined
+/// with commas, which is equivalent according to the HTTP spec
+fn headers_to_map(headers: &HeaderMap) -> BTreeMap<&str, String> {
+    let mut map: BTreeMap<&str, String> = BTreeMap::new();
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes());
+        map.entry(name.as_str())
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            })
+            .or_insert_with(|| value.into_owned());
+    }
+    map
+}
+
+/// Print rows of text as a table, with each column padded to the width of
+/// its widest cell
+fn print_table<const N: usize>(
+    header: [&str; N],
+    rows: impl IntoIterator<Item = [String; N]>,
+) {
+    let mut lines = format_table(header, rows).into_iter();
+    let header_style = Style::new().bold();
+    if let Some(header) = lines.next() {
+        println!("{}", header_style.apply_to(header));
+    }
+    for line in lines {
+        println!("{line}");
+    }
+}
+
+/// Format rows of text as table lines, with the header line first. Each
+/// column is padded to the width of its widest cell, and trailing whitespace
+/// is trimmed
+fn format_table<const N: usize>(
+    header: [&str; N],
+    rows: impl IntoIterator<Item = [String; N]>,
+) -> Vec<String> {
+    let rows = rows.into_iter().collect_vec();
+    let mut widths = header.map(|cell| cell.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let format_row = |row: [&str; N]| {
+        row.iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .join("  ")
+            .trim_end()
+            .to_owned()
+    };
+
+    std::iter::once(format_row(header))
+        .chain(
+            rows.iter()
+                .map(|row| format_row(row.each_ref().map(String::as_str))),
+        )
+        .collect()
+}
+
+#[derive(Copy, Clone, Debug, clap::ValueEnum)]
+enum ExportFormat {
+    /// HTTP Archive 1.2
+    Har,
+}
+
+/// Parse a date or RFC 3339 timestamp from the command line. Dates are
+/// interpreted as midnight in the local timezone.
+fn parse_time(value: &str) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(date) = value.parse::<NaiveDate>() {
+        let midnight = date.and_hms_opt(0, 0, 0).expect("Midnight is valid");
+        Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|time| time.to_utc())
+            .ok_or_else(|| anyhow!("Invalid local time `{midnight}`"))
+    } else {
+        DateTime::parse_from_rfc3339(value)
+            .map(|time| time.to_utc())
+            .with_context(|| {
+                format!(
+                    "Invalid time `{value}`; expected a date (`2024-10-13`) \
+                    or RFC 3339 timestamp (`2024-10-13T09:00:00Z`)"
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use reqwest::StatusCode;
+    use rstest::rstest;
+    use serde_json::json;
+    use slumber_core::{
+        http::{RequestRecord, ResponseRecord},
+        test_util::{header_map, Factory},
+    };
+
+    /// Dates are local midnight, timestamps can have any offset
+    #[rstest]
+    #[case::date(
+        "2024-10-13",
+        Local.with_ymd_and_hms(2024, 10, 13, 0, 0, 0).unwrap().to_utc()
+    )]
+    #[case::utc(
+        "2024-10-13T09:00:00Z",
+        Utc.with_ymd_and_hms(2024, 10, 13, 9, 0, 0).unwrap()
+    )]
+    #[case::offset(
+        "2024-10-13T09:00:00-04:00",
+        Utc.with_ymd_and_hms(2024, 10, 13, 13, 0, 0).unwrap()
+    )]
+    fn test_parse_time(#[case] value: &str, #[case] expected: DateTime<Utc>) {
+        assert_eq!(parse_time(value).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::invalid_date("2024-13-01")]
+    #[case::no_offset("2024-10-13T09:00:00")]
+    #[case::garbage("yesterday")]
+    fn test_parse_time_error(#[case] value: &str) {
+        let error = parse_time(value).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid time"), "{error}");
+    }
+
+    /// Repeated headers are joined into a single comma-separated value
+    #[test]
+    fn test_headers_to_map() {
+        let headers = header_map([
+            ("content-type", "application/json"),
+            ("set-cookie", "a=1"),
+            ("set-cookie", "b=2"),
+        ]);
+        assert_eq!(
+            headers_to_map(&headers),
+            BTreeMap::from([
+                ("content-type", "application/json".to_owned()),
+                ("set-cookie", "a=1, b=2".to_owned()),
+            ])
+        );
+    }
+
+    /// Columns are padded to their widest cell, including the header
+    #[test]
+    fn test_format_table() {
+        let lines = format_table(
+            ["ID", "RECIPE", "LABEL"],
+            [
+                ["1".to_owned(), "login".to_owned(), "".to_owned()],
+                ["22".to_owned(), "ü".to_owned(), "good".to_owned()],
+            ],
+        );
+        assert_eq!(
+            lines,
+            ["ID  RECIPE  LABEL", "1   login", "22  ü       good"]
+        );
+    }
+
+    #[rstest]
+    #[case::json(
+        OutputFormat::Json,
+        "{\n  \"id\": 3,\n  \"tags\": [\n    \"a\"\n  ]\n}"
+    )]
+    #[case::yaml(OutputFormat::Yaml, "id: 3\ntags:\n- a\n")]
+    fn test_serialize(#[case] format: OutputFormat, #[case] expected: &str) {
+        let value = json!({"id": 3, "tags": ["a"]});
+        assert_eq!(format.serialize(&value), expected);
+    }
+
+    /// Table output has to be handled by the caller
+    #[test]
+    #[should_panic(expected = "Table output is printed manually")]
+    fn test_serialize_table() {
+        OutputFormat::Table.serialize(&json!({}));
+    }
+
+    /// `get` can print just the body, headers, or a query, but only one of
+    /// them, and none of them with `--format`
+    #[rstest]
+    #[case::body(&["--body"], true)]
+    #[case::headers(&["--headers"], true)]
+    #[case::query(&["--query", "$.id"], true)]
+    #[case::format(&["--format", "json"], true)]
+    #[case::body_headers(&["--body", "--headers"], false)]
+    #[case::body_query(&["--body", "--query", "$.id"], false)]
+    #[case::headers_format(&["--headers", "--format", "yaml"], false)]
+    #[case::query_format(&["--query", "$.id", "--format", "json"], false)]
+    fn test_get_args(#[case] args: &[&str], #[case] valid: bool) {
+        let id = RequestId::new().to_string();
+        let result = HistoryCommand::try_parse_from(
+            ["history", "get", id.as_str()].iter().chain(args),
+        );
+        assert_eq!(result.is_ok(), valid, "{result:?}");
+    }
+
+    /// Text bodies are written as text, binary bodies as raw bytes
+    #[rstest]
+    #[case::text(b"{\"id\": 3}")]
+    #[case::binary(b"\xff\xfe\x00")]
+    fn test_write_body(#[case] body: &[u8]) {
+        let exchange = Exchange::factory((
+            RequestRecord::factory(()),
+            ResponseRecord {
+                body: body.to_vec().into(),
+                ..ResponseRecord::factory(())
+            },
+        ));
+        let mut output = Vec::new();
+        HistoryCommand::write_body(&exchange, &mut output).unwrap();
+        assert_eq!(output, body);
+    }
+
+    /// `get --query` parses the body according to its content type
+    #[test]
+    fn test_query_body() {
+        let exchange = Exchange::factory((
+            RequestRecord::factory(()),
+            ResponseRecord {
+                status: StatusCode::OK,
+                headers: header_map([("content-type", "application/json")]),
+                body: br#"{"id": 3, "name": "Ted"}"#.to_vec().into(),
+            },
+        ));
+        let query: Query = "$.name".parse().unwrap();
+        assert_eq!(
+            HistoryCommand::query_body(&exchange, &query, None).unwrap(),
+            "Ted"
+        );
+
+        // Without a content type, we don't know how to parse the body
+        let exchange = Exchange::factory((
+            RequestRecord::factory(()),
+            ResponseRecord {
+                body: br#"{"id": 3}"#.to_vec().into(),
+                ..ResponseRecord::factory(())
+            },
+        ));
+        let error = HistoryCommand::query_body(&exchange, &query, None)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Response has no content type; body cannot be queried"
+        );
+    }
+}
@@ -1,10 +1,15 @@
-use crate::{GlobalArgs, Subcommand};
-use anyhow::Context;
+use crate::{util::print_diff, GlobalArgs, Subcommand};
+use anyhow::{bail, Context};
 use clap::{Parser, ValueEnum};
-use slumber_core::collection::Collection;
+use dialoguer::Confirm;
+use slumber_core::{
+    collection::{Collection, MergeReport},
+    util::unified_diff,
+};
 use std::{
-    fs::File,
-    io::{self, Write},
+    fmt::Display,
+    fs::{self, File},
+    io::{self, IsTerminal, Write},
     path::PathBuf,
     process::ExitCode,
 };
@@ -14,10 +19,29 @@ use std::{
 pub struct ImportCommand {
     /// Input format
     format: Format,
-    /// Collection to import
+    /// Collection to import. For `curl`, pass `-` to read from stdin
     input_file: PathBuf,
     /// Destination for the new slumber collection file [default: stdout]
     output_file: Option<PathBuf>,
+    /// Environment file to import as profiles. Can be given multiple times.
+    /// Only supported for the `postman` and `http-file` formats. For
+    /// `http-file`, defaults to `http-client.env.json` and
+    /// `http-client.private.env.json` next to the input file
+    #[clap(long = "environment", short = 'e')]
+    environments: Vec<PathBuf>,
+    /// Merge the import into the existing collection at the output path,
+    /// instead of overwriting it. New operations are added and changed ones
+    /// are updated, while hand-written changes are kept. A summary and diff
+    /// of the changes is shown before writing. Only supported for the
+    /// `openapi` format
+    #[clap(long)]
+    merge: bool,
+    /// Write merged changes without asking for confirmation
+    #[clap(long, short = 'y', requires = "merge")]
+    yes: bool,
+    /// Show what a merge would change, without writing anything
+    #[clap(long, requires = "merge")]
+    dry_run: bool,
 }
 
 #[derive(Copy, Clone, Debug, ValueEnum)]
@@ -25,17 +49,63 @@ pub struct ImportCommand {
 enum Format {
     /// Insomnia export format (JSON or YAML)
     Insomnia,
-    /// OpenAPI v3.0 (JSON or YAML) v3.1 not supported but may work
+    /// OpenAPI v3.0/v3.1 or Swagger 2.0 (JSON or YAML)
     /// https://spec.openapis.org/oas/v3.0.3
     Openapi,
+    /// HTTP Archive 1.2, e.g. exported from browser dev tools
+    Har,
+    /// Postman collection v2.1
+    Postman,
+    /// One or more cURL commands
+    Curl,
+    /// JetBrains HTTP Client / VS Code REST Client `.http` file
+    HttpFile,
 }
 
 impl Subcommand for ImportCommand {
     async fn execute(self, _global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        if !self.environments.is_empty()
+            && !matches!(self.format, Format::Postman | Format::HttpFile)
+        {
+            bail!(
+                "`--environment` is only supported for the `postman` and \
+                `http-file` formats"
+            );
+        }
+
+        if self.merge {
+            if !matches!(self.format, Format::Openapi) {
+                bail!("`--merge` is only supported for the `openapi` format");
+            }
+            let Some(output_file) = &self.output_file else {
+                bail!("`--merge` requires an output file to merge into");
+            };
+            return self.merge(output_file);
+        }
+
         // Load the input
         let collection = match self.format {
             Format::Insomnia => Collection::from_insomnia(&self.input_file)?,
             Format::Openapi => Collection::from_openapi(&self.input_file)?,
+            Format::Har => Collection::from_har(&self.input_file)?,
+            Format::Postman => {
+                Collection::from_postman(&self.input_file, &self.environments)?
+            }
+            Format::Curl => {
+                let commands = if self.input_file.as_os_str() == "-" {
+                    io::read_to_string(io::stdin())
+                        .context("Error reading cURL commands from stdin")?
+                } else {
+                    fs::read_to_string(&self.input_file).with_context(|| {
+                        format!("Error reading cURL file {:?}", self.input_file)
+                    })?
+                };
+                Collection::from_curl(&commands)?
+            }
+            Format::HttpFile => Collection::from_http_file(
+                &self.input_file,
+                &self.environments,
+            )?,
         };
 
         // Write the output
@@ -58,3 +128,227 @@ impl Subcommand for ImportCommand {
         Ok(ExitCode::SUCCESS)
     }
 }
+
+impl ImportCommand {
+    /// Merge the input into an existing collection. Show the changes, then
+    /// write them after confirmation
+    fn merge(&self, output_file: &PathBuf) -> anyhow::Result<ExitCode> {
+        let before = fs::read_to_string(output_file).context(format!(
+            "Error reading collection file {output_file:?}"
+        ))?;
+        // The merged collection is re-serialized, so refuse anything that
+        // wouldn't survive that
+        if let Some((line, what)) = find_unpreservable(&before) {
+            bail!(
+                "Can't merge into {output_file:?} because it contains {what} \
+                on line {line}, which would be lost when the file is \
+                rewritten. Remove it, or merge the changes by hand"
+            );
+        }
+        let existing = Collection::load(output_file)?;
+        let (merged, report) = existing.merge_openapi(&self.input_file)?;
+        print_merge_report(&report);
+        if report.is_empty() {
+            println!("Collection is already up to date");
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        let after = serde_yaml::to_string(&merged)?;
+        let label = output_file.display().to_string();
+        println!();
+        print_diff(&unified_diff(&before, &after, &label, &label));
+        if self.dry_run {
+            return Ok(ExitCode::SUCCESS);
+        }
+
+        if !self.yes {
+            if !io::stdin().is_terminal() {
+                bail!(
+                    "Cannot confirm changes because stdin is not a terminal; \
+                    pass `--yes` to write them without confirmation"
+                );
+            }
+            let confirmed = Confirm::new()
+                .with_prompt(format!("Write changes to {output_file:?}?"))
+                .default(false)
+                .interact()
+                .context("Error reading confirmation")?;
+            if !confirmed {
+                println!("No changes written");
+                return Ok(ExitCode::SUCCESS);
+            }
+        }
+        fs::write(output_file, after).context(format!(
+            "Error writing collection output file {output_file:?}"
+        ))?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Find the first part of a YAML document that would be lost by
+/// re-serializing it: a comment, an anchor or alias, or a `.`-prefixed
+/// top-level key. Return its line number and a description. This scans line
+/// by line rather than parsing, skipping quoted strings and block scalars.
+fn find_unpreservable(text: &str) -> Option<(usize, &'static str)> {
+    // Indentation of the line that opened the current block scalar
+    let mut block_scalar: Option<usize> = None;
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let indentation = line.len() - trimmed.len();
+        if let Some(parent) = block_scalar {
+            if trimmed.is_empty() || indentation > parent {
+                continue;
+            }
+            block_scalar = None;
+        }
+        if line.starts_with('.') {
+            return Some((i + 1, "a `.`-prefixed key"));
+        }
+
+        let mut quote: Option<char> = None;
+        let mut escaped = false;
+        let mut previous = ' ';
+        // Can a node (and therefore an anchor, alias or quoted string) start
+        // here? True after indentation, `key:`, `-` and flow indicators
+        let mut node_start = true;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match quote {
+                Some('"') if escaped => escaped = false,
+                Some('"') if c == '\\' => escaped = true,
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None => match c {
+                    '#' if previous.is_whitespace() => {
+                        return Some((i + 1, "a comment"));
+                    }
+                    '&' | '*'
+                        if node_start
+                            && chars.peek().is_some_and(|next| {
+                                !next.is_whitespace()
+                                    && !",[]{}".contains(*next)
+                            }) =>
+                    {
+                        return Some((i + 1, "an anchor or alias"));
+                    }
+                    '"' | '\'' if node_start => quote = Some(c),
+                    _ => {}
+                },
+            }
+            node_start = match c {
+                ' ' | '\t' => {
+                    node_start || matches!(previous, ':' | '-' | '?' | ',')
+                }
+                '[' | '{' | ',' => quote.is_none(),
+                _ => false,
+            };
+            previous = c;
+        }
+
+        // Block scalar content (`key: |`) is opaque until the indentation
+        // drops back down
+        let tokens: Vec<&str> = trimmed.split_whitespace().collect();
+        if let [.., parent, indicator] = tokens.as_slice() {
+            let is_indicator = indicator.starts_with(['|', '>'])
+                && indicator[1..]
+                    .chars()
+                    .all(|c| matches!(c, '-' | '+' | '0'..='9'));
+            if is_indicator
+                && (parent.ends_with(':')
+                    || *parent == "-"
+                    || parent.starts_with('!'))
+            {
+                block_scalar = Some(indentation);
+            }
+        }
+    }
+    None
+}
+
+/// Print a summary of everything changed or flagged by a merge
+fn print_merge_report(report: &MergeReport) {
+    fn print_list<T: Display>(title: &str, items: &[T]) {
+        if !items.is_empty() {
+            println!("{title}:");
+            for item in items {
+                println!("- {item}");
+            }
+        }
+    }
+
+    print_list("Added recipes", &report.added);
+    for (title, recipes) in [
+        ("Updated recipes", &report.updated),
+        (
+            "Recipes with values not in the spec (left unchanged)",
+            &report.flagged,
+        ),
+    ] {
+        if !recipes.is_empty() {
+            println!("{title}:");
+            for (id, messages) in recipes {
+                println!("- {id}");
+                for message in messages {
+                    println!("  - {message}");
+                }
+            }
+        }
+    }
+    print_list(
+        "Recipes not in the spec, either removed or written by hand (left \
+        unchanged)",
+        &report.missing,
+    );
+    print_list("Added chains", &report.added_chains);
+    print_list("Added profiles", &report.added_profiles);
+    let fields: Vec<String> = report
+        .added_fields
+        .iter()
+        .map(|(profile_id, field)| format!("{field} (profile {profile_id})"))
+        .collect();
+    print_list("Added profile fields", &fields);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Comments, anchors and `.` keys are found, but look-alikes inside
+    /// strings and block scalars aren't
+    #[rstest]
+    #[case::none(
+        "requests:\n  get:\n    url: \"{{host}}/#/users?a=1&b=2\"\n    \
+        headers:\n      Accept: '*/*'\n      X-Tag: a#b *c &d\n",
+        None
+    )]
+    #[case::block_scalar(
+        "requests:\n  post:\n    body: |-\n      # not a comment\n      \
+        *not: &alias\n    url: x\n",
+        None
+    )]
+    #[case::comment("# Comment\nrequests: {}\n", Some((1, "a comment")))]
+    #[case::comment_trailing(
+        "requests:\n  get:\n    url: x # Comment\n",
+        Some((3, "a comment"))
+    )]
+    #[case::comment_after_block_scalar(
+        "requests:\n  post:\n    body: |\n      # body\n  # Comment\n",
+        Some((5, "a comment"))
+    )]
+    #[case::anchor(
+        "profiles:\n  local:\n    data: &data\n      host: x\n",
+        Some((3, "an anchor or alias"))
+    )]
+    #[case::alias(
+        "requests:\n  get:\n    headers: {Accept: *accept}\n",
+        Some((3, "an anchor or alias"))
+    )]
+    #[case::dot_key(".base:\n  url: x\n", Some((1, "a `.`-prefixed key")))]
+    fn test_find_unpreservable(
+        #[case] text: &str,
+        #[case] expected: Option<(usize, &str)>,
+    ) {
+        assert_eq!(find_unpreservable(text), expected);
+    }
+}
@@ -51,8 +51,8 @@ mod tests {
     use rstest::rstest;
     use slumber_core::{
         collection::{
-            Chain, ChainSource, Collection, Folder, Method, Profile, Recipe,
-            RecipeBody, RecipeNode,
+            Chain, ChainSource, Collection, Folder, JsonTemplate, Method,
+            Profile, Recipe, RecipeBody, RecipeNode,
         },
         http::content_type::ContentType,
         test_util::{by_id, temp_dir, Factory, TempDir},
@@ -116,6 +116,7 @@ mod tests {
                     recipe: "example1".into(),
                     trigger: Default::default(),
                     section: Default::default(),
+                    history: Default::default(),
                 },
                 selector: Some("$.data".parse().unwrap()),
                 ..Chain::factory(())
@@ -136,10 +137,13 @@ mod tests {
                         name: Some("Example Request 2".into()),
                         method: Method::Post,
                         url: "{{host}}/anything".into(),
-                        body: Some(RecipeBody::Raw {
-                            body: "{\n  \"data\": \"{{chains.example}}\"\n}"
-                                .into(),
-                            content_type: Some(ContentType::Json),
+                        body: Some(RecipeBody::Structured {
+                            body: JsonTemplate::Object(indexmap! {
+                                "data".into() => JsonTemplate::String(
+                                    "{{chains.example}}".into(),
+                                ),
+                            }),
+                            content_type: ContentType::Json,
                         }),
                         ..Recipe::factory(())
                     })]),
@@ -1,6 +1,6 @@
 use crate::{
     completions::{complete_profile, complete_recipe},
-    util::HeaderDisplay,
+    util::{load_database, HeaderDisplay},
     GlobalArgs, Subcommand,
 };
 use anyhow::{anyhow, Context};
@@ -12,7 +12,7 @@ use itertools::Itertools;
 use slumber_config::Config;
 use slumber_core::{
     collection::{Collection, CollectionFile, ProfileId, RecipeId},
-    db::{CollectionDatabase, Database},
+    db::CollectionDatabase,
     http::{BuildOptions, HttpEngine, RequestSeed, RequestTicket},
     template::{Prompt, Prompter, Select, TemplateContext, TemplateError},
     util::ResultTraced,
@@ -163,9 +163,10 @@ impl BuildRequestCommand {
         trigger_dependencies: bool,
     ) -> anyhow::Result<(CollectionDatabase, RequestTicket)> {
         let collection_path = CollectionFile::try_path(None, global.file)?;
-        let database = Database::load()?.into_collection(&collection_path)?;
-        let collection = Collection::load(&collection_path)?;
         let config = Config::load()?;
+        let database =
+            load_database(&config)?.into_collection(&collection_path)?;
+        let collection = Collection::load(&collection_path)?;
         let http_engine = HttpEngine::new(&config.http);
 
         // Validate profile ID, so we can provide a good error if it's invalid
@@ -12,9 +12,9 @@ mod completions;
 mod util;
 
 use crate::commands::{
-    collections::CollectionsCommand, generate::GenerateCommand,
-    history::HistoryCommand, import::ImportCommand, new::NewCommand,
-    request::RequestCommand, show::ShowCommand,
+    collections::CollectionsCommand, db::DbCommand, export::ExportCommand,
+    generate::GenerateCommand, history::HistoryCommand, import::ImportCommand,
+    new::NewCommand, request::RequestCommand, show::ShowCommand,
 };
 use clap::{CommandFactory, Parser};
 use clap_complete::CompleteEnv;
@@ -65,6 +65,8 @@ pub struct GlobalArgs {
 #[derive(Clone, Debug, clap::Subcommand)]
 pub enum CliCommand {
     Collections(CollectionsCommand),
+    Db(DbCommand),
+    Export(ExportCommand),
     Generate(GenerateCommand),
     History(HistoryCommand),
     Import(ImportCommand),
@@ -78,6 +80,8 @@ impl CliCommand {
     pub async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
         match self {
             Self::Collections(command) => command.execute(global).await,
+            Self::Db(command) => command.execute(global).await,
+            Self::Export(command) => command.execute(global).await,
             Self::Generate(command) => command.execute(global).await,
             Self::History(command) => command.execute(global).await,
             Self::Import(command) => command.execute(global).await,
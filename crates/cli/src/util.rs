@@ -1,8 +1,38 @@
 use dialoguer::console::Style;
 use reqwest::header::HeaderMap;
-use slumber_core::util::MaybeStr;
+use slumber_config::Config;
+use slumber_core::{
+    db::{Database, TerminalPassphrasePrompter},
+    util::MaybeStr,
+};
 use std::fmt::{self, Display, Formatter};
 
+/// Load the history database. If it's encrypted and the key isn't available
+/// from the environment or a key file, prompt for it
+pub fn load_database(config: &Config) -> anyhow::Result<Database> {
+    Database::load(&config.encryption, &TerminalPassphrasePrompter)
+}
+
+/// Print a unified diff to stdout, colored by line type
+pub fn print_diff(diff: &str) {
+    let removed_style = Style::new().red();
+    let added_style = Style::new().green();
+    let hunk_style = Style::new().cyan();
+    for line in diff.lines() {
+        if line.starts_with("---") || line.starts_with("+++") {
+            println!("{}", Style::new().bold().apply_to(line));
+        } else if line.starts_with('-') {
+            println!("{}", removed_style.apply_to(line));
+        } else if line.starts_with('+') {
+            println!("{}", added_style.apply_to(line));
+        } else if line.starts_with("@@") {
+            println!("{}", hunk_style.apply_to(line));
+        } else {
+            println!("{line}");
+        }
+    }
+}
+
 /// Wrapper making it easy to print a header map
 pub struct HeaderDisplay<'a>(pub &'a HeaderMap);
 
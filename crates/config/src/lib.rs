@@ -19,6 +19,7 @@ use anyhow::Context;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use slumber_core::{
+    db::{EncryptionConfig, RetentionConfig},
     http::HttpEngineConfig,
     util::{
         parse_yaml,
@@ -51,6 +52,10 @@ pub struct Config {
     pub input_bindings: IndexMap<Action, InputBinding>,
     /// Visual configuration for the TUI (e.g. colors)
     pub theme: Theme,
+    /// Rules for deleting old requests from history
+    pub retention: RetentionConfig,
+    /// Encryption of the history database at rest
+    pub encryption: EncryptionConfig,
     /// Enable debug monitor in TUI
     pub debug: bool,
 }
@@ -115,6 +120,8 @@ impl Default for Config {
             preview_templates: true,
             input_bindings: Default::default(),
             theme: Default::default(),
+            retention: Default::default(),
+            encryption: Default::default(),
             debug: false,
         }
     }
@@ -2,21 +2,33 @@
 //! possible
 
 mod cereal;
+mod curl;
+mod export;
+mod har;
+mod http_file;
 mod insomnia;
 mod models;
 mod openapi;
+mod postman;
 mod recipe_tree;
 
+pub(crate) use cereal::serde_duration;
 pub use cereal::HasId;
+pub use export::Export;
 pub use models::*;
+pub use openapi::MergeReport;
 pub use recipe_tree::*;
 
-use anyhow::{anyhow, Context};
+use crate::util::parse_yaml;
+use anyhow::{anyhow, bail, Context};
+use indexmap::IndexMap;
 use itertools::Itertools;
 use std::{
     env,
     fmt::Debug,
+    fs,
     future::Future,
+    iter,
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -95,6 +107,233 @@ impl CollectionFile {
                 anyhow!("No collection file found in current or ancestor directories")
             })
     }
+
+    /// Add the recipes and chains of another collection (e.g. one imported
+    /// from cURL) to the end of this collection's file. Top-level recipe IDs
+    /// that are already taken get a suffix; any other conflict is an error.
+    /// Conflicts are checked against the current contents of the file, which
+    /// may have changed since the collection was loaded. The file is edited as
+    /// text rather than re-serialized, so comments and formatting are
+    /// preserved. The loaded collection is *not* updated; the file has to be
+    /// reloaded. Return the IDs of the added recipes.
+    pub fn append(&self, other: &Collection) -> anyhow::Result<Vec<RecipeId>> {
+        if !other.profiles.is_empty() {
+            bail!("Appending profiles is not supported");
+        }
+        let mut text = fs::read_to_string(&self.path).with_context(|| {
+            format!("Error reading collection file {:?}", self.path)
+        })?;
+        let current: Collection =
+            parse_yaml(text.as_bytes()).with_context(|| {
+                format!("Error parsing collection file {:?}", self.path)
+            })?;
+
+        let mut recipes: IndexMap<RecipeId, &RecipeNode> = IndexMap::new();
+        for (lookup_key, node) in other.recipes.iter() {
+            let taken = |id: &RecipeId| {
+                current.recipes.get(id).is_some() || recipes.contains_key(id)
+            };
+            let id = node.id();
+            if lookup_key.as_slice().len() > 1 {
+                if taken(id) {
+                    bail!("Recipe/folder `{id}` already exists");
+                }
+                continue;
+            }
+            let mut unique = id.clone();
+            let mut i = 2;
+            while taken(&unique) {
+                unique = format!("{id} ({i})").into();
+                i += 1;
+            }
+            recipes.insert(unique, node);
+        }
+        if let Some(id) = other
+            .chains
+            .keys()
+            .find(|id| current.chains.contains_key(*id))
+        {
+            bail!("Chain `{id}` already exists");
+        }
+
+        if !other.chains.is_empty() {
+            text = append_entries(
+                &text,
+                "chains",
+                &serde_yaml::to_string(&other.chains)?,
+            )?;
+        }
+        text = append_entries(
+            &text,
+            "requests",
+            &serde_yaml::to_string(&recipes)?,
+        )?;
+        fs::write(&self.path, text).with_context(|| {
+            format!("Error writing collection file {:?}", self.path)
+        })?;
+        Ok(recipes.into_keys().collect())
+    }
+}
+
+/// Add serialized entries to the end of a top-level mapping in a YAML
+/// document, matching the indentation of existing entries. If the mapping
+/// doesn't exist, it's added to the end of the document.
+fn append_entries(
+    text: &str,
+    key: &str,
+    entries: &str,
+) -> anyhow::Result<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let key_line = lines.iter().position(|line| {
+        line.strip_prefix(key)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .is_some()
+    });
+    let Some(key_line) = key_line else {
+        let mut text = text.to_owned();
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(&format!("\n{key}:\n"));
+        text.push_str(&indent(entries, "  "));
+        return Ok(text);
+    };
+    // Inline mappings (`requests: {}`) would need to be rewritten entirely
+    let rest = lines[key_line][key.len() + 1..].trim();
+    if !(rest.is_empty() || rest.starts_with('#')) {
+        bail!(
+            "Can't add to `{key}` because it isn't a block mapping; \
+            edit the collection file manually"
+        );
+    }
+
+    // The mapping ends at the next line that isn't indented. Skip trailing
+    // blank lines and comments so the entries go right after the last entry
+    let end = lines[key_line + 1..]
+        .iter()
+        .position(|line| {
+            !line.is_empty()
+                && !line.starts_with(char::is_whitespace)
+                && !line.starts_with('#')
+        })
+        .map_or(lines.len(), |i| key_line + 1 + i);
+    let last = lines[key_line + 1..end]
+        .iter()
+        .rposition(|line| {
+            let trimmed = line.trim_start();
+            !trimmed.is_empty() && !trimmed.starts_with('#')
+        })
+        .map_or(key_line + 1, |i| key_line + 2 + i);
+    let indentation = lines[key_line + 1..end]
+        .iter()
+        .find(|line| {
+            let trimmed = line.trim_start();
+            !trimmed.is_empty() && !trimmed.starts_with('#')
+        })
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .unwrap_or("  ");
+
+    let mut output = String::with_capacity(text.len() + entries.len());
+    for line in &lines[..last] {
+        output.push_str(line);
+        output.push('\n');
+    }
+    // Separate from the previous entry, to match the usual style
+    if last > key_line + 1 {
+        output.push('\n');
+    }
+    output.push_str(&indent(entries, indentation));
+    for line in &lines[last..] {
+        output.push_str(line);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Re-indent serialized mapping entries, which serde_yaml always indents by
+/// two spaces, so that every nesting level is indented by the given unit.
+/// Block scalar content keeps any indentation beyond its first line. The
+/// entries are separated with blank lines.
+fn indent(text: &str, indentation: &str) -> String {
+    let unit = indentation.len();
+    let mut output = String::with_capacity(text.len() * 2);
+    // (original column, new column) of each open nesting level
+    let mut levels: Vec<(usize, usize)> = Vec::new();
+    // While in a block scalar: the columns of its key, the original column of
+    // its first content line, and whether it has an indentation indicator
+    let mut block: Option<((usize, usize), Option<usize>, bool)> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let mut column = line.len() - line.trim_start().len();
+        if let Some(((key_column, key_new), base, explicit)) = &mut block {
+            if line.trim().is_empty() {
+                output.push('\n');
+                continue;
+            }
+            if column > *key_column {
+                // An indentation indicator is relative to the key, so the
+                // content has to stay at the same offset from it
+                let (new_column, content) = if *explicit {
+                    (*key_new + column - *key_column, line.trim_start())
+                } else {
+                    let base = *base.get_or_insert(column);
+                    (*key_new + unit, &line[base..])
+                };
+                push_indented(&mut output, new_column, content);
+                continue;
+            }
+            block = None;
+        }
+        if line.is_empty() {
+            output.push('\n');
+            continue;
+        }
+
+        if i > 0 && column == 0 {
+            output.push('\n');
+        }
+        while levels
+            .last()
+            .is_some_and(|&(original, _)| original > column)
+        {
+            levels.pop();
+        }
+        let mut new_column = match levels.last() {
+            Some(&(original, new)) if original == column => new,
+            parent => {
+                let new = parent.map_or(0, |&(_, new)| new) + unit;
+                levels.push((column, new));
+                new
+            }
+        };
+        let mut content = line.trim_start();
+        push_indented(&mut output, new_column, content);
+
+        // The contents of a sequence item are aligned after the dash
+        while let Some(item) = content.strip_prefix("- ") {
+            column += 2;
+            new_column += 2;
+            levels.push((column, new_column));
+            content = item;
+        }
+        let header = content.rsplit(' ').next().unwrap_or_default();
+        if let Some(modifiers) = header.strip_prefix(['|', '>']) {
+            if modifiers.len() <= 2
+                && modifiers.chars().all(|c| "+-0123456789".contains(c))
+            {
+                let explicit = modifiers.contains(|c: char| c.is_ascii_digit());
+                block = Some(((column, new_column), None, explicit));
+            }
+        }
+    }
+    output
+}
+
+/// Write a line to the output, indented to the given column
+fn push_indented(output: &mut String, column: usize, line: &str) {
+    output.extend(iter::repeat(' ').take(column));
+    output.push_str(line);
+    output.push('\n');
 }
 
 /// Create a new file with a placeholder path for testing
@@ -223,6 +462,182 @@ mod tests {
         drop(temp_dir); // Dropping deletes the directory
     }
 
+    /// Appended recipes go at the end of the existing mappings, and comments
+    /// and formatting are left alone
+    #[rstest]
+    #[tokio::test]
+    async fn test_append(temp_dir: TempDir) {
+        let path = temp_dir.join("slumber.yml");
+        fs::write(
+            &path,
+            "\
+# My collection
+requests:
+    example.com/users-GET: !request
+        method: GET # Comment
+        url: https://example.com/users
+
+    # Trailing comment
+
+.ignore:
+  base: &base
+    method: GET
+",
+        )
+        .unwrap();
+        let collection_file = CollectionFile::load(path.clone()).await.unwrap();
+        let other = Collection::from_curl(
+            "curl https://example.com/users\n\
+            curl -F file=@data.csv https://example.com/upload",
+        )
+        .unwrap();
+
+        let ids = collection_file.append(&other).unwrap();
+        assert_eq!(
+            ids,
+            vec![
+                "example.com/users-GET (2)".into(),
+                "example.com/upload-POST".into()
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "\
+# My collection
+requests:
+    example.com/users-GET: !request
+        method: GET # Comment
+        url: https://example.com/users
+
+    example.com/users-GET (2): !request
+        name: /users
+        method: GET
+        url: https://example.com/users
+
+    example.com/upload-POST: !request
+        name: /upload
+        method: POST
+        url: https://example.com/upload
+        body: !form_multipart
+            file: '{{chains.example_com_upload-POST_file}}'
+
+    # Trailing comment
+
+.ignore:
+  base: &base
+    method: GET
+
+chains:
+  example_com_upload-POST_file:
+    source: !file
+      path: data.csv
+    sensitive: false
+    selector_mode: auto
+    trim: none
+"
+        );
+        // The appended file has to be valid
+        CollectionFile::load(path).await.unwrap();
+    }
+
+    /// If the mapping is missing it's added, and inline mappings can't be
+    /// added to
+    #[rstest]
+    #[case::missing("profiles: {}", Ok("profiles: {}\n\nrequests:\n  a: 1\n"))]
+    #[case::empty("requests:\n", Ok("requests:\n  a: 1\n"))]
+    #[case::inline("requests: {}", Err(()))]
+    fn test_append_entries(
+        #[case] text: &str,
+        #[case] expected: Result<&str, ()>,
+    ) {
+        assert_eq!(
+            append_entries(text, "requests", "a: 1\n")
+                .as_deref()
+                .map_err(|_| ()),
+            expected
+        );
+    }
+
+    /// The existing indentation is applied at every nesting level of the
+    /// appended entries, including sequence items and block scalars
+    #[rstest]
+    #[case::two("requests:\n  a: 1\n", "  ")]
+    #[case::four("requests:\n    a: 1\n", "    ")]
+    fn test_append_entries_nested(#[case] text: &str, #[case] unit: &str) {
+        let entries = "\
+b:
+  list:
+  - x: 1
+    y: 2
+  text: |-
+    line 1
+      line 2
+
+    line 3
+  z: 3
+c: 4
+";
+        let i = |level: usize| unit.repeat(level);
+        let expected = format!(
+            "{text}
+{i1}b:
+{i2}list:
+{i2}- x: 1
+{i2}  y: 2
+{i2}text: |-
+{i3}line 1
+{i3}  line 2
+
+{i3}line 3
+{i2}z: 3
+
+{i1}c: 4
+",
+            i1 = i(1),
+            i2 = i(2),
+            i3 = i(3),
+        );
+        let appended = append_entries(text, "requests", entries).unwrap();
+        assert_eq!(appended, expected);
+        // Make sure the structure wasn't changed
+        assert_eq!(
+            serde_yaml::from_str::<serde_yaml::Value>(&appended).unwrap(),
+            serde_yaml::from_str::<serde_yaml::Value>(&format!(
+                "requests:\n  a: 1\n{}",
+                indent(entries, "  ")
+            ))
+            .unwrap()
+        );
+    }
+
+    /// Conflicts are checked against the file as it is now, not as it was when
+    /// the collection was loaded
+    #[rstest]
+    #[tokio::test]
+    async fn test_append_modified(temp_dir: TempDir) {
+        let path = temp_dir.join("slumber.yml");
+        fs::write(&path, "requests: {}\n").unwrap();
+        let collection_file = CollectionFile::load(path.clone()).await.unwrap();
+        fs::write(
+            &path,
+            "\
+requests:
+  example.com/users-GET: !request
+    method: GET
+    url: https://example.com/users
+",
+        )
+        .unwrap();
+
+        let other =
+            Collection::from_curl("curl https://example.com/users").unwrap();
+        assert_eq!(
+            collection_file.append(&other).unwrap(),
+            vec!["example.com/users-GET (2)".into()]
+        );
+        CollectionFile::load(path).await.unwrap();
+    }
+
     /// A catch-all regression test, to make sure we don't break anything in the
     /// collection format. This lives at the bottom because it's huge.
     #[rstest]
@@ -365,6 +780,7 @@ mod tests {
                         recipe: "login".into(),
                         trigger: ChainRequestTrigger::Never,
                         section: ChainRequestSection::Body,
+                        history: ChainRequestHistory::Latest,
                     },
                     sensitive: false,
                     selector: Some("$.data".parse().unwrap()),
@@ -378,6 +794,7 @@ mod tests {
                         recipe: "login".into(),
                         trigger: ChainRequestTrigger::Never,
                         section: ChainRequestSection::Body,
+                        history: ChainRequestHistory::Latest,
                     },
                     sensitive: false,
                     selector: None,
@@ -391,6 +808,7 @@ mod tests {
                         recipe: "login".into(),
                         trigger: ChainRequestTrigger::Never,
                         section: ChainRequestSection::Body,
+                        history: ChainRequestHistory::Latest,
                     },
                     sensitive: false,
                     selector: None,
@@ -406,6 +824,7 @@ mod tests {
                             Duration::from_secs(12 * 60 * 60),
                         ),
                         section: ChainRequestSection::Body,
+                        history: ChainRequestHistory::Latest,
                     },
                     sensitive: false,
                     selector: None,
@@ -419,6 +838,7 @@ mod tests {
                         recipe: "login".into(),
                         trigger: ChainRequestTrigger::Never,
                         section: ChainRequestSection::Body,
+                        history: ChainRequestHistory::Latest,
                     },
                     sensitive: false,
                     selector: None,
@@ -432,6 +852,7 @@ mod tests {
                         recipe: "login".into(),
                         trigger: ChainRequestTrigger::Never,
                         section: ChainRequestSection::Body,
+                        history: ChainRequestHistory::Latest,
                     },
                     sensitive: false,
                     selector: None,
@@ -447,6 +868,35 @@ mod tests {
                         section: ChainRequestSection::Header(
                             "content-type".into(),
                         ),
+                        history: ChainRequestHistory::Latest,
+                    },
+                    sensitive: false,
+                    selector: None,
+                    selector_mode: SelectorMode::default(),
+                    content_type: None,
+                    trim: ChainOutputTrim::None,
+                },
+                Chain {
+                    id: "request_history_pinned".into(),
+                    source: ChainSource::Request {
+                        recipe: "login".into(),
+                        trigger: ChainRequestTrigger::Never,
+                        section: ChainRequestSection::Body,
+                        history: ChainRequestHistory::Pinned,
+                    },
+                    sensitive: false,
+                    selector: None,
+                    selector_mode: SelectorMode::default(),
+                    content_type: None,
+                    trim: ChainOutputTrim::None,
+                },
+                Chain {
+                    id: "request_history_label".into(),
+                    source: ChainSource::Request {
+                        recipe: "login".into(),
+                        trigger: ChainRequestTrigger::Never,
+                        section: ChainRequestSection::Body,
+                        history: ChainRequestHistory::Label("baseline".into()),
                     },
                     sensitive: false,
                     selector: None,
@@ -476,6 +926,7 @@ mod tests {
                     headers: indexmap! {
                         "Accept".into() => "application/json".into(),
                     },
+                    protobuf: None,
                 }),
                 RecipeNode::Folder(Folder {
                     id: "users".into(),
@@ -493,16 +944,22 @@ mod tests {
                                 ("value".into(), "{{field2}}".into()),
                             ],
                             headers: indexmap! {},
+                            protobuf: Some(ProtobufSchema {
+                                file: "./protos/user.proto".into(),
+                                message: "users.v1.User".into(),
+                                include: vec![],
+                            }),
                         }),
                         RecipeNode::Recipe(Recipe {
                             id: "json_body".into(),
                             name: Some("Modify User".into()),
                             method: Method::Put,
                             url: "{{host}}/anything/{{user_guid}}".into(),
-                            body: Some(RecipeBody::Raw {
+                            body: Some(RecipeBody::Structured {
                                 body: json!({"username": "new username"})
-                                    .into(),
-                                content_type: Some(ContentType::Json),
+                                    .try_into()
+                                    .unwrap(),
+                                content_type: ContentType::Json,
                             }),
                             authentication: Some(Authentication::Bearer(
                                 "{{chains.auth_token}}".into(),
@@ -511,6 +968,7 @@ mod tests {
                             headers: indexmap! {
                                 "Accept".into() => "application/json".into(),
                             },
+                            protobuf: None,
                         }),
                         RecipeNode::Recipe(Recipe {
                             id: "json_body_but_not".into(),
@@ -518,10 +976,11 @@ mod tests {
                             method: Method::Put,
                             url: "{{host}}/anything/{{user_guid}}".into(),
 
-                            body: Some(RecipeBody::Raw {
+                            body: Some(RecipeBody::Structured {
                                 body: json!(r#"{"warning": "NOT an object"}"#)
-                                    .into(),
-                                content_type: Some(ContentType::Json),
+                                    .try_into()
+                                    .unwrap(),
+                                content_type: ContentType::Json,
                             }),
                             authentication: Some(Authentication::Basic {
                                 username: "{{username}}".into(),
@@ -531,6 +990,7 @@ mod tests {
                             headers: indexmap! {
                                 "Accept".into() => "application/json".into(),
                             },
+                            protobuf: None,
                         }),
                         RecipeNode::Recipe(Recipe {
                             id: "form_urlencoded_body".into(),
@@ -546,6 +1006,7 @@ mod tests {
                             headers: indexmap! {
                                 "Accept".into() => "application/json".into(),
                             },
+                            protobuf: None,
                         }),
                     ]),
                 }),
@@ -242,17 +242,33 @@ impl RecipeBody {
     // by macros, but we need custom implementation
     const STRUCT_NAME: &'static str = "RecipeBody";
     const VARIANT_JSON: &'static str = "json";
+    const VARIANT_YAML: &'static str = "yaml";
+    const VARIANT_XML: &'static str = "xml";
     const VARIANT_FORM_URLENCODED: &'static str = "form_urlencoded";
+    const VARIANT_FORM_JSON: &'static str = "form_json";
     const VARIANT_FORM_MULTIPART: &'static str = "form_multipart";
+    const VARIANT_TYPED: &'static str = "typed";
     const ALL_VARIANTS: &'static [&'static str] = &[
         Self::VARIANT_JSON,
+        Self::VARIANT_YAML,
+        Self::VARIANT_XML,
         Self::VARIANT_FORM_URLENCODED,
+        Self::VARIANT_FORM_JSON,
         Self::VARIANT_FORM_MULTIPART,
+        Self::VARIANT_TYPED,
     ];
 }
 
+/// Contents of the `!typed` tag, for a raw body with a known content type
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TypedBody {
+    content_type: ContentType,
+    body: Template,
+}
+
 /// Custom serialization for RecipeBody, so the `Raw` variant serializes as a
-/// scalar without a tag
+/// scalar without a tag, unless it has a content type
 impl Serialize for RecipeBody {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -261,10 +277,36 @@ impl Serialize for RecipeBody {
         // This involves a lot of duplication, but any abstraction will probably
         // just make it worse
         match self {
+            // Raw JSON bodies (e.g. from imports) get the `!json` tag, other
+            // content types are kept with the `!typed` tag, and bodies without
+            // a content type are stored as a string
             RecipeBody::Raw {
                 body,
                 content_type: None,
             } => body.serialize(serializer),
+            RecipeBody::Raw {
+                body,
+                content_type:
+                    Some(
+                        content_type @ (ContentType::Xml
+                        | ContentType::Yaml
+                        | ContentType::Toml
+                        | ContentType::FormUrlencoded
+                        | ContentType::Csv
+                        | ContentType::Tsv
+                        | ContentType::MessagePack
+                        | ContentType::Cbor
+                        | ContentType::Protobuf),
+                    ),
+            } => serializer.serialize_newtype_variant(
+                Self::STRUCT_NAME,
+                7,
+                Self::VARIANT_TYPED,
+                &TypedBody {
+                    content_type: *content_type,
+                    body: body.clone(),
+                },
+            ),
             RecipeBody::Raw {
                 body,
                 content_type: Some(ContentType::Json),
@@ -295,6 +337,46 @@ impl Serialize for RecipeBody {
                     Self::VARIANT_FORM_MULTIPART,
                     value,
                 ),
+            RecipeBody::Structured {
+                body,
+                content_type: ContentType::Json,
+            } => serializer.serialize_newtype_variant(
+                Self::STRUCT_NAME,
+                1,
+                Self::VARIANT_JSON,
+                body,
+            ),
+            RecipeBody::Structured {
+                body,
+                content_type: ContentType::Yaml,
+            } => serializer.serialize_newtype_variant(
+                Self::STRUCT_NAME,
+                4,
+                Self::VARIANT_YAML,
+                body,
+            ),
+            RecipeBody::Structured {
+                body,
+                content_type: ContentType::Xml,
+            } => serializer.serialize_newtype_variant(
+                Self::STRUCT_NAME,
+                5,
+                Self::VARIANT_XML,
+                body,
+            ),
+            // Nothing else can be deserialized
+            RecipeBody::Structured { content_type, .. } => {
+                Err(S::Error::custom(format!(
+                    "Unsupported structured body type {content_type:?}"
+                )))
+            }
+            RecipeBody::FormJson(value) => serializer
+                .serialize_newtype_variant(
+                    Self::STRUCT_NAME,
+                    6,
+                    Self::VARIANT_FORM_JSON,
+                    value,
+                ),
         }
     }
 }
@@ -352,26 +434,37 @@ impl<'de> Deserialize<'de> for RecipeBody {
             {
                 let (tag, value) = data.variant::<String>()?;
                 match tag.as_str() {
-                    RecipeBody::VARIANT_JSON => {
-                        // Pretty print the JSON now and parse it as a template.
-                        // This is inefficient because we stringify the value
-                        // then immediately clone it during the parse :(
-                        let json: serde_json::Value =
-                            value.newtype_variant()?;
-                        let body = format!("{json:#}")
-                            .parse()
-                            .map_err(A::Error::custom)?;
-                        Ok(RecipeBody::Raw {
-                            body,
-                            content_type: Some(ContentType::Json),
-                        })
-                    }
+                    // Structured bodies are stored as-is and serialized
+                    // after rendering
+                    RecipeBody::VARIANT_JSON => Ok(RecipeBody::Structured {
+                        body: value.newtype_variant()?,
+                        content_type: ContentType::Json,
+                    }),
+                    RecipeBody::VARIANT_YAML => Ok(RecipeBody::Structured {
+                        body: value.newtype_variant()?,
+                        content_type: ContentType::Yaml,
+                    }),
+                    RecipeBody::VARIANT_XML => Ok(RecipeBody::Structured {
+                        body: value.newtype_variant()?,
+                        content_type: ContentType::Xml,
+                    }),
                     RecipeBody::VARIANT_FORM_URLENCODED => {
                         Ok(RecipeBody::FormUrlencoded(value.newtype_variant()?))
                     }
+                    RecipeBody::VARIANT_FORM_JSON => {
+                        Ok(RecipeBody::FormJson(value.newtype_variant()?))
+                    }
                     RecipeBody::VARIANT_FORM_MULTIPART => {
                         Ok(RecipeBody::FormMultipart(value.newtype_variant()?))
                     }
+                    RecipeBody::VARIANT_TYPED => {
+                        let TypedBody { content_type, body } =
+                            value.newtype_variant()?;
+                        Ok(RecipeBody::Raw {
+                            body,
+                            content_type: Some(content_type),
+                        })
+                    }
                     other => Err(A::Error::unknown_variant(
                         other,
                         RecipeBody::ALL_VARIANTS,
@@ -466,6 +559,34 @@ pub mod serde_duration {
         };
         Ok(Duration::from_secs(seconds))
     }
+
+    /// Same format, for optional durations. Use with `#[serde(default)]`
+    pub mod option {
+        use serde::{Deserializer, Serializer};
+        use std::time::Duration;
+
+        pub fn serialize<S>(
+            duration: &Option<Duration>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match duration {
+                Some(duration) => super::serialize(duration, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D>(
+            deserializer: D,
+        ) -> Result<Option<Duration>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            super::deserialize(deserializer).map(Some)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -524,24 +645,63 @@ mod tests {
         RecipeBody::Raw { body: "{{user_id}}".into(), content_type: None },
         "{{user_id}}"
     )]
-    #[case::json(
+    #[case::raw_yaml(
         RecipeBody::Raw {
-            body: serde_json::to_string_pretty(&json!({"user": "{{user_id}}"}))
-                .unwrap()
-                .into(),
-            content_type: Some(ContentType::Json),
+            body: "user: {{user_id}}".into(),
+            content_type: Some(ContentType::Yaml),
+        },
+        serde_yaml::Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new("typed"),
+            value: mapping([
+                ("content_type", "yaml"),
+                ("body", "user: {{user_id}}"),
+            ]),
+        })),
+    )]
+    #[case::raw_toml(
+        RecipeBody::Raw {
+            body: "user = \"{{user_id}}\"".into(),
+            content_type: Some(ContentType::Toml),
+        },
+        serde_yaml::Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new("typed"),
+            value: mapping([
+                ("content_type", "toml"),
+                ("body", "user = \"{{user_id}}\""),
+            ]),
+        })),
+    )]
+    #[case::raw_form_urlencoded(
+        RecipeBody::Raw {
+            body: "user={{user_id}}".into(),
+            content_type: Some(ContentType::FormUrlencoded),
+        },
+        serde_yaml::Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new("typed"),
+            value: mapping([
+                ("content_type", "form_urlencoded"),
+                ("body", "user={{user_id}}"),
+            ]),
+        })),
+    )]
+    #[case::json(
+        RecipeBody::Structured {
+            body: json!({"user": "{{user_id}}", "ids": [1, true, null]})
+                .try_into()
+                .unwrap(),
+            content_type: ContentType::Json,
         },
         serde_yaml::Value::Tagged(Box::new(TaggedValue {
             tag: Tag::new("json"),
-            value: mapping([("user", "{{user_id}}")])
+            value: serde_yaml::to_value(
+                json!({"user": "{{user_id}}", "ids": [1, true, null]})
+            ).unwrap(),
         })),
     )]
     #[case::json_nested(
-        RecipeBody::Raw {
-            body: serde_json::to_string_pretty(
-                &json!(r#"{"warning": "NOT an object"}"#)
-            ).unwrap().into(),
-            content_type: Some(ContentType::Json),
+        RecipeBody::Structured {
+            body: json!(r#"{"warning": "NOT an object"}"#).try_into().unwrap(),
+            content_type: ContentType::Json,
         },
         serde_yaml::Value::Tagged(Box::new(TaggedValue {
             tag: Tag::new("json"),
@@ -561,6 +721,43 @@ mod tests {
             ])
         }))
     )]
+    #[case::yaml(
+        RecipeBody::Structured {
+            body: json!({"user": "{{user_id}}", "ids": [1, true, null]})
+                .try_into()
+                .unwrap(),
+            content_type: ContentType::Yaml,
+        },
+        serde_yaml::Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new("yaml"),
+            value: serde_yaml::to_value(
+                json!({"user": "{{user_id}}", "ids": [1, true, null]})
+            ).unwrap(),
+        })),
+    )]
+    #[case::xml(
+        RecipeBody::Structured {
+            body: json!({"user": {"@id": "{{user_id}}"}}).try_into().unwrap(),
+            content_type: ContentType::Xml,
+        },
+        serde_yaml::Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new("xml"),
+            value: mapping([("user", mapping([("@id", "{{user_id}}")]))]),
+        })),
+    )]
+    #[case::form_json(
+        RecipeBody::FormJson(indexmap! {
+            "username".into() => json!("{{username}}").try_into().unwrap(),
+            "filter".into() => json!({"id": "{{user_id}}"}).try_into().unwrap(),
+        }),
+        serde_yaml::Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new("form_json"),
+            value: serde_yaml::to_value(json!({
+                "username": "{{username}}",
+                "filter": {"id": "{{user_id}}"},
+            })).unwrap(),
+        }))
+    )]
     fn test_serde_recipe_body(
         #[case] body: RecipeBody,
         #[case] yaml: impl Into<serde_yaml::Value>,
@@ -597,7 +794,16 @@ mod tests {
             value: "{{user_id}}".into()
         })),
         "unknown variant `raw`, expected one of \
-        `json`, `form_urlencoded`, `form_multipart`",
+        `json`, `yaml`, `xml`, `form_urlencoded`, `form_json`, \
+        `form_multipart`, `typed`",
+    )]
+    // Strings in structured bodies are parsed as templates
+    #[case::yaml_invalid_template(
+        serde_yaml::Value::Tagged(Box::new(TaggedValue{
+            tag: Tag::new("yaml"),
+            value: mapping([("user", "{{user_id")]),
+        })),
+        "invalid key",
     )]
     #[case::form_urlencoded_wrong_type(
         serde_yaml::Value::Tagged(Box::new(TaggedValue{
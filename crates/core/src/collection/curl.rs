@@ -0,0 +1,816 @@
+//! Import recipes from cURL commands, such as those copied from browser dev
+//! tools or API docs.
+//!
+//! - Each `curl` command maps to a recipe. Other commands (e.g. a `jq` that
+//!   the output is piped to) are ignored
+//! - The URL is kept verbatim, with the query string split into parameters
+//! - `-u` and `Authorization` headers are mapped to authentication when the
+//!   scheme is supported
+//! - Files referenced with `@` in `-d` and `-F` map to chains that load the
+//!   file
+//!
+//! Only common options are supported. Anything that doesn't affect the request
+//! itself (e.g. `--silent` or `--output`) is ignored.
+
+use crate::{
+    collection::{
+        har::parse_authorization, Authentication, Chain, ChainId, ChainSource,
+        Collection, Method, Recipe, RecipeBody, RecipeId, RecipeNode,
+        RecipeTree, SelectorMode,
+    },
+    http::content_type::ContentType,
+    template::{Identifier, Template},
+    util::ResultTraced,
+};
+use anyhow::{anyhow, bail, Context};
+use indexmap::IndexMap;
+use reqwest::header;
+use std::{iter::Peekable, str::Chars};
+use tracing::{debug, warn};
+use url::{form_urlencoded, Url};
+
+/// Short options that take a value. All other short options are flags
+const SHORT_WITH_VALUE: &str = "AbcDdEeFHKmoPQrTtUuwXxYyz";
+/// Long options that take a value, but don't affect the request so they're
+/// ignored. This is needed so the value isn't mistaken for the URL.
+const IGNORED_WITH_VALUE: &[&str] = &[
+    "cacert",
+    "capath",
+    "cert",
+    "cert-type",
+    "config",
+    "connect-timeout",
+    "connect-to",
+    "cookie-jar",
+    "dump-header",
+    "interface",
+    "key",
+    "key-type",
+    "limit-rate",
+    "local-port",
+    "max-filesize",
+    "max-redirs",
+    "max-time",
+    "output",
+    "proxy",
+    "proxy-user",
+    "range",
+    "resolve",
+    "retry",
+    "retry-delay",
+    "retry-max-time",
+    "stderr",
+    "trace",
+    "trace-ascii",
+    "upload-file",
+    "write-out",
+];
+/// Headers that are set automatically by the HTTP client, and would be wrong
+/// if the body changes
+const IGNORED_HEADERS: &[header::HeaderName] =
+    &[header::HOST, header::CONTENT_LENGTH];
+
+impl Collection {
+    /// Convert one or more cURL commands into a collection. Each command
+    /// becomes a recipe. Commands that can't be converted are skipped, but
+    /// it's an error if there are no valid commands at all.
+    pub fn from_curl(commands: &str) -> anyhow::Result<Self> {
+        let mut recipes: IndexMap<RecipeId, RecipeNode> = IndexMap::new();
+        let mut chains = IndexMap::new();
+        for args in split_commands(commands)? {
+            let Some(command) = CurlCommand::parse(args)
+                .context("Skipping invalid cURL command")
+                .traced()
+                .ok()
+                .flatten()
+            else {
+                continue;
+            };
+            let id = unique_id(&recipes, command.id());
+            debug!("Generating recipe `{id}`");
+            let recipe = command.into_recipe(id, &mut chains);
+            recipes.insert(recipe.id.clone(), RecipeNode::Recipe(recipe));
+        }
+        if recipes.is_empty() {
+            bail!("No valid cURL commands found");
+        }
+
+        Ok(Collection {
+            profiles: IndexMap::new(),
+            recipes: RecipeTree::new(recipes)?,
+            chains,
+            _ignore: serde::de::IgnoredAny,
+        })
+    }
+}
+
+/// Add a suffix to an ID if it's already taken
+fn unique_id(recipes: &IndexMap<RecipeId, RecipeNode>, id: String) -> RecipeId {
+    let mut unique: RecipeId = id.clone().into();
+    let mut i = 2;
+    while recipes.contains_key(&unique) {
+        unique = format!("{id} ({i})").into();
+        i += 1;
+    }
+    unique
+}
+
+/// The parts of a cURL command that we care about
+#[derive(Debug, Default)]
+struct CurlCommand {
+    url: Option<Url>,
+    method: Option<String>,
+    headers: Vec<(String, String)>,
+    /// Each `-d` (and similar) value. cURL joins these with `&`
+    data: Vec<Data>,
+    form: Vec<(String, FormValue)>,
+    user: Option<String>,
+    bearer: Option<String>,
+    /// `-G`: send data in the query instead of the body
+    get: bool,
+    /// `-I`
+    head: bool,
+}
+
+#[derive(Debug)]
+enum Data {
+    Text(String),
+    /// `@path`
+    File(String),
+}
+
+#[derive(Debug)]
+enum FormValue {
+    Text(String),
+    /// `@path` or `<path`
+    File(String),
+}
+
+impl CurlCommand {
+    /// Parse a command's arguments. Return `None` if it's not a cURL command
+    fn parse(args: Vec<String>) -> anyhow::Result<Option<Self>> {
+        let mut args = args.into_iter().peekable();
+        // Skip a prompt, for commands copied from docs
+        args.next_if(|arg| arg == "$");
+        match args.next() {
+            Some(program)
+                if program == "curl"
+                    || program.ends_with("/curl")
+                    || program.eq_ignore_ascii_case("curl.exe") => {}
+            program => {
+                debug!("Ignoring non-cURL command {program:?}");
+                return Ok(None);
+            }
+        }
+
+        let mut command = Self::default();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                for url in args.by_ref() {
+                    command.set_url(url)?;
+                }
+            } else if let Some(name) = arg.strip_prefix("--") {
+                let mut value = || {
+                    args.next()
+                        .ok_or_else(|| anyhow!("Option `{arg}` needs a value"))
+                };
+                command.apply_option(name, &mut value)?;
+            } else if let Some(flags) =
+                arg.strip_prefix('-').filter(|flags| !flags.is_empty())
+            {
+                // Short flags can be combined (`-sSL`), and the value of the
+                // last one can be attached (`-XPOST`)
+                for (i, flag) in flags.char_indices() {
+                    let name = long_name(flag);
+                    if SHORT_WITH_VALUE.contains(flag) {
+                        let attached = &flags[i + flag.len_utf8()..];
+                        let mut value = || {
+                            if attached.is_empty() {
+                                args.next().ok_or_else(|| {
+                                    anyhow!("Option `-{flag}` needs a value")
+                                })
+                            } else {
+                                Ok(attached.to_owned())
+                            }
+                        };
+                        match name {
+                            Some(name) => {
+                                command.apply_option(name, &mut value)?
+                            }
+                            // Skip the value so it isn't mistaken for the URL
+                            None => {
+                                value()?;
+                            }
+                        }
+                        break;
+                    } else if let Some(name) = name {
+                        command.apply_option(name, &mut || {
+                            unreachable!("flags don't take values")
+                        })?;
+                    }
+                }
+            } else {
+                command.set_url(arg)?;
+            }
+        }
+
+        if command.url.is_none() {
+            bail!("Missing URL");
+        }
+        Ok(Some(command))
+    }
+
+    /// Apply a single option, by its long name. Call `value` to get the
+    /// option's value, if it takes one.
+    fn apply_option(
+        &mut self,
+        name: &str,
+        value: &mut dyn FnMut() -> anyhow::Result<String>,
+    ) -> anyhow::Result<()> {
+        match name {
+            "url" => self.set_url(value()?)?,
+            "request" => self.method = Some(value()?),
+            "header" => {
+                let header = value()?;
+                // `Name;` sends an empty header, `Name:` removes it
+                if let Some((name, value)) = header.split_once(':') {
+                    let value = value.trim();
+                    if !value.is_empty() {
+                        self.headers.push((name.trim().into(), value.into()));
+                    }
+                } else if let Some(name) = header.strip_suffix(';') {
+                    self.headers.push((name.trim().into(), String::new()));
+                }
+            }
+            "data" | "data-ascii" | "data-binary" => {
+                let data = value()?;
+                self.data.push(match data.strip_prefix('@') {
+                    Some(path) => Data::File(path.to_owned()),
+                    None => Data::Text(data),
+                });
+            }
+            "data-raw" => self.data.push(Data::Text(value()?)),
+            "data-urlencode" => {
+                self.data.push(Data::Text(url_encode_data(&value()?)))
+            }
+            "json" => {
+                self.data.push(Data::Text(value()?));
+                for header in [header::CONTENT_TYPE, header::ACCEPT] {
+                    if self.header(header.as_str()).is_none() {
+                        self.headers.push((
+                            header.to_string(),
+                            mime::APPLICATION_JSON.to_string(),
+                        ));
+                    }
+                }
+            }
+            "form" | "form-string" => {
+                let form = value()?;
+                let (field, value) = form
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("Invalid form field `{form}`"))?;
+                // `--form-string` never reads files
+                let value = match value.strip_prefix(['@', '<']) {
+                    Some(path) if name == "form" => {
+                        // Drop attributes like `;type=image/png`
+                        let path = path.split(';').next().unwrap_or_default();
+                        FormValue::File(path.to_owned())
+                    }
+                    _ => FormValue::Text(value.to_owned()),
+                };
+                self.form.push((field.to_owned(), value));
+            }
+            "user" => self.user = Some(value()?),
+            "oauth2-bearer" => self.bearer = Some(value()?),
+            "user-agent" => self
+                .headers
+                .push((header::USER_AGENT.to_string(), value()?)),
+            "referer" => {
+                self.headers.push((header::REFERER.to_string(), value()?))
+            }
+            "cookie" => {
+                let cookie = value()?;
+                // Without a `=`, this is a file to read cookies from
+                if cookie.contains('=') {
+                    self.headers.push((header::COOKIE.to_string(), cookie));
+                } else {
+                    warn!("Ignoring cookie file `{cookie}`");
+                }
+            }
+            "get" => self.get = true,
+            "head" => self.head = true,
+            name if IGNORED_WITH_VALUE.contains(&name) => {
+                value()?;
+            }
+            // Anything else is a flag that doesn't affect the request, or an
+            // option we don't know about. If it's the latter and it takes a
+            // value, the value will probably be mistaken for the URL, which
+            // will give an error.
+            name => debug!("Ignoring option `{name}`"),
+        }
+        Ok(())
+    }
+
+    fn set_url(&mut self, url: String) -> anyhow::Result<()> {
+        if let Some(existing) = &self.url {
+            bail!("Multiple URLs given: `{existing}` and `{url}`");
+        }
+        // cURL defaults to HTTP if there's no scheme
+        let parsed = if url.contains("://") {
+            Url::parse(&url)
+        } else {
+            Url::parse(&format!("http://{url}"))
+        };
+        self.url =
+            Some(parsed.with_context(|| format!("Invalid URL `{url}`"))?);
+        Ok(())
+    }
+
+    /// Get the value of a header, by case-insensitive name
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn method(&self) -> String {
+        if let Some(method) = &self.method {
+            method.to_uppercase()
+        } else if self.head {
+            "HEAD".into()
+        } else if !self.get && (!self.data.is_empty() || !self.form.is_empty())
+        {
+            "POST".into()
+        } else {
+            "GET".into()
+        }
+    }
+
+    /// Generate a recipe ID. The host is included so recipes for different
+    /// hosts with the same path don't collide
+    fn id(&self) -> String {
+        let url = self.url.as_ref().expect("URL is checked during parsing");
+        format!(
+            "{}{}-{}",
+            &url[url::Position::BeforeHost..url::Position::BeforePath],
+            url.path(),
+            self.method()
+        )
+    }
+
+    fn into_recipe(
+        self,
+        id: RecipeId,
+        chains: &mut IndexMap<ChainId, Chain>,
+    ) -> Recipe {
+        let method = self.method();
+        let method: Method = method
+            .parse()
+            .map_err(|_| anyhow!("Unsupported method `{method}`; using GET"))
+            .with_context(|| format!("Error importing request `{id}`"))
+            .traced()
+            .unwrap_or(Method::Get);
+        let url = self.url.expect("URL is checked during parsing");
+
+        let mut query: Vec<(String, Template)> = url
+            .query_pairs()
+            .map(|(name, value)| {
+                (name.into_owned(), Template::raw(value.into_owned()))
+            })
+            .collect();
+
+        let mut authentication = self.user.map(|user| {
+            let (username, password) = match user.split_once(':') {
+                Some((username, password)) => (username, Some(password)),
+                None => (user.as_str(), None),
+            };
+            Authentication::Basic {
+                username: Template::raw(username.to_owned()),
+                password: password
+                    .filter(|password| !password.is_empty())
+                    .map(|password| Template::raw(password.to_owned())),
+            }
+        });
+        if let Some(token) = self.bearer {
+            authentication = Some(Authentication::Bearer(Template::raw(token)));
+        }
+
+        let mut headers: IndexMap<String, Template> = IndexMap::new();
+        for (name, value) in self.headers {
+            let name = name.to_lowercase();
+            if IGNORED_HEADERS.iter().any(|header| header == name.as_str()) {
+                continue;
+            }
+            if name == header::AUTHORIZATION.as_str() {
+                if let Some(auth) = parse_authorization(&value) {
+                    authentication = Some(auth);
+                    continue;
+                }
+            }
+            headers.insert(name, Template::raw(value));
+        }
+
+        let mut chain = |name: &str, path: String| {
+            let chain_id: ChainId =
+                Identifier::escape(&format!("{id}_{name}")).into();
+            chains.insert(
+                chain_id.clone(),
+                Chain {
+                    id: chain_id.clone(),
+                    source: ChainSource::File {
+                        path: Template::raw(path),
+                    },
+                    sensitive: false,
+                    selector: None,
+                    selector_mode: SelectorMode::default(),
+                    content_type: None,
+                    trim: Default::default(),
+                },
+            );
+            Template::from_chain(chain_id)
+        };
+
+        let body = if !self.form.is_empty() {
+            // The client generates the content type, with a new boundary
+            headers.shift_remove(header::CONTENT_TYPE.as_str());
+            Some(RecipeBody::FormMultipart(
+                self.form
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let value = match value {
+                            FormValue::Text(text) => Template::raw(text),
+                            FormValue::File(path) => chain(&name, path),
+                        };
+                        (name, value)
+                    })
+                    .collect(),
+            ))
+        } else if let [Data::File(path)] = self.data.as_slice() {
+            Some(RecipeBody::Raw {
+                body: chain("body", path.clone()),
+                content_type: None,
+            })
+        } else if !self.data.is_empty() {
+            let data = self
+                .data
+                .into_iter()
+                .map(|data| match data {
+                    Data::Text(text) => text,
+                    Data::File(path) => {
+                        warn!(
+                            "Request `{id}` reads body data from `{path}` \
+                            along with other data; file contents are not \
+                            included"
+                        );
+                        format!("@{path}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("&");
+            if self.get {
+                query.extend(form_urlencoded::parse(data.as_bytes()).map(
+                    |(name, value)| {
+                        (name.into_owned(), Template::raw(value.into_owned()))
+                    },
+                ));
+                None
+            } else {
+                Some(build_body(&id, data, &mut headers))
+            }
+        } else {
+            None
+        };
+
+        Recipe {
+            id,
+            name: Some(url.path().to_owned()),
+            method,
+            url: Template::raw(url[..url::Position::AfterPath].to_owned()),
+            body,
+            authentication,
+            query,
+            headers,
+            protobuf: None,
+        }
+    }
+}
+
+/// Convert `-d` data to a body, based on the content type. cURL sends
+/// form-urlencoded unless told otherwise.
+fn build_body(
+    id: &RecipeId,
+    data: String,
+    headers: &mut IndexMap<String, Template>,
+) -> RecipeBody {
+    let content_type = headers
+        .get(header::CONTENT_TYPE.as_str())
+        .map(|content_type| content_type.display().into_owned());
+    match content_type
+        .as_deref()
+        .map(ContentType::from_mime)
+        .transpose()
+    {
+        Ok(None | Some(ContentType::FormUrlencoded))
+            if is_form_urlencoded(&data) =>
+        {
+            headers.shift_remove(header::CONTENT_TYPE.as_str());
+            RecipeBody::FormUrlencoded(
+                form_urlencoded::parse(data.as_bytes())
+                    .map(|(name, value)| {
+                        (name.into_owned(), Template::raw(value.into_owned()))
+                    })
+                    .collect(),
+            )
+        }
+        Ok(Some(ContentType::Json)) => match serde_json::from_str(&data) {
+            Ok(json) => RecipeBody::untemplated_json(json),
+            Err(error) => {
+                warn!(
+                    "Invalid JSON body for request `{id}`; \
+                    importing as raw text: {error}"
+                );
+                RecipeBody::Raw {
+                    body: Template::raw(data),
+                    content_type: Some(ContentType::Json),
+                }
+            }
+        },
+        Ok(None) => {
+            // Keep cURL's default content type
+            headers.insert(
+                header::CONTENT_TYPE.to_string(),
+                Template::raw(
+                    mime::APPLICATION_WWW_FORM_URLENCODED.to_string(),
+                ),
+            );
+            RecipeBody::Raw {
+                body: Template::raw(data),
+                content_type: None,
+            }
+        }
+        Ok(Some(content_type)) => RecipeBody::Raw {
+            body: Template::raw(data),
+            content_type: Some(content_type),
+        },
+        Err(_) => RecipeBody::Raw {
+            body: Template::raw(data),
+            content_type: None,
+        },
+    }
+}
+
+/// Is the data a valid form-urlencoded string? Each field needs a `=`, because
+/// that's the only way to tell form data apart from arbitrary text
+fn is_form_urlencoded(data: &str) -> bool {
+    !data.is_empty()
+        && data.split('&').all(|field| {
+            field.split_once('=').is_some_and(|(name, _)| {
+                !name.is_empty()
+                    && !name.contains(|c: char| c.is_whitespace() || c == '{')
+            })
+        })
+}
+
+/// Apply the encoding rules of `--data-urlencode`. `name=value` encodes just
+/// the value, anything else is encoded entirely.
+fn url_encode_data(data: &str) -> String {
+    let encode = |value: &str| {
+        form_urlencoded::byte_serialize(value.as_bytes()).collect::<String>()
+    };
+    match data.split_once('=') {
+        Some(("", value)) => encode(value),
+        Some((name, value)) => format!("{name}={}", encode(value)),
+        None => encode(data),
+    }
+}
+
+/// Map a short option to its long name. Return `None` for options that don't
+/// affect the request
+fn long_name(flag: char) -> Option<&'static str> {
+    match flag {
+        'A' => Some("user-agent"),
+        'b' => Some("cookie"),
+        'd' => Some("data"),
+        'e' => Some("referer"),
+        'F' => Some("form"),
+        'G' => Some("get"),
+        'H' => Some("header"),
+        'I' => Some("head"),
+        'u' => Some("user"),
+        'X' => Some("request"),
+        _ => None,
+    }
+}
+
+/// Split shell text into commands, each of which is a list of arguments. This
+/// handles the quoting and escaping that shows up in cURL commands copied from
+/// browsers and docs, but it's not a full shell parser: variables,
+/// substitutions and globs are left as-is.
+fn split_commands(text: &str) -> anyhow::Result<Vec<Vec<String>>> {
+    let mut commands: Vec<Vec<String>> = Vec::new();
+    let mut args: Vec<String> = Vec::new();
+    // `None` when we're between arguments. This is distinct from an empty
+    // argument, which can be created with `''`
+    let mut arg: Option<String> = None;
+
+    fn end_arg(args: &mut Vec<String>, arg: &mut Option<String>) {
+        args.extend(arg.take());
+    }
+    fn end_command(
+        commands: &mut Vec<Vec<String>>,
+        args: &mut Vec<String>,
+        arg: &mut Option<String>,
+    ) {
+        end_arg(args, arg);
+        if !args.is_empty() {
+            commands.push(std::mem::take(args));
+        }
+    }
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                // Line continuation
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(c) => arg.get_or_insert_with(String::new).push(c),
+                None => {}
+            },
+            '\'' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => bail!("Unterminated `'`"),
+                    }
+                }
+            }
+            '"' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c @ ('"' | '\\' | '$' | '`')) => arg.push(c),
+                            Some(c) => {
+                                arg.push('\\');
+                                arg.push(c);
+                            }
+                            None => bail!("Unterminated `\"`"),
+                        },
+                        Some(c) => arg.push(c),
+                        None => bail!("Unterminated `\"`"),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                parse_ansi_c(&mut chars, arg.get_or_insert_with(String::new))?;
+            }
+            '#' if arg.is_none() => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            '\n' | ';' | '|' => end_command(&mut commands, &mut args, &mut arg),
+            '&' if chars.peek() == Some(&'&') => {
+                chars.next();
+                end_command(&mut commands, &mut args, &mut arg);
+            }
+            c if c.is_whitespace() => end_arg(&mut args, &mut arg),
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    end_command(&mut commands, &mut args, &mut arg);
+    Ok(commands)
+}
+
+/// Parse the contents of an ANSI-C quoted string (`$'...'`), which browsers
+/// use when a value contains special characters. The opening quote has already
+/// been consumed.
+fn parse_ansi_c(
+    chars: &mut Peekable<Chars<'_>>,
+    arg: &mut String,
+) -> anyhow::Result<()> {
+    /// Parse up to `max` hex digits as a character
+    fn hex(
+        chars: &mut Peekable<Chars<'_>>,
+        max: usize,
+    ) -> anyhow::Result<char> {
+        let mut digits = String::new();
+        while digits.len() < max {
+            match chars.next_if(char::is_ascii_hexdigit) {
+                Some(c) => digits.push(c),
+                None => break,
+            }
+        }
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| anyhow!("Invalid escape sequence `{digits}`"))
+    }
+
+    loop {
+        let c = match chars.next() {
+            Some('\'') => return Ok(()),
+            Some('\\') => match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('a') => '\x07',
+                Some('b') => '\x08',
+                Some('e' | 'E') => '\x1b',
+                Some('f') => '\x0c',
+                Some('v') => '\x0b',
+                Some('x') => hex(chars, 2)?,
+                Some('u') => hex(chars, 4)?,
+                Some('U') => hex(chars, 8)?,
+                Some(c) => c,
+                None => bail!("Unterminated `$'`"),
+            },
+            Some(c) => c,
+            None => bail!("Unterminated `$'`"),
+        };
+        arg.push(c);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_data_dir;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use std::{fs, path::PathBuf};
+
+    const CURL_FILE: &str = "curl.sh";
+    /// Assertion expectation is stored in a separate file, like the other
+    /// importers
+    const CURL_IMPORTED_FILE: &str = "curl_imported.yml";
+
+    /// Catch-all test for cURL import
+    #[rstest]
+    fn test_curl_import(test_data_dir: PathBuf) {
+        let commands =
+            fs::read_to_string(test_data_dir.join(CURL_FILE)).unwrap();
+        let imported = Collection::from_curl(&commands).unwrap();
+        let expected =
+            Collection::load(&test_data_dir.join(CURL_IMPORTED_FILE)).unwrap();
+        assert_eq!(imported, expected);
+    }
+
+    #[rstest]
+    #[case::empty("", vec![])]
+    #[case::simple("curl https://example.com", vec![vec!["curl", "https://example.com"]])]
+    #[case::quotes(
+        r#"curl -H 'a: "b"' -d "x=\"y\" \z""#,
+        vec![vec!["curl", "-H", r#"a: "b""#, "-d", r#"x="y" \z"#]]
+    )]
+    #[case::continuation(
+        "curl \\\n  -X POST \\\r\n  url",
+        vec![vec!["curl", "-X", "POST", "url"]]
+    )]
+    #[case::ansi_c(
+        r#"curl --data-raw $'{\"a\":\n\'é\x41\'}'"#,
+        vec![vec!["curl", "--data-raw", "{\"a\":\n'\u{e9}A'}"]]
+    )]
+    #[case::empty_arg("curl -d '' url", vec![vec!["curl", "-d", "", "url"]])]
+    #[case::multiple(
+        "# Get\ncurl a | jq .\ncurl b && curl c; curl d",
+        vec![
+            vec!["curl", "a"],
+            vec!["jq", "."],
+            vec!["curl", "b"],
+            vec!["curl", "c"],
+            vec!["curl", "d"],
+        ]
+    )]
+    fn test_split_commands(
+        #[case] text: &str,
+        #[case] expected: Vec<Vec<&str>>,
+    ) {
+        assert_eq!(split_commands(text).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::single("curl 'a")]
+    #[case::double("curl \"a")]
+    #[case::ansi_c("curl $'a")]
+    fn test_split_commands_error(#[case] text: &str) {
+        assert!(split_commands(text).is_err());
+    }
+
+    /// Commands that aren't valid are skipped, but it's an error if there
+    /// aren't any valid ones
+    #[rstest]
+    #[case::no_url("curl -X POST")]
+    #[case::multiple_urls("curl a b")]
+    #[case::not_curl("wget https://example.com")]
+    fn test_invalid(#[case] text: &str) {
+        assert!(Collection::from_curl(text).is_err());
+    }
+}
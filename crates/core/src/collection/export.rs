@@ -0,0 +1,83 @@
+//! Shared types for exporting a collection into external formats. Each
+//! format's exporter lives next to its importer.
+
+use crate::{
+    collection::{Chain, ChainSource, Collection, RecipeNode},
+    template::{Template, TemplateKey},
+};
+use indexmap::IndexSet;
+use tracing::debug;
+
+/// A collection converted into an external format, along with a report of
+/// everything that couldn't be represented in that format
+#[derive(Debug)]
+pub struct Export<T> {
+    pub output: T,
+    /// Descriptions of Slumber features that were dropped or approximated
+    /// during the conversion, in the order they were encountered. Each
+    /// message appears at most once.
+    pub unsupported: Vec<String>,
+}
+
+/// Collector for [Export::unsupported]. Messages are deduplicated, because
+/// the same feature (e.g. a chain) is often used in many places.
+#[derive(Debug, Default)]
+pub(super) struct Unsupported(IndexSet<String>);
+
+impl Unsupported {
+    /// Record a feature that couldn't be exported
+    pub fn add(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        debug!(message, "Unsupported feature in export");
+        self.0.insert(message);
+    }
+
+    /// Pair the export output with the collected messages
+    pub fn finish<T>(self, output: T) -> Export<T> {
+        Export {
+            output,
+            unsupported: self.0.into_iter().collect(),
+        }
+    }
+}
+
+impl Collection {
+    /// Report features that none of the export formats support. Called by
+    /// each exporter, so the report is consistent between formats.
+    pub(super) fn report_unsupported(
+        &self,
+        format: &str,
+        unsupported: &mut Unsupported,
+    ) {
+        for (_, node) in self.recipes.iter() {
+            if let RecipeNode::Recipe(recipe) = node {
+                if recipe.protobuf.is_some() {
+                    unsupported.add(format!(
+                        "Protobuf schema of recipe `{}` has no {format} \
+                        equivalent",
+                        recipe.id
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Get the path of the file loaded by a chain, if the template is exactly
+    /// one reference to a file chain. Formats that can load bodies from a file
+    /// use this to preserve the file reference.
+    pub(super) fn file_chain_path<'a>(
+        &'a self,
+        template: &Template,
+    ) -> Option<&'a Template> {
+        let Some(TemplateKey::Chain(id)) = template.single_key() else {
+            return None;
+        };
+        match self.chains.get(id) {
+            Some(Chain {
+                source: ChainSource::File { path },
+                ..
+            }) => Some(path),
+            _ => None,
+        }
+    }
+}
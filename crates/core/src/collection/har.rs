@@ -0,0 +1,381 @@
+//! Import request collections from an HTTP Archive (HAR) file, such as one
+//! exported from browser dev tools.
+//!
+//! - Each unique origin (scheme+host+port) is stored as a field in a single
+//!   profile, and maps to a folder with the same ID as the field
+//! - Entries are mapped to recipes. Entries with the same origin, method, and
+//!   path are deduplicated; only the first is kept
+//! - `Authorization` headers are mapped to authentication when the scheme is
+//!   supported
+
+use crate::{
+    collection::{
+        Authentication, Collection, Folder, Method, Profile, ProfileId, Recipe,
+        RecipeBody, RecipeId, RecipeNode, RecipeTree,
+    },
+    http::{
+        content_type::ContentType,
+        har::{Entry, Har, NameValue, PostData},
+    },
+    template::{Identifier, Template},
+    util::{ResultTraced, NEW_ISSUE_LINK},
+};
+use anyhow::{anyhow, Context};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use indexmap::{IndexMap, IndexSet};
+use reqwest::header;
+use std::{fs::File, path::Path};
+use tracing::{debug, error, info, warn};
+use url::{form_urlencoded, Url};
+
+/// ID of the single generated profile
+const PROFILE_ID: &str = "har";
+/// Profile field name used when the archive only contains one origin
+const HOST_FIELD: &str = "host";
+/// Headers that are set automatically by the HTTP client, so there's no point
+/// in including them in the recipe
+const IGNORED_HEADERS: &[header::HeaderName] = &[
+    header::HOST,
+    header::CONNECTION,
+    header::CONTENT_LENGTH,
+    header::USER_AGENT,
+];
+
+impl Collection {
+    /// Convert a HAR file into the slumber format
+    ///
+    /// This is not async because it's only called by the CLI, where we don't
+    /// care about blocking. It keeps the code simpler.
+    pub fn from_har(har_file: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let har_file = har_file.as_ref();
+        info!(file = ?har_file, "Loading HAR file");
+        warn!(
+            "The HAR importer is approximate. Dynamic values such as tokens \
+            and IDs are copied verbatim, so you'll likely want to replace them \
+            with templates. If you encounter a bug, please open an issue: \
+            {NEW_ISSUE_LINK}"
+        );
+        let file = File::open(har_file)
+            .context(format!("Error opening HAR file {har_file:?}"))?;
+        let har: Har = serde_json::from_reader(file)
+            .context(format!("Error deserializing HAR file {har_file:?}"))?;
+
+        // Parse all the URLs up front so we can group by origin
+        let entries: Vec<(Url, Entry)> = har
+            .log
+            .entries
+            .into_iter()
+            .filter_map(|entry| {
+                let url = Url::parse(&entry.request.url)
+                    .with_context(|| {
+                        format!(
+                            "Skipping request with invalid URL `{}`",
+                            entry.request.url
+                        )
+                    })
+                    .traced()
+                    .ok()?;
+                Some((url, entry))
+            })
+            .collect();
+
+        let origins: IndexSet<String> = entries
+            .iter()
+            .map(|(url, _)| url.origin().ascii_serialization())
+            .collect();
+        let host_fields = build_host_fields(&origins);
+        let profiles = build_profiles(&host_fields);
+        let recipes = build_recipe_tree(entries, &host_fields)?;
+
+        Ok(Collection {
+            profiles,
+            recipes,
+            chains: IndexMap::new(),
+            _ignore: serde::de::IgnoredAny,
+        })
+    }
+}
+
+/// Map each origin to the name of the profile field that will hold it. If
+/// there's only one origin, use the conventional `host` field. Otherwise,
+/// derive a name from the host so the fields are recognizable.
+fn build_host_fields(origins: &IndexSet<String>) -> IndexMap<String, String> {
+    if let [origin] = origins.iter().collect::<Vec<_>>().as_slice() {
+        return IndexMap::from([((*origin).clone(), HOST_FIELD.to_owned())]);
+    }
+
+    let mut fields: IndexMap<String, String> = IndexMap::new();
+    for origin in origins {
+        // Strip the scheme so the field is just the host and port
+        let host = origin
+            .split_once("://")
+            .map_or(origin.as_str(), |(_, host)| host);
+        let base = Identifier::escape(host).to_string();
+        // The same host can appear with multiple schemes (http and https), so
+        // make sure each field is unique
+        let mut field = base.clone();
+        let mut i = 2;
+        while fields.values().any(|existing| existing == &field) {
+            field = format!("{base}_{i}");
+            i += 1;
+        }
+        fields.insert(origin.clone(), field);
+    }
+    fields
+}
+
+/// Build a single profile that holds every origin
+fn build_profiles(
+    host_fields: &IndexMap<String, String>,
+) -> IndexMap<ProfileId, Profile> {
+    if host_fields.is_empty() {
+        return IndexMap::new();
+    }
+    let id: ProfileId = PROFILE_ID.to_owned().into();
+    let data = host_fields
+        .iter()
+        .map(|(origin, field)| (field.clone(), Template::raw(origin.clone())))
+        .collect();
+    IndexMap::from([(
+        id.clone(),
+        Profile {
+            id,
+            name: Some("HAR".into()),
+            default: true,
+            data,
+        },
+    )])
+}
+
+/// Build one folder per origin, with one recipe per unique method+path
+fn build_recipe_tree(
+    entries: Vec<(Url, Entry)>,
+    host_fields: &IndexMap<String, String>,
+) -> anyhow::Result<RecipeTree> {
+    let mut folders: IndexMap<RecipeId, Folder> = IndexMap::new();
+
+    for (url, entry) in entries {
+        let origin = url.origin().ascii_serialization();
+        let host_field = &host_fields[&origin];
+        let Some(recipe) = build_recipe(&url, entry, host_field) else {
+            continue;
+        };
+
+        // Each origin has its own host field, so the field name makes a
+        // unique folder ID. The host alone would merge http and https
+        let folder_id: RecipeId = host_field.clone().into();
+        let folder =
+            folders.entry(folder_id.clone()).or_insert_with(|| Folder {
+                id: folder_id,
+                name: Some(origin),
+                children: IndexMap::new(),
+            });
+
+        if folder.children.contains_key(&recipe.id) {
+            debug!("Skipping duplicate request `{}`", recipe.id);
+        } else {
+            debug!(
+                "Inserting recipe `{}` in folder `{}`",
+                recipe.id, folder.id
+            );
+            folder
+                .children
+                .insert(recipe.id.clone(), RecipeNode::Recipe(recipe));
+        }
+    }
+
+    Ok(RecipeTree::new(
+        folders
+            .into_iter()
+            .map(|(id, folder)| (id, folder.into()))
+            .collect(),
+    )?)
+}
+
+/// Convert a single HAR entry into a recipe. Return `None` if the entry can't
+/// be converted.
+fn build_recipe(url: &Url, entry: Entry, host_field: &str) -> Option<Recipe> {
+    let request = entry.request;
+    let method: Method = request
+        .method
+        .parse()
+        .map_err(|_| anyhow!("Skipping request with unsupported method"))
+        .with_context(|| format!("{} {}", request.method, request.url))
+        .traced()
+        .ok()?;
+    let path = url.path();
+    // The origin's field is included so recipes from different folders don't
+    // collide
+    let id: RecipeId = format!("{host_field}{path}-{method}").into();
+
+    let url_template = format!("{{{{{host_field}}}}}{path}")
+        .parse()
+        .with_context(|| {
+            format!(
+                "Error generating URL for recipe `{id}`; \
+                full URL will be used"
+            )
+        })
+        .traced()
+        .unwrap_or_else(|_| {
+            Template::raw(url[..url::Position::AfterPath].to_owned())
+        });
+
+    let mut authentication = None;
+    let mut headers: IndexMap<String, Template> = IndexMap::new();
+    for NameValue { name, value } in request.headers {
+        let name = name.to_lowercase();
+        // HTTP/2 pseudo-headers (:method, :path, etc.) are redundant
+        if name.starts_with(':')
+            || IGNORED_HEADERS.iter().any(|header| header == name.as_str())
+        {
+            continue;
+        }
+        if name == header::AUTHORIZATION.as_str() {
+            if let Some(auth) = parse_authorization(&value) {
+                authentication = Some(auth);
+                continue;
+            }
+        }
+        headers.insert(name, Template::raw(value));
+    }
+
+    let query = url
+        .query_pairs()
+        .map(|(name, value)| (name.into_owned(), Template::raw(value.into())))
+        .collect();
+
+    let body = request
+        .post_data
+        .map(|post_data| build_body(&id, post_data));
+
+    Some(Recipe {
+        id,
+        name: Some(path.to_owned()),
+        method,
+        url: url_template,
+        body,
+        authentication,
+        query,
+        headers,
+        protobuf: None,
+    })
+}
+
+/// Convert the body of a request, based on its MIME type
+fn build_body(id: &RecipeId, post_data: PostData) -> RecipeBody {
+    let content_type = ContentType::from_mime(&post_data.mime_type).ok();
+    match content_type {
+        // Fall back to raw text if the JSON isn't valid
+        Some(ContentType::Json) => {
+            match serde_json::from_str(&post_data.text) {
+                Ok(json) => RecipeBody::untemplated_json(json),
+                Err(error) => {
+                    error!(
+                        "Invalid JSON body for request `{id}`; \
+                    importing as raw text: {error}"
+                    );
+                    RecipeBody::Raw {
+                        body: Template::raw(post_data.text),
+                        content_type: Some(ContentType::Json),
+                    }
+                }
+            }
+        }
+        Some(ContentType::FormUrlencoded) => RecipeBody::FormUrlencoded(
+            form_urlencoded::parse(post_data.text.as_bytes())
+                .map(|(name, value)| {
+                    (name.into_owned(), Template::raw(value.into_owned()))
+                })
+                .collect(),
+        ),
+        _ => RecipeBody::Raw {
+            body: Template::raw(post_data.text),
+            content_type: None,
+        },
+    }
+}
+
+/// Map an `Authorization` header value to an authentication scheme. Return
+/// `None` for unsupported schemes, so the header can be kept as-is.
+pub(super) fn parse_authorization(value: &str) -> Option<Authentication> {
+    let (scheme, credentials) = value.split_once(' ')?;
+    let credentials = credentials.trim();
+    if scheme.eq_ignore_ascii_case("bearer") {
+        Some(Authentication::Bearer(Template::raw(
+            credentials.to_owned(),
+        )))
+    } else if scheme.eq_ignore_ascii_case("basic") {
+        let decoded = BASE64_STANDARD.decode(credentials).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (username, password) = match decoded.split_once(':') {
+            Some((username, password)) => (username, Some(password)),
+            None => (decoded.as_str(), None),
+        };
+        Some(Authentication::Basic {
+            username: Template::raw(username.to_owned()),
+            password: password
+                .filter(|password| !password.is_empty())
+                .map(|password| Template::raw(password.to_owned())),
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_data_dir;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use std::path::PathBuf;
+
+    const HAR_FILE: &str = "har.json";
+    /// Assertion expectation is stored in a separate file, like the other
+    /// importers
+    const HAR_IMPORTED_FILE: &str = "har_imported.yml";
+
+    /// Catch-all test for HAR import
+    #[rstest]
+    fn test_har_import(test_data_dir: PathBuf) {
+        let imported =
+            Collection::from_har(test_data_dir.join(HAR_FILE)).unwrap();
+        let expected =
+            Collection::load(&test_data_dir.join(HAR_IMPORTED_FILE)).unwrap();
+        assert_eq!(imported, expected);
+    }
+
+    #[rstest]
+    #[case::bearer(
+        "Bearer abc123",
+        Some(Authentication::Bearer(Template::raw("abc123".into())))
+    )]
+    #[case::bearer_lowercase(
+        "bearer abc123",
+        Some(Authentication::Bearer(Template::raw("abc123".into())))
+    )]
+    #[case::basic(
+        "Basic dXNlcjpwYXNz",
+        Some(Authentication::Basic {
+            username: Template::raw("user".into()),
+            password: Some(Template::raw("pass".into())),
+        })
+    )]
+    #[case::basic_no_password(
+        "Basic dXNlcjo=",
+        Some(Authentication::Basic {
+            username: Template::raw("user".into()),
+            password: None,
+        })
+    )]
+    #[case::basic_invalid("Basic !!!", None)]
+    #[case::digest("Digest username=\"user\"", None)]
+    #[case::no_scheme("abc123", None)]
+    fn test_parse_authorization(
+        #[case] value: &str,
+        #[case] expected: Option<Authentication>,
+    ) {
+        assert_eq!(parse_authorization(value), expected);
+    }
+}
@@ -0,0 +1,1085 @@
+//! Import and export request collections in the `.http` file format, used by
+//! the JetBrains HTTP Client and the VS Code REST Client extension.
+//!
+//! Import:
+//! - Requests are separated by `###` lines. The text after `###` becomes the
+//!   recipe name, and `# @name` becomes the recipe ID. If only one is given,
+//!   it's used for both
+//! - `@variable = value` declarations and `{{variable}}` references map to
+//!   profile fields
+//! - Each environment in `http-client.env.json` (and its private counterpart)
+//!   becomes a profile. REST Client's `$shared` environment is included in
+//!   every profile. File variables take precedence over environment values,
+//!   as they do in both clients
+//! - Request variables (e.g. `{{login.response.body.$.token}}`) map to chains
+//!   that load the referenced recipe's response
+//! - `{{$processEnv VAR}}` and `{{$env.VAR}}` map to environment variables.
+//!   Other dynamic variables are left as plain text
+//! - `< ./file` bodies map to chains that load the file
+//! - Response handlers and scripts are not supported
+//!
+//! Export:
+//! - Recipes are written in tree order. Folders are flattened
+//! - Profiles are written as environments in the `http-client.env.json`
+//!   format
+//! - Chains that load a response body or header map to request variables.
+//!   Other chains have no equivalent, and are written as-is
+
+use crate::{
+    collection::{
+        export::{Export, Unsupported},
+        postman::{field_name, json_body},
+        Authentication, Chain, ChainId, ChainRequestSection,
+        ChainRequestTrigger, ChainSource, Collection, Method, Profile,
+        ProfileId, Recipe, RecipeBody, RecipeId, RecipeNode, RecipeTree,
+        SelectorMode,
+    },
+    http::content_type::ContentType,
+    template::{Identifier, Template, TemplateKey},
+    util::{ResultTraced, NEW_ISSUE_LINK},
+};
+use anyhow::Context;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use indexmap::IndexMap;
+use regex::Regex;
+use reqwest::header;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+use tracing::{debug, error, info, warn};
+use url::form_urlencoded;
+
+/// Environment file that's loaded automatically from the `.http` file's
+/// directory, if no environment files are given
+const ENV_FILE: &str = "http-client.env.json";
+/// Private environment file, typically not checked into version control. Its
+/// values are merged over the public file
+const PRIVATE_ENV_FILE: &str = "http-client.private.env.json";
+/// REST Client environment whose variables are available in all others
+const SHARED_ENVIRONMENT: &str = "$shared";
+/// ID of the profile generated when there are no environments
+const PROFILE_ID: &str = "http";
+/// Multipart boundary used for exported form bodies
+const MULTIPART_BOUNDARY: &str = "boundary";
+
+impl Collection {
+    /// Convert a `.http` file into the slumber format. Each environment in the
+    /// given environment files is converted into a profile. If no environment
+    /// files are given, `http-client.env.json` and
+    /// `http-client.private.env.json` are loaded from the directory of the
+    /// `.http` file, if they exist.
+    ///
+    /// This is not async because it's only called by the CLI, where we don't
+    /// care about blocking. It keeps the code simpler.
+    pub fn from_http_file(
+        http_file: impl AsRef<Path>,
+        environment_files: &[impl AsRef<Path>],
+    ) -> anyhow::Result<Self> {
+        let http_file = http_file.as_ref();
+        info!(file = ?http_file, "Loading .http file");
+        warn!(
+            "The .http importer is approximate. Scripts, response handlers \
+            and most dynamic variables are not supported. If you encounter a \
+            bug, please open an issue: {NEW_ISSUE_LINK}"
+        );
+        let text = fs::read_to_string(http_file)
+            .context(format!("Error reading .http file {http_file:?}"))?;
+
+        let environment_files: Vec<PathBuf> = if environment_files.is_empty() {
+            let directory = http_file.parent().unwrap_or(Path::new(""));
+            [ENV_FILE, PRIVATE_ENV_FILE]
+                .into_iter()
+                .map(|name| directory.join(name))
+                .filter(|path| path.exists())
+                .collect()
+        } else {
+            environment_files
+                .iter()
+                .map(|path| path.as_ref().to_owned())
+                .collect()
+        };
+        let environments = load_environments(&environment_files)?;
+
+        let (variables, requests) = parse(&text);
+        let mut builder = Builder::default();
+        let recipes = requests
+            .into_iter()
+            .filter_map(|request| {
+                let recipe = builder.build_recipe(request)?;
+                Some((recipe.id.clone(), RecipeNode::Recipe(recipe)))
+            })
+            .collect();
+        let name = http_file
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        let profiles = builder.build_profiles(name, variables, environments);
+
+        Ok(Collection {
+            profiles,
+            recipes: RecipeTree::new(recipes)?,
+            chains: builder.chains,
+            _ignore: serde::de::IgnoredAny,
+        })
+    }
+
+    /// Convert the collection into a `.http` file. Profiles aren't included,
+    /// because they map to environments; see [Self::to_http_client_env].
+    pub fn to_http_file(&self) -> Export<String> {
+        let mut exporter = HttpExporter {
+            collection: self,
+            unsupported: Unsupported::default(),
+        };
+        self.report_unsupported(".http", &mut exporter.unsupported);
+        let mut output = String::new();
+        for (_, node) in self.recipes.iter() {
+            match node {
+                RecipeNode::Folder(folder) => {
+                    exporter.unsupported.add(format!(
+                        "Folder `{}` was flattened; .http files don't have \
+                        folders",
+                        folder.id
+                    ))
+                }
+                RecipeNode::Recipe(recipe) => {
+                    if !output.is_empty() {
+                        output.push('\n');
+                    }
+                    exporter.write_request(&mut output, recipe);
+                }
+            }
+        }
+        exporter.unsupported.finish(output)
+    }
+
+    /// Convert profiles into the `http-client.env.json` format, with one
+    /// environment per profile
+    pub fn to_http_client_env(&self) -> Export<serde_json::Value> {
+        let mut exporter = HttpExporter {
+            collection: self,
+            unsupported: Unsupported::default(),
+        };
+        let environments = self
+            .profiles
+            .values()
+            .map(|profile| {
+                let variables = profile
+                    .data
+                    .iter()
+                    .map(|(field, value)| {
+                        (field.clone(), exporter.export_template(value).into())
+                    })
+                    .collect::<serde_json::Map<_, _>>();
+                (profile.id.to_string(), variables.into())
+            })
+            .collect::<serde_json::Map<_, _>>();
+        exporter.unsupported.finish(environments.into())
+    }
+}
+
+/// State for converting a collection to a `.http` file
+struct HttpExporter<'a> {
+    collection: &'a Collection,
+    unsupported: Unsupported,
+}
+
+impl HttpExporter<'_> {
+    /// Write a single request, including its `###` separator
+    fn write_request(&mut self, output: &mut String, recipe: &Recipe) {
+        // Writing to a string is infallible
+        writeln!(output, "### {}", recipe.name()).unwrap();
+        // The title is used as the ID on import, so only include the ID if
+        // it's different
+        if recipe.name() != recipe.id.as_str() {
+            writeln!(output, "# @name {}", recipe.id).unwrap();
+        }
+
+        let mut url = self.export_template(&recipe.url);
+        for (i, (param, value)) in recipe.query.iter().enumerate() {
+            let separator = if i == 0 { '?' } else { '&' };
+            write!(url, "{separator}{param}={}", self.export_template(value))
+                .unwrap();
+        }
+        writeln!(output, "{} {url}", recipe.method).unwrap();
+
+        for (name, value) in &recipe.headers {
+            writeln!(output, "{name}: {}", self.export_template(value))
+                .unwrap();
+        }
+        match &recipe.authentication {
+            Some(Authentication::Basic { username, password }) => {
+                // Both clients accept unencoded credentials
+                write!(
+                    output,
+                    "Authorization: Basic {}",
+                    self.export_template(username)
+                )
+                .unwrap();
+                if let Some(password) = password {
+                    write!(output, " {}", self.export_template(password))
+                        .unwrap();
+                }
+                output.push('\n');
+            }
+            Some(Authentication::Bearer(token)) => writeln!(
+                output,
+                "Authorization: Bearer {}",
+                self.export_template(token)
+            )
+            .unwrap(),
+            None => {}
+        }
+
+        if let Some(body) = &recipe.body {
+            let (content_type, body) = self.export_body(body);
+            let has_content_type = recipe.headers.keys().any(|name| {
+                name.eq_ignore_ascii_case(header::CONTENT_TYPE.as_str())
+            });
+            if let (Some(content_type), false) =
+                (content_type, has_content_type)
+            {
+                writeln!(output, "{}: {content_type}", header::CONTENT_TYPE)
+                    .unwrap();
+            }
+            writeln!(output, "\n{body}").unwrap();
+        }
+    }
+
+    /// Convert a body to text, along with the content type that should be
+    /// sent with it
+    fn export_body(&mut self, body: &RecipeBody) -> (Option<String>, String) {
+        match body {
+            RecipeBody::Raw { body, .. } => {
+                let text = match self.file_path(body) {
+                    Some(path) => format!("< {path}"),
+                    None => self.export_template(body),
+                };
+                (None, text)
+            }
+            RecipeBody::Structured { body, content_type } => {
+                let value = body.to_source_with(&mut |template| {
+                    self.export_template(template)
+                });
+                let text = match content_type {
+                    ContentType::Json => {
+                        serde_json::to_string_pretty(&value).unwrap()
+                    }
+                    _ => content_type.value_to_string(&value),
+                };
+                (Some(content_type.to_mime().to_string()), text)
+            }
+            RecipeBody::FormUrlencoded(fields) => {
+                let text = fields
+                    .iter()
+                    .map(|(field, value)| {
+                        format!("{field}={}", self.export_template(value))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("&");
+                (
+                    Some(mime::APPLICATION_WWW_FORM_URLENCODED.to_string()),
+                    text,
+                )
+            }
+            RecipeBody::FormJson(fields) => {
+                let text = fields
+                    .iter()
+                    .map(|(field, value)| {
+                        let value = value.to_source_with(&mut |template| {
+                            self.export_template(template)
+                        });
+                        let value = match value {
+                            serde_json::Value::String(s) => s,
+                            value => value.to_string(),
+                        };
+                        format!("{field}={value}")
+                    })
+                    .collect::<Vec<_>>()
+                    .join("&");
+                (
+                    Some(mime::APPLICATION_WWW_FORM_URLENCODED.to_string()),
+                    text,
+                )
+            }
+            RecipeBody::FormMultipart(fields) => {
+                let mut text = String::new();
+                for (field, value) in fields {
+                    writeln!(text, "--{MULTIPART_BOUNDARY}").unwrap();
+                    write!(
+                        text,
+                        "Content-Disposition: form-data; name=\"{field}\""
+                    )
+                    .unwrap();
+                    match self.file_path(value) {
+                        Some(path) => {
+                            let file_name = Path::new(&path)
+                                .file_name()
+                                .map(|name| name.to_string_lossy())
+                                .unwrap_or_default();
+                            writeln!(text, "; filename=\"{file_name}\"\n")
+                                .unwrap();
+                            writeln!(text, "< {path}").unwrap();
+                        }
+                        None => {
+                            writeln!(text, "\n").unwrap();
+                            writeln!(text, "{}", self.export_template(value))
+                                .unwrap();
+                        }
+                    }
+                }
+                write!(text, "--{MULTIPART_BOUNDARY}--").unwrap();
+                (
+                    Some(format!(
+                        "{}; boundary={MULTIPART_BOUNDARY}",
+                        mime::MULTIPART_FORM_DATA
+                    )),
+                    text,
+                )
+            }
+        }
+    }
+
+    /// If a template is exactly one chain that loads a file, get the path of
+    /// that file. Both clients can load a body from a file with `< path`.
+    fn file_path(&mut self, template: &Template) -> Option<String> {
+        let path = self.collection.file_chain_path(template)?;
+        Some(self.export_template(path))
+    }
+
+    /// Convert a template to `.http` syntax
+    fn export_template(&mut self, template: &Template) -> String {
+        template.display_with(|key| match key {
+            TemplateKey::Field(field) => format!("{{{{{field}}}}}"),
+            TemplateKey::Environment(variable) => {
+                format!("{{{{$processEnv {variable}}}}}")
+            }
+            TemplateKey::Chain(id) => match self.collection.chains.get(id) {
+                Some(Chain {
+                    source:
+                        ChainSource::Request {
+                            recipe, section, ..
+                        },
+                    selector,
+                    ..
+                }) => {
+                    let path = match section {
+                        ChainRequestSection::Body => format!(
+                            "body.{}",
+                            selector
+                                .as_ref()
+                                .map(ToString::to_string)
+                                .unwrap_or_else(|| "*".into())
+                        ),
+                        ChainRequestSection::Header(name) => {
+                            format!("headers.{}", name.display())
+                        }
+                    };
+                    format!("{{{{{recipe}.response.{path}}}}}")
+                }
+                _ => {
+                    self.unsupported.add(format!(
+                        "Chain `{id}` has no .http equivalent; \
+                        exported as-is"
+                    ));
+                    format!("{{{{{key}}}}}")
+                }
+            },
+        })
+    }
+}
+
+/// Environments from one or more environment files, keyed by name
+type Environments = IndexMap<String, IndexMap<String, serde_json::Value>>;
+
+/// Load and merge environment files. Later files override earlier ones, so
+/// private values can be merged over public ones.
+fn load_environments(files: &[PathBuf]) -> anyhow::Result<Environments> {
+    let mut environments = Environments::new();
+    for path in files {
+        info!(file = ?path, "Loading environment file");
+        let file = File::open(path)
+            .context(format!("Error opening environment file {path:?}"))?;
+        let loaded: Environments = serde_json::from_reader(file).context(
+            format!("Error deserializing environment file {path:?}"),
+        )?;
+        for (name, variables) in loaded {
+            environments.entry(name).or_default().extend(variables);
+        }
+    }
+    Ok(environments)
+}
+
+/// A request parsed from a `.http` file, before conversion
+#[derive(Debug)]
+struct HttpRequest<'a> {
+    /// Text after the `###` separator
+    title: Option<&'a str>,
+    /// Value of the `# @name` metadata comment
+    name: Option<&'a str>,
+    method: &'a str,
+    url: String,
+    headers: Vec<(&'a str, &'a str)>,
+    body: Option<String>,
+}
+
+/// Parse a `.http` file into its file variables and requests
+fn parse(text: &str) -> (Vec<(&str, &str)>, Vec<HttpRequest<'_>>) {
+    let mut variables = Vec::new();
+    let mut requests = Vec::new();
+    let mut title = None;
+    let mut block = Vec::new();
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("###") {
+            requests.extend(parse_block(title, &block, &mut variables));
+            title = Some(rest.trim()).filter(|title| !title.is_empty());
+            block.clear();
+        } else {
+            block.push(line);
+        }
+    }
+    requests.extend(parse_block(title, &block, &mut variables));
+    (variables, requests)
+}
+
+/// Parse the lines between two `###` separators. Variable declarations are
+/// global, so they're added to the list regardless of which block they're
+/// in. Return `None` if the block has no request line.
+fn parse_block<'a>(
+    title: Option<&'a str>,
+    lines: &[&'a str],
+    variables: &mut Vec<(&'a str, &'a str)>,
+) -> Option<HttpRequest<'a>> {
+    static VARIABLE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^@([^\s=]+)\s*=\s*(.*)$").unwrap());
+    static NAME: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"^(?:#|//)\s*@name(?:\s*=\s*|\s+)(\S.*)$").unwrap()
+    });
+    static HTTP_VERSION: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"\s+HTTP/[\d.]+$").unwrap());
+
+    let mut lines = lines.iter().copied().peekable();
+    let mut name = None;
+
+    // Comments, metadata and variables can come before the request line
+    let request_line = loop {
+        let line = lines.next()?.trim();
+        if line.is_empty() {
+            continue;
+        } else if let Some(captures) = NAME.captures(line) {
+            name = Some(captures.get(1).unwrap().as_str().trim());
+        } else if is_comment(line) {
+            continue;
+        } else if let Some(captures) = VARIABLE.captures(line) {
+            variables.push((
+                captures.get(1).unwrap().as_str(),
+                captures.get(2).unwrap().as_str().trim(),
+            ));
+        } else {
+            break line;
+        }
+    };
+
+    // The method is optional, and defaults to GET
+    let (method, url) = match request_line.split_once(char::is_whitespace) {
+        Some((method, url))
+            if method.chars().all(|c| c.is_ascii_uppercase()) =>
+        {
+            (method, url.trim())
+        }
+        _ => ("GET", request_line),
+    };
+    let mut url = HTTP_VERSION.replace(url, "").into_owned();
+    // Query parameters can continue on indented lines
+    while let Some(line) = lines.next_if(|line| {
+        let trimmed = line.trim_start();
+        trimmed.len() < line.len()
+            && (trimmed.starts_with('?') || trimmed.starts_with('&'))
+    }) {
+        url.push_str(line.trim());
+    }
+
+    let mut headers = Vec::new();
+    for line in lines.by_ref() {
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        } else if is_comment(line) {
+            continue;
+        }
+        match line.split_once(':') {
+            Some((name, value)) => headers.push((name.trim(), value.trim())),
+            None => error!("Invalid header line `{line}`; skipping"),
+        }
+    }
+
+    let mut body_lines = Vec::new();
+    for line in lines {
+        // Response handlers and redirects come after the body
+        if line.starts_with("> ")
+            || line.starts_with(">>")
+            || line.starts_with("<>")
+        {
+            warn!("Response handlers are not supported; skipping `{line}`");
+            break;
+        }
+        body_lines.push(line);
+    }
+    while body_lines.last().is_some_and(|line| line.trim().is_empty()) {
+        body_lines.pop();
+    }
+    let body = (!body_lines.is_empty()).then(|| body_lines.join("\n"));
+
+    Some(HttpRequest {
+        title,
+        name,
+        method,
+        url,
+        headers,
+        body,
+    })
+}
+
+fn is_comment(line: &str) -> bool {
+    line.starts_with('#') || line.starts_with("//")
+}
+
+/// Converts parsed requests into recipes, accumulating the chains needed
+/// along the way
+#[derive(Default)]
+struct Builder {
+    /// Every recipe ID in the collection, so we can generate unique IDs
+    ids: HashSet<RecipeId>,
+    chains: IndexMap<ChainId, Chain>,
+    /// Chain generated for each request variable, so repeated references
+    /// share a chain
+    request_variables: HashMap<String, ChainId>,
+}
+
+impl Builder {
+    /// Build profiles from file variables and environments. File variables
+    /// are included in every profile, and override environment values.
+    fn build_profiles(
+        &mut self,
+        name: Option<String>,
+        variables: Vec<(&str, &str)>,
+        mut environments: Environments,
+    ) -> IndexMap<ProfileId, Profile> {
+        let file_data: Vec<(String, Template)> = variables
+            .into_iter()
+            .map(|(name, value)| (field_name(name), self.template(value)))
+            .collect();
+
+        if environments.is_empty() {
+            if file_data.is_empty() {
+                return IndexMap::new();
+            }
+            let id: ProfileId = PROFILE_ID.to_owned().into();
+            return IndexMap::from([(
+                id.clone(),
+                Profile {
+                    id,
+                    name,
+                    default: true,
+                    data: file_data.into_iter().collect(),
+                },
+            )]);
+        }
+
+        let shared = environments
+            .shift_remove(SHARED_ENVIRONMENT)
+            .unwrap_or_default();
+        let default = environments.len() == 1;
+        environments
+            .into_iter()
+            .map(|(name, variables)| {
+                debug!("Generating profile for environment `{name}`");
+                let id: ProfileId = name.clone().into();
+                let data = shared
+                    .iter()
+                    .chain(&variables)
+                    .filter_map(|(variable, value)| {
+                        Some((field_name(variable), self.value(value)?))
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .chain(file_data.iter().cloned())
+                    .collect();
+                (
+                    id.clone(),
+                    Profile {
+                        id,
+                        name: Some(name),
+                        default,
+                        data,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Convert an environment value to a template. Objects and arrays are
+    /// client configuration (e.g. SSL settings), not variables
+    fn value(&mut self, value: &serde_json::Value) -> Option<Template> {
+        match value {
+            serde_json::Value::String(s) => Some(self.template(s)),
+            serde_json::Value::Null => Some(Template::default()),
+            serde_json::Value::Bool(_) | serde_json::Value::Number(_) => {
+                Some(Template::raw(value.to_string()))
+            }
+            serde_json::Value::Array(_) | serde_json::Value::Object(_) => None,
+        }
+    }
+
+    fn build_recipe(&mut self, request: HttpRequest) -> Option<Recipe> {
+        let (base, query) = match request.url.split_once('?') {
+            Some((base, query)) => (base, Some(query)),
+            None => (request.url.as_str(), None),
+        };
+        let name = request
+            .title
+            .or(request.name)
+            .map(String::from)
+            .unwrap_or_else(|| format!("{} {base}", request.method));
+        let id = self.unique_id(request.name.unwrap_or(&name));
+
+        let method: Method = request
+            .method
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Unsupported method"))
+            .with_context(|| {
+                format!(
+                    "Skipping request `{id}` with method `{}`",
+                    request.method
+                )
+            })
+            .traced()
+            .ok()?;
+
+        let url = self.template(base);
+        let query = query
+            .map(|query| {
+                form_urlencoded::parse(query.as_bytes())
+                    .map(|(param, value)| {
+                        (param.into_owned(), self.template(&value))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut authentication = None;
+        let mut headers: IndexMap<String, Template> = IndexMap::new();
+        for (name, value) in request.headers {
+            let name = name.to_lowercase();
+            if name == header::AUTHORIZATION.as_str() {
+                if let Some(auth) = self.authentication(value) {
+                    authentication = Some(auth);
+                    continue;
+                }
+            }
+            let value = self.template(value);
+            headers.insert(name, value);
+        }
+
+        let body = request
+            .body
+            .map(|body| self.build_body(&id, body, &mut headers));
+
+        Some(Recipe {
+            id,
+            name: Some(name),
+            method,
+            url,
+            body,
+            authentication,
+            query,
+            headers,
+            protobuf: None,
+        })
+    }
+
+    /// Recipe IDs have to be unique, but request names don't. Add a suffix to
+    /// duplicate names.
+    fn unique_id(&mut self, name: &str) -> RecipeId {
+        let mut id: RecipeId = name.to_owned().into();
+        let mut i = 2;
+        while self.ids.contains(&id) {
+            id = format!("{name} ({i})").into();
+            i += 1;
+        }
+        self.ids.insert(id.clone());
+        id
+    }
+
+    /// Map an `Authorization` header value to an authentication scheme.
+    /// Basic credentials can be given unencoded as `user password` or
+    /// `user:password`, which is common because it allows variables.
+    fn authentication(&mut self, value: &str) -> Option<Authentication> {
+        let (scheme, credentials) = value.split_once(' ')?;
+        let credentials = credentials.trim();
+        if scheme.eq_ignore_ascii_case("bearer") {
+            Some(Authentication::Bearer(self.template(credentials)))
+        } else if scheme.eq_ignore_ascii_case("basic") {
+            let decoded;
+            let (username, password) = match credentials
+                .split_once(' ')
+                .or_else(|| credentials.split_once(':'))
+            {
+                Some((username, password)) => (username, Some(password)),
+                None => {
+                    decoded = String::from_utf8(
+                        BASE64_STANDARD.decode(credentials).ok()?,
+                    )
+                    .ok()?;
+                    match decoded.split_once(':') {
+                        Some((username, password)) => {
+                            (username, Some(password))
+                        }
+                        None => (decoded.as_str(), None),
+                    }
+                }
+            };
+            Some(Authentication::Basic {
+                username: self.template(username),
+                password: password
+                    .map(str::trim)
+                    .filter(|password| !password.is_empty())
+                    .map(|password| self.template(password)),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn build_body(
+        &mut self,
+        id: &RecipeId,
+        body: String,
+        headers: &mut IndexMap<String, Template>,
+    ) -> RecipeBody {
+        // Load the body from a file. `<@` also substitutes variables in the
+        // file, which we can't do
+        if let Some(path) = body
+            .strip_prefix("< ")
+            .or_else(|| body.strip_prefix("<@ "))
+            .filter(|path| !path.contains('\n'))
+        {
+            return RecipeBody::Raw {
+                body: self.file_chain(id, path.trim()),
+                content_type: None,
+            };
+        }
+
+        let content_type = headers
+            .get(header::CONTENT_TYPE.as_str())
+            .map(|content_type| content_type.display().into_owned());
+        match content_type
+            .as_deref()
+            .map(ContentType::from_mime)
+            .transpose()
+        {
+            Ok(Some(ContentType::Json)) => {
+                json_body(id, body, &mut |text| self.template(text))
+            }
+            Ok(Some(ContentType::FormUrlencoded)) => {
+                headers.shift_remove(header::CONTENT_TYPE.as_str());
+                // Fields can be split across lines
+                let body = body.lines().map(str::trim).collect::<String>();
+                RecipeBody::FormUrlencoded(
+                    form_urlencoded::parse(body.as_bytes())
+                        .map(|(field, value)| {
+                            (field.into_owned(), self.template(&value))
+                        })
+                        .collect(),
+                )
+            }
+            // The content type is already in the headers
+            _ => RecipeBody::Raw {
+                body: self.template(&body),
+                content_type: None,
+            },
+        }
+    }
+
+    /// Create a chain that loads a file, and return a template that renders it
+    fn file_chain(&mut self, id: &RecipeId, path: &str) -> Template {
+        let chain_id = self.unique_chain_id(&format!("{id}_body"));
+        let path = self.template(path);
+        self.chains.insert(
+            chain_id.clone(),
+            Chain {
+                id: chain_id.clone(),
+                source: ChainSource::File { path },
+                sensitive: false,
+                selector: None,
+                selector_mode: SelectorMode::default(),
+                content_type: None,
+                trim: Default::default(),
+            },
+        );
+        Template::from_chain(chain_id)
+    }
+
+    /// Create a chain for a request variable such as
+    /// `login.response.body.$.token`, and return a template that renders it.
+    /// Return `None` if the reference isn't supported.
+    fn request_variable_chain(
+        &mut self,
+        recipe: &str,
+        path: &str,
+    ) -> Option<Template> {
+        let reference = format!("{recipe}.response.{path}");
+        if let Some(chain_id) = self.request_variables.get(&reference) {
+            return Some(Template::from_chain(chain_id.clone()));
+        }
+
+        let (section, selector, suffix) = if path == "body" || path == "body.*"
+        {
+            (ChainRequestSection::Body, None, "body")
+        } else if let Some(selector) = path.strip_prefix("body.") {
+            let selector = selector
+                .parse()
+                .with_context(|| {
+                    format!(
+                        "Invalid selector in request variable `{reference}`"
+                    )
+                })
+                .traced()
+                .ok()?;
+            // Name the chain after the last word in the selector
+            let suffix = selector_suffix(path).unwrap_or("body");
+            (ChainRequestSection::Body, Some(selector), suffix)
+        } else if let Some(header) = path.strip_prefix("headers.") {
+            (
+                ChainRequestSection::Header(Template::raw(header.to_owned())),
+                None,
+                header,
+            )
+        } else {
+            return None;
+        };
+
+        let chain_id = self.unique_chain_id(&format!("{recipe}_{suffix}"));
+        self.chains.insert(
+            chain_id.clone(),
+            Chain {
+                id: chain_id.clone(),
+                source: ChainSource::Request {
+                    recipe: recipe.to_owned().into(),
+                    // Both clients send the referenced request if it hasn't
+                    // been sent yet
+                    trigger: ChainRequestTrigger::NoHistory,
+                    section,
+                    history: Default::default(),
+                },
+                sensitive: false,
+                selector,
+                selector_mode: SelectorMode::default(),
+                content_type: None,
+                trim: Default::default(),
+            },
+        );
+        self.request_variables.insert(reference, chain_id.clone());
+        Some(Template::from_chain(chain_id))
+    }
+
+    fn unique_chain_id(&self, name: &str) -> ChainId {
+        let mut id: ChainId = Identifier::escape(name).into();
+        let mut i = 2;
+        while self.chains.contains_key(&id) {
+            id = Identifier::escape(&format!("{name}_{i}")).into();
+            i += 1;
+        }
+        id
+    }
+
+    /// Convert a string into a template. `{{var}}` becomes a reference to the
+    /// profile field of the same name, and request variables become chains.
+    /// Unsupported dynamic variables, like `{{$guid}}`, are raw text.
+    fn template(&mut self, text: &str) -> Template {
+        static VARIABLE: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"\{\{([^{}]+)\}\}").unwrap());
+
+        let mut last = 0;
+        let mut chunks = Vec::new();
+        for captures in VARIABLE.captures_iter(text) {
+            let Some(key) = self.variable(captures[1].trim()) else {
+                continue;
+            };
+            let matched = captures.get(0).unwrap();
+            chunks.push(Template::raw(text[last..matched.start()].to_owned()));
+            chunks.push(key);
+            last = matched.end();
+        }
+        chunks.push(Template::raw(text[last..].to_owned()));
+        chunks.into_iter().collect()
+    }
+
+    /// Convert the contents of a `{{ }}` variable reference to a template, or
+    /// `None` if it has no equivalent
+    fn variable(&mut self, name: &str) -> Option<Template> {
+        if let Some(variable) = name
+            .strip_prefix("$processEnv ")
+            .or_else(|| name.strip_prefix("$env."))
+            .map(str::trim)
+            .filter(|variable| !variable.is_empty())
+        {
+            Some(Template::from_environment(Identifier::escape(variable)))
+        } else if name.is_empty() || name.starts_with('$') {
+            None
+        } else if let Some((recipe, path)) = name.split_once(".response.") {
+            self.request_variable_chain(recipe, path)
+        } else if name.contains(".request.") {
+            // Chains can't load from the request
+            None
+        } else {
+            Some(Template::from_field(Identifier::escape(name)))
+        }
+    }
+}
+
+/// Get the last identifier-like word in a selector, to name its chain
+fn selector_suffix(selector: &str) -> Option<&str> {
+    static WORD: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"[a-zA-Z0-9_-]+").unwrap());
+    WORD.find_iter(selector).last().map(|word| word.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_data_dir;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use std::path::PathBuf;
+
+    const HTTP_FILE: &str = "http_file.http";
+    /// Assertion expectation is stored in a separate file, like the other
+    /// importers
+    const HTTP_IMPORTED_FILE: &str = "http_file_imported.yml";
+    /// Expected output of exporting the imported collection
+    const HTTP_EXPORTED_FILE: &str = "http_file_exported.http";
+    const HTTP_EXPORTED_ENV_FILE: &str = "http_file_exported.env.json";
+
+    /// Catch-all test for .http import. Environment files are loaded from the
+    /// same directory
+    #[rstest]
+    fn test_http_file_import(test_data_dir: PathBuf) {
+        let imported = Collection::from_http_file(
+            test_data_dir.join(HTTP_FILE),
+            &[] as &[PathBuf],
+        )
+        .unwrap();
+        let expected =
+            Collection::load(&test_data_dir.join(HTTP_IMPORTED_FILE)).unwrap();
+        assert_eq!(imported, expected);
+    }
+
+    /// Catch-all test for .http export
+    #[rstest]
+    fn test_http_file_export(test_data_dir: PathBuf) {
+        let collection =
+            Collection::load(&test_data_dir.join(HTTP_IMPORTED_FILE)).unwrap();
+        let expected =
+            fs::read_to_string(test_data_dir.join(HTTP_EXPORTED_FILE)).unwrap();
+        let exported = collection.to_http_file();
+        assert_eq!(exported.output, expected);
+        assert_eq!(exported.unsupported, Vec::<String>::new());
+
+        let expected_env: serde_json::Value = serde_json::from_reader(
+            File::open(test_data_dir.join(HTTP_EXPORTED_ENV_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(collection.to_http_client_env().output, expected_env);
+    }
+
+    #[rstest]
+    #[case::raw("https://example.com", "https://example.com")]
+    #[case::variable("{{host}}/users", "{{host}}/users")]
+    #[case::invalid_name("{{base url}}/{{ id }}", "{{base_url}}/{{id}}")]
+    #[case::process_env("{{$processEnv HOME}}", "{{env.HOME}}")]
+    #[case::env("{{$env.HOME}}", "{{env.HOME}}")]
+    #[case::dynamic("{{$guid}}", "{_{$guid}}")]
+    #[case::request_variable(
+        "Bearer {{login.response.body.$.token}}",
+        "Bearer {{chains.login_token}}"
+    )]
+    #[case::request_header(
+        "{{login.response.headers.X-Token}}",
+        "{{chains.login_X-Token}}"
+    )]
+    #[case::request_unsupported(
+        "{{login.request.body.$.id}}",
+        "{_{login.request.body.$.id}}"
+    )]
+    fn test_template(#[case] text: &str, #[case] expected: &str) {
+        let mut builder = Builder::default();
+        assert_eq!(builder.template(text).display(), expected);
+    }
+
+    /// Repeated request variables share a chain
+    #[test]
+    fn test_request_variable_dedupe() {
+        let mut builder = Builder::default();
+        let template = builder.template(
+            "{{login.response.body.$.token}} \
+            {{login.response.body.$.token}} {{user.response.body.$.token}}",
+        );
+        assert_eq!(
+            template.display(),
+            "{{chains.login_token}} {{chains.login_token}} \
+            {{chains.user_token}}"
+        );
+        assert_eq!(builder.chains.len(), 2);
+    }
+
+    #[rstest]
+    #[case::no_method("https://example.com", "GET", "https://example.com")]
+    #[case::version(
+        "POST https://example.com HTTP/1.1",
+        "POST",
+        "https://example.com"
+    )]
+    #[case::multiline_query(
+        "GET https://example.com\n    ?a=1\n    &b=2",
+        "GET",
+        "https://example.com?a=1&b=2"
+    )]
+    fn test_parse_request_line(
+        #[case] text: &str,
+        #[case] expected_method: &str,
+        #[case] expected_url: &str,
+    ) {
+        let (_, requests) = parse(text);
+        let [request] = requests.as_slice() else {
+            panic!("Expected one request, got {requests:?}")
+        };
+        assert_eq!(request.method, expected_method);
+        assert_eq!(request.url, expected_url);
+    }
+
+    /// Bodies end at a response handler, and trailing blank lines are removed
+    #[test]
+    fn test_parse_body() {
+        let (_, requests) = parse(
+            "POST https://example.com\n\
+            content-type: text/plain\n\
+            \n\
+            line 1\n\
+            \n\
+            line 2\n\
+            \n\
+            > {% client.global.set(\"a\", 1) %}\n\
+            \n\
+            ### Next\n\
+            GET https://example.com\n\n\n",
+        );
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].headers, [("content-type", "text/plain")]);
+        assert_eq!(requests[0].body.as_deref(), Some("line 1\n\nline 2"));
+        assert_eq!(requests[1].title, Some("Next"));
+        assert_eq!(requests[1].body, None);
+    }
+}
@@ -357,6 +357,7 @@ impl From<Request> for RecipeNode {
                 .collect(),
             headers,
             authentication,
+            protobuf: None,
         })
     }
 }
@@ -366,9 +367,15 @@ impl TryFrom<Body> for RecipeBody {
 
     fn try_from(body: Body) -> anyhow::Result<Self> {
         let body = if body.mime_type == mime::APPLICATION_JSON {
-            RecipeBody::Raw {
-                body: Template::raw(body.try_text()?),
-                content_type: Some(ContentType::Json),
+            let text = body.try_text()?;
+            // Insomnia doesn't require JSON bodies to be valid, so fall back
+            // to raw text if they aren't
+            match serde_json::from_str(&text) {
+                Ok(json) => RecipeBody::untemplated_json(json),
+                Err(_) => RecipeBody::Raw {
+                    body: Template::raw(text),
+                    content_type: Some(ContentType::Json),
+                },
             }
         } else if body.mime_type == mime::APPLICATION_WWW_FORM_URLENCODED {
             RecipeBody::FormUrlencoded(
@@ -6,7 +6,7 @@ use crate::{
         recipe_tree::{RecipeNode, RecipeTree},
     },
     http::{content_type::ContentType, query::Query},
-    template::{Identifier, Template},
+    template::{Identifier, Template, TemplateParseError},
     util::{parse_yaml, ResultTraced},
 };
 use anyhow::{anyhow, Context};
@@ -183,6 +183,7 @@ impl crate::test_util::Factory for Recipe {
             authentication: None,
             query: Vec::new(),
             headers: IndexMap::new(),
+            protobuf: None,
         }
     }
 }
@@ -214,12 +215,22 @@ pub struct Recipe {
     /// wrong which is helpful.
     pub method: Method,
     pub url: Template,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub body: Option<RecipeBody>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub authentication: Option<Authentication>,
-    #[serde(default, with = "cereal::serde_query_parameters")]
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        with = "cereal::serde_query_parameters"
+    )]
     pub query: Vec<(String, Template)>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
     pub headers: IndexMap<String, Template>,
+    /// Schema for decoding protobuf response bodies. Only needed if the
+    /// response is protobuf, since it can't be decoded without a schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protobuf: Option<ProtobufSchema>,
 }
 
 #[derive(
@@ -251,15 +262,40 @@ impl crate::test_util::Factory for RecipeId {
     }
 }
 
+/// Where to find the message type for a protobuf response body
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[serde(deny_unknown_fields)]
+pub struct ProtobufSchema {
+    /// Path to a `.proto` file, or a compiled descriptor set (the output of
+    /// `protoc --descriptor_set_out`). Any file without a `.proto` extension
+    /// is treated as a descriptor set. Relative paths are resolved from the
+    /// current directory, same as file chains.
+    pub file: PathBuf,
+    /// Fully qualified name of the response message, e.g. `users.v1.User`
+    pub message: String,
+    /// Directories to search for imports when compiling a `.proto` file. The
+    /// file's own directory is always included.
+    #[serde(default)]
+    pub include: Vec<PathBuf>,
+}
+
 /// HTTP method. This is duplicated from reqwest's Method so we can enforce
 /// the method is valid during deserialization. This is also generally more
 /// ergonomic at the cost of some flexibility.
 ///
 /// The FromStr implementation will be case-insensitive
 #[derive(
-    Copy, Clone, Debug, Display, EnumIter, FromStr, Serialize, Deserialize,
+    Copy,
+    Clone,
+    Debug,
+    Display,
+    EnumIter,
+    FromStr,
+    PartialEq,
+    Serialize,
+    Deserialize,
 )]
-#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
 #[serde(into = "String", try_from = "String")]
 pub enum Method {
     #[display("CONNECT")]
@@ -298,6 +334,7 @@ impl crate::test_util::Factory for Chain {
                 recipe: RecipeId::factory(()),
                 trigger: Default::default(),
                 section: Default::default(),
+                history: Default::default(),
             },
             sensitive: false,
             selector: None,
@@ -335,13 +372,26 @@ pub enum RecipeBody {
     /// Plain string/bytes body
     Raw {
         body: Template,
-        /// For structured body types such as `!json`, we'll stringify during
-        /// deserialization then just store the content type. This makes
-        /// internal logic much simpler because we can just work with templates
+        /// Content type of the raw text, if known. This is set for bodies
+        /// that were imported from other formats or overridden in the TUI, but
+        /// never for bodies defined directly in YAML
         content_type: Option<ContentType>,
     },
+    /// Structured body (`!json`, `!yaml` or `!xml`). Each string is rendered
+    /// as a template, then the whole value is serialized into the content
+    /// type. This means rendered values are properly escaped for the format.
+    /// A string that is exactly one template key is rendered as its typed
+    /// JSON value; see [JsonTemplate].
+    Structured {
+        body: JsonTemplate,
+        content_type: ContentType,
+    },
     /// `application/x-www-form-urlencoded` fields. Values must be strings
     FormUrlencoded(IndexMap<String, Template>),
+    /// `application/x-www-form-urlencoded` fields with structured values.
+    /// String values are sent as-is; anything else is rendered then encoded
+    /// as JSON
+    FormJson(IndexMap<String, JsonTemplate>),
     /// `multipart/form-data` fields. Values can be binary
     FormMultipart(IndexMap<String, Template>),
 }
@@ -350,9 +400,9 @@ impl RecipeBody {
     /// Build a JSON body *without* parsing the internal strings as templates.
     /// Useful for importing from external formats.
     pub fn untemplated_json(value: serde_json::Value) -> Self {
-        Self::Raw {
-            body: Template::raw(format!("{value:#}")),
-            content_type: Some(ContentType::Json),
+        Self::Structured {
+            body: JsonTemplate::raw(value),
+            content_type: ContentType::Json,
         }
     }
 }
@@ -367,6 +417,104 @@ impl From<&str> for RecipeBody {
     }
 }
 
+/// A value in the JSON data model, where each string is a template. Used for
+/// structured bodies that are serialized after rendering. Object keys are
+/// *not* templates.
+///
+/// If a string is exactly one template key (e.g. `"{{count}}"`), the rendered
+/// value is parsed as JSON so numbers, booleans, arrays and objects can be
+/// injected with their type intact. If the rendered value isn't valid JSON,
+/// it's used as a string. Any other template always renders to a string.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[serde(untagged, try_from = "serde_json::Value")]
+pub enum JsonTemplate {
+    Null,
+    Bool(bool),
+    Number(serde_json::Number),
+    String(Template),
+    Array(Vec<JsonTemplate>),
+    Object(IndexMap<String, JsonTemplate>),
+}
+
+impl JsonTemplate {
+    /// Convert JSON *without* parsing the strings as templates. Useful for
+    /// importing from external formats.
+    pub fn raw(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Self::Null,
+            serde_json::Value::Bool(b) => Self::Bool(b),
+            serde_json::Value::Number(number) => Self::Number(number),
+            serde_json::Value::String(s) => Self::String(Template::raw(s)),
+            serde_json::Value::Array(values) => {
+                Self::Array(values.into_iter().map(Self::raw).collect())
+            }
+            serde_json::Value::Object(map) => Self::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, Self::raw(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Convert to JSON, with each template replaced by its source string. This
+    /// is what the user wrote, and is used to display the value without
+    /// rendering it.
+    pub fn to_source(&self) -> serde_json::Value {
+        self.to_source_with(&mut |template| template.display().into_owned())
+    }
+
+    /// Convert to JSON, with each template stringified by the given function.
+    /// Useful for exporting to formats with their own variable syntax.
+    pub fn to_source_with(
+        &self,
+        display: &mut impl FnMut(&Template) -> String,
+    ) -> serde_json::Value {
+        match self {
+            Self::Null => serde_json::Value::Null,
+            Self::Bool(b) => (*b).into(),
+            Self::Number(number) => number.clone().into(),
+            Self::String(template) => display(template).into(),
+            Self::Array(values) => values
+                .iter()
+                .map(|value| value.to_source_with(display))
+                .collect(),
+            Self::Object(map) => map
+                .iter()
+                .map(|(key, value)| {
+                    (key.clone(), value.to_source_with(display))
+                })
+                .collect::<serde_json::Map<_, _>>()
+                .into(),
+        }
+    }
+}
+
+impl TryFrom<serde_json::Value> for JsonTemplate {
+    type Error = TemplateParseError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        let template = match value {
+            serde_json::Value::Null => Self::Null,
+            serde_json::Value::Bool(b) => Self::Bool(b),
+            serde_json::Value::Number(number) => Self::Number(number),
+            serde_json::Value::String(s) => Self::String(s.parse()?),
+            serde_json::Value::Array(values) => Self::Array(
+                values
+                    .into_iter()
+                    .map(Self::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            serde_json::Value::Object(map) => Self::Object(
+                map.into_iter()
+                    .map(|(key, value)| Ok((key, value.try_into()?)))
+                    .collect::<Result<_, TemplateParseError>>()?,
+            ),
+        };
+        Ok(template)
+    }
+}
+
 /// A chain is a means to data from one response in another request. The chain
 /// is the middleman: it defines where and how to pull the value, then recipes
 /// can use it in a template via `{{chains.<chain_id>}}`.
@@ -383,6 +531,7 @@ pub struct Chain {
     /// Selector to extract a value from the response. This uses JSONPath
     /// regardless of the content type. Non-JSON values will be converted to
     /// JSON, then converted back.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub selector: Option<Query>,
     /// Control selector behavior relative to number of query results
     #[serde(default)]
@@ -392,6 +541,7 @@ pub struct Chain {
     /// correctly. This is needed if the chain source is not an HTTP
     /// response (e.g. a file) **or** if the response's `Content-Type` header
     /// is incorrect.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub content_type: Option<ContentType>,
     #[serde(default)]
     pub trim: ChainOutputTrim,
@@ -450,6 +600,9 @@ pub enum ChainSource {
         trigger: ChainRequestTrigger,
         #[serde(default)]
         section: ChainRequestSection,
+        /// Which response from history should be used?
+        #[serde(default)]
+        history: ChainRequestHistory,
     },
     /// Prompt the user to select a value from a list
     Select {
@@ -514,6 +667,21 @@ pub enum ChainRequestTrigger {
     Always,
 }
 
+/// Define which response from history a chained request should load. The
+/// most recent match for the current profile is always used.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum ChainRequestHistory {
+    /// Use the most recent response
+    #[default]
+    Latest,
+    /// Use the most recent pinned response
+    Pinned,
+    /// Use the most recent response with this label
+    Label(String),
+}
+
 /// Control how a JSONPath selector returns 0 vs 1 vs 2+ results
 #[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
 #[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
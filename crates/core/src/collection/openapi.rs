@@ -1,22 +1,39 @@
-//! Import request collections from an OpenAPI v3.0.X specification.
+//! Import request collections from an OpenAPI v3.0.X specification. OpenAPI
+//! v3.1 and Swagger 2.0 specs are converted to v3.0 before being imported.
 //!
 //! - Servers are mapped to profiles
 //!     - URL of the server is stored in the `host` field
 //! - Operations (i.e. path-method pairs) are mapped to recipes
 //! - Tags are mapped to folders
 //!     - Since tags are m2m but folders are o2m, we only take the first tag
-//! - References are resolved within the same file. We don't support resolving
-//!   from other files.
+//! - References are resolved within the same file. References to other local
+//!   files are inlined before importing. Remote references aren't supported.
+//! - Request bodies without an explicit example get one generated from their
+//!   schema
+//! - Required parameters with a known value (from an example, default or
+//!   enum) are mapped to profile fields. Other required parameters are mapped
+//!   to prompt chains
+//!
+//! An import can also be merged into an existing collection; see
+//! [Collection::merge_openapi].
 
+mod example;
+mod export;
+mod merge;
 mod resolve;
+mod swagger;
+mod v3_1;
+
+pub use merge::MergeReport;
 
 use crate::{
     collection::{
-        openapi::resolve::ReferenceResolver, Authentication, Collection,
+        openapi::{example::ExampleGenerator, resolve::ReferenceResolver},
+        Authentication, Chain, ChainId, ChainSource, Collection,
         DuplicateRecipeIdError, Folder, Method, Profile, ProfileId, Recipe,
-        RecipeBody, RecipeId, RecipeNode, RecipeTree,
+        RecipeBody, RecipeId, RecipeNode, RecipeTree, SelectorMode,
     },
-    template::Template,
+    template::{Identifier, Template},
     util::{ResultTraced, NEW_ISSUE_LINK},
 };
 use anyhow::{anyhow, Context};
@@ -25,8 +42,8 @@ use itertools::Itertools;
 use mime::Mime;
 use openapiv3::{
     APIKeyLocation, Components, MediaType, OpenAPI, Operation, Parameter,
-    PathItem, PathStyle, Paths, ReferenceOr, RequestBody, Schema,
-    SecurityScheme, Server,
+    ParameterData, ParameterSchemaOrContent, PathItem, PathStyle, Paths,
+    ReferenceOr, RequestBody, SecurityScheme, Server,
 };
 use std::{fs::File, iter, path::Path};
 use strum::IntoEnumIterator;
@@ -51,37 +68,138 @@ impl Collection {
             "Error opening OpenAPI collection file {path:?}"
         ))?;
 
-        // The format can be YAML or JSON, so we can just treat it all as YAML
+        // The format can be YAML or JSON, so we can just treat it all as YAML.
+        // Convert to JSON so older/newer spec versions can be rewritten as
+        // v3.0 before deserializing
+        let spec: serde_yaml::Value = serde_yaml::from_reader(file).context(
+            format!("Error deserializing OpenAPI collection file {path:?}"),
+        )?;
+        let mut spec = serde_json::to_value(spec).context(format!(
+            "Error deserializing OpenAPI collection file {path:?}"
+        ))?;
+        resolve::resolve_external_references(&mut spec, path);
+        let spec = convert_to_v3_0(spec);
+
         let OpenAPI {
-            openapi: openapi_version,
             components,
             paths,
             servers,
             ..
-        } = serde_yaml::from_reader(file).context(format!(
+        } = serde_json::from_value(spec).context(format!(
             "Error deserializing OpenAPI collection file {path:?}"
         ))?;
 
-        if !openapi_version.starts_with("3.0.") {
-            warn!(
-                "Importer currently only supports OpenAPI v3.0, this spec is
-                version {openapi_version}. We'll try the import anyway, but you
-                may experience issues."
-            )
-        }
+        let mut profiles = build_profiles(servers);
+        let mut parameters = ParameterTemplates::new(!profiles.is_empty());
+        let recipes = build_recipe_tree(paths, components, &mut parameters)?;
 
-        let profiles = build_profiles(servers);
-        let recipes = build_recipe_tree(paths, components)?;
+        // Add parameter fields to every profile, without overriding the
+        // server's fields
+        for profile in profiles.values_mut() {
+            for (field, value) in &parameters.fields {
+                profile
+                    .data
+                    .entry(field.clone())
+                    .or_insert_with(|| value.clone());
+            }
+        }
 
         Ok(Collection {
             profiles,
             recipes,
-            chains: IndexMap::new(),
+            chains: parameters.chains,
             _ignore: serde::de::IgnoredAny,
         })
     }
 }
 
+/// Convert a spec of any supported version to OpenAPI v3.0, based on its
+/// `swagger`/`openapi` version field
+fn convert_to_v3_0(spec: serde_json::Value) -> serde_json::Value {
+    if let Some(version) = spec.get("swagger") {
+        let version = version.as_str().unwrap_or_default();
+        if !version.starts_with("2.") {
+            warn!(
+                "Unknown Swagger version {version}. We'll try the import \
+                anyway, but you may experience issues."
+            );
+        }
+        return swagger::upgrade(spec);
+    }
+
+    let version = spec
+        .get("openapi")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or_default();
+    if version.starts_with("3.1.") {
+        v3_1::downgrade(spec)
+    } else {
+        if !version.starts_with("3.0.") {
+            warn!(
+                "Importer supports OpenAPI v3.0, v3.1 and Swagger 2.0; this \
+                spec is version {version}. We'll try the import anyway, but \
+                you may experience issues."
+            );
+        }
+        spec
+    }
+}
+
+/// Call a function on every object in a JSON value, recursively. The function
+/// is given the key of the field containing the object (for array items, the
+/// key of the array), if any. Example values and extensions are skipped,
+/// because they're user data rather than spec.
+fn visit_objects(
+    value: &mut serde_json::Value,
+    f: &mut impl FnMut(
+        Option<&str>,
+        &mut serde_json::Map<String, serde_json::Value>,
+    ),
+) {
+    fn visit(
+        parent_key: Option<&str>,
+        value: &mut serde_json::Value,
+        f: &mut impl FnMut(
+            Option<&str>,
+            &mut serde_json::Map<String, serde_json::Value>,
+        ),
+    ) {
+        const DATA_KEYS: &[&str] =
+            &["example", "examples", "default", "enum", "const"];
+        // Maps of user-defined names, where any key is valid
+        const NAME_MAPS: &[&str] = &[
+            "properties",
+            "patternProperties",
+            "$defs",
+            "definitions",
+            "schemas",
+        ];
+        match value {
+            serde_json::Value::Object(object) => {
+                f(parent_key, object);
+                let is_name_map =
+                    parent_key.is_some_and(|key| NAME_MAPS.contains(&key));
+                for (key, value) in object.iter_mut() {
+                    if is_name_map
+                        || !(DATA_KEYS.contains(&key.as_str())
+                            || key.starts_with("x-"))
+                    {
+                        visit(Some(key), value, f);
+                    }
+                }
+            }
+            serde_json::Value::Array(values) => {
+                for value in values {
+                    visit(parent_key, value, f);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    visit(None, value, f)
+}
+
 /// Build one profile per server
 fn build_profiles(servers: Vec<Server>) -> IndexMap<ProfileId, Profile> {
     servers
@@ -124,6 +242,7 @@ fn build_profiles(servers: Vec<Server>) -> IndexMap<ProfileId, Profile> {
 fn build_recipe_tree(
     paths: Paths,
     components: Option<Components>,
+    parameters: &mut ParameterTemplates,
 ) -> Result<RecipeTree, DuplicateRecipeIdError> {
     let reference_resolver = ReferenceResolver::new(components);
     let mut recipes: IndexMap<RecipeId, RecipeNode> = IndexMap::new();
@@ -138,6 +257,7 @@ fn build_recipe_tree(
         let recipe = RecipeBuilder::build_recipe(
             operation,
             &reference_resolver,
+            parameters,
             path,
             method,
         );
@@ -233,6 +353,76 @@ fn take_operation(
     }
 }
 
+/// Templates for required parameters, shared between all recipes. Parameters
+/// with a known value become profile fields, so the value can be changed in
+/// one place. The rest become prompt chains, so the user is asked for a value
+/// when building the request.
+struct ParameterTemplates {
+    /// Fields need a profile to live in. If there are no profiles, we'll
+    /// always use prompts instead
+    use_fields: bool,
+    /// Fields to add to each profile
+    fields: IndexMap<String, Template>,
+    chains: IndexMap<ChainId, Chain>,
+}
+
+impl ParameterTemplates {
+    fn new(use_fields: bool) -> Self {
+        Self {
+            use_fields,
+            fields: IndexMap::new(),
+            chains: IndexMap::new(),
+        }
+    }
+
+    /// Get a template for a required parameter, creating the field or chain
+    /// that it refers to. Parameters with the same name share a field/chain.
+    /// If they have different values, the first one wins.
+    fn template(
+        &mut self,
+        name: &str,
+        value: Option<serde_json::Value>,
+    ) -> Template {
+        if name.is_empty() {
+            return Template::default();
+        }
+        let id = Identifier::escape(name);
+        let value = value.map(|value| match value {
+            serde_json::Value::String(value) => value,
+            // Do *not* prettify here; we want to be able to show this in one
+            // line in the UI
+            value => value.to_string(),
+        });
+
+        match value {
+            Some(value) if self.use_fields => {
+                self.fields
+                    .entry(id.to_string())
+                    .or_insert_with(|| Template::raw(value));
+                Template::from_field(id)
+            }
+            default => {
+                let chain_id: ChainId = id.into();
+                self.chains
+                    .entry(chain_id.clone())
+                    .or_insert_with(|| Chain {
+                        id: chain_id.clone(),
+                        source: ChainSource::Prompt {
+                            message: Some(Template::raw(name.to_owned())),
+                            default: default.map(Template::raw),
+                        },
+                        sensitive: false,
+                        selector: None,
+                        selector_mode: SelectorMode::default(),
+                        content_type: None,
+                        trim: Default::default(),
+                    });
+                Template::from_chain(chain_id)
+            }
+        }
+    }
+}
+
 /// Helper struct to hold intermediate state while converting an operation into
 /// a recipe
 struct RecipeBuilder<'a> {
@@ -245,6 +435,7 @@ struct RecipeBuilder<'a> {
     query: Vec<(String, Template)>,
     headers: IndexMap<String, Template>,
     reference_resolver: &'a ReferenceResolver,
+    parameters: &'a mut ParameterTemplates,
 }
 
 impl<'a> RecipeBuilder<'a> {
@@ -252,6 +443,7 @@ impl<'a> RecipeBuilder<'a> {
     fn build_recipe(
         operation: Operation,
         reference_resolver: &'a ReferenceResolver,
+        parameters: &'a mut ParameterTemplates,
         path_name: &str,
         method: Method,
     ) -> Recipe {
@@ -276,6 +468,7 @@ impl<'a> RecipeBuilder<'a> {
             query: Vec::new(),
             headers: IndexMap::new(),
             reference_resolver,
+            parameters,
         };
 
         if let Some(request_body) = operation.request_body {
@@ -308,6 +501,7 @@ impl<'a> RecipeBuilder<'a> {
             authentication: builder.authentication,
             query: builder.query,
             headers: builder.headers,
+            protobuf: None,
         }
     }
 
@@ -328,19 +522,24 @@ impl<'a> RecipeBuilder<'a> {
                     .traced()
                     .ok()
             })
+            // Collect so the parameters don't borrow self while we mutate it
+            .collect_vec()
+            .into_iter()
             .for_each(|parameter| match parameter.into_owned() {
                 Parameter::Query { parameter_data, .. } => {
-                    self.query.push((parameter_data.name, Template::default()));
+                    let template = self.parameter_template(&parameter_data);
+                    self.query.push((parameter_data.name, template));
                 }
                 Parameter::Header { parameter_data, .. } => {
                     // if the name field is "Accept", "Content-Type" or
                     // "Authorization", the parameter definition SHALL be
                     // ignored. https://spec.openapis.org/oas/v3.0.3#fixed-fields-9
-                    let name = parameter_data.name;
-                    match name.as_str() {
+                    match parameter_data.name.as_str() {
                         "Accept" | "Content-Type" | "Authorization" => {}
                         _ => {
-                            self.headers.insert(name, Template::default());
+                            let template =
+                                self.parameter_template(&parameter_data);
+                            self.headers.insert(parameter_data.name, template);
                         }
                     }
                 }
@@ -348,16 +547,16 @@ impl<'a> RecipeBuilder<'a> {
                     style: PathStyle::Simple,
                     parameter_data,
                 } => {
-                    // Replace path params with a template key. The key probably
-                    // won't refer to anything, but it's better than being
-                    // completely invalid. We have no way of knowing how the
-                    // user actually wants to fill this
-                    // value
-                    let id = parameter_data.name;
-                    // {id} -> {{id}}
+                    // Replace path params with a template key. Path params
+                    // are always required, so this will refer to a profile
+                    // field or prompt chain.
+                    // {id} -> {{id}} or {{chains.id}}
+                    let value = self.parameter_value(&parameter_data);
+                    let template =
+                        self.parameters.template(&parameter_data.name, value);
                     self.url = self.url.replace(
-                        &format!("{{{id}}}"),
-                        &format!("{{{{{id}}}}}"),
+                        &format!("{{{}}}", parameter_data.name),
+                        &template.display(),
                     );
                 }
                 Parameter::Path {
@@ -378,6 +577,59 @@ impl<'a> RecipeBuilder<'a> {
             });
     }
 
+    /// Get the template for a query or header parameter. Required parameters
+    /// refer to a shared field or chain. Optional parameters are left empty
+    /// for the user to fill in.
+    fn parameter_template(
+        &mut self,
+        parameter_data: &ParameterData,
+    ) -> Template {
+        if parameter_data.required {
+            let value = self.parameter_value(parameter_data);
+            self.parameters.template(&parameter_data.name, value)
+        } else {
+            Template::default()
+        }
+    }
+
+    /// Get the known value for a parameter, from its examples or schema. We
+    /// don't generate values here, because a placeholder value such as `0` is
+    /// more likely to be wrong than helpful.
+    fn parameter_value(
+        &self,
+        parameter_data: &ParameterData,
+    ) -> Option<serde_json::Value> {
+        let context = format!(
+            "{id}.parameters.{name}",
+            id = self.id,
+            name = parameter_data.name
+        );
+        parameter_data
+            .example
+            .clone()
+            .or_else(|| {
+                parameter_data.examples.values().find_map(|example| {
+                    self.reference_resolver
+                        .resolve_ref(example)
+                        .with_context(|| context.clone())
+                        .traced()
+                        .ok()?
+                        .value
+                        .clone()
+                })
+            })
+            .or_else(|| match &parameter_data.format {
+                ParameterSchemaOrContent::Schema(schema) => {
+                    ExampleGenerator::new(
+                        self.reference_resolver,
+                        context.clone(),
+                    )
+                    .explicit_example(schema)
+                }
+                ParameterSchemaOrContent::Content(_) => None,
+            })
+    }
+
     /// Imperatively update the recipe to include security scheme(s). Depending
     /// on the scheme this may map to first-class auth, query params, or headers
     fn process_security(
@@ -532,7 +784,8 @@ impl<'a> RecipeBuilder<'a> {
     /// - `media_type.examples` (according to the spec this is mutually
     ///   exclusive with `media_type.example`, but we support both because it's
     ///   easy)
-    /// - `media_type.schema.examples`
+    /// - `media_type.schema`, which includes the schema's own example, or an
+    ///   example generated from the schema if it has none
     fn get_examples(
         &'a self,
         mime: Mime,
@@ -563,18 +816,17 @@ impl<'a> RecipeBuilder<'a> {
                     example.value
                 });
         let schema_example = media_type.schema.as_ref().and_then(|schema| {
-            let schema = self
-                .reference_resolver
-                .resolve::<Schema>(schema.clone())
-                .with_context(|| {
-                    format!(
-                        "{id}.requestBody.content.{mime}.schema",
-                        id = self.id,
-                    )
-                })
-                .traced()
-                .ok()?;
-            schema.schema_data.example.clone()
+            ExampleGenerator::new(
+                self.reference_resolver,
+                format!(
+                    "{id}.requestBody.content.{mime}.schema",
+                    id = self.id,
+                ),
+            )
+            .generate(schema)
+            // An empty string is just a placeholder (e.g. for binary data),
+            // so it's not a useful body
+            .filter(|value| value.as_str() != Some(""))
         });
 
         example.into_iter().chain(examples).chain(schema_example)
@@ -644,22 +896,34 @@ mod tests {
     use serde_json::json;
     use std::{path::PathBuf, sync::OnceLock};
 
-    const OPENAPIV3_FILE: &str = "openapiv3_petstore.yml";
+    /// Catch-all test for OpenAPI import, for each supported version.
     /// Assertion expectation is stored in a separate file. This is for a couple
     /// reasons:
     /// - It's huge so it makes code hard to navigate
     /// - Changes don't require a re-compile
-    const OPENAPIV3_IMPORTED_FILE: &str = "openapiv3_petstore_imported.yml";
-
-    /// Catch-all test for openapiv3 import
     #[rstest]
-    fn test_openapiv3_import(test_data_dir: PathBuf) {
+    #[case::v3_0("openapiv3_petstore.yml", "openapiv3_petstore_imported.yml")]
+    #[case::v3_1(
+        "openapiv3_1_petstore.yml",
+        "openapiv3_1_petstore_imported.yml"
+    )]
+    #[case::swagger_2_0(
+        "swagger2_petstore.yml",
+        "swagger2_petstore_imported.yml"
+    )]
+    #[case::external_references(
+        "openapi_external/openapi.yml",
+        "openapi_external_imported.yml"
+    )]
+    fn test_openapi_import(
+        test_data_dir: PathBuf,
+        #[case] input_file: &str,
+        #[case] expected_file: &str,
+    ) {
         let imported =
-            Collection::from_openapi(test_data_dir.join(OPENAPIV3_FILE))
-                .unwrap();
+            Collection::from_openapi(test_data_dir.join(input_file)).unwrap();
         let expected =
-            Collection::load(&test_data_dir.join(OPENAPIV3_IMPORTED_FILE))
-                .unwrap();
+            Collection::load(&test_data_dir.join(expected_file)).unwrap();
         assert_eq!(imported, expected);
     }
 
@@ -821,6 +1085,7 @@ mod tests {
             headers: Default::default(),
             reference_resolver: RESOLVER
                 .get_or_init(|| ReferenceResolver::new(None)),
+            parameters: Box::leak(Box::new(ParameterTemplates::new(true))),
         }
     }
 }
@@ -0,0 +1,378 @@
+//! Generate example values from schemas, for request bodies and parameters
+//! that don't define an explicit example.
+//!
+//! - A schema's `example`, `default` or first `enum` value is used if present
+//! - Objects include their `required` properties. If no properties are
+//!   required, all of them are included
+//! - `readOnly` schemas are skipped, because they aren't sent in requests
+//! - Arrays contain a single item
+//! - `allOf` branches are merged together. For `oneOf`/`anyOf`, the first
+//!   branch is used
+//! - Strings are empty, unless they have a well-known format such as `date`
+//!   or `uuid`. Numbers use their minimum, or zero
+//! - References are followed, but recursive schemas are cut off at the first
+//!   repeated reference
+
+use crate::{
+    collection::openapi::resolve::ReferenceResolver, util::ResultTraced,
+};
+use anyhow::Context;
+use indexmap::IndexMap;
+use openapiv3::{
+    AnySchema, ReferenceOr, Schema, SchemaKind, StringFormat, Type,
+    VariantOrUnknownOrEmpty,
+};
+use serde_json::{Map, Value};
+use std::borrow::Borrow;
+
+/// Generate example values from a schema, resolving references along the way
+pub struct ExampleGenerator<'a> {
+    reference_resolver: &'a ReferenceResolver,
+    /// Location of the root schema in the spec, for error messages
+    context: String,
+    /// References currently being expanded, used to detect cycles
+    stack: Vec<String>,
+}
+
+impl<'a> ExampleGenerator<'a> {
+    pub fn new(
+        reference_resolver: &'a ReferenceResolver,
+        context: String,
+    ) -> Self {
+        Self {
+            reference_resolver,
+            context,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Get the explicit example for a schema, without synthesizing one. This
+    /// is the `example`, `default` or first `enum` value, in that order
+    pub fn explicit_example(
+        &self,
+        schema: &ReferenceOr<impl Borrow<Schema>>,
+    ) -> Option<Value> {
+        let schema = self.resolve(schema)?;
+        explicit_example(schema)
+    }
+
+    /// Generate an example value for a schema. Return `None` if the schema
+    /// can't be resolved, is read-only, or is a recursive reference
+    pub fn generate(
+        &mut self,
+        schema: &ReferenceOr<impl Borrow<Schema>>,
+    ) -> Option<Value> {
+        let reference = match schema {
+            ReferenceOr::Reference { reference } => {
+                if self.stack.contains(reference) {
+                    return None;
+                }
+                Some(reference)
+            }
+            ReferenceOr::Item(_) => None,
+        };
+        // Copy the resolver reference out so the schema doesn't borrow self
+        let reference_resolver = self.reference_resolver;
+        let schema = reference_resolver
+            .resolve_ref(schema)
+            .with_context(|| self.context.clone())
+            .traced()
+            .ok()?;
+
+        if let Some(reference) = reference {
+            self.stack.push(reference.clone());
+        }
+        let value = self.generate_schema(schema);
+        if reference.is_some() {
+            self.stack.pop();
+        }
+        value
+    }
+
+    fn generate_schema(&mut self, schema: &Schema) -> Option<Value> {
+        if schema.schema_data.read_only {
+            return None;
+        }
+        if let Some(value) = explicit_example(schema) {
+            return Some(value);
+        }
+
+        match &schema.schema_kind {
+            SchemaKind::Type(Type::String(string)) => {
+                Some(string_example(&string.format))
+            }
+            SchemaKind::Type(Type::Number(number)) => {
+                Some(number.minimum.map(Value::from).unwrap_or(0.into()))
+            }
+            SchemaKind::Type(Type::Integer(integer)) => {
+                Some(integer.minimum.unwrap_or(0).into())
+            }
+            SchemaKind::Type(Type::Boolean(_)) => Some(false.into()),
+            SchemaKind::Type(Type::Object(object)) => Some(
+                self.object_example(&object.properties, &object.required)
+                    .into(),
+            ),
+            SchemaKind::Type(Type::Array(array)) => {
+                Some(self.array_example(array.items.as_ref()))
+            }
+            SchemaKind::OneOf { one_of: branches }
+            | SchemaKind::AnyOf { any_of: branches } => {
+                self.generate(branches.first()?)
+            }
+            SchemaKind::AllOf { all_of } => self.all_of_example(all_of),
+            SchemaKind::Not { .. } => None,
+            SchemaKind::Any(any) => self.any_example(any),
+        }
+    }
+
+    /// Generate an object with each included property
+    fn object_example(
+        &mut self,
+        properties: &IndexMap<String, ReferenceOr<Box<Schema>>>,
+        required: &[String],
+    ) -> Map<String, Value> {
+        let include_all = required.is_empty();
+        let mut object = Map::new();
+        for (name, schema) in properties {
+            if include_all || required.contains(name) {
+                if let Some(value) = self.generate(schema) {
+                    object.insert(name.clone(), value);
+                }
+            }
+        }
+        object
+    }
+
+    /// Generate an array with a single item
+    fn array_example(
+        &mut self,
+        items: Option<&ReferenceOr<Box<Schema>>>,
+    ) -> Value {
+        items
+            .and_then(|items| self.generate(items))
+            .into_iter()
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Merge the examples of each branch. If no branch generates an object,
+    /// use the first non-object example instead
+    fn all_of_example(
+        &mut self,
+        branches: &[ReferenceOr<Schema>],
+    ) -> Option<Value> {
+        let mut merged: Option<Map<String, Value>> = None;
+        let mut other = None;
+        for branch in branches {
+            match self.generate(branch) {
+                Some(Value::Object(object)) => {
+                    merged.get_or_insert_with(Map::new).extend(object);
+                }
+                Some(value) => {
+                    other = other.or(Some(value));
+                }
+                None => {}
+            }
+        }
+        merged.map(Value::Object).or(other)
+    }
+
+    /// Generate an example for a schema that mixes keywords, e.g. an object
+    /// with both `properties` and `allOf`
+    fn any_example(&mut self, any: &AnySchema) -> Option<Value> {
+        if let Some(branch) = any.one_of.first().or(any.any_of.first()) {
+            return self.generate(branch);
+        }
+
+        let typ = any.typ.as_deref();
+        if typ == Some("object")
+            || !any.properties.is_empty()
+            || !any.all_of.is_empty()
+        {
+            let mut object = match self.all_of_example(&any.all_of) {
+                Some(Value::Object(object)) => object,
+                _ => Map::new(),
+            };
+            object.extend(self.object_example(&any.properties, &any.required));
+            return Some(object.into());
+        }
+
+        match typ {
+            Some("array") => Some(self.array_example(any.items.as_ref())),
+            Some("string") => Some(string_example(&any.format.clone().into())),
+            Some("number") => {
+                Some(any.minimum.map(Value::from).unwrap_or(0.into()))
+            }
+            Some("integer") => {
+                Some(any.minimum.map(|min| min as i64).unwrap_or(0).into())
+            }
+            Some("boolean") => Some(false.into()),
+            _ => None,
+        }
+    }
+
+    fn resolve<'s>(
+        &'s self,
+        schema: &'s ReferenceOr<impl Borrow<Schema>>,
+    ) -> Option<&'s Schema> {
+        self.reference_resolver
+            .resolve_ref(schema)
+            .with_context(|| self.context.clone())
+            .traced()
+            .ok()
+    }
+}
+
+/// Get a schema's `example`, `default` or first `enum` value
+fn explicit_example(schema: &Schema) -> Option<Value> {
+    fn first<T: Clone + Into<Value>>(values: &[Option<T>]) -> Option<Value> {
+        values.iter().flatten().next().cloned().map(T::into)
+    }
+
+    let data = &schema.schema_data;
+    data.example
+        .clone()
+        .or_else(|| data.default.clone())
+        .or_else(|| match &schema.schema_kind {
+            SchemaKind::Type(Type::String(string)) => {
+                first(&string.enumeration)
+            }
+            SchemaKind::Type(Type::Number(number)) => {
+                first(&number.enumeration)
+            }
+            SchemaKind::Type(Type::Integer(integer)) => {
+                first(&integer.enumeration)
+            }
+            SchemaKind::Type(Type::Boolean(boolean)) => {
+                first(&boolean.enumeration)
+            }
+            SchemaKind::Any(any) => any.enumeration.first().cloned(),
+            _ => None,
+        })
+}
+
+/// Get a placeholder string for a format. Unknown formats get an empty string
+fn string_example(format: &VariantOrUnknownOrEmpty<StringFormat>) -> Value {
+    let example = match format {
+        VariantOrUnknownOrEmpty::Item(StringFormat::Date) => "2024-01-01",
+        VariantOrUnknownOrEmpty::Item(StringFormat::DateTime) => {
+            "2024-01-01T00:00:00Z"
+        }
+        VariantOrUnknownOrEmpty::Item(
+            StringFormat::Password | StringFormat::Byte | StringFormat::Binary,
+        )
+        | VariantOrUnknownOrEmpty::Empty => "",
+        VariantOrUnknownOrEmpty::Unknown(format) => match format.as_str() {
+            "time" => "00:00:00",
+            "email" => "user@example.com",
+            "uuid" => "00000000-0000-0000-0000-000000000000",
+            "uri" | "url" => "https://example.com",
+            "hostname" => "example.com",
+            "ipv4" => "127.0.0.1",
+            "ipv6" => "::1",
+            _ => "",
+        },
+    };
+    example.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openapiv3::Components;
+    use rstest::rstest;
+    use serde_json::json;
+
+    /// Test generating examples from various schemas. Each schema can refer
+    /// to the `Node` and `Named` components
+    #[rstest]
+    #[case::explicit(json!({"type": "integer", "example": 3}), json!(3))]
+    #[case::default(json!({"type": "string", "default": "a"}), json!("a"))]
+    #[case::enum_(json!({"type": "string", "enum": ["b", "c"]}), json!("b"))]
+    #[case::string_format(
+        json!({"type": "string", "format": "date-time"}),
+        json!("2024-01-01T00:00:00Z"),
+    )]
+    #[case::number_minimum(
+        json!({"type": "number", "minimum": 1.5}),
+        json!(1.5),
+    )]
+    #[case::required_only(
+        json!({
+            "type": "object",
+            "required": ["a"],
+            "properties": {"a": {"type": "integer"}, "b": {"type": "integer"}},
+        }),
+        json!({"a": 0}),
+    )]
+    #[case::all_properties(
+        json!({
+            "type": "object",
+            "properties": {"a": {"type": "integer"}, "b": {"type": "boolean"}},
+        }),
+        json!({"a": 0, "b": false}),
+    )]
+    #[case::read_only(
+        json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer", "readOnly": true},
+                "a": {"type": "integer"},
+            },
+        }),
+        json!({"a": 0}),
+    )]
+    #[case::array(
+        json!({"type": "array", "items": {"type": "string", "format": "uuid"}}),
+        json!(["00000000-0000-0000-0000-000000000000"]),
+    )]
+    #[case::one_of(
+        json!({"oneOf": [{"type": "integer"}, {"type": "string"}]}),
+        json!(0),
+    )]
+    #[case::all_of(
+        json!({"allOf": [
+            {"$ref": "#/components/schemas/Named"},
+            {"type": "object", "properties": {"age": {"type": "integer"}}},
+        ]}),
+        json!({"name": "fido", "age": 0}),
+    )]
+    #[case::recursive(
+        json!({"$ref": "#/components/schemas/Node"}),
+        json!({"value": "", "children": []}),
+    )]
+    #[case::unknown_reference(
+        json!({"$ref": "#/components/schemas/Unknown"}),
+        json!(null),
+    )]
+    fn test_generate(#[case] schema: Value, #[case] expected: Value) {
+        let components: Components = serde_json::from_value(json!({
+            "schemas": {
+                "Node": {
+                    "type": "object",
+                    "properties": {
+                        "value": {"type": "string"},
+                        "children": {
+                            "type": "array",
+                            "items": {"$ref": "#/components/schemas/Node"},
+                        },
+                    },
+                },
+                "Named": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string", "example": "fido"},
+                    },
+                },
+            }
+        }))
+        .unwrap();
+        let resolver = ReferenceResolver::new(Some(components));
+        let schema: ReferenceOr<Schema> =
+            serde_json::from_value(schema).unwrap();
+        let example = ExampleGenerator::new(&resolver, "test".into())
+            .generate(&schema)
+            .unwrap_or_default();
+        assert_eq!(example, expected);
+    }
+}
@@ -0,0 +1,679 @@
+//! Export a collection as an OpenAPI v3.0 skeleton. The output describes the
+//! shape of each request, but not its responses.
+//!
+//! - Recipes map to operations. The recipe ID is used as the operation ID
+//! - Folders map to tags. Nested folders are flattened
+//! - If a URL starts with a profile field (e.g. `{{host}}/users`), that field
+//!   is the server. Each profile's value for the field becomes a server, and
+//!   other fields within that value become server variables. If the URL is
+//!   absolute, its origin is the server.
+//! - Other template keys in the path become path parameters
+//! - Query parameters and headers become parameters, with their template as
+//!   the example
+//! - Bodies become request bodies, with their template as the example and a
+//!   schema inferred from the example
+//! - Basic and bearer authentication map to security schemes
+//! - Chains and environment variables have no equivalent, and are written
+//!   as-is
+
+use crate::{
+    collection::{
+        export::{Export, Unsupported},
+        Authentication, Collection, Method, Profile, Recipe, RecipeBody,
+        RecipeNode,
+    },
+    http::content_type::ContentType,
+    template::{Identifier, Template, TemplateKey},
+};
+use indexmap::{IndexMap, IndexSet};
+use reqwest::header;
+use serde_json::{json, Map, Value};
+
+/// OpenAPI version of the exported spec
+const OPENAPI_VERSION: &str = "3.0.3";
+/// Version given to the exported API, which is required by the spec
+const API_VERSION: &str = "1.0.0";
+/// Headers that OpenAPI forbids as parameters. They're described by other
+/// parts of the spec instead.
+const IGNORED_HEADERS: &[header::HeaderName] =
+    &[header::ACCEPT, header::AUTHORIZATION, header::CONTENT_TYPE];
+
+impl Collection {
+    /// Convert the collection into an OpenAPI v3.0 spec. The spec requires a
+    /// title, which isn't stored in the collection so it must be given.
+    pub fn to_openapi(&self, title: &str) -> Export<Value> {
+        let mut exporter = OpenApiExporter {
+            collection: self,
+            unsupported: Unsupported::default(),
+            security_schemes: IndexMap::new(),
+            server_fields: IndexSet::new(),
+        };
+        self.report_unsupported("OpenAPI", &mut exporter.unsupported);
+
+        let mut tags = Vec::new();
+        // Build each operation first, then figure out which server is the
+        // most common to use as the global server list
+        let mut operations = Vec::new();
+        for (lookup_key, node) in self.recipes.iter() {
+            match node {
+                RecipeNode::Folder(folder) => {
+                    if lookup_key.as_slice().len() > 1 {
+                        exporter.unsupported.add(format!(
+                            "Folder `{}` was flattened; OpenAPI tags can't be \
+                            nested",
+                            folder.id
+                        ));
+                    }
+                    tags.push(json!({"name": folder.name()}));
+                }
+                RecipeNode::Recipe(recipe) => {
+                    // The folder is the second-to-last element of the key
+                    let tag = lookup_key
+                        .as_slice()
+                        .iter()
+                        .nth_back(1)
+                        .and_then(|id| self.recipes.get(id))
+                        .map(RecipeNode::name);
+                    if let Some(operation) = exporter.operation(recipe, tag) {
+                        operations.push(operation);
+                    }
+                }
+            }
+        }
+
+        let global_server = most_common(
+            operations
+                .iter()
+                .filter_map(|operation| operation.server.as_ref()),
+        )
+        .cloned();
+        let servers = global_server
+            .as_ref()
+            .map(|server| exporter.servers(server))
+            .unwrap_or_default();
+        let mut paths: IndexMap<String, Map<String, Value>> = IndexMap::new();
+        let mut operation_ids: IndexMap<(String, &str), &str> = IndexMap::new();
+        for mut operation in operations {
+            let key = (operation.path.clone(), operation.method);
+            if let Some(other) = operation_ids.get(&key) {
+                exporter.unsupported.add(format!(
+                    "Recipe `{}` has the same method and path as `{other}`; \
+                    it was skipped",
+                    operation.id
+                ));
+                continue;
+            }
+            operation_ids.insert(key, operation.id);
+
+            if let Some(server) = operation
+                .server
+                .as_ref()
+                .filter(|server| Some(*server) != global_server.as_ref())
+            {
+                let servers = exporter.servers(server);
+                operation.value.insert("servers".into(), servers.into());
+            }
+            paths
+                .entry(operation.path)
+                .or_default()
+                .insert(operation.method.into(), operation.value.into());
+        }
+        exporter.report_profile_fields();
+
+        let mut spec = json!({
+            "openapi": OPENAPI_VERSION,
+            "info": {"title": title, "version": API_VERSION},
+        });
+        if !servers.is_empty() {
+            spec["servers"] = servers.into();
+        }
+        if !tags.is_empty() {
+            spec["tags"] = tags.into();
+        }
+        spec["paths"] = json!(paths);
+        if !exporter.security_schemes.is_empty() {
+            spec["components"] =
+                json!({"securitySchemes": exporter.security_schemes});
+        }
+        exporter.unsupported.finish(spec)
+    }
+}
+
+/// State for converting a collection to OpenAPI
+struct OpenApiExporter<'a> {
+    collection: &'a Collection,
+    unsupported: Unsupported,
+    /// Security schemes used by any operation, keyed by name
+    security_schemes: IndexMap<&'static str, Value>,
+    /// Profile fields that were exported as servers or server variables
+    server_fields: IndexSet<String>,
+}
+
+/// An operation, before it's been inserted into the spec
+struct Operation<'a> {
+    id: &'a str,
+    path: String,
+    method: &'static str,
+    server: Option<ServerSource>,
+    value: Map<String, Value>,
+}
+
+/// Where the server of an operation comes from
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum ServerSource {
+    /// URL starts with a profile field, which holds the server URL
+    Field(Identifier),
+    /// URL is absolute, and this is its origin
+    Origin(String),
+}
+
+impl<'a> OpenApiExporter<'a> {
+    /// Build an operation from a recipe. Return `None` if the recipe's method
+    /// has no OpenAPI equivalent.
+    fn operation(
+        &mut self,
+        recipe: &'a Recipe,
+        tag: Option<&str>,
+    ) -> Option<Operation<'a>> {
+        let method = match recipe.method {
+            Method::Connect => {
+                self.unsupported.add(format!(
+                    "Recipe `{}` uses method CONNECT, which OpenAPI doesn't \
+                    support; it was skipped",
+                    recipe.id
+                ));
+                return None;
+            }
+            Method::Delete => "delete",
+            Method::Get => "get",
+            Method::Head => "head",
+            Method::Options => "options",
+            Method::Patch => "patch",
+            Method::Post => "post",
+            Method::Put => "put",
+            Method::Trace => "trace",
+        };
+
+        let mut value = Map::new();
+        value.insert("operationId".into(), recipe.id.as_str().into());
+        value.insert("summary".into(), recipe.name().into());
+        if let Some(tag) = tag {
+            value.insert("tags".into(), json!([tag]));
+        }
+
+        let (server, path, path_parameters) = self.split_url(recipe);
+        let mut parameters: Vec<Value> = path_parameters
+            .into_iter()
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": {"type": "string"},
+                })
+            })
+            .collect();
+        let mut query_names = IndexSet::new();
+        for (name, template) in &recipe.query {
+            if !query_names.insert(name) {
+                self.unsupported.add(format!(
+                    "Query parameter `{name}` of recipe `{}` is repeated; only \
+                    the first value was exported",
+                    recipe.id
+                ));
+                continue;
+            }
+            parameters.push(self.parameter(name, "query", template));
+        }
+        for (name, template) in &recipe.headers {
+            if IGNORED_HEADERS
+                .iter()
+                .any(|ignored| name.eq_ignore_ascii_case(ignored.as_str()))
+            {
+                // The body's media type comes from the content type header, so
+                // it isn't lost
+                if !name.eq_ignore_ascii_case(header::CONTENT_TYPE.as_str())
+                    || recipe.body.is_none()
+                {
+                    self.unsupported.add(format!(
+                        "Header `{name}` of recipe `{}` can't be an OpenAPI \
+                        parameter; it was dropped",
+                        recipe.id
+                    ));
+                }
+                continue;
+            }
+            parameters.push(self.parameter(name, "header", template));
+        }
+        if !parameters.is_empty() {
+            value.insert("parameters".into(), parameters.into());
+        }
+
+        if let Some(body) = &recipe.body {
+            let (media_type, content) = self.request_body(recipe, body);
+            value.insert(
+                "requestBody".into(),
+                json!({"content": {media_type: content}}),
+            );
+        }
+
+        if let Some(authentication) = &recipe.authentication {
+            let (name, scheme) = match authentication {
+                Authentication::Basic { .. } => ("basicAuth", "basic"),
+                Authentication::Bearer(_) => ("bearerAuth", "bearer"),
+            };
+            self.security_schemes
+                .insert(name, json!({"type": "http", "scheme": scheme}));
+            value.insert("security".into(), json!([{name: []}]));
+        }
+
+        // Responses are required, but we don't know anything about them
+        value.insert(
+            "responses".into(),
+            json!({"default": {"description": "Response"}}),
+        );
+
+        Some(Operation {
+            id: &recipe.id,
+            path,
+            method,
+            server,
+            value,
+        })
+    }
+
+    /// Split a recipe's URL into its server, path and path parameters
+    fn split_url(
+        &mut self,
+        recipe: &Recipe,
+    ) -> (Option<ServerSource>, String, Vec<String>) {
+        let mut parameters = Vec::new();
+        let (server, path) = match recipe.url.split_first_key() {
+            Some((TemplateKey::Field(field), rest)) => (
+                Some(ServerSource::Field(field.clone())),
+                self.path(recipe, &rest, &mut parameters),
+            ),
+            _ => {
+                let url = self.path(recipe, &recipe.url, &mut parameters);
+                match url.split_once("://") {
+                    Some((scheme, rest)) => {
+                        let (host, path) =
+                            rest.split_at(rest.find('/').unwrap_or(rest.len()));
+                        (
+                            Some(ServerSource::Origin(format!(
+                                "{scheme}://{host}"
+                            ))),
+                            path.to_owned(),
+                        )
+                    }
+                    None => (None, url),
+                }
+            }
+        };
+        let path = if path.starts_with('/') {
+            path
+        } else {
+            format!("/{path}")
+        };
+        (server, path, parameters)
+    }
+
+    /// Convert a URL path to OpenAPI syntax, where each key is a path
+    /// parameter
+    fn path(
+        &mut self,
+        recipe: &Recipe,
+        template: &Template,
+        parameters: &mut Vec<String>,
+    ) -> String {
+        template.display_with(|key| {
+            let name = match key {
+                TemplateKey::Field(field) => field.to_string(),
+                TemplateKey::Chain(id) => {
+                    self.unsupported.add(format!(
+                        "Chain `{id}` in the URL of recipe `{}` has no \
+                        OpenAPI equivalent; exported as a path parameter",
+                        recipe.id
+                    ));
+                    id.to_string()
+                }
+                TemplateKey::Environment(variable) => {
+                    self.unsupported.add(format!(
+                        "Environment variable `{variable}` in the URL of \
+                        recipe `{}` has no OpenAPI equivalent; exported as a \
+                        path parameter",
+                        recipe.id
+                    ));
+                    variable.to_string()
+                }
+            };
+            let parameter = format!("{{{name}}}");
+            if !parameters.contains(&name) {
+                parameters.push(name);
+            }
+            parameter
+        })
+    }
+
+    /// Build a query or header parameter
+    fn parameter(
+        &mut self,
+        name: &str,
+        location: &str,
+        template: &Template,
+    ) -> Value {
+        let mut parameter = json!({
+            "name": name,
+            "in": location,
+            "schema": {"type": "string"},
+        });
+        if !template.is_empty() {
+            parameter["example"] = self.export_template(template).into();
+        }
+        parameter
+    }
+
+    /// Build the media type and media type object for a request body
+    fn request_body(
+        &mut self,
+        recipe: &Recipe,
+        body: &RecipeBody,
+    ) -> (String, Value) {
+        match body {
+            RecipeBody::Raw { body, content_type } => {
+                let media_type = content_type
+                    .map(|content_type| content_type.to_mime().to_string())
+                    .or_else(|| {
+                        recipe
+                            .headers
+                            .iter()
+                            .find(|(name, _)| {
+                                name.eq_ignore_ascii_case(
+                                    header::CONTENT_TYPE.as_str(),
+                                )
+                            })
+                            .map(|(_, value)| self.export_template(value))
+                    })
+                    .unwrap_or_else(|| mime::TEXT_PLAIN.to_string());
+                let content = if self.collection.file_chain_path(body).is_some()
+                {
+                    json!({"schema": {"type": "string", "format": "binary"}})
+                } else {
+                    json!({
+                        "schema": {"type": "string"},
+                        "example": self.export_template(body),
+                    })
+                };
+                (media_type, content)
+            }
+            RecipeBody::Structured { body, content_type } => {
+                let example = body.to_source_with(&mut |template| {
+                    self.export_template(template)
+                });
+                let schema = infer_schema(&example);
+                let example = match content_type {
+                    ContentType::Json | ContentType::Yaml => example,
+                    _ => content_type.value_to_string(&example).into(),
+                };
+                (
+                    content_type.to_mime().to_string(),
+                    json!({"schema": schema, "example": example}),
+                )
+            }
+            RecipeBody::FormUrlencoded(fields) => {
+                let example: Value = fields
+                    .iter()
+                    .map(|(field, value)| {
+                        (field.clone(), self.export_template(value).into())
+                    })
+                    .collect::<Map<_, _>>()
+                    .into();
+                (
+                    mime::APPLICATION_WWW_FORM_URLENCODED.to_string(),
+                    json!({"schema": infer_schema(&example), "example": example}),
+                )
+            }
+            RecipeBody::FormJson(fields) => {
+                let example: Value = fields
+                    .iter()
+                    .map(|(field, value)| {
+                        let value = value.to_source_with(&mut |template| {
+                            self.export_template(template)
+                        });
+                        (field.clone(), value)
+                    })
+                    .collect::<Map<_, _>>()
+                    .into();
+                (
+                    mime::APPLICATION_WWW_FORM_URLENCODED.to_string(),
+                    json!({"schema": infer_schema(&example), "example": example}),
+                )
+            }
+            RecipeBody::FormMultipart(fields) => {
+                let mut properties = Map::new();
+                let mut example = Map::new();
+                for (field, value) in fields {
+                    if self.collection.file_chain_path(value).is_some() {
+                        properties.insert(
+                            field.clone(),
+                            json!({"type": "string", "format": "binary"}),
+                        );
+                    } else {
+                        properties
+                            .insert(field.clone(), json!({"type": "string"}));
+                        example.insert(
+                            field.clone(),
+                            self.export_template(value).into(),
+                        );
+                    }
+                }
+                (
+                    mime::MULTIPART_FORM_DATA.to_string(),
+                    json!({
+                        "schema": {"type": "object", "properties": properties},
+                        "example": example,
+                    }),
+                )
+            }
+        }
+    }
+
+    /// Build the server list for a server source. For a profile field, each
+    /// profile that defines the field becomes a server. The default profile
+    /// comes first, because OpenAPI tools treat the first server as the
+    /// default.
+    fn servers(&mut self, source: &ServerSource) -> Vec<Value> {
+        match source {
+            ServerSource::Field(field) => {
+                self.server_fields.insert(field.to_string());
+                let mut profiles: Vec<&Profile> =
+                    self.collection.profiles.values().collect();
+                profiles.sort_by_key(|profile| !profile.default);
+                profiles
+                    .into_iter()
+                    .filter_map(|profile| {
+                        let url = profile.data.get(field.as_str())?;
+                        Some(self.server(profile, field, url))
+                    })
+                    .collect()
+            }
+            ServerSource::Origin(origin) => vec![json!({"url": origin})],
+        }
+    }
+
+    /// Build a server from a profile's value for a server field. Other
+    /// fields used in the URL become server variables, with the profile's
+    /// value as the default.
+    fn server(
+        &mut self,
+        profile: &Profile,
+        field: &Identifier,
+        url: &Template,
+    ) -> Value {
+        let mut variables = Map::new();
+        let url = url.display_with(|key| match key {
+            TemplateKey::Field(variable)
+                if variable != field
+                    && profile.data.contains_key(variable.as_str()) =>
+            {
+                let value = &profile.data[variable.as_str()];
+                let default = self.export_template(value);
+                self.server_fields.insert(variable.to_string());
+                variables
+                    .insert(variable.to_string(), json!({"default": default}));
+                format!("{{{variable}}}")
+            }
+            _ => self.export_key(key),
+        });
+        let mut server = json!({"url": url, "description": profile.name()});
+        if !variables.is_empty() {
+            server["variables"] = variables.into();
+        }
+        server
+    }
+
+    /// Report profile fields that weren't exported as part of a server
+    fn report_profile_fields(&mut self) {
+        let fields: IndexSet<&str> = self
+            .collection
+            .profiles
+            .values()
+            .flat_map(|profile| profile.data.keys())
+            .map(String::as_str)
+            .filter(|field| !self.server_fields.contains(*field))
+            .collect();
+        if !fields.is_empty() {
+            self.unsupported.add(format!(
+                "Only profile fields used as servers were exported; OpenAPI \
+                has no equivalent for {}",
+                fields
+                    .iter()
+                    .map(|field| format!("`{field}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+
+    /// Convert a template to a string for an example value. Keys are written
+    /// as-is, because OpenAPI has no variables outside of servers.
+    fn export_template(&mut self, template: &Template) -> String {
+        template.display_with(|key| self.export_key(key))
+    }
+
+    fn export_key(&mut self, key: &TemplateKey) -> String {
+        match key {
+            TemplateKey::Field(_) => {}
+            TemplateKey::Chain(id) => self.unsupported.add(format!(
+                "Chain `{id}` has no OpenAPI equivalent; exported as-is"
+            )),
+            TemplateKey::Environment(variable) => {
+                self.unsupported.add(format!(
+                    "Environment variable `{variable}` has no OpenAPI \
+                    equivalent; exported as-is"
+                ))
+            }
+        }
+        format!("{{{{{key}}}}}")
+    }
+}
+
+/// Get the most common item. Ties are broken by whichever appears first
+fn most_common<'a, T: Eq + std::hash::Hash>(
+    items: impl Iterator<Item = &'a T>,
+) -> Option<&'a T> {
+    let mut counts: IndexMap<&T, usize> = IndexMap::new();
+    for item in items {
+        *counts.entry(item).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(item, _)| item)
+}
+
+/// Generate a schema that describes an example value
+fn infer_schema(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({"nullable": true}),
+        Value::Bool(_) => json!({"type": "boolean"}),
+        Value::Number(number) if number.is_f64() => json!({"type": "number"}),
+        Value::Number(_) => json!({"type": "integer"}),
+        Value::String(_) => json!({"type": "string"}),
+        Value::Array(values) => json!({
+            "type": "array",
+            "items": values.first().map(infer_schema).unwrap_or(json!({})),
+        }),
+        Value::Object(map) => {
+            let properties = map
+                .iter()
+                .map(|(key, value)| (key.clone(), infer_schema(value)))
+                .collect::<Map<_, _>>();
+            json!({"type": "object", "properties": properties})
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_data_dir;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use std::{fs::File, path::PathBuf};
+
+    /// Collection used for all export tests
+    const EXPORT_FILE: &str = "export.yml";
+    const OPENAPI_EXPORTED_FILE: &str = "export_openapi.yml";
+
+    /// Catch-all test for OpenAPI export
+    #[rstest]
+    fn test_openapi_export(test_data_dir: PathBuf) {
+        let collection =
+            Collection::load(&test_data_dir.join(EXPORT_FILE)).unwrap();
+        let exported = collection.to_openapi("Test");
+        let expected: Value = serde_yaml::from_reader(
+            File::open(test_data_dir.join(OPENAPI_EXPORTED_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(exported.output, expected);
+        assert_eq!(
+            exported.unsupported,
+            [
+                "Protobuf schema of recipe `get_user` has no OpenAPI \
+                equivalent",
+                "Query parameter `include` of recipe `get_user` is repeated; \
+                only the first value was exported",
+                "Header `accept` of recipe `get_user` can't be an OpenAPI \
+                parameter; it was dropped",
+                "Environment variable `HOME` has no OpenAPI equivalent; \
+                exported as-is",
+                "Folder `admin` was flattened; OpenAPI tags can't be nested",
+                "Recipe `get_user_copy` has the same method and path as \
+                `get_user`; it was skipped",
+                "Only profile fields used as servers were exported; OpenAPI \
+                has no equivalent for `token_value`",
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::null(json!(null), json!({"nullable": true}))]
+    #[case::integer(json!(3), json!({"type": "integer"}))]
+    #[case::float(json!(3.5), json!({"type": "number"}))]
+    #[case::empty_array(json!([]), json!({"type": "array", "items": {}}))]
+    #[case::object(
+        json!({"name": "fido", "tags": ["good"]}),
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "tags": {"type": "array", "items": {"type": "string"}},
+            },
+        }),
+    )]
+    fn test_infer_schema(#[case] value: Value, #[case] expected: Value) {
+        assert_eq!(infer_schema(&value), expected);
+    }
+}
@@ -0,0 +1,427 @@
+//! Merge a fresh OpenAPI import into an existing collection, so a collection
+//! can be kept in sync with a changing spec without losing hand-written
+//! changes.
+//!
+//! - Recipes are matched by ID (i.e. operation ID). If there's no match by ID,
+//!   they're matched by method and URL
+//! - New operations are added as recipes, in the folder of their tag
+//! - For matched recipes, the method and URL are updated if they changed.
+//!   A URL is only considered changed if its text differs outside of template
+//!   keys, so a path parameter that was re-pointed to a different field or
+//!   chain is kept
+//! - New query parameters, headers, bodies and authentication are added.
+//!   Existing values are never replaced, and values that are no longer in the
+//!   spec are flagged but kept
+//! - Recipes that aren't in the spec are flagged but kept. There's no way to
+//!   tell whether they were removed from the spec or written by hand
+//! - New chains, profiles and profile fields are added. Existing chains and
+//!   profile values are never modified
+
+use crate::collection::{
+    Chain, ChainId, Collection, DuplicateRecipeIdError, Folder, Profile,
+    ProfileId, Recipe, RecipeId, RecipeNode, RecipeTree,
+};
+use indexmap::IndexMap;
+use std::{collections::HashSet, mem, path::Path};
+use tracing::{error, info};
+
+/// Everything that changed when merging an import into a collection
+#[derive(Debug, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct MergeReport {
+    /// Recipes for operations that are new in the spec
+    pub added: Vec<RecipeId>,
+    /// Recipes that matched an operation in the spec, with a description of
+    /// each change. Recipes without any changes are omitted.
+    pub updated: IndexMap<RecipeId, Vec<String>>,
+    /// Recipes that matched an operation in the spec, but have values that
+    /// aren't in the spec. These values are left unchanged.
+    pub flagged: IndexMap<RecipeId, Vec<String>>,
+    /// Recipes in the collection that don't match any operation in the spec.
+    /// Either the operation was removed from the spec, or the recipe was
+    /// written by hand. These are left unchanged.
+    pub missing: Vec<RecipeId>,
+    pub added_chains: Vec<ChainId>,
+    pub added_profiles: Vec<ProfileId>,
+    /// New fields in existing profiles
+    pub added_fields: Vec<(ProfileId, String)>,
+}
+
+impl MergeReport {
+    /// Did the merge change nothing? Flagged values (including missing
+    /// recipes) aren't considered changes
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.updated.is_empty()
+            && self.added_chains.is_empty()
+            && self.added_profiles.is_empty()
+            && self.added_fields.is_empty()
+    }
+}
+
+impl Collection {
+    /// Merge an updated OpenAPI spec into this collection. See the module
+    /// documentation for how each part of the collection is merged.
+    ///
+    /// This is not async because it's only called by the CLI, where we don't
+    /// care about blocking. It keeps the code simpler.
+    pub fn merge_openapi(
+        self,
+        openapi_file: impl AsRef<Path>,
+    ) -> anyhow::Result<(Self, MergeReport)> {
+        let imported = Self::from_openapi(openapi_file)?;
+        let merged = self.merge(imported)?;
+        Ok(merged)
+    }
+
+    /// Merge an imported collection into this one
+    fn merge(
+        mut self,
+        imported: Collection,
+    ) -> Result<(Self, MergeReport), DuplicateRecipeIdError> {
+        let mut report = MergeReport::default();
+        merge_profiles(&mut self.profiles, imported.profiles, &mut report);
+        merge_chains(&mut self.chains, imported.chains, &mut report);
+
+        let mut tree = mem::take(&mut self.recipes).into_roots();
+        let existing_ids: Vec<RecipeId> = recipes_mut(&mut tree)
+            .into_iter()
+            .map(|recipe| recipe.id.clone())
+            .collect();
+        let mut matched: HashSet<RecipeId> = HashSet::new();
+
+        for (folder, recipe) in flatten(imported.recipes.into_roots(), None) {
+            let existing = find_match(&mut tree, &recipe, &matched);
+            match existing {
+                Some(existing) => {
+                    matched.insert(existing.id.clone());
+                    let (changes, flags) = update_recipe(existing, recipe);
+                    if !changes.is_empty() {
+                        report.updated.insert(existing.id.clone(), changes);
+                    }
+                    if !flags.is_empty() {
+                        report.flagged.insert(existing.id.clone(), flags);
+                    }
+                }
+                None => {
+                    if let Some(id) = insert_recipe(&mut tree, folder, recipe) {
+                        matched.insert(id.clone());
+                        report.added.push(id);
+                    }
+                }
+            }
+        }
+
+        report.missing = existing_ids
+            .into_iter()
+            .filter(|id| !matched.contains(id))
+            .collect();
+        self.recipes = RecipeTree::new(tree)?;
+        info!(?report, "Merged OpenAPI import into collection");
+        Ok((self, report))
+    }
+}
+
+/// Add new profiles, and new fields to existing profiles
+fn merge_profiles(
+    profiles: &mut IndexMap<ProfileId, Profile>,
+    imported: IndexMap<ProfileId, Profile>,
+    report: &mut MergeReport,
+) {
+    for (id, profile) in imported {
+        match profiles.get_mut(&id) {
+            Some(existing) => {
+                for (field, value) in profile.data {
+                    if !existing.data.contains_key(&field) {
+                        report.added_fields.push((id.clone(), field.clone()));
+                        existing.data.insert(field, value);
+                    }
+                }
+            }
+            None => {
+                report.added_profiles.push(id.clone());
+                profiles.insert(id, profile);
+            }
+        }
+    }
+}
+
+/// Add new chains. Existing chains are never modified
+fn merge_chains(
+    chains: &mut IndexMap<ChainId, Chain>,
+    imported: IndexMap<ChainId, Chain>,
+    report: &mut MergeReport,
+) {
+    for (id, chain) in imported {
+        if !chains.contains_key(&id) {
+            report.added_chains.push(id.clone());
+            chains.insert(id, chain);
+        }
+    }
+}
+
+/// ID and name of a folder that a recipe was imported in
+type ImportedFolder = (RecipeId, Option<String>);
+
+/// Flatten imported nodes into recipes, each with the folder it was in. The
+/// importer only generates one level of folders.
+fn flatten(
+    nodes: IndexMap<RecipeId, RecipeNode>,
+    parent: Option<&ImportedFolder>,
+) -> Vec<(Option<ImportedFolder>, Recipe)> {
+    nodes
+        .into_values()
+        .flat_map(|node| match node {
+            RecipeNode::Recipe(recipe) => vec![(parent.cloned(), recipe)],
+            RecipeNode::Folder(folder) => {
+                let parent = (folder.id, folder.name);
+                flatten(folder.children, Some(&parent))
+            }
+        })
+        .collect()
+}
+
+/// Get mutable references to every recipe in the tree
+fn recipes_mut(nodes: &mut IndexMap<RecipeId, RecipeNode>) -> Vec<&mut Recipe> {
+    nodes
+        .values_mut()
+        .flat_map(|node| match node {
+            RecipeNode::Recipe(recipe) => vec![recipe],
+            RecipeNode::Folder(folder) => recipes_mut(&mut folder.children),
+        })
+        .collect()
+}
+
+/// Find the existing recipe that corresponds to an imported one. Match by ID
+/// first, then by method and URL. Recipes that were already matched are
+/// skipped, so two operations can't be merged into the same recipe.
+fn find_match<'a>(
+    tree: &'a mut IndexMap<RecipeId, RecipeNode>,
+    imported: &Recipe,
+    matched: &HashSet<RecipeId>,
+) -> Option<&'a mut Recipe> {
+    let mut recipes = recipes_mut(tree);
+    let index = recipes
+        .iter()
+        .position(|recipe| recipe.id == imported.id)
+        .or_else(|| {
+            recipes.iter().position(|recipe| {
+                !matched.contains(&recipe.id)
+                    && recipe.method == imported.method
+                    && url_shape(recipe) == url_shape(imported)
+            })
+        })
+        .filter(|index| !matched.contains(&recipes[*index].id))?;
+    Some(recipes.swap_remove(index))
+}
+
+/// Get a recipe's URL with each template key blanked out. Two URLs with the
+/// same shape differ only in which fields/chains they use.
+fn url_shape(recipe: &Recipe) -> String {
+    recipe.url.display_with(|_| "{}".into())
+}
+
+/// Apply changes from the spec to an existing recipe. Return a description of
+/// each change, and of each value that's no longer in the spec
+fn update_recipe(
+    existing: &mut Recipe,
+    imported: Recipe,
+) -> (Vec<String>, Vec<String>) {
+    let mut changes = Vec::new();
+    let mut flags = Vec::new();
+    if existing.method != imported.method {
+        changes.push(format!(
+            "Method changed from {} to {}",
+            existing.method, imported.method
+        ));
+        existing.method = imported.method;
+    }
+    if url_shape(existing) != url_shape(&imported) {
+        changes.push(format!(
+            "URL changed from `{}` to `{}`",
+            existing.url.display(),
+            imported.url.display()
+        ));
+        existing.url = imported.url;
+    }
+
+    let mut flagged_params = HashSet::new();
+    for (param, _) in &existing.query {
+        if !imported.query.iter().any(|(imported, _)| imported == param)
+            && flagged_params.insert(param)
+        {
+            flags.push(format!("Query parameter `{param}` is not in the spec"));
+        }
+    }
+    for (param, value) in imported.query {
+        if !existing
+            .query
+            .iter()
+            .any(|(existing, _)| *existing == param)
+        {
+            changes.push(format!("Added query parameter `{param}`"));
+            existing.query.push((param, value));
+        }
+    }
+
+    let has_header = |headers: &IndexMap<String, _>, name: &str| {
+        headers
+            .keys()
+            .any(|header| header.eq_ignore_ascii_case(name))
+    };
+    for header in existing.headers.keys() {
+        if !has_header(&imported.headers, header) {
+            flags.push(format!("Header `{header}` is not in the spec"));
+        }
+    }
+    for (header, value) in imported.headers {
+        if !has_header(&existing.headers, &header) {
+            changes.push(format!("Added header `{header}`"));
+            existing.headers.insert(header, value);
+        }
+    }
+
+    match (&existing.body, imported.body) {
+        (None, Some(body)) => {
+            changes.push("Added body".into());
+            existing.body = Some(body);
+        }
+        (Some(_), None) => flags.push("Body is not in the spec".into()),
+        _ => {}
+    }
+    match (&existing.authentication, imported.authentication) {
+        (None, Some(authentication)) => {
+            changes.push("Added authentication".into());
+            existing.authentication = Some(authentication);
+        }
+        (Some(_), None) => {
+            flags.push("Authentication is not in the spec".into())
+        }
+        _ => {}
+    }
+    (changes, flags)
+}
+
+/// Insert a new recipe into the folder it was imported in. If the folder
+/// doesn't exist, it's created at the root. Return the ID of the recipe, or
+/// `None` if it couldn't be inserted because its ID is taken by a folder.
+fn insert_recipe(
+    tree: &mut IndexMap<RecipeId, RecipeNode>,
+    folder: Option<ImportedFolder>,
+    recipe: Recipe,
+) -> Option<RecipeId> {
+    fn contains(nodes: &IndexMap<RecipeId, RecipeNode>, id: &RecipeId) -> bool {
+        nodes.iter().any(|(node_id, node)| {
+            node_id == id
+                || matches!(node, RecipeNode::Folder(folder)
+                    if contains(&folder.children, id))
+        })
+    }
+    fn find_folder<'a>(
+        nodes: &'a mut IndexMap<RecipeId, RecipeNode>,
+        id: &RecipeId,
+    ) -> Option<&'a mut Folder> {
+        for node in nodes.values_mut() {
+            if let RecipeNode::Folder(folder) = node {
+                if &folder.id == id {
+                    return Some(folder);
+                }
+                if let Some(folder) = find_folder(&mut folder.children, id) {
+                    return Some(folder);
+                }
+            }
+        }
+        None
+    }
+
+    let id = recipe.id.clone();
+    if contains(tree, &id) {
+        error!(
+            "Cannot add recipe `{id}`; a folder already exists with that ID"
+        );
+        return None;
+    }
+    let Some((folder_id, folder_name)) = folder else {
+        tree.insert(id.clone(), recipe.into());
+        return Some(id);
+    };
+    if let Some(folder) = find_folder(tree, &folder_id) {
+        folder.children.insert(id.clone(), recipe.into());
+    } else if contains(tree, &folder_id) {
+        // A recipe has the folder's ID, so put this in the root instead
+        error!(
+            "Cannot create folder `{folder_id}`; a recipe already exists with \
+            that ID"
+        );
+        tree.insert(id.clone(), recipe.into());
+    } else {
+        let folder = Folder {
+            id: folder_id.clone(),
+            name: folder_name,
+            children: [(id.clone(), recipe.into())].into_iter().collect(),
+        };
+        tree.insert(folder_id, folder.into());
+    }
+    Some(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_data_dir;
+    use indexmap::indexmap;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use std::path::PathBuf;
+
+    /// Merge an updated spec into a collection that was imported from an
+    /// older version of it, then edited by hand
+    #[rstest]
+    fn test_openapi_merge(test_data_dir: PathBuf) {
+        let existing =
+            Collection::load(&test_data_dir.join("openapi_merge_existing.yml"))
+                .unwrap();
+        let (merged, report) = existing
+            .merge_openapi(test_data_dir.join("openapi_merge.yml"))
+            .unwrap();
+        let expected =
+            Collection::load(&test_data_dir.join("openapi_merge_merged.yml"))
+                .unwrap();
+        assert_eq!(merged, expected);
+        assert_eq!(
+            report,
+            MergeReport {
+                added: vec!["deleteUser".into(), "health".into()],
+                updated: indexmap! {
+                    "listUsers".into() =>
+                        vec!["Added query parameter `limit`".into()],
+                    "getUser".into() =>
+                        vec!["Added header `X-Request-Id`".into()],
+                },
+                flagged: indexmap! {
+                    "listUsers".into() =>
+                        vec!["Header `X-Debug` is not in the spec".into()],
+                },
+                missing: vec!["getOrders".into()],
+                added_chains: vec![],
+                added_profiles: vec![],
+                added_fields: vec![
+                    ("https://example.com/api".into(), "limit".into()),
+                    ("https://example.com/api".into(), "X-Request-Id".into()),
+                ],
+            }
+        );
+        assert!(!report.is_empty());
+    }
+
+    /// Merging a spec into its own import changes nothing
+    #[rstest]
+    fn test_openapi_merge_unchanged(test_data_dir: PathBuf) {
+        let path = test_data_dir.join("openapi_merge.yml");
+        let existing = Collection::from_openapi(&path).unwrap();
+        let (merged, report) = existing.merge_openapi(&path).unwrap();
+        assert_eq!(merged, Collection::from_openapi(&path).unwrap());
+        assert_eq!(report, MergeReport::default());
+        assert!(report.is_empty());
+    }
+}
@@ -4,15 +4,26 @@
 //! Spec: <https://spec.openapis.org/oas/v3.0.3#reference-object>
 //!
 //! This module is an implementation of an easily-extendable resolver for
-//! components stored inside an OpenAPI specifications.
+//! components stored inside an OpenAPI specifications. References to other
+//! files are handled separately: before the spec is deserialized, they're
+//! inlined into the root document by [resolve_external_references].
 
+use crate::util::ResultTraced;
+use anyhow::{anyhow, Context};
 use indexmap::IndexMap;
 use openapiv3::{
     Components, Example, Parameter, ReferenceOr, RequestBody, Schema,
     SecurityScheme,
 };
-use std::borrow::Cow;
+use serde_json::{json, Value};
+use std::{
+    borrow::{Borrow, Cow},
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
+use tracing::{debug, error};
 use winnow::{
     combinator::{preceded, rest},
     error::ErrorKind,
@@ -20,7 +31,8 @@ use winnow::{
 };
 
 /// Helper struct for resolving references within a single OpenAPI spec. This
-/// does *not* resolve references across multiple files.
+/// does *not* resolve references across multiple files; those should already
+/// have been inlined by [resolve_external_references].
 pub struct ReferenceResolver(Components);
 
 /// An error that can occur while resolving a reference
@@ -139,6 +151,21 @@ impl ReferenceResolver {
         }
     }
 
+    /// Resolve a borrowed [ReferenceOr] into a reference to the contained item.
+    /// Unlike [Self::resolve], this never clones. The item can be boxed, as is
+    /// the case with nested schemas.
+    pub fn resolve_ref<'r, T: ComponentKind>(
+        &'r self,
+        reference_or: &'r ReferenceOr<impl Borrow<T>>,
+    ) -> Result<&'r T, ResolveError> {
+        match reference_or {
+            ReferenceOr::Item(item) => Ok(item.borrow()),
+            ReferenceOr::Reference { reference } => {
+                self.get_by_reference(reference)
+            }
+        }
+    }
+
     /// Resolve a reference URI. The reference must refer to an object of a
     /// statically known type (`T`), and must be in the same file.
     fn get_by_reference<T: ComponentKind>(
@@ -191,10 +218,263 @@ fn parse_reference<T: ComponentKind>(
         })
 }
 
+/// Replace references to other files with the referenced values, so the spec
+/// can be deserialized and resolved as a single document. `path` is the
+/// location of the spec, which relative file paths are resolved against.
+///
+/// - Files can be YAML or JSON, and each file is only loaded once
+/// - References within a referenced file (e.g. `#/Address` inside
+///   `schemas/user.yaml`) are resolved relative to that file
+/// - References back into the root document become local references
+/// - Schemas that refer to themselves (directly or indirectly) can't be
+///   inlined. They're added to the root document's schemas instead, and
+///   referenced from there
+///
+/// References that can't be resolved are logged and left in place. Remote
+/// (URL) references aren't supported.
+pub fn resolve_external_references(spec: &mut Value, path: &Path) {
+    let root = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+    // Swagger 2.0 specs keep their schemas under a different key
+    let schemas_pointer = if spec.get("swagger").is_some() {
+        "/definitions"
+    } else {
+        "/components/schemas"
+    };
+    let existing_schemas = spec
+        .pointer(schemas_pointer)
+        .and_then(Value::as_object)
+        .map(|schemas| schemas.keys().cloned().collect())
+        .unwrap_or_default();
+    let mut resolver = ExternalResolver {
+        root: root.clone(),
+        schemas_pointer,
+        existing_schemas,
+        documents: HashMap::new(),
+        stack: Vec::new(),
+        hoisted: HashMap::new(),
+        hoisted_schemas: IndexMap::new(),
+    };
+    resolver.resolve_value(spec, &root);
+
+    if resolver.hoisted_schemas.is_empty() {
+        return;
+    }
+    let schemas =
+        schemas_pointer.split('/').skip(1).fold(spec, |value, key| {
+            if !value.get(key).is_some_and(Value::is_object) {
+                value[key] = json!({});
+            }
+            &mut value[key]
+        });
+    if let Value::Object(schemas) = schemas {
+        schemas.extend(resolver.hoisted_schemas);
+    }
+}
+
+/// A value within a file, identified by the file's canonical path and a JSON
+/// pointer
+type Location = (PathBuf, String);
+
+/// State for [resolve_external_references]
+struct ExternalResolver {
+    /// Canonical path of the root spec
+    root: PathBuf,
+    /// Where named schemas are stored in the root spec
+    schemas_pointer: &'static str,
+    /// Names of the schemas defined in the root spec, to prevent hoisted
+    /// schemas from colliding with them
+    existing_schemas: Vec<String>,
+    /// Cache of loaded files, keyed by canonical path
+    documents: HashMap<PathBuf, Value>,
+    /// Locations that are currently being inlined, to detect cycles
+    stack: Vec<Location>,
+    /// Locations that are part of a cycle, mapped to the name of the schema
+    /// they'll be stored under in the root spec
+    hoisted: HashMap<Location, String>,
+    /// Fully resolved values for each hoisted location, keyed by name
+    hoisted_schemas: IndexMap<String, Value>,
+}
+
+impl ExternalResolver {
+    /// Replace all external references in a value, which was loaded from
+    /// `file`
+    fn resolve_value(&mut self, value: &mut Value, file: &Path) {
+        /// Keys whose values are user data, rather than part of the spec
+        const DATA_KEYS: &[&str] = &["example", "default", "enum", "const"];
+
+        match value {
+            Value::Object(object) => {
+                if let Some(Value::String(reference)) = object.get("$ref") {
+                    let reference = reference.clone();
+                    if let Some(resolved) =
+                        self.resolve_reference(&reference, file)
+                    {
+                        *value = resolved;
+                    }
+                    return;
+                }
+                for (key, value) in object.iter_mut() {
+                    if !DATA_KEYS.contains(&key.as_str())
+                        && !key.starts_with("x-")
+                    {
+                        self.resolve_value(value, file);
+                    }
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    self.resolve_value(value, file);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Get the value that a reference should be replaced with. Return `None`
+    /// if the reference should be left as is, either because it's local to
+    /// the root spec or because it couldn't be resolved.
+    fn resolve_reference(
+        &mut self,
+        reference: &str,
+        file: &Path,
+    ) -> Option<Value> {
+        let (target_file, pointer) =
+            reference.split_once('#').unwrap_or((reference, ""));
+        let target = if target_file.is_empty() {
+            if file == self.root {
+                // Local references are handled by [ReferenceResolver]
+                return None;
+            }
+            file.to_owned()
+        } else if target_file.contains("://") {
+            error!(
+                "Unsupported reference `{reference}`: remote references are \
+                not supported"
+            );
+            return None;
+        } else {
+            let path = file.parent().unwrap_or(Path::new("")).join(target_file);
+            path.canonicalize()
+                .with_context(|| {
+                    format!(
+                        "Unresolved reference `{reference}` in {file:?}: \
+                        error opening {path:?}"
+                    )
+                })
+                .traced()
+                .ok()?
+        };
+
+        if target == self.root {
+            return Some(json!({ "$ref": format!("#{pointer}") }));
+        }
+
+        let location = (target, pointer.to_owned());
+        if let Some(name) = self.hoisted.get(&location) {
+            return Some(self.schema_reference(name));
+        }
+        if self.stack.contains(&location) {
+            // We're already inside this value, so inlining it would never
+            // end. Store it as a named schema instead
+            let name = self.hoisted_name(&location);
+            debug!(
+                "Reference cycle through `{reference}`; \
+                storing as schema `{name}`"
+            );
+            let value = self.schema_reference(&name);
+            self.hoisted.insert(location, name);
+            return Some(value);
+        }
+
+        let mut value = self
+            .load(&location.0)?
+            .pointer(pointer)
+            .cloned()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unresolved reference `{reference}` in {file:?}: \
+                    no value at `{pointer}` in {target:?}",
+                    target = location.0,
+                )
+            })
+            .traced()
+            .ok()?;
+
+        let target = location.0.clone();
+        self.stack.push(location);
+        self.resolve_value(&mut value, &target);
+        let location = self.stack.pop().expect("Pushed above");
+
+        match self.hoisted.get(&location) {
+            Some(name) => {
+                let reference = self.schema_reference(name);
+                self.hoisted_schemas.insert(name.clone(), value);
+                Some(reference)
+            }
+            None => Some(value),
+        }
+    }
+
+    /// Load a file, or get it from the cache
+    fn load(&mut self, path: &Path) -> Option<&Value> {
+        if !self.documents.contains_key(path) {
+            let document = fs::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| {
+                    // The format can be YAML or JSON, so we can just treat it
+                    // all as YAML
+                    let document: serde_yaml::Value =
+                        serde_yaml::from_str(&content)?;
+                    Ok(serde_json::to_value(document)?)
+                })
+                .with_context(|| {
+                    format!("Error loading referenced file {path:?}")
+                })
+                .traced()
+                .ok()?;
+            self.documents.insert(path.to_owned(), document);
+        }
+        self.documents.get(path)
+    }
+
+    /// Generate a unique name for a hoisted schema, based on the last segment
+    /// of its pointer or the file name
+    fn hoisted_name(&self, (path, pointer): &Location) -> String {
+        let base = pointer
+            .rsplit('/')
+            .next()
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+            .or_else(|| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "schema".to_owned());
+        let is_taken = |name: &str| {
+            self.hoisted.values().any(|hoisted| hoisted == name)
+                || self
+                    .existing_schemas
+                    .iter()
+                    .any(|existing| existing == name)
+        };
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while is_taken(&name) {
+            name = format!("{base}_{suffix}");
+            suffix += 1;
+        }
+        name
+    }
+
+    fn schema_reference(&self, name: &str) -> Value {
+        json!({ "$ref": format!("#{}/{name}", self.schemas_pointer) })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::assert_err;
+    use crate::{assert_err, test_util::test_data_dir};
     use indexmap::{indexmap, IndexMap};
     use openapiv3::{Components, OAuth2Flows};
     use rstest::rstest;
@@ -287,4 +567,69 @@ mod tests {
         let result = components.get_by_reference::<RequestBody>(reference);
         assert_err!(result, expected_error);
     }
+
+    /// Test inlining references to other files, including a cycle and a
+    /// reference back into the root spec
+    #[rstest]
+    fn test_resolve_external_references(test_data_dir: PathBuf) {
+        let path = test_data_dir.join("openapi_external/openapi.yml");
+        let mut spec = json!({
+            "paths": {"/users": {"post": {"requestBody": {"content": {
+                "application/json": {
+                    "schema": {"$ref": "./schemas/user.yml#/User"},
+                },
+            }}}}},
+            "components": {"schemas": {"Country": {"type": "string"}}},
+        });
+        resolve_external_references(&mut spec, &path);
+
+        let expected_user = json!({
+            "type": "object",
+            "required": ["name", "address", "friends"],
+            "properties": {
+                "name": {"type": "string", "example": "Alice"},
+                "address": {
+                    "type": "object",
+                    "properties": {
+                        "city": {"type": "string", "example": "Wellington"},
+                        "country": {"$ref": "#/components/schemas/Country"},
+                    },
+                },
+                "friends": {
+                    "type": "array",
+                    "items": {"$ref": "#/components/schemas/User"},
+                },
+            },
+        });
+        assert_eq!(
+            spec,
+            json!({
+                "paths": {"/users": {"post": {"requestBody": {"content": {
+                    "application/json": {
+                        "schema": {"$ref": "#/components/schemas/User"},
+                    },
+                }}}}},
+                "components": {"schemas": {
+                    "Country": {"type": "string"},
+                    "User": expected_user,
+                }},
+            })
+        );
+    }
+
+    /// Unresolvable references are left in place
+    #[rstest]
+    #[case::missing_file("./fake.yml#/User")]
+    #[case::missing_pointer("./parameters.json#/fake")]
+    #[case::remote("https://example.com/openapi.yml#/User")]
+    fn test_resolve_external_references_error(
+        test_data_dir: PathBuf,
+        #[case] reference: &str,
+    ) {
+        let path = test_data_dir.join("openapi_external/openapi.yml");
+        let mut spec = json!({"schema": {"$ref": reference}});
+        let expected = spec.clone();
+        resolve_external_references(&mut spec, &path);
+        assert_eq!(spec, expected);
+    }
 }
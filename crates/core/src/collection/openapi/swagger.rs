@@ -0,0 +1,558 @@
+//! Convert a Swagger 2.0 specification to OpenAPI v3.0, so it can be imported
+//! with the same logic. The conversion only covers what the importer uses, so
+//! the output isn't necessarily a complete v3.0 spec.
+//!
+//! - `host`, `basePath` and `schemes` map to servers
+//! - `definitions`, `responses` and `securityDefinitions` map to components,
+//!   and references to them are rewritten
+//! - `body` and `formData` parameters map to a request body, using the content
+//!   types from `consumes`. Parameter references are resolved inline
+//!
+//! Spec: <https://swagger.io/specification/v2/>
+
+use crate::collection::openapi::visit_objects;
+use serde_json::{json, Map, Value};
+use tracing::error;
+
+/// Content type for bodies and responses, if `consumes`/`produces` isn't
+/// given
+const DEFAULT_MIME: &str = "application/json";
+const FORM_URLENCODED: &str = "application/x-www-form-urlencoded";
+const FORM_MULTIPART: &str = "multipart/form-data";
+/// Fields of a non-body parameter that describe its value. In v3.0 these are
+/// moved into the parameter's schema
+const SCHEMA_FIELDS: &[&str] = &[
+    "type",
+    "format",
+    "items",
+    "default",
+    "maximum",
+    "exclusiveMaximum",
+    "minimum",
+    "exclusiveMinimum",
+    "maxLength",
+    "minLength",
+    "pattern",
+    "maxItems",
+    "minItems",
+    "uniqueItems",
+    "enum",
+    "multipleOf",
+];
+/// Methods that can be defined on a path item
+const METHODS: &[&str] =
+    &["get", "put", "post", "delete", "options", "head", "patch"];
+
+/// Convert a Swagger 2.0 spec to OpenAPI v3.0. If the spec isn't an object,
+/// it's returned unchanged and will fail deserialization
+pub fn upgrade(spec: Value) -> Value {
+    let Value::Object(mut spec) = spec else {
+        return spec;
+    };
+
+    let converter = Converter {
+        consumes: string_list(spec.remove("consumes"))
+            .unwrap_or_else(|| vec![DEFAULT_MIME.to_owned()]),
+        produces: string_list(spec.remove("produces"))
+            .unwrap_or_else(|| vec![DEFAULT_MIME.to_owned()]),
+        parameters: take_object(&mut spec, "parameters"),
+    };
+
+    let servers = build_servers(
+        spec.remove("host"),
+        spec.remove("basePath"),
+        spec.remove("schemes"),
+    );
+
+    let paths: Map<String, Value> = take_object(&mut spec, "paths")
+        .into_iter()
+        .map(|(path, item)| (path, converter.upgrade_path_item(item)))
+        .collect();
+
+    let responses: Map<String, Value> = take_object(&mut spec, "responses")
+        .into_iter()
+        .map(|(name, response)| {
+            (name, upgrade_response(response, &converter.produces))
+        })
+        .collect();
+    let security_schemes: Map<String, Value> =
+        take_object(&mut spec, "securityDefinitions")
+            .into_iter()
+            .filter_map(|(name, scheme)| {
+                let scheme = upgrade_security_scheme(&name, scheme)?;
+                Some((name, scheme))
+            })
+            .collect();
+    let components = json!({
+        "schemas": take_object(&mut spec, "definitions"),
+        "responses": responses,
+        "securitySchemes": security_schemes,
+    });
+
+    spec.remove("swagger");
+    spec.insert("openapi".into(), "3.0.3".into());
+    spec.insert("servers".into(), servers.into());
+    spec.insert("paths".into(), paths.into());
+    spec.insert("components".into(), components);
+
+    let mut spec = Value::Object(spec);
+    upgrade_references(&mut spec);
+    visit_objects(&mut spec, &mut |_, object| upgrade_schema(object));
+    spec
+}
+
+/// Global settings needed to convert each operation
+struct Converter {
+    consumes: Vec<String>,
+    produces: Vec<String>,
+    /// Global parameter definitions, which can be referenced by operations
+    parameters: Map<String, Value>,
+}
+
+impl Converter {
+    fn upgrade_path_item(&self, item: Value) -> Value {
+        let Value::Object(mut item) = item else {
+            return item;
+        };
+        // Path-level parameters are merged into each operation, because body
+        // parameters have to become part of the operation's request body
+        let parameters = match item.remove("parameters") {
+            Some(Value::Array(parameters)) => parameters,
+            _ => Vec::new(),
+        };
+        for method in METHODS {
+            if let Some(Value::Object(operation)) = item.remove(*method) {
+                item.insert(
+                    (*method).to_owned(),
+                    self.upgrade_operation(operation, &parameters).into(),
+                );
+            }
+        }
+        item.into()
+    }
+
+    fn upgrade_operation(
+        &self,
+        mut operation: Map<String, Value>,
+        path_parameters: &[Value],
+    ) -> Map<String, Value> {
+        let consumes = string_list(operation.remove("consumes"))
+            .unwrap_or_else(|| self.consumes.clone());
+        let produces = string_list(operation.remove("produces"))
+            .unwrap_or_else(|| self.produces.clone());
+        operation.remove("schemes");
+
+        let operation_parameters = match operation.remove("parameters") {
+            Some(Value::Array(parameters)) => parameters,
+            _ => Vec::new(),
+        };
+        // Operation parameters override path parameters with the same name
+        // and location
+        let mut merged: Vec<Map<String, Value>> = Vec::new();
+        for parameter in path_parameters.iter().chain(&operation_parameters) {
+            let Some(parameter) = self.resolve_parameter(parameter) else {
+                continue;
+            };
+            let key = |parameter: &Map<String, Value>| {
+                (parameter.get("name").cloned(), parameter.get("in").cloned())
+            };
+            merged.retain(|existing| key(existing) != key(&parameter));
+            merged.push(parameter);
+        }
+
+        let mut parameters = Vec::new();
+        let mut body = None;
+        let mut form_fields = Vec::new();
+        for parameter in merged {
+            match parameter.get("in").and_then(Value::as_str) {
+                Some("body") => body = Some(parameter),
+                Some("formData") => form_fields.push(parameter),
+                _ => parameters.push(upgrade_parameter(parameter)),
+            }
+        }
+        operation.insert("parameters".into(), parameters.into());
+
+        if let Some(body) = body {
+            operation
+                .insert("requestBody".into(), body_request(body, &consumes));
+        } else if !form_fields.is_empty() {
+            operation.insert(
+                "requestBody".into(),
+                form_request(form_fields, &consumes),
+            );
+        }
+
+        let responses = match operation.remove("responses") {
+            Some(Value::Object(responses)) => responses
+                .into_iter()
+                .map(|(status, response)| {
+                    (status, upgrade_response(response, &produces))
+                })
+                .collect(),
+            _ => Map::new(),
+        };
+        operation.insert("responses".into(), responses.into());
+
+        operation
+    }
+
+    /// Get a parameter as an object, resolving it if it's a reference to a
+    /// global parameter. Return `None` if it's invalid
+    fn resolve_parameter(
+        &self,
+        parameter: &Value,
+    ) -> Option<Map<String, Value>> {
+        let parameter = match parameter.get("$ref").and_then(Value::as_str) {
+            Some(reference) => {
+                let parameter = reference
+                    .strip_prefix("#/parameters/")
+                    .and_then(|name| self.parameters.get(name));
+                if parameter.is_none() {
+                    error!("Unknown parameter reference `{reference}`");
+                }
+                parameter?
+            }
+            None => parameter,
+        };
+        parameter.as_object().cloned()
+    }
+}
+
+/// Convert a non-body parameter. Its value constraints move into a schema
+fn upgrade_parameter(mut parameter: Map<String, Value>) -> Value {
+    let schema: Map<String, Value> = SCHEMA_FIELDS
+        .iter()
+        .filter_map(|field| {
+            Some(((*field).to_owned(), parameter.remove(*field)?))
+        })
+        .collect();
+    parameter.remove("collectionFormat");
+    if let Some(example) = parameter.remove("x-example") {
+        parameter.insert("example".into(), example);
+    }
+    parameter.insert("schema".into(), schema.into());
+    parameter.into()
+}
+
+/// Convert a `body` parameter into a request body, with one entry per
+/// content type
+fn body_request(
+    mut parameter: Map<String, Value>,
+    consumes: &[String],
+) -> Value {
+    let schema = parameter.remove("schema").unwrap_or_else(|| json!({}));
+    let media_type = match parameter.remove("x-example") {
+        Some(example) => json!({"schema": schema, "example": example}),
+        None => json!({"schema": schema}),
+    };
+    let content: Map<String, Value> = consumes
+        .iter()
+        .map(|mime| (mime.clone(), media_type.clone()))
+        .collect();
+    json!({
+        "description": parameter.remove("description"),
+        "required": parameter.remove("required").unwrap_or(false.into()),
+        "content": content,
+    })
+}
+
+/// Convert `formData` parameters into a request body. Each parameter becomes
+/// a field of an object schema, and an example is generated so the importer
+/// creates a form with every field.
+fn form_request(fields: Vec<Map<String, Value>>, consumes: &[String]) -> Value {
+    let has_file = fields
+        .iter()
+        .any(|field| field.get("type").and_then(Value::as_str) == Some("file"));
+    let has = |mime: &str| consumes.iter().any(|consumed| consumed == mime);
+    let mime = if has_file || (has(FORM_MULTIPART) && !has(FORM_URLENCODED)) {
+        FORM_MULTIPART
+    } else {
+        FORM_URLENCODED
+    };
+
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    let mut example = Map::new();
+    for mut field in fields {
+        let Some(Value::String(name)) = field.remove("name") else {
+            continue;
+        };
+        if field.remove("required") == Some(true.into()) {
+            required.push(Value::from(name.clone()));
+        }
+        let value = field
+            .remove("x-example")
+            .or_else(|| field.get("default").cloned())
+            .unwrap_or_else(|| "".into());
+        example.insert(name.clone(), value);
+        let schema: Map<String, Value> = SCHEMA_FIELDS
+            .iter()
+            .chain(&["description"])
+            .filter_map(|key| Some(((*key).to_owned(), field.remove(*key)?)))
+            .collect();
+        properties.insert(name, schema.into());
+    }
+
+    json!({
+        "content": {
+            mime: {
+                "schema": {
+                    "type": "object",
+                    "properties": properties,
+                    "required": required,
+                },
+                "example": example,
+            }
+        }
+    })
+}
+
+fn upgrade_response(response: Value, produces: &[String]) -> Value {
+    let Value::Object(mut response) = response else {
+        return response;
+    };
+    if response.contains_key("$ref") {
+        return response.into();
+    }
+    let schema = response.remove("schema");
+    let examples = match response.remove("examples") {
+        Some(Value::Object(examples)) => examples,
+        _ => Map::new(),
+    };
+    // v2 headers describe their value directly instead of with a schema. We
+    // don't use response headers, so it's not worth converting them
+    response.remove("headers");
+
+    if schema.is_some() || !examples.is_empty() {
+        let mut content = Map::new();
+        for mime in produces.iter().chain(examples.keys()) {
+            let mut media_type = Map::new();
+            if let Some(schema) = &schema {
+                media_type.insert("schema".into(), schema.clone());
+            }
+            if let Some(example) = examples.get(mime) {
+                media_type.insert("example".into(), example.clone());
+            }
+            content.insert(mime.clone(), media_type.into());
+        }
+        response.insert("content".into(), content.into());
+    }
+    response.entry("description").or_insert_with(|| "".into());
+    response.into()
+}
+
+fn upgrade_security_scheme(name: &str, scheme: Value) -> Option<Value> {
+    let Value::Object(mut scheme) = scheme else {
+        error!("Invalid security definition `{name}`");
+        return None;
+    };
+    let description = scheme.remove("description");
+    let mut upgraded = match scheme.get("type").and_then(Value::as_str) {
+        Some("basic") => json!({"type": "http", "scheme": "basic"}),
+        Some("apiKey") => json!({
+            "type": "apiKey",
+            "name": scheme.remove("name"),
+            "in": scheme.remove("in"),
+        }),
+        Some("oauth2") => {
+            let flow = match scheme.get("flow").and_then(Value::as_str) {
+                Some("implicit") => "implicit",
+                Some("password") => "password",
+                Some("application") => "clientCredentials",
+                Some("accessCode") => "authorizationCode",
+                flow => {
+                    error!(
+                        "Unknown OAuth2 flow `{flow:?}` for security \
+                        definition `{name}`"
+                    );
+                    return None;
+                }
+            };
+            let mut flow_object = Map::new();
+            for field in ["authorizationUrl", "tokenUrl", "scopes"] {
+                if let Some(value) = scheme.remove(field) {
+                    flow_object.insert(field.into(), value);
+                }
+            }
+            json!({"type": "oauth2", "flows": {flow: flow_object}})
+        }
+        scheme_type => {
+            error!(
+                "Unknown type `{scheme_type:?}` for security definition \
+                `{name}`"
+            );
+            return None;
+        }
+    };
+    if let Some(description) = description {
+        upgraded["description"] = description;
+    }
+    Some(upgraded)
+}
+
+/// Build one server per scheme. If there's no host, the base path is used on
+/// its own
+fn build_servers(
+    host: Option<Value>,
+    base_path: Option<Value>,
+    schemes: Option<Value>,
+) -> Vec<Value> {
+    let base_path = base_path
+        .as_ref()
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim_end_matches('/');
+    let Some(host) = host.as_ref().and_then(Value::as_str) else {
+        return if base_path.is_empty() {
+            Vec::new()
+        } else {
+            vec![json!({"url": base_path})]
+        };
+    };
+
+    // If no schemes are given, the spec says to use the one that was used to
+    // access the spec. We don't know that, so default to HTTPS
+    let schemes = string_list(schemes)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|scheme| scheme == "http" || scheme == "https")
+        .collect::<Vec<_>>();
+    let schemes = if schemes.is_empty() {
+        vec!["https".to_owned()]
+    } else {
+        schemes
+    };
+    schemes
+        .into_iter()
+        .map(|scheme| json!({"url": format!("{scheme}://{host}{base_path}")}))
+        .collect()
+}
+
+/// Rewrite references to top-level definitions to point to components
+fn upgrade_references(spec: &mut Value) {
+    const PREFIXES: &[(&str, &str)] = &[
+        ("#/definitions/", "#/components/schemas/"),
+        ("#/responses/", "#/components/responses/"),
+    ];
+
+    visit_objects(spec, &mut |_, object| {
+        if let Some(Value::String(reference)) = object.get_mut("$ref") {
+            for (old, new) in PREFIXES {
+                if let Some(name) = reference.strip_prefix(old) {
+                    *reference = format!("{new}{name}");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// Convert v2-only schema features to their v3.0 equivalents
+fn upgrade_schema(object: &mut Map<String, Value>) {
+    if object.get("type").and_then(Value::as_str) == Some("file") {
+        object.insert("type".into(), "string".into());
+        object.insert("format".into(), "binary".into());
+    }
+    if let Some(nullable) = object.remove("x-nullable") {
+        object.insert("nullable".into(), nullable);
+    }
+    if let Some(Value::String(property)) = object.get("discriminator") {
+        let discriminator = json!({"propertyName": property});
+        object.insert("discriminator".into(), discriminator);
+    }
+}
+
+/// Get a list of strings, e.g. `consumes` or `schemes`. Return `None` if the
+/// value is missing or invalid
+fn string_list(value: Option<Value>) -> Option<Vec<String>> {
+    match value? {
+        Value::Array(values) => Some(
+            values
+                .into_iter()
+                .filter_map(|value| match value {
+                    Value::String(s) => Some(s),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Remove a field that should be an object. If it's missing or not an object,
+/// return an empty map
+fn take_object(
+    object: &mut Map<String, Value>,
+    key: &str,
+) -> Map<String, Value> {
+    match object.remove(key) {
+        Some(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Test mapping host/basePath/schemes to servers
+    #[rstest]
+    #[case::full(
+        Some("example.com"),
+        Some("/v2/"),
+        Some(json!(["http", "https"])),
+        &["http://example.com/v2", "https://example.com/v2"],
+    )]
+    #[case::default_scheme(
+        Some("example.com"),
+        None,
+        None,
+        &["https://example.com"],
+    )]
+    #[case::unsupported_scheme(
+        Some("example.com"),
+        None,
+        Some(json!(["ws"])),
+        &["https://example.com"],
+    )]
+    #[case::base_path_only(None, Some("/v2"), None, &["/v2"])]
+    #[case::empty(None, None, None, &[])]
+    fn test_build_servers(
+        #[case] host: Option<&str>,
+        #[case] base_path: Option<&str>,
+        #[case] schemes: Option<Value>,
+        #[case] expected: &[&str],
+    ) {
+        let servers = build_servers(
+            host.map(Value::from),
+            base_path.map(Value::from),
+            schemes,
+        );
+        let expected: Vec<Value> =
+            expected.iter().map(|url| json!({"url": url})).collect();
+        assert_eq!(servers, expected);
+    }
+
+    /// Test schema conversions in [upgrade_schema]
+    #[rstest]
+    #[case::file(
+        json!({"type": "file"}),
+        json!({"type": "string", "format": "binary"}),
+    )]
+    #[case::nullable(
+        json!({"type": "string", "x-nullable": true}),
+        json!({"type": "string", "nullable": true}),
+    )]
+    #[case::discriminator(
+        json!({"discriminator": "petType"}),
+        json!({"discriminator": {"propertyName": "petType"}}),
+    )]
+    fn test_upgrade_schema(#[case] schema: Value, #[case] expected: Value) {
+        let Value::Object(mut schema) = schema else {
+            unreachable!()
+        };
+        upgrade_schema(&mut schema);
+        assert_eq!(Value::Object(schema), expected);
+    }
+}
@@ -0,0 +1,199 @@
+//! Convert an OpenAPI v3.1 specification to v3.0, so it can be imported with
+//! the same logic. v3.1 schemas are based on JSON Schema 2020-12, which isn't
+//! backward compatible with v3.0 schemas.
+//!
+//! - `type` arrays map to a single type, plus `nullable` if `null` is
+//!   included. Schemas that allow multiple non-null types are left untyped
+//! - `const` maps to a single-value `enum`
+//! - Numeric `exclusiveMinimum`/`exclusiveMaximum` map to
+//!   `minimum`/`maximum` with the boolean flag set
+//! - The first of a schema's `examples` becomes its `example`
+//! - Webhooks and mutual TLS security schemes have no equivalent, and are
+//!   removed
+//!
+//! Spec: <https://spec.openapis.org/oas/v3.1.0>
+
+use crate::collection::openapi::visit_objects;
+use serde_json::{json, Map, Value};
+use tracing::{info, warn};
+
+/// Methods that can be defined on a path item
+const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+/// Keys whose value is a map of names to schemas, rather than a schema
+/// itself. These could contain a property called e.g. `const`, which
+/// shouldn't be mistaken for the keyword.
+const SCHEMA_MAPS: &[&str] =
+    &["properties", "patternProperties", "$defs", "schemas"];
+
+/// Convert a v3.1 spec to v3.0. If the spec isn't an object, it's returned
+/// unchanged and will fail deserialization
+pub fn downgrade(spec: Value) -> Value {
+    let Value::Object(mut spec) = spec else {
+        return spec;
+    };
+
+    if let Some(Value::Object(webhooks)) = spec.remove("webhooks") {
+        if !webhooks.is_empty() {
+            info!(
+                "Skipping {} webhook(s); webhooks aren't supported",
+                webhooks.len()
+            );
+        }
+    }
+    spec.insert("openapi".into(), "3.0.3".into());
+
+    if let Some(Value::Object(components)) = spec.get_mut("components") {
+        components.remove("pathItems");
+        if let Some(Value::Object(schemes)) =
+            components.get_mut("securitySchemes")
+        {
+            schemes.retain(|name, scheme| {
+                let is_mutual_tls = scheme.get("type").and_then(Value::as_str)
+                    == Some("mutualTLS");
+                if is_mutual_tls {
+                    warn!("Unsupported security scheme `{name}`: mutualTLS");
+                }
+                !is_mutual_tls
+            });
+        }
+    }
+
+    // Paths and responses are optional in v3.1
+    let paths = spec.entry("paths").or_insert_with(|| json!({}));
+    if let Value::Object(paths) = paths {
+        for item in paths.values_mut() {
+            for method in METHODS {
+                if let Some(Value::Object(operation)) = item.get_mut(method) {
+                    operation.entry("responses").or_insert_with(|| json!({}));
+                }
+            }
+        }
+    }
+
+    let mut spec = Value::Object(spec);
+    visit_objects(&mut spec, &mut |parent_key, object| {
+        if !parent_key.is_some_and(|key| SCHEMA_MAPS.contains(&key)) {
+            downgrade_schema(object);
+        }
+    });
+    spec
+}
+
+/// Convert JSON Schema 2020-12 features to their v3.0 equivalents
+fn downgrade_schema(object: &mut Map<String, Value>) {
+    match object.get("type") {
+        Some(Value::Array(types)) => {
+            let nullable =
+                types.iter().any(|value| value.as_str() == Some("null"));
+            let types: Vec<Value> = types
+                .iter()
+                .filter(|value| value.as_str() != Some("null"))
+                .cloned()
+                .collect();
+            match <[Value; 1]>::try_from(types) {
+                Ok([value]) => {
+                    object.insert("type".into(), value);
+                }
+                Err(_) => {
+                    object.remove("type");
+                }
+            }
+            if nullable {
+                object.insert("nullable".into(), true.into());
+            }
+        }
+        Some(Value::String(value)) if value == "null" => {
+            object.remove("type");
+            object.insert("nullable".into(), true.into());
+        }
+        _ => {}
+    }
+
+    if let Some(value) = object.remove("const") {
+        object.entry("enum").or_insert_with(|| json!([value]));
+    }
+
+    for (exclusive, bound) in [
+        ("exclusiveMinimum", "minimum"),
+        ("exclusiveMaximum", "maximum"),
+    ] {
+        if let Some(Value::Number(value)) = object.get(exclusive) {
+            let value = value.clone();
+            object.insert(bound.into(), value.into());
+            object.insert(exclusive.into(), true.into());
+        }
+    }
+
+    // `examples` is only an array in schemas. Elsewhere it's a map
+    if let Some(Value::Array(examples)) = object.get("examples") {
+        let first = examples.first().cloned();
+        object.remove("examples");
+        if let Some(first) = first {
+            object.entry("example").or_insert(first);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Test schema conversions in [downgrade_schema]
+    #[rstest]
+    #[case::nullable_type(
+        json!({"type": ["string", "null"]}),
+        json!({"type": "string", "nullable": true}),
+    )]
+    #[case::multiple_types(
+        json!({"type": ["string", "integer"]}),
+        json!({}),
+    )]
+    #[case::null_type(json!({"type": "null"}), json!({"nullable": true}))]
+    #[case::const_(json!({"const": 3}), json!({"enum": [3]}))]
+    #[case::exclusive_numeric(
+        json!({"exclusiveMinimum": 0, "exclusiveMaximum": 10}),
+        json!({
+            "minimum": 0,
+            "exclusiveMinimum": true,
+            "maximum": 10,
+            "exclusiveMaximum": true,
+        }),
+    )]
+    #[case::exclusive_bool(
+        json!({"minimum": 0, "exclusiveMinimum": true}),
+        json!({"minimum": 0, "exclusiveMinimum": true}),
+    )]
+    #[case::examples(
+        json!({"examples": [1, 2], "type": "integer"}),
+        json!({"example": 1, "type": "integer"}),
+    )]
+    fn test_downgrade_schema(#[case] schema: Value, #[case] expected: Value) {
+        let Value::Object(mut schema) = schema else {
+            unreachable!()
+        };
+        downgrade_schema(&mut schema);
+        assert_eq!(Value::Object(schema), expected);
+    }
+
+    /// Properties named after keywords should not be converted
+    #[test]
+    fn test_downgrade_property_names() {
+        let spec = json!({
+            "openapi": "3.1.0",
+            "components": {"schemas": {"Thing": {
+                "properties": {"const": {"type": ["string", "null"]}},
+            }}},
+        });
+        let expected = json!({
+            "openapi": "3.0.3",
+            "paths": {},
+            "components": {"schemas": {"Thing": {
+                "properties": {"const": {"type": "string", "nullable": true}},
+            }}},
+        });
+        assert_eq!(downgrade(spec), expected);
+    }
+}
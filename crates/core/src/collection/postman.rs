@@ -0,0 +1,893 @@
+//! Import request collections from Postman. Based on the Postman Collection
+//! v2.1 format.
+//!
+//! - Folders map to folders and requests map to recipes. Recipe IDs are the
+//!   request names, deduplicated across the whole collection
+//! - Collection variables are included in every profile. If environment files
+//!   are given, each environment becomes a profile; otherwise a single profile
+//!   holds the collection variables
+//! - `{{var}}` references are mapped to profile fields. Dynamic variables such
+//!   as `{{$guid}}` have no equivalent and are left as plain text
+//! - Authentication is inherited from the parent folder or collection, as in
+//!   Postman. Basic and bearer map to `authentication`; API keys map to a
+//!   header or query parameter
+//! - File bodies and file form fields map to chains that load the file
+
+mod export;
+
+use crate::{
+    collection::{
+        self, cereal::HasId, Chain, ChainId, ChainSource, Collection, Folder,
+        JsonTemplate, Method, Profile, ProfileId, Recipe, RecipeBody, RecipeId,
+        RecipeNode, RecipeTree, SelectorMode,
+    },
+    http::content_type::ContentType,
+    template::{Identifier, Template},
+    util::{ResultTraced, NEW_ISSUE_LINK},
+};
+use anyhow::{anyhow, Context};
+use indexmap::IndexMap;
+use regex::Regex;
+use serde::Deserialize;
+use std::{collections::HashSet, fs::File, path::Path, sync::LazyLock};
+use tracing::{debug, error, info, warn};
+
+/// ID of the profile generated when no environments are given
+const PROFILE_ID: &str = "postman";
+
+impl Collection {
+    /// Convert a Postman collection into the slumber format. Each given
+    /// environment file is converted into a profile.
+    ///
+    /// This is not async because it's only called by the CLI, where we don't
+    /// care about blocking. It keeps the code simpler.
+    pub fn from_postman(
+        postman_file: impl AsRef<Path>,
+        environment_files: &[impl AsRef<Path>],
+    ) -> anyhow::Result<Self> {
+        let postman_file = postman_file.as_ref();
+        info!(file = ?postman_file, "Loading Postman collection");
+        warn!(
+            "The Postman importer is approximate. Scripts, tests and some \
+            authentication types are not supported. If you would like to \
+            request support for a particular Postman feature, please open an \
+            issue: {NEW_ISSUE_LINK}"
+        );
+        let postman: PostmanCollection = load_json(postman_file, "collection")?;
+        if !postman.info.schema.contains("v2.1") {
+            warn!(
+                "Importer currently only supports Postman collection v2.1, \
+                this collection uses schema {}. We'll try the import anyway, \
+                but you may experience issues.",
+                postman.info.schema
+            );
+        }
+        let environments = environment_files
+            .iter()
+            .map(|path| load_json(path.as_ref(), "environment"))
+            .collect::<anyhow::Result<Vec<Environment>>>()?;
+
+        let profiles =
+            build_profiles(&postman.info.name, postman.variable, environments);
+        let mut builder = TreeBuilder::default();
+        let tree = builder.build_nodes(postman.item, postman.auth.as_ref());
+
+        Ok(Collection {
+            profiles,
+            recipes: RecipeTree::new(tree)?,
+            chains: builder.chains,
+            _ignore: serde::de::IgnoredAny,
+        })
+    }
+}
+
+/// Load a JSON file exported from Postman
+fn load_json<T: for<'de> Deserialize<'de>>(
+    path: &Path,
+    kind: &str,
+) -> anyhow::Result<T> {
+    let file = File::open(path)
+        .context(format!("Error opening Postman {kind} file {path:?}"))?;
+    serde_json::from_reader(file)
+        .context(format!("Error deserializing Postman {kind} file {path:?}"))
+}
+
+#[derive(Debug, Deserialize)]
+struct PostmanCollection {
+    info: Info,
+    #[serde(default)]
+    item: Vec<Item>,
+    #[serde(default)]
+    variable: Vec<Variable>,
+    auth: Option<Auth>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Info {
+    name: String,
+    #[serde(default)]
+    schema: String,
+}
+
+/// An environment, exported as its own file
+#[derive(Debug, Deserialize)]
+struct Environment {
+    name: String,
+    #[serde(default)]
+    values: Vec<Variable>,
+}
+
+/// A collection or environment variable
+#[derive(Debug, Deserialize)]
+struct Variable {
+    key: String,
+    #[serde(default)]
+    value: serde_json::Value,
+    /// Environments use `enabled` rather than `disabled`
+    #[serde(default = "default_true")]
+    enabled: bool,
+    #[serde(default)]
+    disabled: bool,
+}
+
+/// A node in the collection tree. Folders are distinguished by having
+/// children.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Item {
+    Folder {
+        name: String,
+        item: Vec<Item>,
+        auth: Option<Auth>,
+    },
+    Request {
+        name: String,
+        request: Request,
+    },
+}
+
+/// A request can be just a URL, in which case it's a `GET`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Request {
+    Url(String),
+    Full(Box<FullRequest>),
+}
+
+#[derive(Debug, Deserialize)]
+struct FullRequest {
+    #[serde(default = "default_method")]
+    method: String,
+    url: Option<Url>,
+    #[serde(default)]
+    header: Vec<KeyValue>,
+    body: Option<Body>,
+    auth: Option<Auth>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Url {
+    Raw(String),
+    Parsed {
+        #[serde(default)]
+        raw: String,
+        /// If this is present, it takes precedence over the query in `raw`
+        query: Option<Vec<KeyValue>>,
+        /// Values for path variables, e.g. `:id`
+        #[serde(default)]
+        variable: Vec<KeyValue>,
+    },
+}
+
+/// A header, query parameter or form field
+#[derive(Debug, Deserialize)]
+struct KeyValue {
+    key: String,
+    value: Option<String>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Body {
+    mode: String,
+    raw: Option<String>,
+    #[serde(default)]
+    urlencoded: Vec<KeyValue>,
+    #[serde(default)]
+    formdata: Vec<FormParam>,
+    file: Option<FileSource>,
+    graphql: Option<GraphQl>,
+    options: Option<BodyOptions>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct BodyOptions {
+    raw: Option<RawOptions>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawOptions {
+    language: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileSource {
+    src: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQl {
+    #[serde(default)]
+    query: String,
+    variables: Option<String>,
+}
+
+/// One field in a multipart form
+#[derive(Debug, Deserialize)]
+struct FormParam {
+    key: String,
+    value: Option<String>,
+    #[serde(default, rename = "type")]
+    kind: FormParamKind,
+    /// Path of the file, for file fields only. Postman allows multiple files
+    /// per field, but we only take the first
+    src: Option<FileSrc>,
+    #[serde(default)]
+    disabled: bool,
+}
+
+#[derive(Copy, Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum FormParamKind {
+    #[default]
+    Text,
+    File,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum FileSrc {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Authentication on a collection, folder or request. The parameters of each
+/// type are stored as a list of key-value pairs under a field named after the
+/// type.
+#[derive(Debug, Deserialize)]
+struct Auth {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    basic: Vec<AuthAttribute>,
+    #[serde(default)]
+    bearer: Vec<AuthAttribute>,
+    #[serde(default)]
+    apikey: Vec<AuthAttribute>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthAttribute {
+    key: String,
+    #[serde(default)]
+    value: serde_json::Value,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_method() -> String {
+    "GET".into()
+}
+
+impl Variable {
+    fn is_enabled(&self) -> bool {
+        self.enabled && !self.disabled
+    }
+
+    /// Convert to a profile field. Non-string values are stringified
+    fn into_field(self) -> (String, Template) {
+        let value = match self.value {
+            serde_json::Value::Null => String::new(),
+            serde_json::Value::String(value) => value,
+            value => value.to_string(),
+        };
+        (field_name(&self.key), template(&value))
+    }
+}
+
+impl FileSrc {
+    fn first(&self) -> Option<&str> {
+        match self {
+            Self::One(src) => Some(src),
+            Self::Many(srcs) => srcs.first().map(String::as_str),
+        }
+    }
+}
+
+impl Auth {
+    /// Get a parameter of the auth type, as a template
+    fn get(&self, attributes: &[AuthAttribute], key: &str) -> Option<Template> {
+        let attribute = attributes.iter().find(|attr| attr.key == key)?;
+        match &attribute.value {
+            serde_json::Value::String(value) => Some(template(value)),
+            serde_json::Value::Null => None,
+            value => Some(Template::raw(value.to_string())),
+        }
+    }
+}
+
+/// Build profiles from collection variables and environments. Collection
+/// variables are the base for every environment, so they can be overridden.
+fn build_profiles(
+    collection_name: &str,
+    variables: Vec<Variable>,
+    environments: Vec<Environment>,
+) -> IndexMap<ProfileId, Profile> {
+    let base_data: IndexMap<String, Template> = variables
+        .into_iter()
+        .filter(Variable::is_enabled)
+        .map(Variable::into_field)
+        .collect();
+
+    if environments.is_empty() {
+        if base_data.is_empty() {
+            return IndexMap::new();
+        }
+        let id: ProfileId = PROFILE_ID.to_owned().into();
+        return IndexMap::from([(
+            id.clone(),
+            Profile {
+                id,
+                name: Some(collection_name.to_owned()),
+                default: true,
+                data: base_data,
+            },
+        )]);
+    }
+
+    let default = environments.len() == 1;
+    environments
+        .into_iter()
+        .map(|environment| {
+            debug!("Generating profile for environment `{}`", environment.name);
+            let id: ProfileId = environment.name.clone().into();
+            // Start with base data so we can overwrite it
+            let data = base_data
+                .clone()
+                .into_iter()
+                .chain(
+                    environment
+                        .values
+                        .into_iter()
+                        .filter(Variable::is_enabled)
+                        .map(Variable::into_field),
+                )
+                .collect();
+            (
+                id.clone(),
+                Profile {
+                    id,
+                    name: Some(environment.name),
+                    default,
+                    data,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Recursively converts items into recipe nodes, accumulating the chains
+/// needed along the way
+#[derive(Default)]
+struct TreeBuilder {
+    /// Every recipe/folder ID in the tree, so we can generate unique IDs
+    ids: HashSet<RecipeId>,
+    chains: IndexMap<ChainId, Chain>,
+}
+
+impl TreeBuilder {
+    /// Convert a list of sibling items. `auth` is the authentication inherited
+    /// from the parent
+    fn build_nodes(
+        &mut self,
+        items: Vec<Item>,
+        auth: Option<&Auth>,
+    ) -> IndexMap<RecipeId, RecipeNode> {
+        items
+            .into_iter()
+            .map(|item| {
+                let node = match item {
+                    Item::Folder {
+                        name,
+                        item,
+                        auth: folder_auth,
+                    } => {
+                        let id = self.unique_id(&name);
+                        debug!("Generating folder `{id}`");
+                        let children = self
+                            .build_nodes(item, folder_auth.as_ref().or(auth));
+                        RecipeNode::Folder(Folder {
+                            id,
+                            name: Some(name),
+                            children,
+                        })
+                    }
+                    Item::Request { name, request } => {
+                        let id = self.unique_id(&name);
+                        debug!("Generating recipe `{id}`");
+                        RecipeNode::Recipe(
+                            self.build_recipe(id, name, request, auth),
+                        )
+                    }
+                };
+                (node.id().clone(), node)
+            })
+            .collect()
+    }
+
+    /// Recipe IDs have to be unique across the entire tree, but Postman names
+    /// don't. Add a suffix to duplicate names.
+    fn unique_id(&mut self, name: &str) -> RecipeId {
+        let mut id: RecipeId = name.to_owned().into();
+        let mut i = 2;
+        while self.ids.contains(&id) {
+            id = format!("{name} ({i})").into();
+            i += 1;
+        }
+        self.ids.insert(id.clone());
+        id
+    }
+
+    fn build_recipe(
+        &mut self,
+        id: RecipeId,
+        name: String,
+        request: Request,
+        inherited_auth: Option<&Auth>,
+    ) -> Recipe {
+        let request = match request {
+            Request::Url(url) => FullRequest {
+                method: default_method(),
+                url: Some(Url::Raw(url)),
+                header: Vec::new(),
+                body: None,
+                auth: None,
+            },
+            Request::Full(request) => *request,
+        };
+
+        let method: Method = request
+            .method
+            .parse()
+            .map_err(|_| {
+                anyhow!("Unsupported method `{}`; using GET", request.method)
+            })
+            .with_context(|| format!("Error importing request `{id}`"))
+            .traced()
+            .unwrap_or(Method::Get);
+
+        let (url, mut query) = request.url.map(build_url).unwrap_or_default();
+
+        let mut headers: IndexMap<String, Template> = request
+            .header
+            .into_iter()
+            .filter(|header| !header.disabled)
+            .map(|header| {
+                (
+                    header.key.to_lowercase(),
+                    template(&header.value.unwrap_or_default()),
+                )
+            })
+            .collect();
+
+        let mut authentication = None;
+        if let Some(auth) = request.auth.as_ref().or(inherited_auth) {
+            match auth.kind.as_str() {
+                "noauth" => {}
+                "basic" => {
+                    authentication = Some(collection::Authentication::Basic {
+                        username: auth
+                            .get(&auth.basic, "username")
+                            .unwrap_or_default(),
+                        password: auth
+                            .get(&auth.basic, "password")
+                            .filter(|password| !password.is_empty()),
+                    });
+                }
+                "bearer" => {
+                    authentication = Some(collection::Authentication::Bearer(
+                        auth.get(&auth.bearer, "token").unwrap_or_default(),
+                    ));
+                }
+                "apikey" => {
+                    let key = auth
+                        .get(&auth.apikey, "key")
+                        .map(|key| key.display().into_owned())
+                        .unwrap_or_default();
+                    let value =
+                        auth.get(&auth.apikey, "value").unwrap_or_default();
+                    let location = auth
+                        .get(&auth.apikey, "in")
+                        .map(|location| location.display().into_owned());
+                    if location.as_deref() == Some("query") {
+                        query.push((key, value));
+                    } else {
+                        headers.insert(key.to_lowercase(), value);
+                    }
+                }
+                kind => error!(
+                    "Ignoring authentication of unsupported type `{kind}` \
+                    for request `{id}`"
+                ),
+            }
+        }
+
+        let body = request
+            .body
+            .filter(|body| !body.disabled)
+            .and_then(|body| self.build_body(&id, body));
+
+        Recipe {
+            id,
+            name: Some(name),
+            method,
+            url,
+            body,
+            authentication,
+            query,
+            headers,
+            protobuf: None,
+        }
+    }
+
+    /// Convert a request body, based on its mode. Return `None` if the body is
+    /// empty or the mode is unsupported.
+    fn build_body(&mut self, id: &RecipeId, body: Body) -> Option<RecipeBody> {
+        match body.mode.as_str() {
+            "raw" => {
+                let text = body.raw.filter(|text| !text.is_empty())?;
+                let language = body
+                    .options
+                    .and_then(|options| options.raw)
+                    .map(|raw| raw.language);
+                match language.as_deref() {
+                    Some("json") => Some(json_body(id, text, &mut template)),
+                    Some("xml") => Some(RecipeBody::Raw {
+                        body: template(&text),
+                        content_type: Some(ContentType::Xml),
+                    }),
+                    _ => Some(RecipeBody::Raw {
+                        body: template(&text),
+                        content_type: None,
+                    }),
+                }
+            }
+            "urlencoded" => Some(RecipeBody::FormUrlencoded(
+                body.urlencoded
+                    .into_iter()
+                    .filter(|param| !param.disabled)
+                    .map(|param| {
+                        (param.key, template(&param.value.unwrap_or_default()))
+                    })
+                    .collect(),
+            )),
+            "formdata" => Some(RecipeBody::FormMultipart(
+                body.formdata
+                    .into_iter()
+                    .filter(|param| !param.disabled)
+                    .filter_map(|param| self.build_form_param(id, param))
+                    .collect(),
+            )),
+            "file" => {
+                let path = body.file.and_then(|file| file.src)?;
+                Some(RecipeBody::Raw {
+                    body: self.file_chain(&id.to_string(), path),
+                    content_type: None,
+                })
+            }
+            "graphql" => {
+                let graphql = body.graphql?;
+                let variables = graphql
+                    .variables
+                    .filter(|variables| !variables.trim().is_empty())
+                    .map(|variables| {
+                        serde_json::from_str(&variables)
+                            .map(|variables| {
+                                json_template(variables, &mut template)
+                            })
+                            .context("Invalid GraphQL variables; ignoring")
+                            .with_context(|| {
+                                format!("Error importing body for `{id}`")
+                            })
+                            .traced()
+                            .unwrap_or(JsonTemplate::Null)
+                    })
+                    .unwrap_or(JsonTemplate::Null);
+                Some(RecipeBody::Structured {
+                    body: JsonTemplate::Object(IndexMap::from([
+                        (
+                            "query".to_owned(),
+                            JsonTemplate::String(template(&graphql.query)),
+                        ),
+                        ("variables".to_owned(), variables),
+                    ])),
+                    content_type: ContentType::Json,
+                })
+            }
+            mode => {
+                error!("Ignoring body of unsupported mode `{mode}` for `{id}`");
+                None
+            }
+        }
+    }
+
+    /// Convert a multipart form field. File fields are mapped to a chain that
+    /// loads the file
+    fn build_form_param(
+        &mut self,
+        id: &RecipeId,
+        param: FormParam,
+    ) -> Option<(String, Template)> {
+        match param.kind {
+            FormParamKind::Text => {
+                let value = template(&param.value.unwrap_or_default());
+                Some((param.key, value))
+            }
+            FormParamKind::File => {
+                let Some(path) = param.src.as_ref().and_then(FileSrc::first)
+                else {
+                    error!(
+                        "Form field `{}` of request `{id}` is of type `file` \
+                        but has no file; skipping",
+                        param.key
+                    );
+                    return None;
+                };
+                let chain = self.file_chain(
+                    &format!("{id}_{}", param.key),
+                    path.to_owned(),
+                );
+                Some((param.key, chain))
+            }
+        }
+    }
+
+    /// Create a chain that loads a file, and return a template that renders it
+    fn file_chain(&mut self, name: &str, path: String) -> Template {
+        let id: ChainId = Identifier::escape(name).into();
+        self.chains.insert(
+            id.clone(),
+            Chain {
+                id: id.clone(),
+                source: ChainSource::File {
+                    path: template(&path),
+                },
+                sensitive: false,
+                selector: None,
+                selector_mode: SelectorMode::default(),
+                content_type: None,
+                trim: Default::default(),
+            },
+        );
+        Template::from_chain(id)
+    }
+}
+
+/// Convert a URL into a URL template and query parameters. Postman includes
+/// the query in the URL, but we store it separately.
+fn build_url(url: Url) -> (Template, Vec<(String, Template)>) {
+    let (raw, query, variables) = match url {
+        Url::Raw(raw) => (raw, None, Vec::new()),
+        Url::Parsed {
+            raw,
+            query,
+            variable,
+        } => (raw, query, variable),
+    };
+    let (base, raw_query) = match raw.split_once('?') {
+        Some((base, query)) => (base, Some(query)),
+        None => (raw.as_str(), None),
+    };
+
+    // Replace path variables (`/users/:id`) with their values
+    let base = base
+        .split('/')
+        .map(|segment| {
+            segment
+                .strip_prefix(':')
+                .and_then(|name| {
+                    variables.iter().find(|variable| variable.key == name)
+                })
+                .and_then(|variable| variable.value.as_deref())
+                .unwrap_or(segment)
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let query = match query {
+        Some(query) => query
+            .into_iter()
+            .filter(|param| !param.disabled)
+            .map(|param| {
+                (param.key, template(&param.value.unwrap_or_default()))
+            })
+            .collect(),
+        // Postman doesn't encode values in the raw URL, so we don't decode
+        None => raw_query
+            .into_iter()
+            .flat_map(|query| query.split('&'))
+            .filter(|param| !param.is_empty())
+            .map(|param| {
+                let (key, value) = param.split_once('=').unwrap_or((param, ""));
+                (key.to_owned(), template(value))
+            })
+            .collect(),
+    };
+
+    (template(&base), query)
+}
+
+/// Convert a raw JSON body, converting each string with the given template
+/// function. Postman allows variables as bare values (e.g.
+/// `{"id": {{user_id}}}`), which isn't valid JSON. Those are quoted, so they
+/// render as typed values. If the body still isn't valid JSON, it's imported
+/// as text.
+pub(super) fn json_body(
+    id: &RecipeId,
+    text: String,
+    template: &mut impl FnMut(&str) -> Template,
+) -> RecipeBody {
+    static BARE_VARIABLE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#"([:\[,]\s*)(\{\{[^{}"]+\}\})(\s*[,\]}])"#).unwrap()
+    });
+
+    let parsed = serde_json::from_str(&text).or_else(|error| {
+        let quoted = BARE_VARIABLE.replace_all(&text, r#"$1"$2"$3"#);
+        serde_json::from_str(&quoted).map_err(|_| error)
+    });
+    match parsed {
+        Ok(json) => RecipeBody::Structured {
+            body: json_template(json, template),
+            content_type: ContentType::Json,
+        },
+        Err(error) => {
+            error!(
+                "Invalid JSON body for request `{id}`; \
+                importing as raw text: {error}"
+            );
+            RecipeBody::Raw {
+                body: template(&text),
+                content_type: Some(ContentType::Json),
+            }
+        }
+    }
+}
+
+/// Convert JSON to a JSON template, converting variables in each string
+fn json_template(
+    value: serde_json::Value,
+    template: &mut impl FnMut(&str) -> Template,
+) -> JsonTemplate {
+    match value {
+        serde_json::Value::String(s) => JsonTemplate::String(template(&s)),
+        serde_json::Value::Array(values) => JsonTemplate::Array(
+            values
+                .into_iter()
+                .map(|value| json_template(value, template))
+                .collect(),
+        ),
+        serde_json::Value::Object(map) => JsonTemplate::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, json_template(value, template)))
+                .collect(),
+        ),
+        value => JsonTemplate::raw(value),
+    }
+}
+
+/// Convert a Postman string into a template. `{{var}}` becomes a reference to
+/// the profile field of the same name. Everything else, including dynamic
+/// variables like `{{$guid}}`, is raw text.
+fn template(text: &str) -> Template {
+    static VARIABLE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"\{\{([^{}]+)\}\}").unwrap());
+
+    let mut last = 0;
+    let mut chunks = Vec::new();
+    for captures in VARIABLE.captures_iter(text) {
+        let name = captures[1].trim();
+        if name.is_empty() || name.starts_with('$') {
+            continue;
+        }
+        let matched = captures.get(0).unwrap();
+        chunks.push(Template::raw(text[last..matched.start()].to_owned()));
+        chunks.push(Template::from_field(Identifier::escape(name)));
+        last = matched.end();
+    }
+    chunks.push(Template::raw(text[last..].to_owned()));
+    chunks.into_iter().collect()
+}
+
+/// Postman variable names can contain any character, but profile fields can't
+pub(super) fn field_name(name: &str) -> String {
+    if name.is_empty() {
+        // Identifier can't be empty
+        "_".into()
+    } else {
+        Identifier::escape(name).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_data_dir;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    const POSTMAN_FILE: &str = "postman.json";
+    const POSTMAN_ENVIRONMENT_FILE: &str = "postman_environment.json";
+    /// Assertion expectation is stored in a separate file, like the other
+    /// importers
+    const POSTMAN_IMPORTED_FILE: &str = "postman_imported.yml";
+
+    /// Catch-all test for Postman import
+    #[rstest]
+    fn test_postman_import(test_data_dir: PathBuf) {
+        let imported = Collection::from_postman(
+            test_data_dir.join(POSTMAN_FILE),
+            &[test_data_dir.join(POSTMAN_ENVIRONMENT_FILE)],
+        )
+        .unwrap();
+        let expected =
+            Collection::load(&test_data_dir.join(POSTMAN_IMPORTED_FILE))
+                .unwrap();
+        assert_eq!(imported, expected);
+    }
+
+    #[rstest]
+    #[case::raw("https://example.com", "https://example.com")]
+    #[case::variable("{{host}}/users", "{{host}}/users")]
+    #[case::invalid_name("{{base url}}/{{ id }}", "{{base_url}}/{{id}}")]
+    #[case::dynamic("{{$guid}}", "{_{$guid}}")]
+    #[case::braces("{{{token}}}", "{_{{token}}}")]
+    fn test_template(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(template(text).display(), expected);
+    }
+
+    #[rstest]
+    #[case::valid(r#"{"id": 3}"#, json!({"id": 3}))]
+    #[case::bare_variable(
+        r#"{"ids": [{{id}}, 4], "name": "{{name}}"}"#,
+        json!({"ids": ["{{id}}", 4], "name": "{{name}}"})
+    )]
+    fn test_json_body(#[case] text: &str, #[case] expected: serde_json::Value) {
+        assert_eq!(
+            json_body(&"test".into(), text.into(), &mut template),
+            RecipeBody::Structured {
+                body: expected.try_into().unwrap(),
+                content_type: ContentType::Json,
+            }
+        );
+    }
+
+    /// Invalid JSON is imported as text
+    #[test]
+    fn test_json_body_invalid() {
+        assert_eq!(
+            json_body(&"test".into(), r#"{"id": }"#.into(), &mut template),
+            RecipeBody::Raw {
+                body: r#"{"id": }"#.into(),
+                content_type: Some(ContentType::Json),
+            }
+        );
+    }
+}
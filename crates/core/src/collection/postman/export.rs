@@ -0,0 +1,418 @@
+//! Export a collection in the Postman Collection v2.1 format
+//!
+//! - Folders and recipes map to folders and requests
+//! - Profiles map to environments, one per profile
+//! - Profile fields are referenced as `{{field}}`, which Postman resolves from
+//!   the selected environment
+//! - Chains and environment variables have no equivalent. They're referenced
+//!   as variables of the same name, and declared as empty collection
+//!   variables so they can be filled in manually
+//! - Chains that load a file map to file bodies and file form fields
+
+use crate::{
+    collection::{
+        export::{Export, Unsupported},
+        Authentication, Collection, Folder, ProfileId, Recipe, RecipeBody,
+        RecipeNode,
+    },
+    http::content_type::ContentType,
+    template::{Template, TemplateKey},
+};
+use indexmap::{IndexMap, IndexSet};
+use reqwest::header;
+use serde_json::{json, Value};
+
+/// Schema URL that identifies the collection format
+const SCHEMA: &str =
+    "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+impl Collection {
+    /// Convert the collection into a Postman collection. Postman requires a
+    /// name, which isn't stored in the collection so it must be given.
+    /// Profiles aren't included, because they map to environments; see
+    /// [Self::to_postman_environments].
+    pub fn to_postman(&self, name: &str) -> Export<Value> {
+        let mut exporter = PostmanExporter {
+            collection: self,
+            unsupported: Unsupported::default(),
+            variables: IndexSet::new(),
+        };
+        self.report_unsupported("Postman", &mut exporter.unsupported);
+        // Environments are exported separately, but any variables they need
+        // have to be declared in the collection
+        for value in self
+            .profiles
+            .values()
+            .flat_map(|profile| profile.data.values())
+        {
+            exporter.export_template(value);
+        }
+        let items = exporter.items(self.recipes.iter().filter_map(
+            |(lookup_key, node)| {
+                // Only take the root nodes; children are handled recursively
+                (lookup_key.as_slice().len() <= 1).then_some(node)
+            },
+        ));
+
+        let mut collection = json!({
+            "info": {"name": name, "schema": SCHEMA},
+            "item": items,
+        });
+        if !exporter.variables.is_empty() {
+            collection["variable"] = exporter
+                .variables
+                .iter()
+                .map(|key| json!({"key": key, "value": ""}))
+                .collect();
+        }
+        exporter.unsupported.finish(collection)
+    }
+
+    /// Convert each profile into a Postman environment, keyed by profile ID
+    pub fn to_postman_environments(
+        &self,
+    ) -> Export<IndexMap<ProfileId, Value>> {
+        let mut exporter = PostmanExporter {
+            collection: self,
+            unsupported: Unsupported::default(),
+            variables: IndexSet::new(),
+        };
+        let environments = self
+            .profiles
+            .values()
+            .map(|profile| {
+                if profile.default {
+                    exporter.unsupported.add(format!(
+                        "Profile `{}` is the default; Postman environments \
+                        have no default",
+                        profile.id
+                    ));
+                }
+                let values = profile
+                    .data
+                    .iter()
+                    .map(|(field, value)| {
+                        json!({
+                            "key": field,
+                            "value": exporter.export_template(value),
+                            "enabled": true,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                let environment = json!({
+                    "name": profile.name(),
+                    "values": values,
+                    "_postman_variable_scope": "environment",
+                });
+                (profile.id.clone(), environment)
+            })
+            .collect();
+        exporter.unsupported.finish(environments)
+    }
+}
+
+/// State for converting a collection to Postman
+struct PostmanExporter<'a> {
+    collection: &'a Collection,
+    unsupported: Unsupported,
+    /// Collection variables standing in for chains and environment variables,
+    /// which have to be set manually in Postman
+    variables: IndexSet<String>,
+}
+
+impl PostmanExporter<'_> {
+    /// Convert a list of recipe nodes into items
+    fn items<'n>(
+        &mut self,
+        nodes: impl IntoIterator<Item = &'n RecipeNode>,
+    ) -> Vec<Value> {
+        nodes
+            .into_iter()
+            .map(|node| match node {
+                RecipeNode::Folder(folder) => self.folder(folder),
+                RecipeNode::Recipe(recipe) => self.request(recipe),
+            })
+            .collect()
+    }
+
+    fn folder(&mut self, folder: &Folder) -> Value {
+        json!({
+            "name": folder.name(),
+            "item": self.items(folder.children.values()),
+        })
+    }
+
+    fn request(&mut self, recipe: &Recipe) -> Value {
+        let mut url = self.export_template(&recipe.url);
+        let query = recipe
+            .query
+            .iter()
+            .map(|(param, value)| (param, self.export_template(value)))
+            .collect::<Vec<_>>();
+        for (i, (param, value)) in query.iter().enumerate() {
+            let separator = if i == 0 { '?' } else { '&' };
+            url.push_str(&format!("{separator}{param}={value}"));
+        }
+        let mut url = json!({"raw": url});
+        if !query.is_empty() {
+            url["query"] = query
+                .into_iter()
+                .map(|(key, value)| json!({"key": key, "value": value}))
+                .collect();
+        }
+
+        let mut headers = recipe
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                json!({"key": name, "value": self.export_template(value)})
+            })
+            .collect::<Vec<_>>();
+
+        let mut request = json!({
+            "method": recipe.method.to_string(),
+            "url": url,
+        });
+        if let Some(body) = &recipe.body {
+            let (content_type, body) = self.body(body);
+            let has_content_type = recipe.headers.keys().any(|name| {
+                name.eq_ignore_ascii_case(header::CONTENT_TYPE.as_str())
+            });
+            if let (Some(content_type), false) =
+                (content_type, has_content_type)
+            {
+                headers.push(json!({
+                    "key": header::CONTENT_TYPE.as_str(),
+                    "value": content_type,
+                }));
+            }
+            request["body"] = body;
+        }
+        if !headers.is_empty() {
+            request["header"] = headers.into();
+        }
+        if let Some(authentication) = &recipe.authentication {
+            request["auth"] = self.auth(authentication);
+        }
+
+        json!({"name": recipe.name(), "request": request})
+    }
+
+    /// Convert a body, along with the content type that must be sent with it.
+    /// The content type is `None` when Postman sets it automatically.
+    fn body(&mut self, body: &RecipeBody) -> (Option<String>, Value) {
+        match body {
+            RecipeBody::Raw { body, content_type } => {
+                if let Some(path) = self.collection.file_chain_path(body) {
+                    let src = self.export_template(path);
+                    return (
+                        content_type.map(|content_type| {
+                            content_type.to_mime().to_string()
+                        }),
+                        json!({"mode": "file", "file": {"src": src}}),
+                    );
+                }
+                let text = self.export_template(body);
+                raw_body(*content_type, text)
+            }
+            RecipeBody::Structured { body, content_type } => {
+                let value = body.to_source_with(&mut |template| {
+                    self.export_template(template)
+                });
+                let text = match content_type {
+                    ContentType::Json => {
+                        serde_json::to_string_pretty(&value).unwrap()
+                    }
+                    _ => content_type.value_to_string(&value),
+                };
+                raw_body(Some(*content_type), text)
+            }
+            RecipeBody::FormUrlencoded(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, value)| {
+                        json!({
+                            "key": field,
+                            "value": self.export_template(value),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                (None, json!({"mode": "urlencoded", "urlencoded": fields}))
+            }
+            RecipeBody::FormJson(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, value)| {
+                        let value =
+                            match value.to_source_with(&mut |template| {
+                                self.export_template(template)
+                            }) {
+                                Value::String(s) => s,
+                                value => value.to_string(),
+                            };
+                        json!({"key": field, "value": value})
+                    })
+                    .collect::<Vec<_>>();
+                (None, json!({"mode": "urlencoded", "urlencoded": fields}))
+            }
+            RecipeBody::FormMultipart(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, value)| {
+                        match self.collection.file_chain_path(value) {
+                            Some(path) => json!({
+                                "key": field,
+                                "type": "file",
+                                "src": self.export_template(path),
+                            }),
+                            None => json!({
+                                "key": field,
+                                "type": "text",
+                                "value": self.export_template(value),
+                            }),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                (None, json!({"mode": "formdata", "formdata": fields}))
+            }
+        }
+    }
+
+    fn auth(&mut self, authentication: &Authentication) -> Value {
+        let attribute = |key: &str, value: String| json!({"key": key, "value": value, "type": "string"});
+        match authentication {
+            Authentication::Basic { username, password } => {
+                let mut attributes =
+                    vec![attribute("username", self.export_template(username))];
+                if let Some(password) = password {
+                    attributes.push(attribute(
+                        "password",
+                        self.export_template(password),
+                    ));
+                }
+                json!({"type": "basic", "basic": attributes})
+            }
+            Authentication::Bearer(token) => json!({
+                "type": "bearer",
+                "bearer": [attribute("token", self.export_template(token))],
+            }),
+        }
+    }
+
+    /// Convert a template to Postman syntax. Chains and environment variables
+    /// are replaced with collection variables of the same name.
+    fn export_template(&mut self, template: &Template) -> String {
+        template.display_with(|key| {
+            let name = match key {
+                TemplateKey::Field(field) => field.to_string(),
+                TemplateKey::Chain(id) => {
+                    self.unsupported.add(format!(
+                        "Chain `{id}` has no Postman equivalent; exported as \
+                        collection variable `{id}`, which must be set manually"
+                    ));
+                    self.variables.insert(id.to_string());
+                    id.to_string()
+                }
+                TemplateKey::Environment(variable) => {
+                    self.unsupported.add(format!(
+                        "Environment variable `{variable}` has no Postman \
+                        equivalent; exported as collection variable \
+                        `{variable}`, which must be set manually"
+                    ));
+                    self.variables.insert(variable.to_string());
+                    variable.to_string()
+                }
+            };
+            format!("{{{{{name}}}}}")
+        })
+    }
+}
+
+/// Build a raw body. Postman sets the content type for JSON and XML bodies
+/// based on their language. Other content types must be set with a header.
+fn raw_body(
+    content_type: Option<ContentType>,
+    text: String,
+) -> (Option<String>, Value) {
+    let language = match content_type {
+        Some(ContentType::Json) => "json",
+        Some(ContentType::Xml) => "xml",
+        Some(content_type) => {
+            return (
+                Some(content_type.to_mime().to_string()),
+                json!({"mode": "raw", "raw": text}),
+            )
+        }
+        None => "text",
+    };
+    (
+        None,
+        json!({
+            "mode": "raw",
+            "raw": text,
+            "options": {"raw": {"language": language}},
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_data_dir;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use std::{fs::File, path::PathBuf};
+
+    /// Collection used for all export tests
+    const EXPORT_FILE: &str = "export.yml";
+    const POSTMAN_EXPORTED_FILE: &str = "export_postman.json";
+    /// Exported environments, keyed by profile ID
+    const POSTMAN_EXPORTED_ENVIRONMENTS_FILE: &str =
+        "export_postman_environments.json";
+
+    /// Catch-all test for Postman export
+    #[rstest]
+    fn test_postman_export(test_data_dir: PathBuf) {
+        let collection =
+            Collection::load(&test_data_dir.join(EXPORT_FILE)).unwrap();
+        let exported = collection.to_postman("Test");
+        let expected: Value = serde_json::from_reader(
+            File::open(test_data_dir.join(POSTMAN_EXPORTED_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(exported.output, expected);
+        assert_eq!(
+            exported.unsupported,
+            [
+                "Protobuf schema of recipe `get_user` has no Postman \
+                equivalent",
+                "Environment variable `PROD_TOKEN` has no Postman \
+                equivalent; exported as collection variable `PROD_TOKEN`, \
+                which must be set manually",
+                "Environment variable `HOME` has no Postman equivalent; \
+                exported as collection variable `HOME`, which must be set \
+                manually",
+                "Chain `token` has no Postman equivalent; exported as \
+                collection variable `token`, which must be set manually",
+            ]
+        );
+
+        let exported = collection.to_postman_environments();
+        let expected: IndexMap<ProfileId, Value> = serde_json::from_reader(
+            File::open(test_data_dir.join(POSTMAN_EXPORTED_ENVIRONMENTS_FILE))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(exported.output, expected);
+        assert_eq!(
+            exported.unsupported,
+            [
+                "Profile `dev` is the default; Postman environments have no \
+                default",
+                "Environment variable `PROD_TOKEN` has no Postman \
+                equivalent; exported as collection variable `PROD_TOKEN`, \
+                which must be set manually",
+            ]
+        );
+    }
+}
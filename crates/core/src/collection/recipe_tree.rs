@@ -136,6 +136,12 @@ impl RecipeTree {
             .filter(|id| self.get_recipe(id).is_some())
     }
 
+    /// Take the root nodes out of the tree, so they can be modified. Use
+    /// [Self::new] to rebuild the tree afterward.
+    pub fn into_roots(self) -> IndexMap<RecipeId, RecipeNode> {
+        self.tree
+    }
+
     /// Get a flat iterator over all nodes in the tree, using depth first
     /// search. Each yielded item will include the lookup key to retrieve
     /// that item.
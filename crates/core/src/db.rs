@@ -2,27 +2,51 @@
 //! responses.
 
 mod convert;
+mod encryption;
 mod migrations;
 
+pub use encryption::{
+    read_key_file, DatabaseKeyError, EncryptionConfig, PassphrasePrompter,
+    TerminalPassphrasePrompter, KEY_ENV_VAR,
+};
+
 use crate::{
-    collection::{ProfileId, RecipeId},
-    db::convert::{CollectionPath, JsonEncoded, SqlWrap},
-    http::{Exchange, ExchangeSummary, RequestId},
+    collection::{serde_duration, ProfileId, RecipeId},
+    db::{
+        convert::{CollectionPath, JsonEncoded, SqlWrap},
+        encryption::{CipherState, KeyMetadata},
+    },
+    http::{Exchange, ExchangeAnnotation, ExchangeSummary, RequestId},
     util::{paths, ResultTraced},
 };
 use anyhow::{anyhow, Context};
+use chrono::{DateTime, Utc};
 use derive_more::Display;
+use itertools::Itertools;
+use reqwest::StatusCode;
 use rusqlite::{named_params, Connection, DatabaseName, OptionalExtension};
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     fmt::Debug,
-    ops::{Deref, DerefMut},
+    ops::{Deref, DerefMut, RangeInclusive},
     path::{Path, PathBuf},
-    sync::{Arc, Mutex},
+    str::FromStr,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
 };
+use thiserror::Error;
 use tracing::{debug, info, trace};
 use uuid::Uuid;
 
+/// Columns needed to load an [Exchange] from `requests_v2`. Blobs are
+/// decrypted on the way out
+const EXCHANGE_COLUMNS: &str = "id, profile_id, recipe_id, start_time, \
+    end_time, method, url, status_code, \
+    slumber_decrypt(request_headers) AS request_headers, \
+    slumber_decrypt(request_body) AS request_body, \
+    slumber_decrypt(response_headers) AS response_headers, \
+    slumber_decrypt(response_body) AS response_body";
+
 /// A SQLite database for persisting data. Generally speaking, any error that
 /// occurs *after* opening the DB connection should be an internal bug, but
 /// should be shown to the user whenever possible. All operations are blocking,
@@ -44,6 +68,9 @@ pub struct Database {
     /// one connection per thread, but the code would be a bit more
     /// complicated.
     connection: Arc<Mutex<Connection>>,
+    /// Keys for encrypting data at rest, shared with the SQL functions that
+    /// do the encryption. Empty if the database isn't encrypted.
+    ciphers: Arc<RwLock<CipherState>>,
 }
 
 impl Database {
@@ -52,7 +79,13 @@ impl Database {
     /// Load the database. This will perform migrations, but can be called from
     /// anywhere in the app. The migrations will run on first connection, and
     /// not after that.
-    pub fn load() -> anyhow::Result<Self> {
+    ///
+    /// If the database is encrypted, or encryption is enabled, the passphrase
+    /// is loaded according to the config, falling back to the prompter.
+    pub fn load(
+        encryption: &EncryptionConfig,
+        prompter: &dyn PassphrasePrompter,
+    ) -> anyhow::Result<Self> {
         let path = Self::path();
         paths::create_parent(&path)?;
 
@@ -66,11 +99,140 @@ impl Database {
         // Use WAL for concurrency
         connection.pragma_update(None, "journal_mode", "WAL")?;
         Self::migrate(&mut connection)?;
+        let database = Self::from_connection(connection)?;
+        database.unlock(encryption, prompter)?;
+        Ok(database)
+    }
+
+    /// Wrap a migrated connection
+    fn from_connection(connection: Connection) -> anyhow::Result<Self> {
+        let ciphers = Arc::default();
+        encryption::register_functions(&connection, &ciphers)?;
         Ok(Self {
             connection: Arc::new(Mutex::new(connection)),
+            ciphers,
         })
     }
 
+    /// Load the key for an encrypted database. If the database isn't
+    /// encrypted but encryption is enabled, encrypt it now.
+    fn unlock(
+        &self,
+        config: &EncryptionConfig,
+        prompter: &dyn PassphrasePrompter,
+    ) -> anyhow::Result<()> {
+        let metadata = KeyMetadata::load(&self.connection())
+            .context("Error loading database encryption metadata")?;
+        match metadata {
+            Some(metadata) => {
+                let passphrase = config.passphrase(prompter, false)?;
+                let cipher = Arc::new(metadata.unlock(&passphrase)?);
+                let mut ciphers =
+                    self.ciphers.write().expect("Cipher lock poisoned");
+                ciphers.read = Some(Arc::clone(&cipher));
+                ciphers.write = Some(cipher);
+            }
+            None if config.enabled => {
+                let passphrase = config.passphrase(prompter, true)?;
+                self.rekey(Some(&passphrase))?;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Is data encrypted at rest?
+    pub fn is_encrypted(&self) -> bool {
+        self.ciphers
+            .read()
+            .expect("Cipher lock poisoned")
+            .is_encrypted()
+    }
+
+    /// Re-encrypt all data with a key derived from a new passphrase. If the
+    /// passphrase is `None`, decrypt all data instead. This works for
+    /// unencrypted databases too, so it's also used to enable encryption.
+    ///
+    /// The legacy `requests` table, whose data has already been migrated to
+    /// `requests_v2`, is cleared because it can't be encrypted in place. The
+    /// search index is rebuilt in the same transaction, and the database is
+    /// vacuumed afterward, so no plain text is left behind in free pages. If
+    /// only the vacuum fails, the rekey has still been applied and a
+    /// [RekeyVacuumError] is returned; use [Self::vacuum] to retry.
+    pub fn rekey(&self, passphrase: Option<&str>) -> anyhow::Result<()> {
+        let (cipher, metadata) = passphrase
+            .map(KeyMetadata::generate)
+            .transpose()?
+            .map(|(cipher, metadata)| (Arc::new(cipher), metadata))
+            .unzip();
+        info!(encrypted = cipher.is_some(), "Rekeying database");
+
+        // Decryption continues to use the old key, while encryption uses the
+        // new one. This lets us rekey with a single UPDATE per table
+        self.ciphers.write().expect("Cipher lock poisoned").write =
+            cipher.clone();
+        let mut connection = self.connection();
+        let result = (|| {
+            let transaction = connection.transaction()?;
+            transaction.execute_batch(
+                "UPDATE requests_v2 SET
+                    request_headers =
+                        slumber_encrypt(slumber_decrypt(request_headers)),
+                    request_body =
+                        slumber_encrypt(slumber_decrypt(request_body)),
+                    response_headers =
+                        slumber_encrypt(slumber_decrypt(response_headers)),
+                    response_body =
+                        slumber_encrypt(slumber_decrypt(response_body));
+                UPDATE ui_state_v2 SET
+                    value = slumber_encrypt(slumber_decrypt(value));
+                DELETE FROM requests;
+                -- There's no UPDATE trigger for the index, and its shadow
+                -- tables still hold the old plain text tokens
+                INSERT INTO requests_v2_fts (requests_v2_fts)
+                VALUES ('rebuild');",
+            )?;
+            KeyMetadata::save(metadata.as_ref(), &transaction)?;
+            transaction.commit()
+        })();
+
+        let mut ciphers = self.ciphers.write().expect("Cipher lock poisoned");
+        if let Err(error) = result {
+            ciphers.write = ciphers.read.clone();
+            return Err(
+                anyhow::Error::from(error).context("Error rekeying database")
+            )
+            .traced();
+        }
+        ciphers.read = cipher;
+        drop(ciphers);
+
+        Self::vacuum_connection(&connection)
+            .map_err(|error| anyhow::Error::from(RekeyVacuumError(error)))
+            .traced()?;
+        Ok(())
+    }
+
+    /// Rebuild the database file, so space from deleted data is returned to
+    /// the file system and stale data in unused pages is overwritten
+    pub fn vacuum(&self) -> anyhow::Result<()> {
+        info!("Vacuuming database");
+        Self::vacuum_connection(&self.connection())
+            .context("Error vacuuming database")
+            .traced()
+    }
+
+    fn vacuum_connection(connection: &Connection) -> rusqlite::Result<()> {
+        connection.execute_batch(
+            // VACUUM can change rowids, which the full-text index relies on,
+            // so the index has to be rebuilt afterward. Checkpointing clears
+            // old pages out of the WAL too
+            "VACUUM;
+            INSERT INTO requests_v2_fts (requests_v2_fts) VALUES ('rebuild');
+            PRAGMA wal_checkpoint(TRUNCATE);",
+        )
+    }
+
     /// Path to the database file
     pub fn path() -> PathBuf {
         paths::data_directory().join(Self::FILE)
@@ -169,6 +331,130 @@ impl Database {
         Ok(())
     }
 
+    /// Delete requests from history according to the retention rules. This
+    /// applies to *all* collections. If `dry_run` is enabled, nothing is
+    /// deleted, but the report shows what would have been. If `vacuum` is
+    /// enabled, the database file is compacted afterward to return the freed
+    /// space to the OS. Vacuuming can be slow for large databases.
+    pub fn prune(
+        &self,
+        retention: &RetentionConfig,
+        dry_run: bool,
+        vacuum: bool,
+    ) -> anyhow::Result<PruneReport> {
+        info!(?retention, dry_run, vacuum, "Pruning request history");
+        let cutoff = retention
+            .max_age
+            .map(|max_age| {
+                chrono::Duration::from_std(max_age)
+                    .map(|max_age| Utc::now() - max_age)
+            })
+            .transpose()
+            .context("Invalid `max_age`")?;
+
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        let pruned: Vec<(RequestId, u64)> = transaction
+            .prepare(
+                // Apply the per-recipe and age rules first, then the size rule
+                // to whatever is left. That way we don't delete more than
+                // necessary to get under the size limit. Size only counts
+                // request/response data, not the DB's own overhead. If pinned
+                // requests are kept, they're excluded entirely, so they don't
+                // count toward any limit.
+                "WITH ranked AS (
+                    SELECT
+                        id,
+                        start_time,
+                        length(url)
+                            + length(request_headers)
+                            + ifnull(length(request_body), 0)
+                            + length(response_headers)
+                            + length(response_body) AS size,
+                        row_number() OVER (
+                            PARTITION BY collection_id, profile_id, recipe_id
+                            ORDER BY start_time DESC
+                        ) AS recipe_rank
+                    FROM requests_v2
+                    WHERE NOT (pinned AND :keep_pinned)
+                ),
+                expired AS (
+                    SELECT id, size FROM ranked
+                    WHERE recipe_rank > :max_per_recipe
+                        OR start_time < :cutoff
+                ),
+                remaining AS (
+                    SELECT id, size, sum(size) OVER (
+                        ORDER BY start_time DESC ROWS UNBOUNDED PRECEDING
+                    ) AS total_size
+                    FROM ranked WHERE id NOT IN (SELECT id FROM expired)
+                )
+                SELECT id, size FROM expired
+                UNION ALL
+                SELECT id, size FROM remaining WHERE total_size > :max_size",
+            )?
+            .query_map(
+                named_params! {
+                    // Comparisons against NULL are never true, which disables
+                    // each rule when it isn't configured
+                    ":max_per_recipe": retention.max_per_recipe,
+                    ":cutoff": cutoff,
+                    ":max_size": retention.max_size,
+                    ":keep_pinned": retention.keep_pinned,
+                },
+                |row| Ok((row.get("id")?, row.get("size")?)),
+            )
+            .context("Error finding requests to prune")
+            .traced()?
+            .collect::<rusqlite::Result<_>>()
+            .context("Error finding requests to prune")
+            .traced()?;
+
+        let mut report = PruneReport {
+            deleted: pruned.len(),
+            deleted_size: pruned.iter().map(|(_, size)| size).sum(),
+            reclaimed_size: None,
+        };
+        if dry_run {
+            return Ok(report);
+        }
+
+        {
+            let mut statement = transaction
+                .prepare("DELETE FROM requests_v2 WHERE id = :id")?;
+            for (id, _) in &pruned {
+                statement
+                    .execute(named_params! {":id": id})
+                    .context("Error deleting request")
+                    .traced()?;
+            }
+        }
+        transaction.commit()?;
+        info!(deleted = report.deleted, "Pruned request history");
+
+        if vacuum {
+            let size_before = Self::file_size(&connection)?;
+            Self::vacuum_connection(&connection)
+                .context("Error vacuuming database")
+                .traced()?;
+            let size_after = Self::file_size(&connection)?;
+            report.reclaimed_size =
+                Some(size_before.saturating_sub(size_after));
+        }
+
+        Ok(report)
+    }
+
+    /// Get the size of the database, in bytes. This is based on the page
+    /// count, so it doesn't include the WAL file
+    fn file_size(connection: &Connection) -> anyhow::Result<u64> {
+        let page_count: u64 =
+            connection.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let page_size: u64 =
+            connection.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        Ok(page_count * page_size)
+    }
+
     /// Convert this database connection into a handle for a single collection
     /// file. This will store the collection in the DB if it isn't already,
     /// then grab its generated ID to create a [CollectionDatabase].
@@ -242,10 +528,12 @@ impl CollectionDatabase {
         self.database
             .connection()
             .query_row(
-                "SELECT * FROM requests_v2
+                &format!(
+                    "SELECT {EXCHANGE_COLUMNS} FROM requests_v2
                 WHERE collection_id = :collection_id
                     AND id = :request_id
-                ORDER BY start_time DESC LIMIT 1",
+                ORDER BY start_time DESC LIMIT 1"
+                ),
                 named_params! {
                     // Include collection ID just to be extra safe
                     ":collection_id": self.collection_id,
@@ -277,11 +565,13 @@ impl CollectionDatabase {
             .connection()
             .query_row(
                 // `IS` needed for profile_id so `None` will match `NULL`
-                "SELECT * FROM requests_v2
+                &format!(
+                    "SELECT {EXCHANGE_COLUMNS} FROM requests_v2
                 WHERE collection_id = :collection_id
                     AND profile_id IS :profile_id
                     AND recipe_id = :recipe_id
-                ORDER BY start_time DESC LIMIT 1",
+                ORDER BY start_time DESC LIMIT 1"
+                ),
                 named_params! {
                     ":collection_id": self.collection_id,
                     ":profile_id": profile_id,
@@ -305,11 +595,19 @@ impl CollectionDatabase {
     /// inserting its own exchanges. Only requests that received a valid HTTP
     /// response should be stored. In-flight requests, invalid requests, and
     /// requests that failed to complete (e.g. because of a network error)
-    /// should not (and cannot) be stored.
+    /// should not (and cannot) be stored. Sensitive values in the request and
+    /// response headers are redacted before storage.
     pub fn insert_exchange(&self, exchange: &Exchange) -> anyhow::Result<()> {
+        // Never persist sensitive values
+        let request = exchange.request.redacted();
+        // The server can echo secrets back, or set its own (e.g. Set-Cookie)
+        let response_headers = exchange
+            .request
+            .redactor
+            .redact_headers(&exchange.response.headers);
         debug!(
             id = %exchange.id,
-            url = %exchange.request.url,
+            url = %request.url,
             "Adding exchange to database",
         );
         self.database
@@ -340,27 +638,27 @@ impl CollectionDatabase {
                     :end_time,
                     :method,
                     :url,
-                    :request_headers,
-                    :request_body,
+                    slumber_encrypt(:request_headers),
+                    slumber_encrypt(:request_body),
                     :status_code,
-                    :response_headers,
-                    :response_body
+                    slumber_encrypt(:response_headers),
+                    slumber_encrypt(:response_body)
                 )",
                 named_params! {
                     ":id": exchange.id,
                     ":collection_id": self.collection_id,
-                    ":profile_id": &exchange.request.profile_id,
-                    ":recipe_id": &exchange.request.recipe_id,
+                    ":profile_id": &request.profile_id,
+                    ":recipe_id": &request.recipe_id,
                     ":start_time": &exchange.start_time,
                     ":end_time": &exchange.end_time,
 
-                    ":method": exchange.request.method.as_str(),
-                    ":url": exchange.request.url.as_str(),
-                    ":request_headers": SqlWrap(&exchange.request.headers),
-                    ":request_body": exchange.request.body(),
+                    ":method": request.method.as_str(),
+                    ":url": request.url.as_str(),
+                    ":request_headers": SqlWrap(&request.headers),
+                    ":request_body": request.body(),
 
                     ":status_code": exchange.response.status.as_u16(),
-                    ":response_headers": SqlWrap(&exchange.response.headers),
+                    ":response_headers": SqlWrap(&response_headers),
                     ":response_body": exchange.response.body.bytes().deref(),
                 },
             )
@@ -386,7 +684,9 @@ impl CollectionDatabase {
         self.database
             .connection()
             .prepare(
-                "SELECT id, start_time, end_time, status_code FROM requests_v2
+                "SELECT id, profile_id, recipe_id, start_time, end_time,
+                    status_code, pinned, label, note
+                FROM requests_v2
                 WHERE collection_id = :collection_id
                     AND profile_id IS :profile_id
                     AND recipe_id = :recipe_id
@@ -406,6 +706,140 @@ impl CollectionDatabase {
             .context("Error extracting request history")
     }
 
+    /// Search request history across all recipes and profiles in the
+    /// collection. The text query is matched against the URL, headers and
+    /// bodies of both the request and response, using the full-text index.
+    /// All other filters are exact. Results are sorted by start time,
+    /// descending.
+    pub fn search_requests(
+        &self,
+        filter: &HistoryFilter,
+    ) -> anyhow::Result<Vec<ExchangeSummary>> {
+        trace!(?filter, "Searching request history in database");
+        let status = filter.status.map(StatusFilter::range);
+        self.database
+            .connection()
+            .prepare(
+                // `IS` needed for profile_id so `None` will match `NULL`
+                "SELECT id, profile_id, recipe_id, start_time, end_time,
+                    status_code, pinned, label, note
+                FROM requests_v2
+                WHERE collection_id = :collection_id
+                    AND (:query IS NULL OR rowid IN (
+                        SELECT rowid FROM requests_v2_fts
+                        WHERE requests_v2_fts MATCH :query
+                    ))
+                    AND (:any_profile OR profile_id IS :profile_id)
+                    AND (:recipe_id IS NULL OR recipe_id = :recipe_id)
+                    AND (:recipe_glob IS NULL OR recipe_id GLOB :recipe_glob)
+                    AND (:status_min IS NULL OR status_code >= :status_min)
+                    AND (:status_max IS NULL OR status_code <= :status_max)
+                    AND (:since IS NULL OR start_time >= :since)
+                    AND (:until IS NULL OR start_time < :until)
+                    AND (NOT :pinned OR pinned)
+                    AND (:label IS NULL OR label = :label)
+                ORDER BY start_time DESC
+                LIMIT :limit",
+            )?
+            .query_map(
+                named_params! {
+                    ":collection_id": self.collection_id,
+                    ":query": filter.query.as_deref().and_then(fts_query),
+                    ":any_profile": filter.profile_id.is_none(),
+                    ":profile_id": filter.profile_id.as_ref().and_then(Option::as_ref),
+                    ":recipe_id": &filter.recipe_id,
+                    ":recipe_glob": &filter.recipe_glob,
+                    ":status_min": status.as_ref().map(|range| *range.start()),
+                    ":status_max": status.as_ref().map(|range| *range.end()),
+                    ":since": filter.since,
+                    ":until": filter.until,
+                    ":pinned": filter.pinned,
+                    ":label": &filter.label,
+                    // Negative means no limit
+                    ":limit": filter.limit.map(|limit| limit as i64).unwrap_or(-1),
+                },
+                |row| row.try_into(),
+            )
+            .context("Error searching request history")
+            .traced()?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Error extracting request history")
+    }
+
+    /// Get the most recent request matching a filter, or `None` if there are
+    /// no matches. The filter's limit is ignored.
+    pub fn get_latest_matching(
+        &self,
+        filter: &HistoryFilter,
+    ) -> anyhow::Result<Option<Exchange>> {
+        let filter = HistoryFilter {
+            limit: Some(1),
+            ..filter.clone()
+        };
+        match self.search_requests(&filter)?.first() {
+            Some(summary) => self.get_request(summary.id),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the user annotation for a request, or `None` if the request does
+    /// not exist in history
+    pub fn get_annotation(
+        &self,
+        request_id: RequestId,
+    ) -> anyhow::Result<Option<ExchangeAnnotation>> {
+        self.database
+            .connection()
+            .query_row(
+                "SELECT pinned, label, note FROM requests_v2
+                WHERE collection_id = :collection_id AND id = :request_id",
+                named_params! {
+                    ":collection_id": self.collection_id,
+                    ":request_id": request_id,
+                },
+                |row| row.try_into(),
+            )
+            .optional()
+            .with_context(|| {
+                format!("Error fetching annotation for request {request_id}")
+            })
+            .traced()
+    }
+
+    /// Replace the user annotation for a request. Return an error if the
+    /// request does not exist in history.
+    pub fn set_annotation(
+        &self,
+        request_id: RequestId,
+        annotation: &ExchangeAnnotation,
+    ) -> anyhow::Result<()> {
+        debug!(%request_id, ?annotation, "Setting request annotation");
+        let updated = self
+            .database
+            .connection()
+            .execute(
+                "UPDATE requests_v2
+                SET pinned = :pinned, label = :label, note = :note
+                WHERE collection_id = :collection_id AND id = :request_id",
+                named_params! {
+                    ":collection_id": self.collection_id,
+                    ":request_id": request_id,
+                    ":pinned": annotation.pinned,
+                    ":label": &annotation.label,
+                    ":note": &annotation.note,
+                },
+            )
+            .with_context(|| {
+                format!("Error saving annotation for request {request_id}")
+            })
+            .traced()?;
+        if updated == 0 {
+            Err(anyhow!("Request `{request_id}` not found"))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Get the value of a UI state field. Key type is included as part of the
     /// key, to disambiguate between keys of identical structure
     pub fn get_ui<K, V>(
@@ -421,7 +855,7 @@ impl CollectionDatabase {
             .database
             .connection()
             .query_row(
-                "SELECT value FROM ui_state_v2
+                "SELECT slumber_decrypt(value) AS value FROM ui_state_v2
                 WHERE collection_id = :collection_id
                     AND key_type = :key_type
                     AND key = :key",
@@ -459,7 +893,9 @@ impl CollectionDatabase {
             .execute(
                 // Upsert!
                 "INSERT INTO ui_state_v2 (collection_id, key_type, key, value)
-                VALUES (:collection_id, :key_type, :key, :value)
+                VALUES (
+                    :collection_id, :key_type, :key, slumber_encrypt(:value)
+                )
                 ON CONFLICT DO UPDATE SET value = excluded.value",
                 named_params! {
                     ":collection_id": self.collection_id,
@@ -491,15 +927,161 @@ impl CollectionId {
     }
 }
 
+/// Rules for deleting old requests from history. Each rule is optional, and
+/// a request is deleted if it violates *any* rule. By default, nothing is ever
+/// deleted. Pinned requests are exempt from all rules, unless `keep_pinned` is
+/// disabled.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Maximum number of requests to keep for each recipe+profile
+    /// combination. The oldest requests are deleted first.
+    pub max_per_recipe: Option<usize>,
+    /// Delete requests older than this
+    #[serde(with = "serde_duration::option")]
+    pub max_age: Option<Duration>,
+    /// Maximum total size of stored requests and responses, in bytes, across
+    /// all collections. The oldest requests are deleted first.
+    pub max_size: Option<u64>,
+    /// Apply these rules automatically when the TUI starts
+    pub prune_on_startup: bool,
+    /// Never delete pinned requests, and don't count them toward any limit.
+    /// If disabled, pinned requests are treated like any other
+    pub keep_pinned: bool,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_per_recipe: None,
+            max_age: None,
+            max_size: None,
+            prune_on_startup: false,
+            keep_pinned: true,
+        }
+    }
+}
+
+/// Outcome of pruning history
+#[derive(Debug)]
+pub struct PruneReport {
+    /// Number of requests deleted (or that would be, in a dry run)
+    pub deleted: usize,
+    /// Total size of the deleted requests and responses, in bytes
+    pub deleted_size: u64,
+    /// Space returned to the filesystem by vacuuming, in bytes. `None` if
+    /// the database wasn't vacuumed
+    pub reclaimed_size: Option<u64>,
+}
+
+/// Criteria for searching request history. All populated filters must match
+/// for a request to be included. The default filter matches everything.
+#[derive(Clone, Debug, Default)]
+pub struct HistoryFilter {
+    /// Text to search for in the URL, headers and bodies of the request and
+    /// response. Each whitespace-separated term must appear in the request,
+    /// in any field. Terms are matched as whole words, not substrings.
+    pub query: Option<String>,
+    /// Only include requests for this recipe
+    pub recipe_id: Option<RecipeId>,
+    /// Only include requests whose recipe ID matches this glob pattern.
+    /// Supports `*`, `?` and `[...]` wildcards, and is case-sensitive
+    pub recipe_glob: Option<String>,
+    /// Only include requests for this profile. `Some(None)` matches requests
+    /// made with no profile, while `None` matches any profile.
+    pub profile_id: Option<Option<ProfileId>>,
+    /// Only include responses with a matching status code
+    pub status: Option<StatusFilter>,
+    /// Only include requests started at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only include requests started before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Only include pinned requests
+    pub pinned: bool,
+    /// Only include requests with this label
+    pub label: Option<String>,
+    /// Maximum number of results to return
+    pub limit: Option<usize>,
+}
+
+/// Match response status codes, either exactly (`404`) or by class (`4xx`)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StatusFilter {
+    /// Match a single status code
+    Code(StatusCode),
+    /// Match all codes in a class, e.g. `5` for 500-599
+    Class(u16),
+}
+
+impl StatusFilter {
+    /// Get the range of status codes that this filter matches
+    fn range(self) -> RangeInclusive<u16> {
+        match self {
+            Self::Code(code) => code.as_u16()..=code.as_u16(),
+            Self::Class(class) => class * 100..=class * 100 + 99,
+        }
+    }
+}
+
+impl FromStr for StatusFilter {
+    type Err = StatusFilterParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || StatusFilterParseError(s.to_owned());
+        match s.as_bytes() {
+            [class @ b'1'..=b'5', b'x' | b'X', b'x' | b'X'] => {
+                Ok(Self::Class((class - b'0').into()))
+            }
+            _ => {
+                let code: u16 = s.parse().map_err(|_| error())?;
+                StatusCode::from_u16(code)
+                    .map(Self::Code)
+                    .map_err(|_| error())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+#[error(
+    "Invalid status filter `{0}`; expected a status code (e.g. `404`) or \
+    class (e.g. `4xx`)"
+)]
+pub struct StatusFilterParseError(String);
+
+/// A rekey was applied, but vacuuming the database afterward failed. All data
+/// is stored with the new key, but data from before the rekey may remain in
+/// unused pages of the database file until it's vacuumed.
+#[derive(Debug, Error)]
+#[error(
+    "Database was rekeyed, but vacuuming it failed. Data from before the \
+    rekey may remain in unused pages of the database file"
+)]
+pub struct RekeyVacuumError(#[source] rusqlite::Error);
+
+/// Convert user search text into an FTS5 query. Each whitespace-separated term
+/// is quoted so it's matched literally, rather than being parsed with the FTS
+/// query syntax. Terms are implicitly ANDed. Return `None` if there are no
+/// terms.
+fn fts_query(text: &str) -> Option<String> {
+    let query = text
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .join(" ");
+    if query.is_empty() {
+        None
+    } else {
+        Some(query)
+    }
+}
+
 /// Create an in-memory DB, only for testing
 #[cfg(any(test, feature = "test"))]
 impl crate::test_util::Factory for Database {
     fn factory(_: ()) -> Self {
         let mut connection = Connection::open_in_memory().unwrap();
         Self::migrate(&mut connection).unwrap();
-        Self {
-            connection: Arc::new(Mutex::new(connection)),
-        }
+        Self::from_connection(connection).unwrap()
     }
 }
 
@@ -517,9 +1099,15 @@ impl crate::test_util::Factory for CollectionDatabase {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{test_util::Factory, util::paths::get_repo_root};
-    use itertools::Itertools;
-    use std::collections::HashMap;
+    use crate::{
+        assert_err,
+        http::{RequestRecord, ResponseRecord},
+        test_util::{temp_dir, Factory, TempDir},
+        util::paths::get_repo_root,
+    };
+    use chrono::TimeZone;
+    use rstest::rstest;
+    use std::{collections::HashMap, fs};
 
     #[test]
     fn test_merge() {
@@ -730,6 +1318,695 @@ mod tests {
         }
     }
 
+    /// Test searching history by text and metadata filters
+    #[test]
+    fn test_search_requests() {
+        let database = CollectionDatabase::factory(());
+        let other_collection = Database::factory(())
+            .into_collection(&get_repo_root().join("README.md"))
+            .unwrap();
+
+        let exchange = |profile_id: Option<&str>,
+                        recipe_id: &str,
+                        url: &str,
+                        status: u16,
+                        body: &str,
+                        start_time: DateTime<Utc>| {
+            let mut request = RequestRecord::factory((
+                profile_id.map(ProfileId::from),
+                recipe_id.into(),
+            ));
+            request.url = url.parse().unwrap();
+            let response = ResponseRecord {
+                body: body.to_owned().into_bytes().into(),
+                ..ResponseRecord::factory(StatusCode::from_u16(status).unwrap())
+            };
+            Exchange {
+                start_time,
+                end_time: start_time,
+                ..Exchange::factory((request, response))
+            }
+        };
+        let day =
+            |day: u32| Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap();
+        let orders_1 = exchange(
+            Some("prod"),
+            "orders",
+            "http://localhost/orders/12345",
+            200,
+            r#"{"order_id": 12345, "state": "shipped"}"#,
+            day(1),
+        );
+        let orders_2 = exchange(
+            Some("prod"),
+            "orders",
+            "http://localhost/orders/678",
+            500,
+            r#"{"error": "database-timeout"}"#,
+            day(2),
+        );
+        let users = exchange(
+            None,
+            "users",
+            "http://localhost/users",
+            503,
+            r#"[{"id": 1, "last_order": 12345}]"#,
+            day(3),
+        );
+        for exchange in [&orders_1, &orders_2, &users] {
+            database.insert_exchange(exchange).unwrap();
+        }
+        // Should never be included
+        other_collection
+            .insert_exchange(&exchange(
+                None,
+                "orders",
+                "http://localhost/orders/12345",
+                200,
+                "12345",
+                day(1),
+            ))
+            .unwrap();
+
+        let search = |filter: HistoryFilter| {
+            database
+                .search_requests(&filter)
+                .unwrap()
+                .into_iter()
+                .map(|exchange| exchange.id)
+                .collect_vec()
+        };
+
+        assert_eq!(
+            search(HistoryFilter::default()),
+            [users.id, orders_2.id, orders_1.id]
+        );
+        // Match in URL and body
+        assert_eq!(
+            search(HistoryFilter {
+                query: Some("12345".into()),
+                ..Default::default()
+            }),
+            [users.id, orders_1.id]
+        );
+        // All terms must match. Punctuation doesn't break the query
+        assert_eq!(
+            search(HistoryFilter {
+                query: Some("12345 \"shipped".into()),
+                ..Default::default()
+            }),
+            [orders_1.id]
+        );
+        assert_eq!(
+            search(HistoryFilter {
+                query: Some("database-timeout".into()),
+                ..Default::default()
+            }),
+            [orders_2.id]
+        );
+        // Recipe glob
+        assert_eq!(
+            search(HistoryFilter {
+                recipe_glob: Some("ord*".into()),
+                ..Default::default()
+            }),
+            [orders_2.id, orders_1.id]
+        );
+        assert_eq!(
+            search(HistoryFilter {
+                recipe_glob: Some("*s".into()),
+                ..Default::default()
+            }),
+            [users.id, orders_2.id, orders_1.id]
+        );
+        assert_eq!(
+            search(HistoryFilter {
+                recipe_glob: Some("Orders".into()),
+                ..Default::default()
+            }),
+            []
+        );
+        // Match in request headers
+        assert_eq!(
+            search(HistoryFilter {
+                query: Some("slumber".into()),
+                recipe_id: Some("orders".into()),
+                ..Default::default()
+            }),
+            [orders_2.id, orders_1.id]
+        );
+        assert_eq!(
+            search(HistoryFilter {
+                profile_id: Some(None),
+                ..Default::default()
+            }),
+            [users.id]
+        );
+        assert_eq!(
+            search(HistoryFilter {
+                status: Some("5xx".parse().unwrap()),
+                ..Default::default()
+            }),
+            [users.id, orders_2.id]
+        );
+        assert_eq!(
+            search(HistoryFilter {
+                status: Some("500".parse().unwrap()),
+                ..Default::default()
+            }),
+            [orders_2.id]
+        );
+        assert_eq!(
+            search(HistoryFilter {
+                since: Some(day(2)),
+                until: Some(day(3)),
+                ..Default::default()
+            }),
+            [orders_2.id]
+        );
+        assert_eq!(
+            search(HistoryFilter {
+                limit: Some(1),
+                ..Default::default()
+            }),
+            [users.id]
+        );
+    }
+
+    /// Test deleting requests by each retention rule
+    #[rstest]
+    #[case::nothing(RetentionConfig::default(), &[])]
+    #[case::max_per_recipe(
+        RetentionConfig {
+            max_per_recipe: Some(1),
+            ..Default::default()
+        },
+        // Oldest for each recipe+profile
+        &[0, 1],
+    )]
+    #[case::max_age(
+        RetentionConfig {
+            max_age: Some(Duration::from_secs(60 * 60 * 24 * 3)),
+            ..Default::default()
+        },
+        &[0],
+    )]
+    #[case::max_size(
+        RetentionConfig {
+            // Each exchange is 17 (URL) + 4 (body) bytes
+            max_size: Some(21 * 2),
+            ..Default::default()
+        },
+        &[0, 1],
+    )]
+    #[case::combined(
+        RetentionConfig {
+            max_per_recipe: Some(2),
+            max_age: Some(Duration::from_secs(60 * 60 * 24 * 3)),
+            max_size: Some(21 * 2),
+            ..Default::default()
+        },
+        &[0, 1],
+    )]
+    fn test_prune(
+        #[case] retention: RetentionConfig,
+        #[case] expected_deleted: &[usize],
+    ) {
+        let database = Database::factory(());
+        let collection = database
+            .clone()
+            .into_collection(&get_repo_root().join("slumber.yml"))
+            .unwrap();
+        let now = Utc::now();
+        // Oldest first, with each one day newer than the last. The oldest is
+        // just under 4 days old
+        let exchanges = [
+            (Some("profile1"), "recipe1"),
+            (None, "recipe1"),
+            (Some("profile1"), "recipe1"),
+            (None, "recipe1"),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (profile_id, recipe_id))| {
+            let start_time = now - chrono::Duration::days(4 - i as i64)
+                + chrono::Duration::hours(1);
+            let request = RequestRecord {
+                url: "http://localhost/".parse().unwrap(),
+                headers: Default::default(),
+                ..RequestRecord::factory((
+                    profile_id.map(ProfileId::from),
+                    recipe_id.into(),
+                ))
+            };
+            let response = ResponseRecord {
+                body: b"body".as_slice().into(),
+                ..ResponseRecord::factory(())
+            };
+            let exchange = Exchange {
+                start_time,
+                end_time: start_time,
+                ..Exchange::factory((request, response))
+            };
+            collection.insert_exchange(&exchange).unwrap();
+            exchange
+        })
+        .collect_vec();
+        let remaining_ids = || {
+            collection
+                .search_requests(&HistoryFilter::default())
+                .unwrap()
+                .into_iter()
+                .map(|exchange| exchange.id)
+                .collect_vec()
+        };
+        let expected_remaining = exchanges
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !expected_deleted.contains(i))
+            .map(|(_, exchange)| exchange.id)
+            // Search results are newest first
+            .rev()
+            .collect_vec();
+
+        // Dry run shouldn't delete anything
+        let report = database.prune(&retention, true, false).unwrap();
+        assert_eq!(report.deleted, expected_deleted.len());
+        assert_eq!(report.deleted_size, 21 * expected_deleted.len() as u64);
+        assert_eq!(report.reclaimed_size, None);
+        assert_eq!(remaining_ids().len(), exchanges.len());
+
+        let report = database.prune(&retention, false, true).unwrap();
+        assert_eq!(report.deleted, expected_deleted.len());
+        assert!(report.reclaimed_size.is_some());
+        assert_eq!(remaining_ids(), expected_remaining);
+    }
+
+    /// The count and age rules apply together. A request that breaks both is
+    /// only deleted (and counted) once
+    #[test]
+    fn test_prune_count_and_age() {
+        let database = Database::factory(());
+        let collection = database
+            .clone()
+            .into_collection(&get_repo_root().join("slumber.yml"))
+            .unwrap();
+        let now = Utc::now();
+        let exchanges = [
+            ("recipe1", 10),
+            ("recipe1", 2),
+            ("recipe1", 1),
+            ("recipe1", 0),
+            ("recipe2", 10),
+            ("recipe2", 3),
+        ]
+        .map(|(recipe_id, days_old)| {
+            let start_time = now - chrono::Duration::days(days_old);
+            let exchange = Exchange {
+                start_time,
+                end_time: start_time,
+                ..Exchange::factory((
+                    RequestRecord::factory((None, recipe_id.into())),
+                    ResponseRecord::factory(()),
+                ))
+            };
+            collection.insert_exchange(&exchange).unwrap();
+            exchange.id
+        });
+
+        let retention = RetentionConfig {
+            max_per_recipe: Some(2),
+            max_age: Some(Duration::from_secs(60 * 60 * 24 * 5)),
+            ..Default::default()
+        };
+        let report = database.prune(&retention, false, false).unwrap();
+        // recipe1 loses its 10-day-old request to both rules, and its
+        // 2-day-old request to the count rule. recipe2 loses its 10-day-old
+        // request to the age rule
+        assert_eq!(report.deleted, 3);
+        let remaining = collection
+            .search_requests(&HistoryFilter::default())
+            .unwrap()
+            .into_iter()
+            .map(|exchange| exchange.id)
+            .collect_vec();
+        assert_eq!(remaining, [exchanges[3], exchanges[2], exchanges[5]]);
+    }
+
+    /// The full-text index should line up with the remaining requests after
+    /// pruning and vacuuming, which can change rowids
+    #[test]
+    fn test_prune_search_index() {
+        let database = Database::factory(());
+        let collection = database
+            .clone()
+            .into_collection(&get_repo_root().join("slumber.yml"))
+            .unwrap();
+        let now = Utc::now();
+        // Oldest first, so the deleted requests have the lowest rowids
+        let words = ["alpha", "bravo", "charlie", "delta"];
+        let exchanges = words.into_iter().enumerate().map(|(i, word)| {
+            let start_time = now - chrono::Duration::days(4 - i as i64);
+            let exchange = Exchange {
+                start_time,
+                end_time: start_time,
+                ..Exchange::factory((
+                    RequestRecord::factory((None, "recipe1".into())),
+                    ResponseRecord {
+                        body: format!("common {word}").into_bytes().into(),
+                        ..ResponseRecord::factory(())
+                    },
+                ))
+            };
+            collection.insert_exchange(&exchange).unwrap();
+            exchange.id
+        });
+        let exchanges = exchanges.collect_vec();
+
+        let retention = RetentionConfig {
+            max_per_recipe: Some(2),
+            ..Default::default()
+        };
+        let report = database.prune(&retention, false, true).unwrap();
+        assert_eq!(report.deleted, 2);
+
+        let search = |query: &str| {
+            collection
+                .search_requests(&HistoryFilter {
+                    query: Some(query.into()),
+                    ..Default::default()
+                })
+                .unwrap()
+                .into_iter()
+                .map(|exchange| exchange.id)
+                .collect_vec()
+        };
+        assert_eq!(search("alpha"), []);
+        assert_eq!(search("bravo"), []);
+        assert_eq!(search("charlie"), [exchanges[2]]);
+        assert_eq!(search("delta"), [exchanges[3]]);
+        assert_eq!(search("common"), [exchanges[3], exchanges[2]]);
+    }
+
+    /// Encrypt, reopen and decrypt a database. Encrypted values should be
+    /// unreadable at rest, and only the URL should be searchable
+    #[rstest]
+    fn test_encryption(temp_dir: TempDir) {
+        let path = temp_dir.join("state.sqlite");
+        let open = |passphrase: Option<&'static str>| {
+            let mut connection = Connection::open(&path).unwrap();
+            Database::migrate(&mut connection).unwrap();
+            let database = Database::from_connection(connection).unwrap();
+            let prompter = |_: &str, _: bool| {
+                passphrase
+                    .map(String::from)
+                    .ok_or_else(|| DatabaseKeyError::Missing.into())
+            };
+            database
+                .unlock(&EncryptionConfig::default(), &prompter)
+                .map(|()| database)
+        };
+        let collection = |database: &Database| {
+            database
+                .clone()
+                .into_collection(&get_repo_root().join("slumber.yml"))
+                .unwrap()
+        };
+        let search = |database: &Database, query: &str| {
+            collection(database)
+                .search_requests(&HistoryFilter {
+                    query: Some(query.into()),
+                    ..Default::default()
+                })
+                .unwrap()
+                .len()
+        };
+
+        let database = open(None).unwrap();
+        let exchange = Exchange::factory((
+            RequestRecord::factory(()),
+            ResponseRecord {
+                body: b"secret customer data".as_slice().into(),
+                ..ResponseRecord::factory(())
+            },
+        ));
+        collection(&database).insert_exchange(&exchange).unwrap();
+        collection(&database)
+            .set_ui("Test", "key", "value")
+            .unwrap();
+        assert_eq!(search(&database, "customer"), 1);
+
+        database.rekey(Some("hunter2")).unwrap();
+        assert!(database.is_encrypted());
+        let raw: Vec<u8> = database
+            .connection()
+            .query_row("SELECT response_body FROM requests_v2", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert!(raw.starts_with(b"SLUMENC1"));
+        assert_eq!(search(&database, "customer"), 0);
+        assert_eq!(search(&database, "localhost"), 1);
+        drop(database);
+
+        assert_err!(open(None), "No key provided");
+        assert_err!(open(Some("hunter3")), "Incorrect key");
+        let database = open(Some("hunter2")).unwrap();
+        let loaded = collection(&database)
+            .get_request(exchange.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.response, exchange.response);
+        assert_eq!(
+            collection(&database)
+                .get_ui::<_, String>("Test", "key")
+                .unwrap()
+                .as_deref(),
+            Some("value")
+        );
+
+        // Decrypting makes everything readable again, without a key
+        database.rekey(None).unwrap();
+        drop(database);
+        let database = open(None).unwrap();
+        assert!(!database.is_encrypted());
+        let loaded = collection(&database)
+            .get_request(exchange.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.response, exchange.response);
+        assert_eq!(search(&database, "customer"), 1);
+    }
+
+    /// After a rekey, no plain text from before the rekey should be left in
+    /// the database file, including in free pages and the search index's
+    /// shadow tables. The URL isn't encrypted, but every copy of it should be
+    /// part of an encrypted row
+    #[rstest]
+    fn test_rekey_no_plaintext(temp_dir: TempDir) {
+        let path = temp_dir.join("state.sqlite");
+        let mut connection = Connection::open(&path).unwrap();
+        Database::migrate(&mut connection).unwrap();
+        let database = Database::from_connection(connection).unwrap();
+        let collection = database
+            .clone()
+            .into_collection(&get_repo_root().join("slumber.yml"))
+            .unwrap();
+
+        // The search index stores prefix-compressed terms, so use tokens that
+        // don't share a prefix with any other term
+        let url = "http://localhost/plaintext-url";
+        let exchange = Exchange::factory((
+            RequestRecord {
+                url: url.parse().unwrap(),
+                body: Some(b"xqrequestsecret".as_slice().into()),
+                ..RequestRecord::factory(())
+            },
+            ResponseRecord {
+                body: b"zqresponsesecret".as_slice().into(),
+                ..ResponseRecord::factory(())
+            },
+        ));
+        collection.insert_exchange(&exchange).unwrap();
+        // Filler makes the index span many pages, so a stale index would leave
+        // pages behind when the post-vacuum rebuild shrinks it
+        for _ in 0..50 {
+            let body = (0..50)
+                .map(|_| Uuid::new_v4().simple().to_string())
+                .join(" ");
+            let exchange = Exchange::factory((
+                RequestRecord::factory(()),
+                ResponseRecord {
+                    body: body.into_bytes().into(),
+                    ..ResponseRecord::factory(())
+                },
+            ));
+            collection.insert_exchange(&exchange).unwrap();
+        }
+
+        database.rekey(Some("hunter2")).unwrap();
+        drop(collection);
+        drop(database);
+
+        let mut bytes = fs::read(&path).unwrap();
+        if let Ok(wal) = fs::read(temp_dir.join("state.sqlite-wal")) {
+            bytes.extend(wal);
+        }
+        let positions = |needle: &[u8]| -> Vec<usize> {
+            bytes
+                .windows(needle.len())
+                .positions(|window| window == needle)
+                .collect_vec()
+        };
+        assert_eq!(positions(b"xqrequestsecret"), Vec::<usize>::new());
+        assert_eq!(positions(b"zqresponsesecret"), Vec::<usize>::new());
+        // The URL column is followed by the encrypted request headers
+        let urls = positions(url.as_bytes());
+        assert!(!urls.is_empty());
+        for position in urls {
+            let after = &bytes[position + url.len()..];
+            assert!(
+                after.starts_with(b"SLUMENC1"),
+                "Plain text row at {position}"
+            );
+        }
+    }
+
+    /// Test pinning, labelling, and annotating requests. Annotations should be
+    /// filterable, and pinned requests should survive pruning
+    #[test]
+    fn test_annotation() {
+        let database = Database::factory(());
+        let collection = database
+            .clone()
+            .into_collection(&get_repo_root().join("slumber.yml"))
+            .unwrap();
+        let old = Exchange {
+            start_time: Utc::now() - chrono::Duration::days(2),
+            ..Exchange::factory(())
+        };
+        let new = Exchange::factory(());
+        collection.insert_exchange(&old).unwrap();
+        collection.insert_exchange(&new).unwrap();
+
+        assert_eq!(
+            collection.get_annotation(old.id).unwrap(),
+            Some(ExchangeAnnotation::default())
+        );
+        assert_eq!(collection.get_annotation(RequestId::new()).unwrap(), None);
+
+        let annotation = ExchangeAnnotation {
+            pinned: true,
+            label: Some("baseline".into()),
+            note: Some("Known good".into()),
+        };
+        collection.set_annotation(old.id, &annotation).unwrap();
+        assert_eq!(
+            collection.get_annotation(old.id).unwrap(),
+            Some(annotation.clone())
+        );
+        assert!(collection
+            .set_annotation(RequestId::new(), &annotation)
+            .is_err());
+
+        let search = |filter: HistoryFilter| {
+            collection
+                .search_requests(&filter)
+                .unwrap()
+                .into_iter()
+                .map(|exchange| (exchange.id, exchange.annotation))
+                .collect_vec()
+        };
+        let expected = vec![(old.id, annotation.clone())];
+        assert_eq!(
+            search(HistoryFilter {
+                pinned: true,
+                ..Default::default()
+            }),
+            expected
+        );
+        assert_eq!(
+            search(HistoryFilter {
+                label: Some("baseline".into()),
+                ..Default::default()
+            }),
+            expected
+        );
+        assert_eq!(
+            search(HistoryFilter {
+                label: Some("other".into()),
+                ..Default::default()
+            }),
+            []
+        );
+        assert_eq!(
+            collection
+                .get_latest_matching(&HistoryFilter {
+                    pinned: true,
+                    ..Default::default()
+                })
+                .unwrap()
+                .map(|exchange| exchange.id),
+            Some(old.id)
+        );
+
+        // Pinned request is older than the limit, but isn't deleted
+        let report = database
+            .prune(
+                &RetentionConfig {
+                    max_per_recipe: Some(1),
+                    max_age: Some(Duration::from_secs(60 * 60)),
+                    ..Default::default()
+                },
+                false,
+                false,
+            )
+            .unwrap();
+        assert_eq!(report.deleted, 0);
+        assert_eq!(search(HistoryFilter::default()).len(), 2);
+
+        // Unless pinned requests are opted into pruning
+        let report = database
+            .prune(
+                &RetentionConfig {
+                    max_age: Some(Duration::from_secs(60 * 60)),
+                    keep_pinned: false,
+                    ..Default::default()
+                },
+                false,
+                false,
+            )
+            .unwrap();
+        assert_eq!(report.deleted, 1);
+        assert_eq!(
+            search(HistoryFilter::default())
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect_vec(),
+            [new.id]
+        );
+    }
+
+    #[rstest]
+    #[case::code("404", StatusFilter::Code(StatusCode::NOT_FOUND))]
+    #[case::class("2xx", StatusFilter::Class(2))]
+    #[case::class_upper("5XX", StatusFilter::Class(5))]
+    fn test_parse_status_filter(
+        #[case] input: &str,
+        #[case] expected: StatusFilter,
+    ) {
+        assert_eq!(input.parse::<StatusFilter>().unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::invalid_code("1000")]
+    #[case::invalid_class("6xx")]
+    #[case::text("abc")]
+    fn test_parse_status_filter_error(#[case] input: &str) {
+        assert!(input.parse::<StatusFilter>().is_err());
+    }
+
     /// Test UI state storage and retrieval
     #[test]
     fn test_ui_state() {
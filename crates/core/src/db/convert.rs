@@ -4,7 +4,8 @@ use crate::{
     collection::{ProfileId, RecipeId},
     db::CollectionId,
     http::{
-        Exchange, ExchangeSummary, RequestId, RequestRecord, ResponseRecord,
+        Exchange, ExchangeAnnotation, ExchangeSummary, RequestId,
+        RequestRecord, ResponseRecord,
     },
     util::ResultTraced,
 };
@@ -187,7 +188,7 @@ impl<T: DeserializeOwned> FromSql for JsonEncoded<T> {
     }
 }
 
-/// Convert from `SELECT * FROM requests_v2`
+/// Convert from `SELECT {EXCHANGE_COLUMNS} FROM requests_v2`
 impl<'a, 'b> TryFrom<&'a Row<'b>> for Exchange {
     type Error = rusqlite::Error;
 
@@ -208,6 +209,8 @@ impl<'a, 'b> TryFrom<&'a Row<'b>> for Exchange {
                 body: row
                     .get::<_, Option<SqlWrap<Bytes>>>("request_body")?
                     .map(|wrap| wrap.0),
+                // Requests are redacted before being persisted
+                redactor: Default::default(),
             }),
             response: ResponseRecord {
                 status: row.get::<_, SqlWrap<StatusCode>>("status_code")?.0,
@@ -227,9 +230,25 @@ impl<'a, 'b> TryFrom<&'a Row<'b>> for ExchangeSummary {
     fn try_from(row: &'a Row<'b>) -> Result<Self, Self::Error> {
         Ok(Self {
             id: row.get("id")?,
+            profile_id: row.get("profile_id")?,
+            recipe_id: row.get("recipe_id")?,
             start_time: row.get("start_time")?,
             end_time: row.get("end_time")?,
             status: row.get::<_, SqlWrap<StatusCode>>("status_code")?.0,
+            annotation: row.try_into()?,
+        })
+    }
+}
+
+/// Convert from `SELECT ... FROM requests_v2`
+impl<'a, 'b> TryFrom<&'a Row<'b>> for ExchangeAnnotation {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &'a Row<'b>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            pinned: row.get("pinned")?,
+            label: row.get("label")?,
+            note: row.get("note")?,
         })
     }
 }
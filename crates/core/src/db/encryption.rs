@@ -0,0 +1,454 @@
+//! Optional encryption of history data at rest. When enabled, the
+//! request/response blobs in `requests_v2` and the values in `ui_state_v2` are
+//! encrypted with a key derived from a user-provided passphrase. Metadata
+//! (IDs, timestamps, URLs, status codes) stays in plain text so history can
+//! still be listed and filtered without decrypting every row.
+//!
+//! Encryption is done in SQL, via the `slumber_encrypt` and `slumber_decrypt`
+//! functions registered on the connection. This keeps the row conversion code
+//! oblivious to encryption.
+
+use crate::util::paths::expand_home;
+use anyhow::{anyhow, Context};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+use dialoguer::Password;
+use rusqlite::{
+    functions::FunctionFlags,
+    named_params,
+    types::{Value, ValueRef},
+    Connection, OptionalExtension,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fmt, fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+use thiserror::Error;
+
+/// Environment variable to provide the passphrase for an encrypted database
+pub const KEY_ENV_VAR: &str = "SLUMBER_DB_KEY";
+
+/// Prefix on every encrypted value, so they can be told apart from plain text
+/// values. The full-text index relies on this to skip encrypted values (see
+/// migrations).
+const MAGIC: &[u8] = b"SLUMENC1";
+const NONCE_SIZE: usize = 24;
+const SALT_SIZE: usize = 16;
+/// Encrypted and stored alongside the salt, so we can tell if a key is correct
+/// without trying to decrypt real data
+const VERIFIER: &[u8] = b"slumber";
+/// Encrypted values are always stored as blobs, so the plain text is prefixed
+/// with a tag to restore the original SQL type on decryption
+const TAG_TEXT: u8 = b't';
+const TAG_BLOB: u8 = b'b';
+
+/// Key derivation cost for new keys. The parameters are stored with the key,
+/// so they can be changed without breaking existing databases. Tests use the
+/// cheapest possible parameters, because Argon2 is *slow* in debug builds.
+#[cfg(not(test))]
+const KDF_PARAMS: KdfParams = KdfParams {
+    m_cost: Params::DEFAULT_M_COST,
+    t_cost: Params::DEFAULT_T_COST,
+    p_cost: Params::DEFAULT_P_COST,
+};
+#[cfg(test)]
+const KDF_PARAMS: KdfParams = KdfParams {
+    m_cost: Params::MIN_M_COST,
+    t_cost: Params::MIN_T_COST,
+    p_cost: Params::MIN_P_COST,
+};
+
+/// Configuration for encrypting the history database
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EncryptionConfig {
+    /// Encrypt the database. If the database isn't already encrypted, it
+    /// will be encrypted the next time it's opened. Disabling this does *not*
+    /// decrypt an encrypted database; use `slumber db rekey --decrypt`.
+    pub enabled: bool,
+    /// File containing the passphrase. Used if the environment variable isn't
+    /// set
+    pub key_file: Option<PathBuf>,
+}
+
+impl EncryptionConfig {
+    /// Get the database passphrase, from the first available of:
+    /// - `$SLUMBER_DB_KEY`
+    /// - The configured key file
+    /// - Prompting the user
+    ///
+    /// If `new` is set, the passphrase is about to be used for the first
+    /// time, so the user is asked to confirm it.
+    pub fn passphrase(
+        &self,
+        prompter: &dyn PassphrasePrompter,
+        new: bool,
+    ) -> anyhow::Result<String> {
+        if let Ok(passphrase) = env::var(KEY_ENV_VAR) {
+            return Ok(passphrase);
+        }
+        if let Some(path) = &self.key_file {
+            return read_key_file(&expand_home(path));
+        }
+        prompter.prompt_passphrase("History database passphrase", new)
+    }
+}
+
+/// Read a passphrase from a file. A trailing newline is ignored, because most
+/// editors add one.
+pub fn read_key_file(path: &Path) -> anyhow::Result<String> {
+    let passphrase = fs::read_to_string(path)
+        .with_context(|| format!("Error reading key file {path:?}"))?;
+    Ok(passphrase.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+/// Ask the user for the database passphrase. This is only used when the
+/// passphrase isn't available from the environment or a key file.
+pub trait PassphrasePrompter {
+    /// Prompt for a passphrase. If `confirm` is set, the passphrase is new
+    /// and the user should enter it twice. If the user can't be prompted (e.g.
+    /// there's no terminal), return [DatabaseKeyError::Missing].
+    fn prompt_passphrase(
+        &self,
+        message: &str,
+        confirm: bool,
+    ) -> anyhow::Result<String>;
+}
+
+impl<F> PassphrasePrompter for F
+where
+    F: Fn(&str, bool) -> anyhow::Result<String>,
+{
+    fn prompt_passphrase(
+        &self,
+        message: &str,
+        confirm: bool,
+    ) -> anyhow::Result<String> {
+        self(message, confirm)
+    }
+}
+
+/// Prompt for the passphrase on the terminal. The TUI uses this too, because
+/// the database is loaded before it takes over the terminal.
+pub struct TerminalPassphrasePrompter;
+
+impl PassphrasePrompter for TerminalPassphrasePrompter {
+    fn prompt_passphrase(
+        &self,
+        message: &str,
+        confirm: bool,
+    ) -> anyhow::Result<String> {
+        if !io::stdin().is_terminal() {
+            return Err(DatabaseKeyError::Missing.into());
+        }
+        let mut password = Password::new().with_prompt(message);
+        if confirm {
+            password = password.with_confirmation(
+                "Confirm passphrase",
+                "Passphrases don't match",
+            );
+        }
+        password.interact().context("Error reading passphrase")
+    }
+}
+
+/// Error accessing an encrypted database
+#[derive(Debug, Error)]
+pub enum DatabaseKeyError {
+    #[error(
+        "No key provided for the encrypted history database. Set the \
+        `{KEY_ENV_VAR}` environment variable, set `encryption.key_file` in \
+        the config, or run in a terminal to be prompted"
+    )]
+    Missing,
+    #[error("Incorrect key for the history database")]
+    Incorrect,
+}
+
+/// Argon2 cost parameters
+#[derive(Copy, Clone, Debug)]
+struct KdfParams {
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+}
+
+/// Encryption metadata, stored in the single-row `encryption` table. If the
+/// table is empty, the database isn't encrypted.
+#[derive(Debug)]
+pub(super) struct KeyMetadata {
+    salt: Vec<u8>,
+    params: KdfParams,
+    verifier: Vec<u8>,
+}
+
+impl KeyMetadata {
+    /// Generate a new key from a passphrase, with a random salt
+    pub fn generate(passphrase: &str) -> anyhow::Result<(Cipher, Self)> {
+        let mut salt = vec![0; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = Cipher::derive(passphrase, &salt, KDF_PARAMS)?;
+        let verifier = cipher.encrypt(VERIFIER)?;
+        Ok((
+            cipher,
+            Self {
+                salt,
+                params: KDF_PARAMS,
+                verifier,
+            },
+        ))
+    }
+
+    /// Derive the key for this database from a passphrase, and make sure it's
+    /// the right one
+    pub fn unlock(&self, passphrase: &str) -> anyhow::Result<Cipher> {
+        let cipher = Cipher::derive(passphrase, &self.salt, self.params)?;
+        match cipher.decrypt(&self.verifier) {
+            Ok(verifier) if verifier == VERIFIER => Ok(cipher),
+            _ => Err(DatabaseKeyError::Incorrect.into()),
+        }
+    }
+
+    /// Load metadata from the DB. `None` if the DB isn't encrypted
+    pub fn load(connection: &Connection) -> rusqlite::Result<Option<Self>> {
+        connection
+            .query_row(
+                "SELECT salt, m_cost, t_cost, p_cost, verifier FROM encryption",
+                [],
+                |row| {
+                    Ok(Self {
+                        salt: row.get("salt")?,
+                        params: KdfParams {
+                            m_cost: row.get("m_cost")?,
+                            t_cost: row.get("t_cost")?,
+                            p_cost: row.get("p_cost")?,
+                        },
+                        verifier: row.get("verifier")?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    /// Replace the metadata in the DB. `None` marks the DB as unencrypted
+    pub fn save(
+        metadata: Option<&Self>,
+        connection: &Connection,
+    ) -> rusqlite::Result<()> {
+        connection.execute("DELETE FROM encryption", [])?;
+        if let Some(metadata) = metadata {
+            connection.execute(
+                "INSERT INTO encryption
+                    (id, salt, m_cost, t_cost, p_cost, verifier)
+                VALUES (0, :salt, :m_cost, :t_cost, :p_cost, :verifier)",
+                named_params! {
+                    ":salt": metadata.salt,
+                    ":m_cost": metadata.params.m_cost,
+                    ":t_cost": metadata.params.t_cost,
+                    ":p_cost": metadata.params.p_cost,
+                    ":verifier": metadata.verifier,
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Symmetric cipher for database values
+pub(super) struct Cipher(XChaCha20Poly1305);
+
+impl Cipher {
+    fn derive(
+        passphrase: &str,
+        salt: &[u8],
+        params: KdfParams,
+    ) -> anyhow::Result<Self> {
+        let params =
+            Params::new(params.m_cost, params.t_cost, params.p_cost, None)
+                .map_err(|error| anyhow!("Invalid key parameters: {error}"))?;
+        let mut key = [0; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|error| anyhow!("Error deriving key: {error}"))?;
+        Ok(Self(XChaCha20Poly1305::new(&key.into())))
+    }
+
+    /// Encrypt a value with a random nonce. The output is
+    /// `MAGIC + nonce + ciphertext`
+    fn encrypt(&self, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow!("Error encrypting value"))?;
+        Ok([MAGIC, nonce.as_slice(), &ciphertext].concat())
+    }
+
+    /// Decrypt a value produced by [Self::encrypt]
+    fn decrypt(&self, value: &[u8]) -> Result<Vec<u8>, DatabaseKeyError> {
+        let value = value
+            .strip_prefix(MAGIC)
+            .filter(|value| value.len() >= NONCE_SIZE)
+            .ok_or(DatabaseKeyError::Incorrect)?;
+        let (nonce, ciphertext) = value.split_at(NONCE_SIZE);
+        self.0
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| DatabaseKeyError::Incorrect)
+    }
+}
+
+/// The ciphers used by the SQL functions. These are only different while the
+/// database is being rekeyed.
+#[derive(Default)]
+pub(super) struct CipherState {
+    /// Decrypts values. `None` if the database isn't encrypted
+    pub read: Option<Arc<Cipher>>,
+    /// Encrypts values. `None` to store values in plain text
+    pub write: Option<Arc<Cipher>>,
+}
+
+impl CipherState {
+    pub fn is_encrypted(&self) -> bool {
+        self.read.is_some()
+    }
+}
+
+/// Don't leak the keys
+impl fmt::Debug for CipherState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CipherState")
+            .field("encrypted", &self.is_encrypted())
+            .finish()
+    }
+}
+
+/// Register `slumber_encrypt` and `slumber_decrypt` on a connection. Both
+/// pass `NULL` and non-text/blob values through unchanged. When the database
+/// isn't encrypted, they're both no-ops.
+pub(super) fn register_functions(
+    connection: &Connection,
+    ciphers: &Arc<RwLock<CipherState>>,
+) -> rusqlite::Result<()> {
+    // These can only be called from top-level statements, so a malicious
+    // view/trigger can't use them
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DIRECTONLY;
+
+    let state = Arc::clone(ciphers);
+    connection.create_scalar_function(
+        "slumber_encrypt",
+        1,
+        flags,
+        move |ctx| {
+            let state = state.read().expect("Cipher lock poisoned");
+            let value = ctx.get_raw(0);
+            let Some(cipher) = &state.write else {
+                return Ok(Value::from(value));
+            };
+            let plaintext = match value {
+                ValueRef::Text(text) => [&[TAG_TEXT], text].concat(),
+                ValueRef::Blob(blob) => [&[TAG_BLOB], blob].concat(),
+                _ => return Ok(Value::from(value)),
+            };
+            let ciphertext = cipher.encrypt(&plaintext).map_err(|error| {
+                rusqlite::Error::UserFunctionError(error.into())
+            })?;
+            Ok(Value::Blob(ciphertext))
+        },
+    )?;
+
+    let state = Arc::clone(ciphers);
+    connection.create_scalar_function(
+        "slumber_decrypt",
+        1,
+        flags,
+        move |ctx| {
+            let state = state.read().expect("Cipher lock poisoned");
+            let value = ctx.get_raw(0);
+            let ValueRef::Blob(blob) = value else {
+                return Ok(Value::from(value));
+            };
+            if !blob.starts_with(MAGIC) {
+                return Ok(Value::from(value));
+            }
+            let error = |error: DatabaseKeyError| {
+                rusqlite::Error::UserFunctionError(error.into())
+            };
+            let cipher = state
+                .read
+                .as_ref()
+                .ok_or_else(|| error(DatabaseKeyError::Missing))?;
+            let plaintext = cipher.decrypt(blob).map_err(error)?;
+            match plaintext.split_first() {
+                Some((&TAG_TEXT, text)) => String::from_utf8(text.to_owned())
+                    .map(Value::Text)
+                    .map_err(|error| {
+                        rusqlite::Error::UserFunctionError(error.into())
+                    }),
+                Some((&TAG_BLOB, blob)) => Ok(Value::Blob(blob.to_owned())),
+                _ => Err(error(DatabaseKeyError::Incorrect)),
+            }
+        },
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_err,
+        test_util::{temp_dir, TempDir},
+    };
+    use rstest::rstest;
+
+    /// The passphrase comes from the env var first, then the key file, then
+    /// the prompt
+    #[rstest]
+    fn test_passphrase(temp_dir: TempDir) {
+        let key_file = temp_dir.join("key");
+        fs::write(&key_file, "from_file\n").unwrap();
+        let prompter = |_: &str, _: bool| Ok("from_prompt".to_owned());
+
+        let with_file = EncryptionConfig {
+            enabled: true,
+            key_file: Some(key_file),
+        };
+        let without_file = EncryptionConfig::default();
+
+        {
+            let _guard = env_lock::lock_env([(KEY_ENV_VAR, Some("from_env"))]);
+            assert_eq!(
+                with_file.passphrase(&prompter, false).unwrap(),
+                "from_env"
+            );
+        }
+        let _guard = env_lock::lock_env([(KEY_ENV_VAR, None::<&str>)]);
+        assert_eq!(
+            with_file.passphrase(&prompter, false).unwrap(),
+            "from_file"
+        );
+        assert_eq!(
+            without_file.passphrase(&prompter, false).unwrap(),
+            "from_prompt"
+        );
+    }
+
+    /// Only the right passphrase unlocks the key
+    #[test]
+    fn test_unlock() {
+        let (cipher, metadata) = KeyMetadata::generate("hunter2").unwrap();
+        let encrypted = cipher.encrypt(b"hello").unwrap();
+        assert!(encrypted.starts_with(MAGIC));
+
+        let unlocked = metadata.unlock("hunter2").unwrap();
+        assert_eq!(unlocked.decrypt(&encrypted).unwrap(), b"hello");
+        assert_err!(metadata.unlock("hunter3").map(|_| ()), "Incorrect key");
+    }
+}
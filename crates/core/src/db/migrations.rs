@@ -109,6 +109,153 @@ pub fn migrations() -> Migrations<'static> {
         // also doesn't support dropping columns with UNIQUE so the old
         // one would still be there
         M::up_with_hook("", migrate_collection_paths),
+        // Full-text index over request history. This uses the requests table
+        // as external content, so the text isn't stored twice. Triggers keep
+        // the index in sync with the requests table, and the final statement
+        // indexes all existing rows. Blobs are tokenized as UTF-8 text. Only
+        // the indexed columns are relevant, and none of them are ever updated,
+        // so there's no update trigger.
+        M::up(
+            "CREATE VIRTUAL TABLE requests_v2_fts USING fts5(
+                url,
+                request_headers,
+                request_body,
+                response_headers,
+                response_body,
+                content = 'requests_v2'
+            );
+            CREATE TRIGGER requests_v2_fts_insert AFTER INSERT ON requests_v2
+            BEGIN
+                INSERT INTO requests_v2_fts (
+                    rowid,
+                    url,
+                    request_headers,
+                    request_body,
+                    response_headers,
+                    response_body
+                ) VALUES (
+                    new.rowid,
+                    new.url,
+                    new.request_headers,
+                    new.request_body,
+                    new.response_headers,
+                    new.response_body
+                );
+            END;
+            CREATE TRIGGER requests_v2_fts_delete AFTER DELETE ON requests_v2
+            BEGIN
+                INSERT INTO requests_v2_fts (
+                    requests_v2_fts,
+                    rowid,
+                    url,
+                    request_headers,
+                    request_body,
+                    response_headers,
+                    response_body
+                ) VALUES (
+                    'delete',
+                    old.rowid,
+                    old.url,
+                    old.request_headers,
+                    old.request_body,
+                    old.response_headers,
+                    old.response_body
+                );
+            END;
+            INSERT INTO requests_v2_fts (requests_v2_fts) VALUES ('rebuild');",
+        ),
+        // User annotations for history entries. These are editable, unlike
+        // everything else in the table. None of them are in the full-text
+        // index, so the FTS triggers don't need to change.
+        M::up(
+            "ALTER TABLE requests_v2 ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT 0;
+            ALTER TABLE requests_v2 ADD COLUMN label TEXT;
+            ALTER TABLE requests_v2 ADD COLUMN note TEXT;",
+        ),
+        // Optional encryption at rest. The `encryption` table holds the key
+        // metadata, and is empty if the DB isn't encrypted. Encrypted values
+        // are gibberish to the full-text index, so the index now reads from a
+        // view that hides them. Only the URL is searchable for encrypted rows.
+        // The view is used by the triggers *and* by rebuilds, so the indexed
+        // values always match what gets deleted later. The prefix is
+        // `SLUMENC1`, and must match `encryption::MAGIC`.
+        M::up(
+            "CREATE TABLE encryption (
+                id          INTEGER PRIMARY KEY NOT NULL CHECK (id = 0),
+                salt        BLOB NOT NULL,
+                m_cost      INTEGER NOT NULL,
+                t_cost      INTEGER NOT NULL,
+                p_cost      INTEGER NOT NULL,
+                verifier    BLOB NOT NULL
+            );
+
+            DROP TRIGGER requests_v2_fts_insert;
+            DROP TRIGGER requests_v2_fts_delete;
+            DROP TABLE requests_v2_fts;
+            CREATE VIEW requests_v2_fts_content AS
+            SELECT
+                rowid AS row_id,
+                url,
+                CASE WHEN substr(request_headers, 1, 8) = X'534C554D454E4331'
+                    THEN NULL ELSE request_headers END AS request_headers,
+                CASE WHEN substr(request_body, 1, 8) = X'534C554D454E4331'
+                    THEN NULL ELSE request_body END AS request_body,
+                CASE WHEN substr(response_headers, 1, 8) = X'534C554D454E4331'
+                    THEN NULL ELSE response_headers END AS response_headers,
+                CASE WHEN substr(response_body, 1, 8) = X'534C554D454E4331'
+                    THEN NULL ELSE response_body END AS response_body
+            FROM requests_v2;
+            CREATE VIRTUAL TABLE requests_v2_fts USING fts5(
+                url,
+                request_headers,
+                request_body,
+                response_headers,
+                response_body,
+                content = 'requests_v2_fts_content',
+                content_rowid = 'row_id'
+            );
+            CREATE TRIGGER requests_v2_fts_insert AFTER INSERT ON requests_v2
+            BEGIN
+                INSERT INTO requests_v2_fts (
+                    rowid,
+                    url,
+                    request_headers,
+                    request_body,
+                    response_headers,
+                    response_body
+                )
+                SELECT
+                    row_id,
+                    url,
+                    request_headers,
+                    request_body,
+                    response_headers,
+                    response_body
+                FROM requests_v2_fts_content WHERE row_id = new.rowid;
+            END;
+            CREATE TRIGGER requests_v2_fts_delete BEFORE DELETE ON requests_v2
+            BEGIN
+                INSERT INTO requests_v2_fts (
+                    requests_v2_fts,
+                    rowid,
+                    url,
+                    request_headers,
+                    request_body,
+                    response_headers,
+                    response_body
+                )
+                SELECT
+                    'delete',
+                    row_id,
+                    url,
+                    request_headers,
+                    request_body,
+                    response_headers,
+                    response_body
+                FROM requests_v2_fts_content WHERE row_id = old.rowid;
+            END;
+            INSERT INTO requests_v2_fts (requests_v2_fts) VALUES ('rebuild');",
+        ),
     ])
 }
 
@@ -323,7 +470,7 @@ mod tests {
     use super::*;
     use crate::{
         db::convert::{CollectionPath, JsonEncoded},
-        http::{RequestRecord, ResponseRecord},
+        http::{RequestId, RequestRecord, ResponseRecord},
         test_util::Factory,
         util::paths::get_repo_root,
     };
@@ -338,6 +485,7 @@ mod tests {
     const MIGRATION_REQUESTS_V2: usize = MIGRATION_ALL_V1 + 1;
     const MIGRATION_UI_STATE_V2: usize = MIGRATION_REQUESTS_V2 + 1;
     const MIGRATION_COLLECTION_PATHS: usize = MIGRATION_UI_STATE_V2 + 1;
+    const MIGRATION_REQUESTS_FTS: usize = MIGRATION_COLLECTION_PATHS + 1;
 
     #[fixture]
     fn collection_path() -> CollectionPath {
@@ -580,4 +728,101 @@ mod tests {
             .unwrap();
         assert_eq!(&migrated, &collections);
     }
+
+    /// Test that requests that existed before the full-text index was created
+    /// are indexed by the migration
+    #[rstest]
+    fn test_migrate_requests_fts(
+        collection_path: CollectionPath,
+        mut connection: Connection,
+    ) {
+        let migrations = migrations();
+        migrations
+            .to_version(&mut connection, MIGRATION_COLLECTION_PATHS)
+            .unwrap();
+
+        let collection_id = CollectionId::new();
+        connection
+            .execute(
+                "INSERT INTO collections (id, path) VALUES (:id, :path)",
+                named_params! {
+                    ":id": collection_id,
+                    ":path": collection_path,
+                },
+            )
+            .unwrap();
+        let exchange = Exchange::factory((
+            RequestRecord::factory(()),
+            ResponseRecord {
+                body: json!({"username": "ted"}).into(),
+                ..ResponseRecord::factory(StatusCode::OK)
+            },
+        ));
+        connection
+            .execute(
+                "INSERT INTO requests_v2 (
+                    id,
+                    collection_id,
+                    profile_id,
+                    recipe_id,
+                    start_time,
+                    end_time,
+                    method,
+                    url,
+                    request_headers,
+                    request_body,
+                    status_code,
+                    response_headers,
+                    response_body
+                ) VALUES (
+                    :id,
+                    :collection_id,
+                    :profile_id,
+                    :recipe_id,
+                    :start_time,
+                    :end_time,
+                    :method,
+                    :url,
+                    :request_headers,
+                    :request_body,
+                    :status_code,
+                    :response_headers,
+                    :response_body
+                )",
+                named_params! {
+                    ":id": exchange.id,
+                    ":collection_id": collection_id,
+                    ":profile_id": &exchange.request.profile_id,
+                    ":recipe_id": &exchange.request.recipe_id,
+                    ":start_time": &exchange.start_time,
+                    ":end_time": &exchange.end_time,
+                    ":method": exchange.request.method.as_str(),
+                    ":url": exchange.request.url.as_str(),
+                    ":request_headers": SqlWrap(&exchange.request.headers),
+                    ":request_body": exchange.request.body(),
+                    ":status_code": exchange.response.status.as_u16(),
+                    ":response_headers": SqlWrap(&exchange.response.headers),
+                    ":response_body": exchange.response.body.bytes().deref(),
+                },
+            )
+            .unwrap();
+
+        migrations
+            .to_version(&mut connection, MIGRATION_REQUESTS_FTS)
+            .unwrap();
+
+        let matched: Vec<RequestId> = connection
+            .prepare(
+                "SELECT id FROM requests_v2 WHERE rowid IN (
+                    SELECT rowid FROM requests_v2_fts
+                    WHERE requests_v2_fts MATCH 'ted'
+                )",
+            )
+            .unwrap()
+            .query_map([], |row| row.get("id"))
+            .unwrap()
+            .try_collect()
+            .unwrap();
+        assert_eq!(matched, [exchange.id]);
+    }
 }
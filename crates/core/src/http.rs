@@ -37,34 +37,41 @@
 
 mod cereal;
 pub mod content_type;
+pub mod diff;
+pub mod generate;
+pub mod har;
 mod models;
 pub mod query;
+pub mod redact;
 
 pub use models::*;
 
 use crate::{
-    collection::{Authentication, Method, Recipe, RecipeBody},
+    collection::{Authentication, JsonTemplate, Method, Recipe, RecipeBody},
     db::CollectionDatabase,
-    http::content_type::ContentType,
-    template::{Template, TemplateContext},
+    http::{
+        content_type::ContentType,
+        redact::{RedactionConfig, Redactor},
+    },
+    template::{Template, TemplateContext, TemplateError},
     util::ResultTraced,
 };
 use anyhow::Context;
 use bytes::Bytes;
 use chrono::Utc;
 use futures::{
-    future::{self, try_join_all, OptionFuture},
+    future::{self, try_join_all, BoxFuture, OptionFuture},
     try_join, Future,
 };
 use mime::Mime;
 use reqwest::{
     header::{self, HeaderMap, HeaderName, HeaderValue},
-    multipart::{Form, Part},
     Client, RequestBuilder, Response, Url,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::{collections::HashSet, sync::Arc};
 use tracing::{info, info_span};
+use uuid::Uuid;
 
 const USER_AGENT: &str = concat!("slumber/", env!("CARGO_PKG_VERSION"));
 
@@ -82,6 +89,7 @@ pub struct HttpEngine {
     /// creating a client because it's expensive.
     danger_client: Option<(Client, HashSet<String>)>,
     large_body_size: usize,
+    redaction: Arc<RedactionConfig>,
 }
 
 impl HttpEngine {
@@ -107,6 +115,7 @@ impl HttpEngine {
             client,
             danger_client,
             large_body_size: config.large_body_size,
+            redaction: Arc::new(config.redaction.clone()),
         }
     }
 
@@ -170,6 +179,10 @@ impl HttpEngine {
                 template_context.selected_profile.clone(),
                 &request,
                 self.large_body_size,
+                Redactor::new(
+                    Arc::clone(&self.redaction),
+                    template_context.state.sensitive_values(),
+                ),
             )
             .into(),
             client: client.clone(),
@@ -300,6 +313,8 @@ pub struct HttpEngineConfig {
     /// Request/response bodies over this size are treated differently, for
     /// performance reasons
     pub large_body_size: usize,
+    /// Mask sensitive values before requests are persisted or displayed
+    pub redaction: RedactionConfig,
 }
 
 impl HttpEngineConfig {
@@ -316,6 +331,7 @@ impl Default for HttpEngineConfig {
         Self {
             ignore_certificate_hosts: Default::default(),
             large_body_size: 1000 * 1000, // 1MB
+            redaction: Default::default(),
         }
     }
 }
@@ -618,6 +634,51 @@ impl Recipe {
                 let rendered = try_join_all(iter).await?;
                 RenderedBody::FormUrlencoded(rendered)
             }
+            RecipeBody::Structured { body, content_type } => {
+                let value = body
+                    .render(template_context)
+                    .await
+                    .context("Error rendering body")?;
+                RenderedBody::Raw(
+                    content_type
+                        .parse_json(value)
+                        .prettify()
+                        .into_bytes()
+                        .into(),
+                )
+            }
+            RecipeBody::FormJson(fields) => {
+                let iter = fields.iter().enumerate().filter_map(
+                    |(i, (field, value))| {
+                        // Overrides are plain templates, so they're sent as-is
+                        let override_template =
+                            match options.form_fields.get_override(i) {
+                                Some(BuildFieldOverride::Omit) => return None,
+                                Some(BuildFieldOverride::Override(
+                                    template,
+                                )) => Some(template),
+                                None => None,
+                            };
+                        Some(async move {
+                            let value = match override_template {
+                                Some(template) => template
+                                    .render_string(template_context)
+                                    .await?
+                                    .into(),
+                                None => value.render(template_context).await?,
+                            };
+                            // Strings are sent as-is, everything else as JSON
+                            let value =
+                                ContentType::Json.value_to_string(&value);
+                            Ok::<_, TemplateError>((field.clone(), value))
+                        })
+                    },
+                );
+                let rendered = try_join_all(iter)
+                    .await
+                    .context("Error rendering form field")?;
+                RenderedBody::FormUrlencoded(rendered)
+            }
             RecipeBody::FormMultipart(fields) => {
                 let iter = fields.iter().enumerate().filter_map(
                     |(i, (field, value_template))| {
@@ -661,15 +722,64 @@ impl RecipeBody {
             RecipeBody::Raw { content_type, .. } => {
                 content_type.as_ref().map(ContentType::to_mime)
             }
+            RecipeBody::Structured { content_type, .. } => {
+                Some(content_type.to_mime())
+            }
             // Do *not* set anything for these, because reqwest will do that
             // automatically and we don't want to interfere
-            RecipeBody::FormUrlencoded(_) | RecipeBody::FormMultipart(_) => {
-                None
-            }
+            RecipeBody::FormUrlencoded(_)
+            | RecipeBody::FormJson(_)
+            | RecipeBody::FormMultipart(_) => None,
         }
     }
 }
 
+impl JsonTemplate {
+    /// Render each template in the value, producing plain JSON. Strings are
+    /// rendered concurrently. A string that's a single key is parsed as JSON,
+    /// so it can produce any type.
+    fn render<'a>(
+        &'a self,
+        template_context: &'a TemplateContext,
+    ) -> BoxFuture<'a, Result<serde_json::Value, TemplateError>> {
+        Box::pin(async move {
+            let value = match self {
+                Self::Null => serde_json::Value::Null,
+                Self::Bool(b) => (*b).into(),
+                Self::Number(number) => number.clone().into(),
+                // A lone key can inject any JSON type
+                Self::String(template) if template.is_single_key() => {
+                    let rendered =
+                        template.render_string(template_context).await?;
+                    serde_json::from_str(&rendered)
+                        .unwrap_or(serde_json::Value::String(rendered))
+                }
+                Self::String(template) => {
+                    template.render_string(template_context).await?.into()
+                }
+                Self::Array(values) => try_join_all(
+                    values.iter().map(|value| value.render(template_context)),
+                )
+                .await?
+                .into(),
+                Self::Object(map) => {
+                    try_join_all(map.iter().map(|(key, value)| async move {
+                        Ok::<_, TemplateError>((
+                            key.clone(),
+                            value.render(template_context).await?,
+                        ))
+                    }))
+                    .await?
+                    .into_iter()
+                    .collect::<serde_json::Map<_, _>>()
+                    .into()
+                }
+            };
+            Ok(value)
+        })
+    }
+}
+
 /// Body ready to be added to the request. Each variant corresponds to a method
 /// by which we'll add it to the request. This means it is **not** 1:1 with
 /// [RecipeBody]
@@ -689,17 +799,45 @@ impl RenderedBody {
             RenderedBody::Raw(bytes) => builder.body(bytes),
             RenderedBody::FormUrlencoded(fields) => builder.form(&fields),
             RenderedBody::FormMultipart(fields) => {
-                let mut form = Form::new();
-                for (field, value) in fields {
-                    let part = Part::bytes(value);
-                    form = form.part(field, part);
-                }
-                builder.multipart(form)
+                // Encode the form ourselves instead of using reqwest's
+                // multipart support, because reqwest always turns the form
+                // into a stream. Raw bytes can be stored in the request
+                // record, and used to generate the request for other clients
+                let boundary = Uuid::new_v4().simple().to_string();
+                builder
+                    .header(
+                        header::CONTENT_TYPE,
+                        format!("multipart/form-data; boundary={boundary}"),
+                    )
+                    .body(encode_multipart(&boundary, fields))
             }
         }
     }
 }
 
+/// Encode fields as a `multipart/form-data` body
+fn encode_multipart(boundary: &str, fields: Vec<(String, Vec<u8>)>) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (field, value) in fields {
+        // Quotes and newlines would break out of the header value
+        let name = field
+            .replace('"', "%22")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A");
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\n\
+                Content-Disposition: form-data; name=\"{name}\"\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&value);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+    body
+}
+
 impl From<Method> for reqwest::Method {
     fn from(method: Method) -> Self {
         match method {
@@ -882,6 +1020,7 @@ mod tests {
                 url: expected_url,
                 body: Some(Vec::from(expected_body).into()),
                 headers: expected_headers,
+                redactor: Default::default(),
             }
         );
     }
@@ -970,6 +1109,56 @@ mod tests {
         assert_eq!(body.as_deref(), Some(expected_body));
     }
 
+    /// Test that a structured body field that's a single key is rendered as
+    /// its typed JSON value, while everything else renders to a string
+    #[rstest]
+    #[tokio::test]
+    async fn test_build_body_typed(http_engine: &HttpEngine) {
+        let body = json!({
+            "id": "{{user_id}}",
+            "admin": "{{admin}}",
+            "user": "{{user}}",
+            "quote": "{{quote}}",
+            "label": "user {{user_id}}",
+        });
+        let mut template_context = template_context(
+            [Recipe {
+                body: Some(RecipeBody::Structured {
+                    body: body.try_into().unwrap(),
+                    content_type: ContentType::Json,
+                }),
+                ..Recipe::factory(())
+            }],
+            [],
+        );
+        template_context.overrides = indexmap! {
+            "admin".into() => "true".into(),
+            "user".into() => r#"{"name": "Ted", "tags": [1, 2]}"#.into(),
+            "quote".into() => r#"say "hi""#.into(),
+        };
+        let seed = RequestSeed::new(
+            template_context.collection.first_recipe_id().clone(),
+            BuildOptions::default(),
+        );
+        let body = http_engine
+            .build_body(seed, &template_context)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            json!({
+                "id": 1,
+                "admin": true,
+                "user": {"name": "Ted", "tags": [1, 2]},
+                // Not valid JSON, so it's a string. Quotes are escaped
+                "quote": r#"say "hi""#,
+                "label": "user 1",
+            })
+        );
+    }
+
     /// Test building requests with various authentication methods
     #[rstest]
     #[case::basic(
@@ -1022,6 +1211,7 @@ mod tests {
                     ("authorization", expected_header)
                 ]),
                 body: None,
+                redactor: Default::default(),
             }
         );
     }
@@ -1040,7 +1230,6 @@ mod tests {
         None,
         Some(b"{\n  \"group_id\": \"3\"\n}".as_slice()),
         "^application/json$",
-        &[],
     )]
     // Content-Type has been overridden by an explicit header
     #[case::json_content_type_override(
@@ -1051,7 +1240,6 @@ mod tests {
         Some("text/plain"),
         Some(b"{\n  \"group_id\": \"3\"\n}".as_slice()),
         "^text/plain$",
-        &[],
     )]
     #[case::form_urlencoded(
         RecipeBody::FormUrlencoded(indexmap! {
@@ -1061,7 +1249,6 @@ mod tests {
         None,
         Some(b"user_id=1&token=tokenzzz".as_slice()),
         "^application/x-www-form-urlencoded$",
-        &[],
     )]
     // reqwest sets the content type when initializing the body, so make sure
     // that doesn't override the user's value
@@ -1070,20 +1257,45 @@ mod tests {
         Some("text/plain"),
         Some(b"".as_slice()),
         "^text/plain$",
-        &[],
     )]
-    #[case::form_multipart(
-        RecipeBody::FormMultipart(indexmap! {
-            "user_id".into() => "{{user_id}}".into(),
-            "binary".into() => "{{chains.binary}}".into()
-        }),
+    #[case::yaml(
+        RecipeBody::Structured {
+            body: json!({"group_id": "{{group_id}}", "tags": ["a: b"]})
+                .try_into()
+                .unwrap(),
+            content_type: ContentType::Yaml,
+        },
         None,
-        // multipart bodies are automatically turned into streams by reqwest,
-        // and we don't store stream bodies atm
-        // https://github.com/LucasPickering/slumber/issues/256
+        Some(b"group_id: 3\ntags:\n- 'a: b'".as_slice()),
+        "^application/yaml$",
+    )]
+    #[case::xml(
+        RecipeBody::Structured {
+            body: json!({"user": {"id": "{{user_id}}", "name": "<Ted>"}})
+                .try_into()
+                .unwrap(),
+            content_type: ContentType::Xml,
+        },
         None,
-        "^multipart/form-data; boundary=[a-f0-9-]{67}$",
-        &[("content-length", "321")],
+        Some(
+            b"<user>\n  <id>1</id>\n  <name>&lt;Ted&gt;</name>\n</user>"
+                .as_slice()
+        ),
+        "^application/xml$",
+    )]
+    #[case::form_json(
+        RecipeBody::FormJson(indexmap! {
+            "user".into() => json!({"id": "{{user_id}}", "admin": true})
+                .try_into()
+                .unwrap(),
+            "token".into() => json!("{{token}}").try_into().unwrap(),
+        }),
+        None,
+        Some(
+            b"user=%7B%22id%22%3A1%2C%22admin%22%3Atrue%7D&token=tokenzzz"
+                .as_slice()
+        ),
+        "^application/x-www-form-urlencoded$",
     )]
     #[tokio::test]
     async fn test_structured_body(
@@ -1092,9 +1304,7 @@ mod tests {
         #[case] body: RecipeBody,
         #[case] content_type: Option<&str>,
         #[case] expected_body: Option<&'static [u8]>,
-        // For multipart bodies, the content type includes random content
         #[case] expected_content_type: Regex,
-        #[case] extra_headers: &[(&str, &str)],
     ) {
         let headers = if let Some(content_type) = content_type {
             indexmap! {"content-type".into() => content_type.into()}
@@ -1147,11 +1357,7 @@ mod tests {
                 // Use the actual content type here, because the expected
                 // content type maybe be a pattern and we need an exactl string.
                 // We checked actual=expected above so this is fine
-                headers: header_map(
-                    [("content-type", actual_content_type)]
-                        .into_iter()
-                        .chain(extra_headers.iter().copied())
-                ),
+                headers: header_map([("content-type", actual_content_type)]),
                 ..RequestRecord::factory((
                     Some(
                         template_context.collection.first_profile_id().clone()
@@ -1162,6 +1368,68 @@ mod tests {
         );
     }
 
+    /// Multipart bodies are encoded as raw bytes rather than a stream, so they
+    /// can be stored in the request record
+    #[rstest]
+    #[tokio::test]
+    async fn test_multipart_body(
+        http_engine: &HttpEngine,
+        invalid_utf8_chain: ChainSource,
+    ) {
+        let recipe = Recipe {
+            body: Some(RecipeBody::FormMultipart(indexmap! {
+                "user_id".into() => "{{user_id}}".into(),
+                "binary".into() => "{{chains.binary}}".into()
+            })),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let template_context = template_context(
+            [recipe],
+            [Chain {
+                id: "binary".into(),
+                source: invalid_utf8_chain,
+                ..Chain::factory(())
+            }],
+        );
+
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+
+        let content_type = ticket
+            .record
+            .headers
+            .get(header::CONTENT_TYPE)
+            .expect("Missing Content-Type header")
+            .to_str()
+            .unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .expect("Invalid Content-Type header");
+        assert!(Regex::new("^[a-f0-9]{32}$").unwrap().is_match(boundary));
+        let expected_body = [
+            format!(
+                "--{boundary}\r\n\
+                Content-Disposition: form-data; name=\"user_id\"\r\n\r\n\
+                1\r\n\
+                --{boundary}\r\n\
+                Content-Disposition: form-data; name=\"binary\"\r\n\r\n"
+            )
+            .as_bytes(),
+            b"\xc3\x28",
+            format!("\r\n--{boundary}--\r\n").as_bytes(),
+        ]
+        .concat();
+        assert_eq!(
+            ticket.record.body.as_deref(),
+            Some(expected_body.as_slice())
+        );
+        assert_eq!(
+            ticket.request.body().and_then(Body::as_bytes),
+            Some(expected_body.as_slice())
+        );
+    }
+
     /// Test disabling and overriding authentication, query params, headers, and
     /// bodies
     #[rstest]
@@ -1244,6 +1512,7 @@ mod tests {
                     ("content-type", "application/json"),
                 ]),
                 body: Some(b"hunter2".as_slice().into()),
+                redactor: Default::default(),
             }
         );
     }
@@ -1295,6 +1564,7 @@ mod tests {
                     "application/x-www-form-urlencoded"
                 ),]),
                 body: Some(b"user_id=1&preference=small".as_slice().into()),
+                redactor: Default::default(),
             }
         );
     }
@@ -1375,6 +1645,134 @@ mod tests {
         );
     }
 
+    /// Sensitive values are redacted from the request and response headers
+    /// before they're persisted, but the request is sent with the real values
+    #[rstest]
+    #[tokio::test]
+    async fn test_send_request_redacted(http_engine: &HttpEngine) {
+        let server = MockServer::start().await;
+        let host = server.uri();
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path("/get"))
+            .and(matchers::header("authorization", "Bearer tokenzzz"))
+            .and(matchers::header("x-secret", "first"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("set-cookie", "session=abc123")
+                    .insert_header("x-echo", "secret=first"),
+            )
+            .mount(&server)
+            .await;
+
+        let recipe = Recipe {
+            url: format!("{host}/get?secret={{{{chains.secret}}}}")
+                .as_str()
+                .into(),
+            headers: indexmap! {"X-Secret".into() => "{{chains.secret}}".into()},
+            authentication: Some(Authentication::Bearer("{{token}}".into())),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let chain = Chain {
+            id: "secret".into(),
+            source: ChainSource::Prompt {
+                message: None,
+                default: None,
+            },
+            sensitive: true,
+            ..Chain::factory(())
+        };
+        let template_context = template_context([recipe], [chain]);
+
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+        let exchange = ticket.send(&template_context.database).await.unwrap();
+        assert_eq!(exchange.response.status, StatusCode::OK);
+
+        let stored = template_context
+            .database
+            .get_request(exchange.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            stored.request.url.as_str(),
+            format!("{host}/get?secret=REDACTED")
+        );
+        assert_eq!(
+            stored.request.headers,
+            header_map([
+                ("x-secret", "REDACTED"),
+                ("authorization", "REDACTED"),
+            ])
+        );
+        let response_headers = &stored.response.headers;
+        assert_eq!(response_headers["set-cookie"], "REDACTED");
+        assert_eq!(response_headers["x-echo"], "secret=REDACTED");
+    }
+
+    /// Sensitive values are redacted in whatever encoding they end up in the
+    /// request: percent-encoded in the URL path, form-urlencoded in the query,
+    /// and escaped in a JSON body
+    #[rstest]
+    #[tokio::test]
+    async fn test_send_request_redacted_encoded(http_engine: &HttpEngine) {
+        let server = MockServer::start().await;
+        let host = server.uri();
+        let secret = r#"p@ss "w&rd""#;
+        Mock::given(matchers::method("POST"))
+            .and(matchers::query_param("secret", secret))
+            .and(matchers::body_json(json!({"password": secret})))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let recipe = Recipe {
+            method: collection::Method::Post,
+            url: format!("{host}/users/{{{{chains.secret}}}}")
+                .as_str()
+                .into(),
+            query: vec![("secret".into(), "{{chains.secret}}".into())],
+            body: Some(RecipeBody::Structured {
+                body: json!({"password": "{{chains.secret}}"})
+                    .try_into()
+                    .unwrap(),
+                content_type: ContentType::Json,
+            }),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let chain = Chain {
+            id: "secret".into(),
+            source: ChainSource::Prompt {
+                message: None,
+                default: Some(secret.into()),
+            },
+            sensitive: true,
+            ..Chain::factory(())
+        };
+        let mut template_context = template_context([recipe], [chain]);
+        template_context.prompter = Box::<TestPrompter>::default();
+
+        let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+        let ticket = http_engine.build(seed, &template_context).await.unwrap();
+        let exchange = ticket.send(&template_context.database).await.unwrap();
+        assert_eq!(exchange.response.status, StatusCode::OK);
+
+        let stored = template_context
+            .database
+            .get_request(exchange.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            stored.request.url.as_str(),
+            format!("{host}/users/REDACTED?secret=REDACTED")
+        );
+        assert_eq!(
+            stored.request.body_str().unwrap(),
+            Some("{\n  \"password\": \"REDACTED\"\n}")
+        );
+    }
+
     /// Leading/trailing newlines should be stripped from rendered header
     /// values. These characters are invalid and trigger an error, so we assume
     /// they're unintentional and the user won't miss them.
@@ -5,13 +5,28 @@
 //! not a value, use [ContentType]. If you want to parse dynamically based on
 //! the response's metadata, use [ResponseRecord::parse_body].
 
-use crate::util::Mapping;
+mod binary;
+mod csv;
+mod protobuf;
+mod xml;
+
+pub use csv::CsvTable;
+pub use xml::XPathItem;
+
+use crate::{
+    collection::ProtobufSchema, http::query::QueryError, util::Mapping,
+};
 use anyhow::{anyhow, Context};
 use derive_more::{Deref, Display, From};
-use mime::{Mime, APPLICATION, JSON};
+use mime::{Mime, APPLICATION, CSV, JSON, TEXT, WWW_FORM_URLENCODED, XML};
 use reqwest::header::{self, HeaderMap, HeaderValue};
 use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, ffi::OsStr, fmt::Debug, path::Path};
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    fmt::{self, Debug},
+    path::Path,
+};
 
 /// All supported content types. Each variant should have a corresponding
 /// implementation of [ResponseContent].
@@ -28,16 +43,37 @@ use std::{borrow::Cow, ffi::OsStr, fmt::Debug, path::Path};
 #[serde(rename_all = "snake_case")]
 pub enum ContentType {
     Json,
+    Xml,
+    Yaml,
+    Toml,
+    FormUrlencoded,
+    Csv,
+    Tsv,
+    #[serde(rename = "msgpack")]
+    MessagePack,
+    Cbor,
+    Protobuf,
 }
 
 impl ContentType {
     /// File extensions for each content type
-    const EXTENSIONS: Mapping<'static, ContentType> =
-        Mapping::new(&[(Self::Json, &["json"])]);
+    const EXTENSIONS: Mapping<'static, ContentType> = Mapping::new(&[
+        (Self::Json, &["json"]),
+        (Self::Xml, &["xml"]),
+        (Self::Yaml, &["yaml", "yml"]),
+        (Self::Toml, &["toml"]),
+        // Form data doesn't have a file extension
+        (Self::FormUrlencoded, &[]),
+        (Self::Csv, &["csv"]),
+        (Self::Tsv, &["tsv"]),
+        (Self::MessagePack, &["msgpack", "mpk"]),
+        (Self::Cbor, &["cbor"]),
+        (Self::Protobuf, &["binpb"]),
+    ]);
 
     /// Parse the value of the content-type header and map it to a known content
     /// type
-    fn from_mime(mime_type: &str) -> anyhow::Result<Self> {
+    pub(crate) fn from_mime(mime_type: &str) -> anyhow::Result<Self> {
         let mime_type: Mime = mime_type
             .parse()
             .with_context(|| format!("Invalid content type `{mime_type}`"))?;
@@ -49,6 +85,47 @@ impl ContentType {
             (APPLICATION, JSON, _) | (APPLICATION, _, Some("json")) => {
                 Ok(Self::Json)
             }
+            // Same for XML, e.g. "application/soap+xml"
+            (APPLICATION | TEXT, XML, _) | (APPLICATION, _, Some("xml")) => {
+                Ok(Self::Xml)
+            }
+            // YAML doesn't have an official MIME type, so there are a few
+            // variants floating around
+            (APPLICATION | TEXT, subtype, _)
+                if matches!(subtype.as_str(), "yaml" | "x-yaml") =>
+            {
+                Ok(Self::Yaml)
+            }
+            (APPLICATION, _, Some("yaml")) => Ok(Self::Yaml),
+            (APPLICATION | TEXT, subtype, _) if subtype == "toml" => {
+                Ok(Self::Toml)
+            }
+            (APPLICATION, WWW_FORM_URLENCODED, _) => Ok(Self::FormUrlencoded),
+            (TEXT | APPLICATION, CSV, _) => Ok(Self::Csv),
+            (TEXT, subtype, _) if subtype == "tab-separated-values" => {
+                Ok(Self::Tsv)
+            }
+            (APPLICATION, subtype, _)
+                if matches!(
+                    subtype.as_str(),
+                    "msgpack" | "x-msgpack" | "vnd.msgpack"
+                ) =>
+            {
+                Ok(Self::MessagePack)
+            }
+            (APPLICATION, subtype, _) if subtype == "cbor" => Ok(Self::Cbor),
+            (APPLICATION, _, Some("cbor")) => Ok(Self::Cbor),
+            (APPLICATION, subtype, _)
+                if matches!(
+                    subtype.as_str(),
+                    "protobuf"
+                        | "x-protobuf"
+                        | "x-protobuffer"
+                        | "vnd.google.protobuf"
+                ) =>
+            {
+                Ok(Self::Protobuf)
+            }
             _ => Err(anyhow!("Unknown content type `{mime_type}`")),
         }
     }
@@ -57,6 +134,18 @@ impl ContentType {
     pub fn to_mime(&self) -> Mime {
         match self {
             ContentType::Json => mime::APPLICATION_JSON,
+            // mime doesn't have a constant for this one
+            ContentType::Xml => "application/xml".parse().unwrap(),
+            ContentType::Yaml => "application/yaml".parse().unwrap(),
+            ContentType::Toml => "application/toml".parse().unwrap(),
+            ContentType::FormUrlencoded => {
+                mime::APPLICATION_WWW_FORM_URLENCODED
+            }
+            ContentType::Csv => mime::TEXT_CSV,
+            ContentType::Tsv => "text/tab-separated-values".parse().unwrap(),
+            ContentType::MessagePack => "application/msgpack".parse().unwrap(),
+            ContentType::Cbor => "application/cbor".parse().unwrap(),
+            ContentType::Protobuf => "application/x-protobuf".parse().unwrap(),
         }
     }
 
@@ -90,6 +179,47 @@ impl ContentType {
     ) -> anyhow::Result<Box<dyn ResponseContent>> {
         match self {
             Self::Json => Ok(Box::new(Json::parse(content)?)),
+            Self::Xml => Ok(Box::new(Xml::parse(content)?)),
+            Self::Yaml => Ok(Box::new(Yaml::parse(content)?)),
+            Self::Toml => Ok(Box::new(Toml::parse(content)?)),
+            Self::FormUrlencoded => {
+                Ok(Box::new(FormUrlencoded::parse(content)?))
+            }
+            Self::Csv => {
+                Ok(Box::new(Csv::parse_delimited(Tabular::Csv, content)?))
+            }
+            Self::Tsv => {
+                Ok(Box::new(Csv::parse_delimited(Tabular::Tsv, content)?))
+            }
+            Self::MessagePack => Ok(Box::new(Decoded {
+                content_type: self,
+                json: binary::msgpack_to_json(content)?,
+            })),
+            Self::Cbor => Ok(Box::new(Decoded {
+                content_type: self,
+                json: binary::cbor_to_json(content)?,
+            })),
+            Self::Protobuf => Err(anyhow!(
+                "Protobuf body cannot be decoded without a schema. Add a \
+                `protobuf` field to the recipe"
+            )),
+        }
+    }
+
+    /// Parse some content of this type, using a protobuf schema if available.
+    /// The schema is only used if this is [ContentType::Protobuf]; for any
+    /// other content type, this is the same as [Self::parse_content].
+    pub fn parse_content_with_schema(
+        self,
+        content: &[u8],
+        protobuf: Option<&ProtobufSchema>,
+    ) -> anyhow::Result<Box<dyn ResponseContent>> {
+        match (self, protobuf) {
+            (Self::Protobuf, Some(schema)) => Ok(Box::new(Decoded {
+                content_type: self,
+                json: protobuf::decode(content, schema)?,
+            })),
+            _ => self.parse_content(content),
         }
     }
 
@@ -101,24 +231,88 @@ impl ContentType {
     ) -> Box<dyn ResponseContent> {
         match self {
             Self::Json => Box::new(Json(content)),
+            Self::Xml => Box::new(Xml::from_json(content)),
+            Self::Yaml => Box::new(Yaml(content)),
+            Self::Toml => Box::new(Toml(content)),
+            Self::FormUrlencoded => Box::new(FormUrlencoded(content)),
+            Self::Csv => Box::new(Csv {
+                format: Tabular::Csv,
+                json: content,
+            }),
+            Self::Tsv => Box::new(Csv {
+                format: Tabular::Tsv,
+                json: content,
+            }),
+            Self::MessagePack | Self::Cbor | Self::Protobuf => {
+                Box::new(Decoded {
+                    content_type: self,
+                    json: content,
+                })
+            }
         }
     }
 
     /// Stringify a single JSON value into this format
     pub fn value_to_string(self, value: &serde_json::Value) -> String {
-        match self {
-            ContentType::Json => match value {
-                serde_json::Value::Null => "".into(),
-                serde_json::Value::String(s) => s.clone(),
-                other => other.to_string(),
-            },
+        match value {
+            serde_json::Value::Null => "".into(),
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Bool(_) | serde_json::Value::Number(_) => {
+                value.to_string()
+            }
+            // Only compound values need to be converted to the actual format
+            serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+                match self {
+                    // Binary formats are displayed as JSON
+                    Self::Json
+                    | Self::MessagePack
+                    | Self::Cbor
+                    | Self::Protobuf => value.to_string(),
+                    Self::Xml => xml::from_json(value),
+                    Self::Yaml => yaml_to_string(value),
+                    Self::Toml => json_to_toml(value)
+                        .map(|value| value.to_string())
+                        .unwrap_or_default(),
+                    Self::FormUrlencoded => form_to_string(value),
+                    Self::Csv => {
+                        csv::to_string(value, Tabular::Csv.delimiter())
+                    }
+                    Self::Tsv => {
+                        csv::to_string(value, Tabular::Tsv.delimiter())
+                    }
+                }
+            }
         }
     }
 
     /// Stringify a list of JSON values into this format
     pub fn vec_to_string(self, values: &Vec<&serde_json::Value>) -> String {
         match self {
-            ContentType::Json => serde_json::to_string(&values).unwrap(),
+            ContentType::Json
+            | ContentType::MessagePack
+            | ContentType::Cbor
+            | ContentType::Protobuf => serde_json::to_string(&values).unwrap(),
+            ContentType::Xml => xml::from_json(&serde_json::Value::Array(
+                values.iter().map(|value| (*value).clone()).collect(),
+            )),
+            ContentType::Yaml => yaml_to_string(values),
+            ContentType::Toml => toml::Value::Array(
+                values
+                    .iter()
+                    .filter_map(|value| json_to_toml(value))
+                    .collect(),
+            )
+            .to_string(),
+            // Form data has no list format, so fall back to JSON
+            ContentType::FormUrlencoded => {
+                serde_json::to_string(&values).unwrap()
+            }
+            ContentType::Csv => {
+                csv::vec_to_string(values, Tabular::Csv.delimiter())
+            }
+            ContentType::Tsv => {
+                csv::vec_to_string(values, Tabular::Tsv.delimiter())
+            }
         }
     }
 }
@@ -143,6 +337,12 @@ pub trait ResponseContent: Debug + Display + Send + Sync {
     /// querying intenally, so everything needs to be convertible to/from JSON.
     fn to_json(&self) -> Cow<'_, serde_json::Value>;
 
+    /// Convert the content to XML. XPath queries can only be applied to XML,
+    /// so other content types are converted to JSON, then to XML.
+    fn to_xml(&self) -> Cow<'_, Xml> {
+        Cow::Owned(Xml::from_json(self.to_json().into_owned()))
+    }
+
     /// Facilitate downcasting generic parsed bodies to concrete types for tests
     #[cfg(test)]
     fn as_any(&self) -> &dyn std::any::Any;
@@ -176,6 +376,423 @@ impl ResponseContent for Json {
     }
 }
 
+/// XML content type. The original document text is retained so XPath queries
+/// can be applied to it without loss. The JSON form is generated up front
+/// because it's needed for JSONPath queries and chain selectors.
+#[derive(Clone, Debug, Display, PartialEq)]
+#[display("{document}")]
+pub struct Xml {
+    document: String,
+    json: serde_json::Value,
+}
+
+impl Xml {
+    /// Convert a JSON value to an XML document. See the [xml] module for the
+    /// conventions used.
+    pub fn from_json(json: serde_json::Value) -> Self {
+        Self {
+            document: xml::from_json(&json),
+            json,
+        }
+    }
+
+    /// Apply an XPath query to this document
+    pub fn evaluate_xpath(
+        &self,
+        xpath: &sxd_xpath::XPath,
+    ) -> Result<Vec<XPathItem>, QueryError> {
+        let package = xml::parse(&self.document)
+            .map_err(|error| QueryError::XPath(format!("{error:#}")))?;
+        xml::evaluate(&package.as_document(), xpath)
+    }
+
+    /// Combine the results of an XPath query into a new document
+    pub fn from_xpath_results(items: Vec<XPathItem>) -> Self {
+        let document = xml::results_document(items);
+        // We just generated this so it should be valid, but if not we can
+        // fall back to an empty JSON value
+        let json = xml::parse(&document)
+            .map(|package| xml::to_json(&package.as_document()))
+            .unwrap_or_default();
+        Self { document, json }
+    }
+}
+
+impl ResponseContent for Xml {
+    fn content_type(&self) -> ContentType {
+        ContentType::Xml
+    }
+
+    fn parse(body: &[u8]) -> anyhow::Result<Self> {
+        let document = std::str::from_utf8(body)?;
+        let package = xml::parse(document)?;
+        let json = xml::to_json(&package.as_document());
+        Ok(Self {
+            document: document.to_owned(),
+            json,
+        })
+    }
+
+    fn prettify(&self) -> String {
+        // Documents generated from JSON may not be valid (e.g. undeclared
+        // namespace prefixes), in which case we just show the raw text
+        match xml::parse(&self.document) {
+            Ok(package) => xml::prettify(&package.as_document()),
+            Err(_) => self.document.clone(),
+        }
+    }
+
+    fn to_json(&self) -> Cow<'_, serde_json::Value> {
+        Cow::Borrowed(&self.json)
+    }
+
+    fn to_xml(&self) -> Cow<'_, Xml> {
+        Cow::Borrowed(self)
+    }
+
+    #[cfg(test)]
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
+
+/// YAML content type. The content is stored as JSON, since that's what we need
+/// for querying. This means YAML features that aren't supported by JSON, such
+/// as non-string keys and custom tags, can't be parsed.
+#[derive(Debug, Deref, From, PartialEq)]
+pub struct Yaml(serde_json::Value);
+
+impl ResponseContent for Yaml {
+    fn content_type(&self) -> ContentType {
+        ContentType::Yaml
+    }
+
+    fn parse(body: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self(serde_yaml::from_slice(body)?))
+    }
+
+    fn prettify(&self) -> String {
+        yaml_to_string(&self.0)
+    }
+
+    fn to_json(&self) -> Cow<'_, serde_json::Value> {
+        Cow::Borrowed(&self.0)
+    }
+
+    #[cfg(test)]
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
+
+impl fmt::Display for Yaml {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", yaml_to_string(&self.0))
+    }
+}
+
+/// Serialize a value as YAML, without the trailing newline
+fn yaml_to_string(value: &impl Serialize) -> String {
+    // serde_yaml can't fail serializing a JSON value
+    let mut yaml = serde_yaml::to_string(value).unwrap();
+    yaml.truncate(yaml.trim_end().len());
+    yaml
+}
+
+/// TOML content type. The content is stored as JSON, since that's what we need
+/// for querying. Datetimes are converted to strings. TOML has no null value,
+/// so nulls are dropped when converting back.
+#[derive(Debug, Deref, From, PartialEq)]
+pub struct Toml(serde_json::Value);
+
+impl ResponseContent for Toml {
+    fn content_type(&self) -> ContentType {
+        ContentType::Toml
+    }
+
+    fn parse(body: &[u8]) -> anyhow::Result<Self> {
+        let table: toml::Table = std::str::from_utf8(body)?.parse()?;
+        Ok(Self(toml_to_json(toml::Value::Table(table))))
+    }
+
+    fn prettify(&self) -> String {
+        self.to_string()
+    }
+
+    fn to_json(&self) -> Cow<'_, serde_json::Value> {
+        Cow::Borrowed(&self.0)
+    }
+
+    #[cfg(test)]
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
+
+impl fmt::Display for Toml {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match json_to_toml(&self.0) {
+            // Tables are displayed as a full document. Anything else (i.e.
+            // query results) can only be shown inline
+            Some(toml::Value::Table(table)) => {
+                write!(f, "{}", table.to_string().trim_end())
+            }
+            Some(value) => write!(f, "{value}"),
+            None => Ok(()),
+        }
+    }
+}
+
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => s.into(),
+        toml::Value::Integer(i) => i.into(),
+        // NaN and infinity aren't allowed in JSON, so stringify those
+        toml::Value::Float(f) => serde_json::Number::from_f64(f)
+            .map(serde_json::Value::Number)
+            .unwrap_or_else(|| f.to_string().into()),
+        toml::Value::Boolean(b) => b.into(),
+        toml::Value::Datetime(datetime) => datetime.to_string().into(),
+        toml::Value::Array(array) => {
+            array.into_iter().map(toml_to_json).collect()
+        }
+        toml::Value::Table(table) => table
+            .into_iter()
+            .map(|(key, value)| (key, toml_to_json(value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+    }
+}
+
+/// Convert JSON to TOML. Return `None` for null, which TOML doesn't support
+fn json_to_toml(value: &serde_json::Value) -> Option<toml::Value> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::Bool(b) => Some((*b).into()),
+        serde_json::Value::Number(number) => Some(match number.as_i64() {
+            Some(i) => i.into(),
+            // Fall back to a float for u64s that are too big
+            None => number.as_f64().unwrap_or_default().into(),
+        }),
+        serde_json::Value::String(s) => Some(s.as_str().into()),
+        serde_json::Value::Array(array) => Some(toml::Value::Array(
+            array.iter().filter_map(json_to_toml).collect(),
+        )),
+        serde_json::Value::Object(map) => Some(toml::Value::Table(
+            map.iter()
+                .filter_map(|(key, value)| {
+                    Some((key.clone(), json_to_toml(value)?))
+                })
+                .collect(),
+        )),
+    }
+}
+
+/// `application/x-www-form-urlencoded` content type. The content is stored as
+/// a JSON object of strings. If a key appears multiple times, its values are
+/// grouped into an array.
+#[derive(Debug, Deref, From, PartialEq)]
+pub struct FormUrlencoded(serde_json::Value);
+
+impl ResponseContent for FormUrlencoded {
+    fn content_type(&self) -> ContentType {
+        ContentType::FormUrlencoded
+    }
+
+    fn parse(body: &[u8]) -> anyhow::Result<Self> {
+        let pairs: Vec<(String, String)> = serde_urlencoded::from_bytes(body)?;
+        let mut map = serde_json::Map::new();
+        for (key, value) in pairs {
+            match map.get_mut(&key) {
+                Some(serde_json::Value::Array(values)) => {
+                    values.push(value.into())
+                }
+                Some(existing) => {
+                    *existing = vec![existing.take(), value.into()].into()
+                }
+                None => {
+                    map.insert(key, value.into());
+                }
+            }
+        }
+        Ok(Self(map.into()))
+    }
+
+    /// Show one decoded `key=value` pair per line
+    fn prettify(&self) -> String {
+        match form_pairs(&self.0) {
+            Some(pairs) => pairs
+                .into_iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            None => self.0.to_string(),
+        }
+    }
+
+    fn to_json(&self) -> Cow<'_, serde_json::Value> {
+        Cow::Borrowed(&self.0)
+    }
+
+    #[cfg(test)]
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
+
+impl fmt::Display for FormUrlencoded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", form_to_string(&self.0))
+    }
+}
+
+/// Flatten a JSON object into form key-value pairs. Arrays become repeated
+/// keys. Return `None` if the value isn't an object.
+fn form_pairs(value: &serde_json::Value) -> Option<Vec<(&str, String)>> {
+    let map = value.as_object()?;
+    let mut pairs = Vec::with_capacity(map.len());
+    for (key, value) in map {
+        match value {
+            serde_json::Value::Array(values) => {
+                pairs.extend(values.iter().map(|value| {
+                    (key.as_str(), ContentType::Json.value_to_string(value))
+                }))
+            }
+            value => pairs
+                .push((key.as_str(), ContentType::Json.value_to_string(value))),
+        }
+    }
+    Some(pairs)
+}
+
+/// URL-encode a JSON object. Anything else has no form representation, so it
+/// falls back to JSON.
+fn form_to_string(value: &serde_json::Value) -> String {
+    match form_pairs(value) {
+        // Encoding a list of string pairs can't fail
+        Some(pairs) => serde_urlencoded::to_string(pairs).unwrap(),
+        None => value.to_string(),
+    }
+}
+
+/// CSV or TSV content type. The table is stored as JSON, an array with one
+/// object per row, so it can be queried the same as any other format. All
+/// cells are parsed as strings.
+#[derive(Debug, PartialEq)]
+pub struct Csv {
+    format: Tabular,
+    json: serde_json::Value,
+}
+
+impl Csv {
+    /// Parse a table delimited according to the given format
+    fn parse_delimited(format: Tabular, body: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self {
+            format,
+            json: csv::parse(body, format.delimiter())?,
+        })
+    }
+}
+
+/// The subset of content types that are delimited tables
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Tabular {
+    Csv,
+    Tsv,
+}
+
+impl Tabular {
+    /// Get the cell delimiter
+    fn delimiter(self) -> u8 {
+        match self {
+            Self::Csv => b',',
+            Self::Tsv => b'\t',
+        }
+    }
+}
+
+impl From<Tabular> for ContentType {
+    fn from(format: Tabular) -> Self {
+        match format {
+            Tabular::Csv => Self::Csv,
+            Tabular::Tsv => Self::Tsv,
+        }
+    }
+}
+
+impl ResponseContent for Csv {
+    fn content_type(&self) -> ContentType {
+        self.format.into()
+    }
+
+    fn parse(body: &[u8]) -> anyhow::Result<Self> {
+        Self::parse_delimited(Tabular::Csv, body)
+    }
+
+    fn prettify(&self) -> String {
+        self.to_string()
+    }
+
+    fn to_json(&self) -> Cow<'_, serde_json::Value> {
+        Cow::Borrowed(&self.json)
+    }
+
+    #[cfg(test)]
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
+
+impl fmt::Display for Csv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let delimiter = self.format.delimiter();
+        write!(f, "{}", csv::to_string(&self.json, delimiter))
+    }
+}
+
+/// A binary format that's been decoded to JSON for display and querying:
+/// MessagePack, CBOR, or protobuf. Decoding is lossy (see the [binary] and
+/// [protobuf] modules), so the content is always displayed as JSON rather
+/// than re-encoded.
+#[derive(Debug, PartialEq)]
+pub struct Decoded {
+    content_type: ContentType,
+    json: serde_json::Value,
+}
+
+impl ResponseContent for Decoded {
+    fn content_type(&self) -> ContentType {
+        self.content_type
+    }
+
+    fn parse(body: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self {
+            content_type: ContentType::MessagePack,
+            json: binary::msgpack_to_json(body)?,
+        })
+    }
+
+    fn prettify(&self) -> String {
+        // serde_json can't fail serializing its own Value type
+        serde_json::to_string_pretty(&self.json).unwrap()
+    }
+
+    fn to_json(&self) -> Cow<'_, serde_json::Value> {
+        Cow::Borrowed(&self.json)
+    }
+
+    #[cfg(test)]
+    fn as_any(&self) -> &dyn std::any::Any {
+        self as &dyn std::any::Any
+    }
+}
+
+impl fmt::Display for Decoded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.json)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -197,6 +814,29 @@ mod tests {
     )]
     // Test extended MIME type
     #[case::json_extended("application/geo+json", ContentType::Json)]
+    #[case::xml("application/xml", ContentType::Xml)]
+    #[case::xml_text("text/xml; charset=utf-8", ContentType::Xml)]
+    #[case::xml_extended("application/soap+xml", ContentType::Xml)]
+    #[case::yaml("application/yaml", ContentType::Yaml)]
+    #[case::yaml_x("application/x-yaml", ContentType::Yaml)]
+    #[case::yaml_text("text/yaml", ContentType::Yaml)]
+    #[case::yaml_extended("application/openapi+yaml", ContentType::Yaml)]
+    #[case::toml("application/toml", ContentType::Toml)]
+    #[case::form_urlencoded(
+        "application/x-www-form-urlencoded",
+        ContentType::FormUrlencoded
+    )]
+    #[case::csv("text/csv; charset=utf-8", ContentType::Csv)]
+    #[case::tsv("text/tab-separated-values", ContentType::Tsv)]
+    #[case::msgpack("application/msgpack", ContentType::MessagePack)]
+    #[case::msgpack_x("application/x-msgpack", ContentType::MessagePack)]
+    #[case::cbor("application/cbor", ContentType::Cbor)]
+    #[case::cbor_extended("application/senml+cbor", ContentType::Cbor)]
+    #[case::protobuf("application/x-protobuf", ContentType::Protobuf)]
+    #[case::protobuf_google(
+        "application/vnd.google.protobuf",
+        ContentType::Protobuf
+    )]
     fn test_try_from_mime(
         #[case] mime_type: &str,
         #[case] expected: ContentType,
@@ -223,6 +863,22 @@ mod tests {
             ContentType::from_path(Path::new("turbo.json")).unwrap(),
             ContentType::Json
         );
+        assert_eq!(
+            ContentType::from_path(Path::new("turbo.xml")).unwrap(),
+            ContentType::Xml
+        );
+        assert_eq!(
+            ContentType::from_path(Path::new("turbo.yml")).unwrap(),
+            ContentType::Yaml
+        );
+        assert_eq!(
+            ContentType::from_path(Path::new("turbo.toml")).unwrap(),
+            ContentType::Toml
+        );
+        assert_eq!(
+            ContentType::from_path(Path::new("turbo.tsv")).unwrap(),
+            ContentType::Tsv
+        );
 
         // Errors
         assert_err!(
@@ -242,14 +898,81 @@ mod tests {
         "{\"hello\": \"goodbye\"}",
         Json(json!({"hello": "goodbye"}))
     )]
+    #[case::xml(
+        "application/xml",
+        "<hello>goodbye</hello>",
+        Xml {
+            document: "<hello>goodbye</hello>".into(),
+            json: json!({"hello": "goodbye"}),
+        }
+    )]
+    #[case::yaml(
+        "application/yaml",
+        "hello: goodbye\nlist: [1, true]",
+        Yaml(json!({"hello": "goodbye", "list": [1, true]}))
+    )]
+    #[case::toml(
+        "application/toml",
+        "hello = \"goodbye\"\ndate = 1979-05-27\n[table]\nf = 1.5",
+        Toml(json!({
+            "hello": "goodbye",
+            "date": "1979-05-27",
+            "table": {"f": 1.5},
+        }))
+    )]
+    #[case::form_urlencoded(
+        "application/x-www-form-urlencoded",
+        "hello=good+bye%21&list=1&list=2&list=3",
+        FormUrlencoded(json!({
+            "hello": "good bye!",
+            "list": ["1", "2", "3"],
+        }))
+    )]
+    #[case::csv(
+        "text/csv",
+        "name,count\napple,3\n\"pear, bosc\",2",
+        Csv {
+            format: Tabular::Csv,
+            json: json!([
+                {"name": "apple", "count": "3"},
+                {"name": "pear, bosc", "count": "2"},
+            ]),
+        }
+    )]
+    #[case::tsv(
+        "text/tab-separated-values",
+        "name\tcount\napple,fuji\t3",
+        Csv {
+            format: Tabular::Tsv,
+            json: json!([{"name": "apple,fuji", "count": "3"}]),
+        }
+    )]
+    #[case::msgpack(
+        "application/msgpack",
+        // {"a": [1, true]}
+        b"\x81\xa1a\x92\x01\xc3".as_slice(),
+        Decoded {
+            content_type: ContentType::MessagePack,
+            json: json!({"a": [1, true]}),
+        }
+    )]
+    #[case::cbor(
+        "application/cbor",
+        // {"a": [1, true]}
+        b"\xa1\x61a\x82\x01\xf5".as_slice(),
+        Decoded {
+            content_type: ContentType::Cbor,
+            json: json!({"a": [1, true]}),
+        }
+    )]
     fn test_parse_body<T: ResponseContent + PartialEq + 'static>(
         #[case] content_type: &str,
-        #[case] body: &str,
+        #[case] body: impl AsRef<[u8]>,
         #[case] expected: T,
     ) {
         let response = ResponseRecord {
             headers: headers(content_type),
-            body: body.into(),
+            body: body.as_ref().into(),
             ..ResponseRecord::factory(())
         };
         let content_type =
@@ -281,6 +1004,23 @@ mod tests {
         "not json!",
         "expected ident"
     )]
+    #[case::invalid_xml(Some("application/xml"), "<a></b>", "Invalid XML")]
+    #[case::invalid_yaml(
+        Some("application/yaml"),
+        "a: [",
+        "did not find expected"
+    )]
+    #[case::invalid_toml(Some("application/toml"), "a = ", "TOML parse error")]
+    #[case::invalid_csv(
+        Some("text/csv"),
+        "a,b\n1,2,3",
+        "found record with 3 fields"
+    )]
+    #[case::protobuf_no_schema(
+        Some("application/x-protobuf"),
+        "",
+        "cannot be decoded without a schema"
+    )]
     fn test_parse_body_error<
         T: TryInto<HeaderValue, Error = InvalidHeaderValue>,
     >(
@@ -303,6 +1043,48 @@ mod tests {
         assert_err!(result, expected_error);
     }
 
+    /// Test prettification of each content type
+    #[rstest]
+    #[case::yaml(ContentType::Yaml, "{a: 1, b: [x, y]}", "a: 1\nb:\n- x\n- y")]
+    #[case::toml(
+        ContentType::Toml,
+        "b = { c = true }\na = 1",
+        "a = 1\n\n[b]\nc = true"
+    )]
+    #[case::form_urlencoded(
+        ContentType::FormUrlencoded,
+        "a=hello+world&b=1&b=2",
+        "a=hello world\nb=1\nb=2"
+    )]
+    #[case::csv(ContentType::Csv, "a,b\n\"1,2\",3\n", "a,b\n\"1,2\",3")]
+    fn test_prettify(
+        #[case] content_type: ContentType,
+        #[case] body: &str,
+        #[case] expected: &str,
+    ) {
+        let content = content_type.parse_content(body.as_bytes()).unwrap();
+        assert_eq!(content.prettify(), expected);
+    }
+
+    /// Test stringifying values into each format
+    #[rstest]
+    #[case::yaml_scalar(ContentType::Yaml, json!("hello"), "hello")]
+    #[case::yaml_object(ContentType::Yaml, json!({"a": [1]}), "a:\n- 1")]
+    #[case::toml_object(ContentType::Toml, json!({"a": 1}), "{ a = 1 }")]
+    #[case::form_object(
+        ContentType::FormUrlencoded,
+        json!({"a": "b c", "d": [1, 2]}),
+        "a=b+c&d=1&d=2"
+    )]
+    #[case::form_array(ContentType::FormUrlencoded, json!([1, 2]), "[1,2]")]
+    fn test_value_to_string(
+        #[case] content_type: ContentType,
+        #[case] value: serde_json::Value,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(content_type.value_to_string(&value), expected);
+    }
+
     /// Create header map with the given value for the content-type header
     fn headers(
         content_type: impl TryInto<HeaderValue, Error = InvalidHeaderValue>,
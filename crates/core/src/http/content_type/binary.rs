@@ -0,0 +1,187 @@
+//! Binary serialization formats (MessagePack and CBOR). These are decoded to
+//! JSON for display and querying. Both formats are a superset of JSON, so the
+//! conversion is lossy:
+//! - Binary values are base64-encoded into strings
+//! - Integers too big for JSON are stringified
+//! - Map keys that aren't strings are stringified
+//! - CBOR tags and MessagePack extension types are dropped, leaving just the
+//!   inner value
+
+use anyhow::anyhow;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde_json::Value;
+
+/// Decode a single MessagePack value into JSON
+pub fn msgpack_to_json(mut body: &[u8]) -> anyhow::Result<Value> {
+    let value = rmpv::decode::read_value(&mut body)?;
+    if !body.is_empty() {
+        return Err(anyhow!(
+            "Unexpected {} trailing byte(s) after MessagePack value",
+            body.len()
+        ));
+    }
+    Ok(from_msgpack(value))
+}
+
+/// Decode a single CBOR value into JSON
+pub fn cbor_to_json(body: &[u8]) -> anyhow::Result<Value> {
+    let value: ciborium::Value = ciborium::from_reader(body)?;
+    Ok(from_cbor(value))
+}
+
+fn from_msgpack(value: rmpv::Value) -> Value {
+    match value {
+        rmpv::Value::Nil => Value::Null,
+        rmpv::Value::Boolean(b) => b.into(),
+        rmpv::Value::Integer(i) => match (i.as_u64(), i.as_i64()) {
+            (Some(u), _) => u.into(),
+            (_, Some(i)) => i.into(),
+            // Every MessagePack integer fits in either a u64 or i64
+            (None, None) => unreachable!("Invalid MessagePack integer {i}"),
+        },
+        rmpv::Value::F32(f) => float(f.into()),
+        rmpv::Value::F64(f) => float(f),
+        rmpv::Value::String(s) => match s.as_str() {
+            Some(s) => s.into(),
+            // Invalid UTF-8 is treated as binary
+            None => BASE64_STANDARD.encode(s.as_bytes()).into(),
+        },
+        rmpv::Value::Binary(bytes) | rmpv::Value::Ext(_, bytes) => {
+            BASE64_STANDARD.encode(bytes).into()
+        }
+        rmpv::Value::Array(values) => {
+            values.into_iter().map(from_msgpack).collect()
+        }
+        rmpv::Value::Map(entries) => entries
+            .into_iter()
+            .map(|(key, value)| {
+                (map_key(from_msgpack(key)), from_msgpack(value))
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+    }
+}
+
+fn from_cbor(value: ciborium::Value) -> Value {
+    match value {
+        ciborium::Value::Null => Value::Null,
+        ciborium::Value::Bool(b) => b.into(),
+        ciborium::Value::Integer(i) => {
+            let i = i128::from(i);
+            // CBOR integers can be bigger than JSON supports. Stringify those
+            // so they're not lost
+            u64::try_from(i)
+                .map(Value::from)
+                .or_else(|_| i64::try_from(i).map(Value::from))
+                .unwrap_or_else(|_| i.to_string().into())
+        }
+        ciborium::Value::Float(f) => float(f),
+        ciborium::Value::Text(s) => s.into(),
+        ciborium::Value::Bytes(bytes) => BASE64_STANDARD.encode(bytes).into(),
+        ciborium::Value::Tag(_, value) => from_cbor(*value),
+        ciborium::Value::Array(values) => {
+            values.into_iter().map(from_cbor).collect()
+        }
+        ciborium::Value::Map(entries) => entries
+            .into_iter()
+            .map(|(key, value)| (map_key(from_cbor(key)), from_cbor(value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        // Value is non-exhaustive
+        _ => Value::Null,
+    }
+}
+
+/// JSON doesn't support NaN or infinity, so stringify those
+fn float(f: f64) -> Value {
+    serde_json::Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or_else(|| f.to_string().into())
+}
+
+/// JSON only supports string keys, so stringify anything else
+fn map_key(key: Value) -> String {
+    match key {
+        Value::String(s) => s,
+        key => key.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    #[case::map(
+        rmpv::Value::Map(vec![
+            ("name".into(), "apple".into()),
+            ("count".into(), 3.into()),
+            ("negative".into(), (-3).into()),
+            (1.into(), rmpv::Value::Nil),
+            ("ratio".into(), 0.5.into()),
+        ]),
+        json!({
+            "name": "apple",
+            "count": 3,
+            "negative": -3,
+            "1": null,
+            "ratio": 0.5,
+        })
+    )]
+    #[case::binary(
+        rmpv::Value::Array(vec![
+            rmpv::Value::Binary(vec![1, 2, 3]),
+            rmpv::Value::Ext(1, vec![255]),
+        ]),
+        json!(["AQID", "/w=="])
+    )]
+    #[case::nan(rmpv::Value::F64(f64::NAN), json!("NaN"))]
+    fn test_msgpack_to_json(
+        #[case] value: rmpv::Value,
+        #[case] expected: Value,
+    ) {
+        let mut body = Vec::new();
+        rmpv::encode::write_value(&mut body, &value).unwrap();
+        assert_eq!(msgpack_to_json(&body).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_msgpack_trailing_bytes() {
+        let mut body = Vec::new();
+        rmpv::encode::write_value(&mut body, &3.into()).unwrap();
+        body.push(0);
+        assert_eq!(
+            msgpack_to_json(&body).unwrap_err().to_string(),
+            "Unexpected 1 trailing byte(s) after MessagePack value"
+        );
+    }
+
+    #[rstest]
+    #[case::map(
+        ciborium::Value::Map(vec![
+            ("name".into(), "apple".into()),
+            ("count".into(), 3.into()),
+            (true.into(), ciborium::Value::Null),
+        ]),
+        json!({"name": "apple", "count": 3, "true": null})
+    )]
+    #[case::bytes(ciborium::Value::Bytes(vec![1, 2, 3]), json!("AQID"))]
+    #[case::tag(
+        ciborium::Value::Tag(0, Box::new("2024-01-01T00:00:00Z".into())),
+        json!("2024-01-01T00:00:00Z")
+    )]
+    #[case::big_int(
+        ciborium::Value::Integer((i64::MIN as i128 - 1).try_into().unwrap()),
+        json!("-9223372036854775809")
+    )]
+    fn test_cbor_to_json(
+        #[case] value: ciborium::Value,
+        #[case] expected: Value,
+    ) {
+        let mut body = Vec::new();
+        ciborium::into_writer(&value, &mut body).unwrap();
+        assert_eq!(cbor_to_json(&body).unwrap(), expected);
+    }
+}
@@ -0,0 +1,214 @@
+//! CSV/TSV support. Tables are converted to JSON as an array of objects, with
+//! one object per row keyed by the header row. That allows querying columns
+//! and cells by header name, e.g. `$[*].name` or `$[0].name`.
+
+use csv::{ReaderBuilder, StringRecord, Terminator, WriterBuilder};
+use indexmap::IndexSet;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Parse a delimited table into a JSON array of row objects. The first row is
+/// treated as the header row. Duplicate headers get a numeric suffix (`name`,
+/// `name_2`, ...) so no column is lost.
+pub fn parse(body: &[u8], delimiter: u8) -> anyhow::Result<Value> {
+    let mut reader =
+        ReaderBuilder::new().delimiter(delimiter).from_reader(body);
+    let headers = unique_headers(reader.headers()?);
+    let rows = reader
+        .records()
+        .map(|record| {
+            let record = record?;
+            Ok(headers
+                .iter()
+                .zip(&record)
+                .map(|(header, cell)| (header.to_owned(), cell.into()))
+                .collect::<serde_json::Map<_, _>>()
+                .into())
+        })
+        .collect::<anyhow::Result<Vec<Value>>>()?;
+    Ok(rows.into())
+}
+
+/// Make header names unique by adding a suffix to repeats. A suffixed name
+/// never takes the name of another header in the row.
+fn unique_headers(headers: &StringRecord) -> Vec<String> {
+    let original: HashSet<&str> = headers.iter().collect();
+    let mut unique: IndexSet<String> = IndexSet::new();
+    for header in headers {
+        let mut name = header.to_owned();
+        let mut i = 2;
+        while unique.contains(&name)
+            || (name != header && original.contains(name.as_str()))
+        {
+            name = format!("{header}_{i}");
+            i += 1;
+        }
+        unique.insert(name);
+    }
+    unique.into_iter().collect()
+}
+
+/// Serialize a JSON value as a delimited table. Arrays of objects (and single
+/// objects) become a table with a header row. Any other array is written with
+/// one value per line, and a scalar is just written as itself.
+pub fn to_string(value: &Value, delimiter: u8) -> String {
+    match value {
+        Value::Array(values) => {
+            vec_to_string(&values.iter().collect::<Vec<_>>(), delimiter)
+        }
+        Value::Object(_) => vec_to_string(&[value], delimiter),
+        _ => cell_to_string(value),
+    }
+}
+
+/// Serialize a list of JSON values as a delimited table. See [to_string]
+pub fn vec_to_string(values: &[&Value], delimiter: u8) -> String {
+    let mut writer = WriterBuilder::new()
+        .delimiter(delimiter)
+        .terminator(Terminator::Any(b'\n'))
+        // Rows of a list of scalars won't have headers
+        .flexible(true)
+        .from_writer(Vec::new());
+
+    // Writing to a Vec can't fail
+    if let Some(table) = CsvTable::from_values(values) {
+        writer.write_record(&table.headers).unwrap();
+        for row in &table.rows {
+            writer.write_record(row).unwrap();
+        }
+    } else {
+        for value in values {
+            writer.write_record([cell_to_string(value)]).unwrap();
+        }
+    }
+
+    let mut output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    output.truncate(output.trim_end_matches('\n').len());
+    output
+}
+
+/// A delimited table of strings, derived from JSON. This is what gets rendered
+/// for a CSV/TSV body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvTable {
+    pub headers: Vec<String>,
+    /// Each row has exactly one cell per header
+    pub rows: Vec<Vec<String>>,
+}
+
+impl CsvTable {
+    /// Build a table from a JSON array of objects, or a single object. The
+    /// headers are the union of all keys, in the order they first appear. Any
+    /// missing cells are empty. Return `None` if the value isn't table-shaped.
+    pub fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::Array(values) => {
+                Self::from_values(&values.iter().collect::<Vec<_>>())
+            }
+            Value::Object(_) => Self::from_values(&[value]),
+            _ => None,
+        }
+    }
+
+    fn from_values(values: &[&Value]) -> Option<Self> {
+        let objects = values
+            .iter()
+            .map(|value| value.as_object())
+            .collect::<Option<Vec<_>>>()?;
+        if objects.is_empty() {
+            return None;
+        }
+
+        let headers: IndexSet<&String> =
+            objects.iter().flat_map(|object| object.keys()).collect();
+        let rows = objects
+            .iter()
+            .map(|object| {
+                headers
+                    .iter()
+                    .map(|header| {
+                        object
+                            .get(*header)
+                            .map(cell_to_string)
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect();
+        Some(Self {
+            headers: headers.into_iter().cloned().collect(),
+            rows,
+        })
+    }
+}
+
+/// Stringify a single cell. Nested values are written as JSON
+fn cell_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    #[case::table("a,b\n1,\"x,y\"\n2,", json!([{"a": "1", "b": "x,y"}, {"a": "2", "b": ""}]))]
+    #[case::duplicate_headers(
+        "a,a,b,a_2\n1,2,3,4",
+        json!([{"a": "1", "a_3": "2", "b": "3", "a_2": "4"}])
+    )]
+    fn test_parse(#[case] body: &str, #[case] expected: Value) {
+        assert_eq!(parse(body.as_bytes(), b',').unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::table(
+        json!([{"a": "1", "b": "x,y"}, {"a": "2", "b": ""}]),
+        "a,b\n1,\"x,y\"\n2,"
+    )]
+    #[case::ragged_objects(
+        json!([{"a": 1}, {"b": true, "a": null}]),
+        "a,b\n1,\n,true"
+    )]
+    #[case::object(json!({"a": 1, "b": [1, 2]}), "a,b\n1,\"[1,2]\"")]
+    #[case::column(json!(["x", "y", 3]), "x\ny\n3")]
+    #[case::scalar(json!("x,y"), "x,y")]
+    fn test_to_string(#[case] value: Value, #[case] expected: &str) {
+        assert_eq!(to_string(&value, b','), expected);
+    }
+
+    #[test]
+    fn test_to_string_tsv() {
+        assert_eq!(
+            to_string(&json!([{"a": "1", "b": "2"}]), b'\t'),
+            "a\tb\n1\t2"
+        );
+    }
+
+    #[rstest]
+    #[case::table(
+        json!([{"a": "1", "b": "2"}, {"b": "3"}]),
+        Some(CsvTable {
+            headers: vec!["a".into(), "b".into()],
+            rows: vec![
+                vec!["1".into(), "2".into()],
+                vec!["".into(), "3".into()],
+            ],
+        })
+    )]
+    #[case::empty(json!([]), None)]
+    #[case::scalars(json!(["a", "b"]), None)]
+    #[case::scalar(json!(3), None)]
+    fn test_table_from_json(
+        #[case] value: Value,
+        #[case] expected: Option<CsvTable>,
+    ) {
+        assert_eq!(CsvTable::from_json(&value), expected);
+    }
+}
@@ -0,0 +1,227 @@
+//! Protobuf decoding. Unlike other formats, protobuf isn't self-describing,
+//! so decoding requires a schema. The schema is defined on the recipe, and
+//! points to either a `.proto` file or a compiled descriptor set. Decoded
+//! messages are converted to JSON according to the
+//! [canonical JSON mapping](https://protobuf.dev/programming-guides/proto3/#json),
+//! except that fields with default values are included.
+
+use crate::{collection::ProtobufSchema, util::paths::expand_home};
+use anyhow::{anyhow, Context};
+use prost_reflect::{DescriptorPool, DynamicMessage, SerializeOptions};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs, iter,
+    path::{self, Path, PathBuf},
+    sync::{LazyLock, Mutex},
+    time::SystemTime,
+};
+
+/// Loaded schemas, keyed by file path and include paths. Each pool is stored
+/// with the modification time of its file, and reloaded when that changes.
+/// This saves recompiling a `.proto` file every time a body is decoded.
+static POOLS: LazyLock<Mutex<HashMap<PoolKey, (SystemTime, DescriptorPool)>>> =
+    LazyLock::new(Default::default);
+
+/// Absolute schema file path, and include paths
+type PoolKey = (PathBuf, Vec<PathBuf>);
+
+/// Decode a protobuf message into JSON, using the given schema
+pub fn decode(body: &[u8], schema: &ProtobufSchema) -> anyhow::Result<Value> {
+    let pool = get_pool(schema)?;
+    let descriptor =
+        pool.get_message_by_name(&schema.message).ok_or_else(|| {
+            anyhow!(
+                "Message `{}` not found in schema {:?}",
+                schema.message,
+                schema.file
+            )
+        })?;
+    let message =
+        DynamicMessage::decode(descriptor, body).with_context(|| {
+            format!("Error decoding body as protobuf `{}`", schema.message)
+        })?;
+    let options = SerializeOptions::new().skip_default_fields(false);
+    Ok(message
+        .serialize_with_options(serde_json::value::Serializer, &options)?)
+}
+
+/// Get the descriptor pool for a schema, from the cache if the file hasn't
+/// changed since it was loaded. Only the schema file itself is checked, so
+/// changes to an imported file require touching the schema file too.
+fn get_pool(schema: &ProtobufSchema) -> anyhow::Result<DescriptorPool> {
+    let path = expand_home(&schema.file);
+    let Ok(modified) = fs::metadata(&path).and_then(|m| m.modified()) else {
+        // Loading will fail with a more useful error
+        return load_pool(schema);
+    };
+    let key = (
+        path::absolute(&path).unwrap_or_else(|_| path.into_owned()),
+        schema.include.clone(),
+    );
+
+    if let Some((cached_modified, pool)) =
+        POOLS.lock().expect("Protobuf cache poisoned").get(&key)
+    {
+        if *cached_modified == modified {
+            return Ok(pool.clone());
+        }
+    }
+    let pool = load_pool(schema)?;
+    POOLS
+        .lock()
+        .expect("Protobuf cache poisoned")
+        .insert(key, (modified, pool.clone()));
+    Ok(pool)
+}
+
+/// Load all message descriptors from the schema file. `.proto` files are
+/// compiled on the fly. Anything else is assumed to be a descriptor set.
+fn load_pool(schema: &ProtobufSchema) -> anyhow::Result<DescriptorPool> {
+    let path = expand_home(&schema.file);
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "proto")
+    {
+        // The file has to be under one of the include paths, so always include
+        // its own directory
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let includes = iter::once(parent.to_owned()).chain(
+            schema
+                .include
+                .iter()
+                .map(|path| expand_home(path).into_owned()),
+        );
+        let mut compiler = protox::Compiler::new(includes)?;
+        compiler
+            .open_file(&path)
+            .with_context(|| format!("Error compiling {path:?}"))?;
+        Ok(compiler.descriptor_pool())
+    } else {
+        let bytes = fs::read(&path).with_context(|| {
+            format!("Error reading descriptor set {path:?}")
+        })?;
+        DescriptorPool::decode(bytes.as_slice())
+            .with_context(|| format!("Invalid descriptor set {path:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_err,
+        test_util::{temp_dir, test_data_dir, TempDir},
+    };
+    use prost::Message;
+    use rstest::rstest;
+    use serde_json::json;
+    use std::time::Duration;
+
+    const MESSAGE: &str = "users.v1.User";
+
+    /// Encode a test message, using the JSON mapping to build it
+    fn encode(pool: &DescriptorPool, json: Value) -> Vec<u8> {
+        let descriptor = pool.get_message_by_name(MESSAGE).unwrap();
+        DynamicMessage::deserialize(descriptor, json)
+            .unwrap()
+            .encode_to_vec()
+    }
+
+    fn schema(file: PathBuf) -> ProtobufSchema {
+        ProtobufSchema {
+            file,
+            message: MESSAGE.into(),
+            include: vec![],
+        }
+    }
+
+    /// Decode using a .proto file, with imports
+    #[rstest]
+    fn test_decode_proto(test_data_dir: PathBuf) {
+        let schema = schema(test_data_dir.join("protobuf/user.proto"));
+        let pool = load_pool(&schema).unwrap();
+        let body = encode(
+            &pool,
+            json!({"id": 3, "name": "Ted", "address": {"city": "Boston"}}),
+        );
+        assert_eq!(
+            decode(&body, &schema).unwrap(),
+            json!({
+                "id": 3,
+                "name": "Ted",
+                // Default fields are included
+                "tags": [],
+                "address": {"city": "Boston", "zip": ""},
+            })
+        );
+    }
+
+    /// Decode using a pre-compiled descriptor set
+    #[rstest]
+    fn test_decode_descriptor_set(test_data_dir: PathBuf, temp_dir: TempDir) {
+        let pool =
+            load_pool(&schema(test_data_dir.join("protobuf/user.proto")))
+                .unwrap();
+        let path = temp_dir.join("user.binpb");
+        fs::write(&path, pool.encode_to_vec()).unwrap();
+
+        let body = encode(&pool, json!({"id": 3, "tags": ["a"]}));
+        assert_eq!(
+            decode(&body, &schema(path)).unwrap(),
+            json!({"id": 3, "name": "", "tags": ["a"]})
+        );
+    }
+
+    /// The schema is only loaded again once the file is modified
+    #[rstest]
+    fn test_decode_cache(test_data_dir: PathBuf, temp_dir: TempDir) {
+        for file in ["user.proto", "address.proto"] {
+            fs::copy(
+                test_data_dir.join("protobuf").join(file),
+                temp_dir.join(file),
+            )
+            .unwrap();
+        }
+        let path = temp_dir.join("user.proto");
+        let schema = schema(path.clone());
+        let body = encode(&load_pool(&schema).unwrap(), json!({"id": 3}));
+        decode(&body, &schema).unwrap();
+
+        // Break the file without changing its modification time. If it were
+        // compiled again, decoding would fail
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        fs::write(&path, "not protobuf").unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(modified).unwrap();
+        assert_eq!(decode(&body, &schema).unwrap()["id"], json!(3));
+
+        // Now it's modified, so it's compiled again
+        file.set_modified(modified + Duration::from_secs(1))
+            .unwrap();
+        assert_err!(decode(&body, &schema), "Error compiling");
+    }
+
+    #[rstest]
+    #[case::unknown_message(
+        "users.v1.Unknown",
+        b"",
+        "Message `users.v1.Unknown` not found"
+    )]
+    #[case::invalid_body(MESSAGE, b"\xff", "Error decoding body as protobuf")]
+    fn test_decode_error(
+        test_data_dir: PathBuf,
+        #[case] message: &str,
+        #[case] body: &[u8],
+        #[case] expected_error: &str,
+    ) {
+        let schema = ProtobufSchema {
+            message: message.into(),
+            ..schema(test_data_dir.join("protobuf/user.proto"))
+        };
+        assert_err!(decode(body, &schema), expected_error);
+    }
+}
@@ -0,0 +1,639 @@
+//! XML parsing, formatting, and querying. XML has no canonical mapping to JSON,
+//! so we use these conventions when converting between the two:
+//!
+//! - The document is an object with a single key: the root element's name
+//! - Attributes are stored under `@name`
+//! - Text content is stored under `#text`, or as a plain string if the element
+//!   has no attributes or child elements
+//! - Child elements are stored by name. Repeated names are grouped into an
+//!   array
+//! - An empty element is `null`
+
+use crate::http::query::QueryError;
+use anyhow::Context as _;
+use indexmap::IndexSet;
+use serde_json::{Map, Value};
+use std::{borrow::Cow, fmt::Write};
+use sxd_document::{
+    dom::{
+        Attribute, ChildOfElement, ChildOfRoot, Comment, Document, Element,
+        ProcessingInstruction,
+    },
+    parser, Package,
+};
+use sxd_xpath::{nodeset::Node, Context};
+
+/// Indentation used for prettified output
+const INDENT: &str = "  ";
+/// Root element used to wrap JSON values that don't map to a single element
+const ROOT_ELEMENT: &str = "root";
+/// Element used for each item of a wrapped JSON array
+const ITEM_ELEMENT: &str = "item";
+/// Root element for the results of an XPath query
+const RESULTS_ELEMENT: &str = "results";
+/// Element used to wrap non-element XPath results
+const RESULT_ELEMENT: &str = "result";
+
+/// Parse an XML document
+pub fn parse(text: &str) -> anyhow::Result<Package> {
+    parser::parse(text).context("Invalid XML")
+}
+
+/// Format a document with one element per line and indentation. Whitespace
+/// between elements is discarded, and text content is trimmed.
+pub fn prettify(document: &Document) -> String {
+    let mut buf = String::new();
+    for child in document.root().children() {
+        match child {
+            ChildOfRoot::Element(element) => {
+                write_element(&mut buf, element, true, Some(0))
+            }
+            ChildOfRoot::Comment(comment) => {
+                write!(&mut buf, "<!--{}-->", comment.text()).unwrap()
+            }
+            ChildOfRoot::ProcessingInstruction(pi) => {
+                write_pi(&mut buf, pi.target(), pi.value())
+            }
+        }
+        buf.push('\n');
+    }
+    // Remove the trailing newline
+    buf.pop();
+    buf
+}
+
+/// Convert a document to JSON. See module doc for the conventions used.
+pub fn to_json(document: &Document) -> Value {
+    let mut map = Map::new();
+    for child in document.root().children() {
+        if let ChildOfRoot::Element(element) = child {
+            map.insert(element_name(element).into(), element_to_json(element));
+        }
+    }
+    Value::Object(map)
+}
+
+/// Convert a JSON value to an XML document. This is the inverse of
+/// [to_json]. Values that don't map to a single root element (arrays, scalars,
+/// and objects with multiple keys) are wrapped in a `<root>` element.
+pub fn from_json(value: &Value) -> String {
+    let mut buf = String::new();
+    match value {
+        Value::Object(map) if map.len() == 1 => {
+            let (key, inner) = map.iter().next().unwrap();
+            if is_element_key(key) && !inner.is_array() {
+                write_json(&mut buf, key, inner);
+            } else {
+                write_json(&mut buf, ROOT_ELEMENT, value);
+            }
+        }
+        Value::Array(items) => {
+            write!(&mut buf, "<{ROOT_ELEMENT}>").unwrap();
+            for item in items {
+                write_json(&mut buf, ITEM_ELEMENT, item);
+            }
+            write!(&mut buf, "</{ROOT_ELEMENT}>").unwrap();
+        }
+        _ => write_json(&mut buf, ROOT_ELEMENT, value),
+    }
+    buf
+}
+
+/// A single result from an XPath query
+#[derive(Debug, PartialEq)]
+pub enum XPathItem {
+    /// An element node, serialized as XML
+    Element(String),
+    /// Any other type of node (attribute, text, etc.) or a scalar value
+    /// (string, number, boolean), as text
+    Text(String),
+}
+
+impl XPathItem {
+    pub fn into_string(self) -> String {
+        match self {
+            Self::Element(s) | Self::Text(s) => s,
+        }
+    }
+}
+
+/// Evaluate an XPath expression against a document. Namespace prefixes
+/// declared on the root element can be used in the expression. Node results
+/// are returned in document order. Scalar results (string, number, boolean)
+/// are returned as a single item.
+pub fn evaluate(
+    document: &Document,
+    xpath: &sxd_xpath::XPath,
+) -> Result<Vec<XPathItem>, QueryError> {
+    let mut context = Context::new();
+    for child in document.root().children() {
+        if let ChildOfRoot::Element(element) = child {
+            for namespace in element.namespaces_in_scope() {
+                context.set_namespace(namespace.prefix(), namespace.uri());
+            }
+        }
+    }
+
+    let value = xpath
+        .evaluate(&context, document.root())
+        .map_err(|error| QueryError::XPath(error.to_string()))?;
+    let items = match value {
+        sxd_xpath::Value::Nodeset(nodes) => nodes
+            .document_order()
+            .into_iter()
+            .map(|node| match node {
+                // Selecting the root gives back the whole document
+                Node::Root(_) => XPathItem::Element(serialize(document)),
+                Node::Element(element) => {
+                    let mut buf = String::new();
+                    write_element(&mut buf, element, true, None);
+                    XPathItem::Element(buf)
+                }
+                node => XPathItem::Text(node.string_value()),
+            })
+            .collect(),
+        value => vec![XPathItem::Text(value.into_string())],
+    };
+    Ok(items)
+}
+
+/// Combine XPath results into a single document, under a `<results>` root.
+/// Non-element items are wrapped in a `<result>` element.
+pub fn results_document(items: Vec<XPathItem>) -> String {
+    let mut buf = String::new();
+    write!(&mut buf, "<{RESULTS_ELEMENT}>").unwrap();
+    for item in items {
+        match item {
+            XPathItem::Element(element) => buf.push_str(&element),
+            XPathItem::Text(text) => write!(
+                &mut buf,
+                "<{RESULT_ELEMENT}>{}</{RESULT_ELEMENT}>",
+                escape_text(&text)
+            )
+            .unwrap(),
+        }
+    }
+    write!(&mut buf, "</{RESULTS_ELEMENT}>").unwrap();
+    buf
+}
+
+/// Serialize a document compactly, with no added whitespace
+fn serialize(document: &Document) -> String {
+    let mut buf = String::new();
+    for child in document.root().children() {
+        if let ChildOfRoot::Element(element) = child {
+            write_element(&mut buf, element, true, None);
+        }
+    }
+    buf
+}
+
+/// Write an element and all its descendants. If `depth` is given, output will
+/// be indented and text will be trimmed. Otherwise, output is compact and text
+/// is written as-is. If `is_top` is set, all namespaces in scope will be
+/// declared on the element, so the output is a valid standalone document.
+/// Otherwise only namespaces introduced by this element will be declared.
+fn write_element(
+    buf: &mut String,
+    element: Element,
+    is_top: bool,
+    depth: Option<usize>,
+) {
+    write!(buf, "<{}", element_name(element)).unwrap();
+    write_namespaces(buf, element, is_top);
+    for attribute in element.attributes() {
+        write!(
+            buf,
+            " {}=\"{}\"",
+            attribute_name(attribute),
+            escape_attribute(attribute.value())
+        )
+        .unwrap();
+    }
+
+    let children: Vec<_> = merge_text(element.children())
+        .into_iter()
+        // Whitespace between tags is meaningless when prettifying
+        .filter(|child| match (child, depth) {
+            (Child::Text(text), Some(_)) => !text.trim().is_empty(),
+            _ => true,
+        })
+        .collect();
+    if children.is_empty() {
+        buf.push_str("/>");
+        return;
+    }
+    buf.push('>');
+
+    // If the element holds nothing but text, keep it all on one line
+    let text_only =
+        children.iter().all(|child| matches!(child, Child::Text(_)));
+    let child_depth = depth.filter(|_| !text_only).map(|depth| depth + 1);
+    for child in children {
+        if let Some(depth) = child_depth {
+            buf.push('\n');
+            buf.push_str(&INDENT.repeat(depth));
+        }
+        match child {
+            Child::Element(child) => {
+                write_element(buf, child, false, child_depth)
+            }
+            Child::Comment(comment) => {
+                write!(buf, "<!--{}-->", comment.text()).unwrap()
+            }
+            Child::ProcessingInstruction(pi) => {
+                write_pi(buf, pi.target(), pi.value())
+            }
+            Child::Text(text) => {
+                let text = if depth.is_some() { text.trim() } else { &text };
+                buf.push_str(&escape_text(text));
+            }
+        }
+    }
+    if let Some(depth) = depth.filter(|_| !text_only) {
+        buf.push('\n');
+        buf.push_str(&INDENT.repeat(depth));
+    }
+    write!(buf, "</{}>", element_name(element)).unwrap();
+}
+
+/// Child of an element, with adjacent text nodes merged together. The parser
+/// breaks text up around entity references, so `a &amp; b` is three nodes.
+enum Child<'d> {
+    Text(String),
+    Element(Element<'d>),
+    Comment(Comment<'d>),
+    ProcessingInstruction(ProcessingInstruction<'d>),
+}
+
+fn merge_text(children: Vec<ChildOfElement>) -> Vec<Child> {
+    let mut merged = Vec::with_capacity(children.len());
+    for child in children {
+        match (child, merged.last_mut()) {
+            (ChildOfElement::Text(text), Some(Child::Text(previous))) => {
+                previous.push_str(text.text())
+            }
+            (ChildOfElement::Text(text), _) => {
+                merged.push(Child::Text(text.text().to_owned()))
+            }
+            (ChildOfElement::Element(element), _) => {
+                merged.push(Child::Element(element))
+            }
+            (ChildOfElement::Comment(comment), _) => {
+                merged.push(Child::Comment(comment))
+            }
+            (ChildOfElement::ProcessingInstruction(pi), _) => {
+                merged.push(Child::ProcessingInstruction(pi))
+            }
+        }
+    }
+    merged
+}
+
+/// Write `xmlns` attributes for an element. For a top-level element, declare
+/// every prefix used within it. Otherwise, just declare whatever this element
+/// introduced to the scope.
+fn write_namespaces(buf: &mut String, element: Element, is_top: bool) {
+    if is_top {
+        if let Some(uri) = element.recursive_default_namespace_uri() {
+            write_namespace(buf, None, uri);
+        }
+        let mut prefixes = IndexSet::new();
+        used_prefixes(element, &mut prefixes);
+        for prefix in prefixes {
+            if let Some(uri) = element.namespace_uri_for_prefix(prefix) {
+                write_namespace(buf, Some(prefix), uri);
+            }
+        }
+    } else {
+        if let Some(uri) = element.default_namespace_uri() {
+            write_namespace(buf, None, uri);
+        }
+        let parent_namespaces = element
+            .parent()
+            .and_then(|parent| parent.element())
+            .map(|parent| parent.namespaces_in_scope())
+            .unwrap_or_default();
+        for namespace in element.namespaces_in_scope() {
+            let inherited = parent_namespaces.iter().any(|parent| {
+                parent.prefix() == namespace.prefix()
+                    && parent.uri() == namespace.uri()
+            });
+            // The xml prefix is always implicitly declared
+            if namespace.prefix() != "xml" && !inherited {
+                write_namespace(buf, Some(namespace.prefix()), namespace.uri());
+            }
+        }
+    }
+}
+
+/// Write a single `xmlns` attribute. Use `None` for the default namespace
+fn write_namespace(buf: &mut String, prefix: Option<&str>, uri: &str) {
+    let uri = escape_attribute(uri);
+    match prefix {
+        Some(prefix) => write!(buf, " xmlns:{prefix}=\"{uri}\"").unwrap(),
+        None => write!(buf, " xmlns=\"{uri}\"").unwrap(),
+    }
+}
+
+/// Collect all namespace prefixes used by an element, its attributes, and its
+/// descendants
+fn used_prefixes<'d>(element: Element<'d>, prefixes: &mut IndexSet<&'d str>) {
+    prefixes.extend(element.preferred_prefix());
+    prefixes.extend(
+        element
+            .attributes()
+            .into_iter()
+            .filter_map(|attribute| attribute.preferred_prefix()),
+    );
+    for child in element.children() {
+        if let ChildOfElement::Element(child) = child {
+            used_prefixes(child, prefixes);
+        }
+    }
+}
+
+fn write_pi(buf: &mut String, target: &str, value: Option<&str>) {
+    match value {
+        Some(value) => write!(buf, "<?{target} {value}?>").unwrap(),
+        None => write!(buf, "<?{target}?>").unwrap(),
+    }
+}
+
+/// Write a JSON value as an element with the given name
+fn write_json(buf: &mut String, name: &str, value: &Value) {
+    let name = sanitize_name(name);
+    match value {
+        Value::Null => write!(buf, "<{name}/>").unwrap(),
+        // Arrays are represented as repeated elements
+        Value::Array(items) => {
+            for item in items {
+                write_json(buf, &name, item);
+            }
+        }
+        Value::Object(map) => {
+            write!(buf, "<{name}").unwrap();
+            for (key, value) in map {
+                if let Some(attribute) = key.strip_prefix('@') {
+                    write!(
+                        buf,
+                        " {}=\"{}\"",
+                        sanitize_name(attribute),
+                        escape_attribute(&scalar_to_string(value))
+                    )
+                    .unwrap();
+                }
+            }
+            buf.push('>');
+            for (key, value) in map {
+                if key == "#text" {
+                    buf.push_str(&escape_text(&scalar_to_string(value)));
+                } else if is_element_key(key) {
+                    write_json(buf, key, value);
+                }
+            }
+            write!(buf, "</{name}>").unwrap();
+        }
+        scalar => write!(
+            buf,
+            "<{name}>{}</{name}>",
+            escape_text(&scalar_to_string(scalar))
+        )
+        .unwrap(),
+    }
+}
+
+fn element_to_json(element: Element) -> Value {
+    let mut map = Map::new();
+    for attribute in element.attributes() {
+        map.insert(
+            format!("@{}", attribute_name(attribute)),
+            attribute.value().into(),
+        );
+    }
+
+    let mut text = String::new();
+    for child in element.children() {
+        match child {
+            ChildOfElement::Element(child) => {
+                let name = element_name(child);
+                let value = element_to_json(child);
+                // Group repeated elements into an array
+                match map.get_mut(name.as_ref()) {
+                    Some(Value::Array(values)) => values.push(value),
+                    Some(existing) => {
+                        *existing = Value::Array(vec![existing.take(), value])
+                    }
+                    None => {
+                        map.insert(name.into(), value);
+                    }
+                }
+            }
+            ChildOfElement::Text(child) => text.push_str(child.text()),
+            ChildOfElement::Comment(_)
+            | ChildOfElement::ProcessingInstruction(_) => {}
+        }
+    }
+
+    let text = text.trim();
+    match (map.is_empty(), text.is_empty()) {
+        (true, true) => Value::Null,
+        (true, false) => text.into(),
+        (false, true) => Value::Object(map),
+        (false, false) => {
+            map.insert("#text".into(), text.into());
+            Value::Object(map)
+        }
+    }
+}
+
+/// Get an element's name, including its namespace prefix if it has one
+fn element_name(element: Element) -> Cow<'_, str> {
+    qualified_name(element.preferred_prefix(), element.name().local_part())
+}
+
+/// Get an attribute's name, including its namespace prefix if it has one
+fn attribute_name(attribute: Attribute) -> Cow<'_, str> {
+    qualified_name(attribute.preferred_prefix(), attribute.name().local_part())
+}
+
+fn qualified_name<'a>(prefix: Option<&str>, local: &'a str) -> Cow<'a, str> {
+    match prefix {
+        Some(prefix) => format!("{prefix}:{local}").into(),
+        None => local.into(),
+    }
+}
+
+/// Does this JSON object key map to a child element? Keys for attributes and
+/// text do not.
+fn is_element_key(key: &str) -> bool {
+    !key.starts_with(['@', '#'])
+}
+
+/// Replace any characters that aren't allowed in an XML name
+fn sanitize_name(name: &str) -> Cow<'_, str> {
+    let is_valid_start = |c: char| c.is_alphabetic() || c == '_';
+    let is_valid = |c: char| c.is_alphanumeric() || "_-.:".contains(c);
+    let valid = name.starts_with(is_valid_start) && name.chars().all(is_valid);
+    if valid {
+        name.into()
+    } else {
+        let mut sanitized = String::with_capacity(name.len() + 1);
+        if !name.starts_with(is_valid_start) {
+            sanitized.push('_');
+        }
+        sanitized
+            .extend(name.chars().map(|c| if is_valid(c) { c } else { '_' }));
+        sanitized.into()
+    }
+}
+
+/// Stringify a JSON value for use as text/attribute content
+fn scalar_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn escape_text(text: &str) -> Cow<'_, str> {
+    escape(text, &['&', '<', '>'])
+}
+
+fn escape_attribute(text: &str) -> Cow<'_, str> {
+    escape(text, &['&', '<', '"'])
+}
+
+fn escape<'a>(text: &'a str, chars: &[char]) -> Cow<'a, str> {
+    if !text.contains(chars) {
+        return text.into();
+    }
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' if chars.contains(&c) => escaped.push_str("&amp;"),
+            '<' if chars.contains(&c) => escaped.push_str("&lt;"),
+            '>' if chars.contains(&c) => escaped.push_str("&gt;"),
+            '"' if chars.contains(&c) => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+
+    const NAMESPACE: &str =
+        "xmlns:soap=\"http://www.w3.org/2003/05/soap-envelope\"";
+    const DOCUMENT: &str = r#"<?xml version="1.0"?>
+<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope">
+<soap:Body><order id="1" status="open"><item>apple</item>
+<item>pear</item><note>Fresh &amp; tasty</note><empty/></order></soap:Body>
+</soap:Envelope>"#;
+
+    #[test]
+    fn test_prettify() {
+        let package = parse(DOCUMENT).unwrap();
+        assert_eq!(
+            prettify(&package.as_document()),
+            r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope">
+  <soap:Body>
+    <order id="1" status="open">
+      <item>apple</item>
+      <item>pear</item>
+      <note>Fresh &amp; tasty</note>
+      <empty/>
+    </order>
+  </soap:Body>
+</soap:Envelope>"#
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let package = parse(DOCUMENT).unwrap();
+        assert_eq!(
+            to_json(&package.as_document()),
+            json!({
+                "soap:Envelope": {
+                    "soap:Body": {
+                        "order": {
+                            "@id": "1",
+                            "@status": "open",
+                            "item": ["apple", "pear"],
+                            "note": "Fresh & tasty",
+                            "empty": null,
+                        }
+                    }
+                }
+            })
+        );
+    }
+
+    #[rstest]
+    #[case::element(
+        json!({"order": {"@id": 1, "#text": "a < b", "item": ["x", "y"]}}),
+        r#"<order id="1">a &lt; b<item>x</item><item>y</item></order>"#,
+    )]
+    #[case::multiple_keys(
+        json!({"a": 1, "b": null}),
+        "<root><a>1</a><b/></root>",
+    )]
+    #[case::array(json!(["x", {"y": true}]), "<root><item>x</item><item><y>true</y></item></root>")]
+    #[case::scalar(json!("hello"), "<root>hello</root>")]
+    #[case::invalid_name(json!({"1 bad": "x"}), "<_1_bad>x</_1_bad>")]
+    fn test_from_json(#[case] value: Value, #[case] expected: &str) {
+        assert_eq!(from_json(&value), expected);
+    }
+
+    #[rstest]
+    #[case::elements(
+        "//item",
+        &[
+            XPathItem::Element("<item>apple</item>".into()),
+            XPathItem::Element("<item>pear</item>".into()),
+        ],
+    )]
+    #[case::namespaced(
+        "/soap:Envelope/soap:Body/order/@status",
+        &[XPathItem::Text("open".into())],
+    )]
+    #[case::element_with_namespace(
+        "//soap:Body",
+        &[XPathItem::Element(format!(
+            "<soap:Body {NAMESPACE}><order id=\"1\" status=\"open\"/></soap:Body>"
+        ))],
+    )]
+    #[case::text("//item[2]/text()", &[XPathItem::Text("pear".into())])]
+    #[case::number("count(//item)", &[XPathItem::Text("2".into())])]
+    #[case::empty("//bogus", &[])]
+    fn test_evaluate(#[case] xpath: &str, #[case] expected: &[XPathItem]) {
+        // Elements selected out of the document carry the namespace
+        // declarations they need with them, so they're valid on their own
+        let document = format!(
+            "<soap:Envelope {NAMESPACE}>\
+            <soap:Body><order id=\"1\" status=\"open\"/></soap:Body>\
+            <item>apple</item><item>pear</item></soap:Envelope>"
+        );
+        let package = parse(&document).unwrap();
+        let xpath = sxd_xpath::Factory::new().build(xpath).unwrap().unwrap();
+        assert_eq!(evaluate(&package.as_document(), &xpath).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_results_document() {
+        assert_eq!(
+            results_document(vec![
+                XPathItem::Element("<a>1</a>".into()),
+                XPathItem::Text("x & y".into())
+            ]),
+            "<results><a>1</a><result>x &amp; y</result></results>"
+        );
+    }
+}
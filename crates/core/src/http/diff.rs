@@ -0,0 +1,506 @@
+//! Compare two exchanges from history. The diff covers the response status,
+//! headers, and body. Bodies of a known content type are compared
+//! structurally, so that formatting and key order don't show up as changes.
+
+use crate::{
+    http::{content_type::ResponseContent, Exchange, ResponseRecord},
+    util::unified_diff,
+};
+use reqwest::{header::HeaderMap, StatusCode};
+use serde_json::Value;
+use similar::{DiffTag, TextDiff};
+use std::fmt::Write;
+
+/// The differences between the responses of two exchanges. The first exchange
+/// is considered the "old" side, and the second is the "new" side.
+#[derive(Debug)]
+pub struct ExchangeDiff {
+    pub status: Change<StatusCode>,
+    /// One entry per header value, in the order they appear in the responses
+    pub headers: Vec<(String, Change<String>)>,
+    pub body: BodyDiff,
+    /// Full text representation of each response, used to generate the
+    /// unified diff
+    documents: [String; 2],
+}
+
+/// A single item in a diff, e.g. a header value or a line of text
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change<T> {
+    /// Same on both sides
+    Unchanged(T),
+    /// Only on the new side
+    Added(T),
+    /// Only on the old side
+    Removed(T),
+    /// Present on both sides, with different values
+    Modified(T, T),
+}
+
+/// Differences between two response bodies
+#[derive(Debug)]
+pub struct BodyDiff {
+    /// Changes between the parsed values of both bodies, keyed by JSONPath.
+    /// `None` if either body couldn't be parsed. Unchanged values are not
+    /// included.
+    pub values: Option<Vec<(String, Change<Value>)>>,
+    /// Line-by-line diff of both bodies. Parsed bodies are prettified before
+    /// comparison, so this is suitable for displaying side by side.
+    pub lines: Vec<Change<String>>,
+}
+
+impl ExchangeDiff {
+    /// Compare the responses of two exchanges. If a response body has already
+    /// been parsed (see [ResponseRecord::set_parsed_body]), the parsed value
+    /// will be used. Otherwise it will be parsed according to its
+    /// `Content-Type` header.
+    pub fn new(old: &Exchange, new: &Exchange) -> Self {
+        let old_response = &old.response;
+        let new_response = &new.response;
+
+        let status = if old_response.status == new_response.status {
+            Change::Unchanged(old_response.status)
+        } else {
+            Change::Modified(old_response.status, new_response.status)
+        };
+        let headers =
+            diff_headers(&old_response.headers, &new_response.headers);
+
+        let old_parsed = parse_body(old_response);
+        let new_parsed = parse_body(new_response);
+        let old_content = old_response.body.parsed().or(old_parsed.as_deref());
+        let new_content = new_response.body.parsed().or(new_parsed.as_deref());
+        let (values, old_body, new_body) = match (old_content, new_content) {
+            (Some(old_content), Some(new_content)) => {
+                let old_json = old_content.to_json();
+                let mut new_json = new_content.to_json().into_owned();
+                // Match the key order of the old value, so that reordered keys
+                // don't show up in the line diff
+                align_keys(&old_json, &mut new_json);
+
+                let mut values = Vec::new();
+                diff_values("$".into(), &old_json, &new_json, &mut values);
+                let new_body =
+                    new_content.content_type().parse_json(new_json).prettify();
+                (Some(values), old_content.prettify(), new_body)
+            }
+            _ => (
+                None,
+                body_text(old_response, old_content),
+                body_text(new_response, new_content),
+            ),
+        };
+
+        Self {
+            status,
+            headers,
+            body: BodyDiff {
+                values,
+                lines: diff_lines(&old_body, &new_body),
+            },
+            documents: [
+                document(old_response, &old_body),
+                document(new_response, &new_body),
+            ],
+        }
+    }
+
+    /// Are the two responses identical? Formatting differences in parsed
+    /// bodies are not considered changes.
+    pub fn is_empty(&self) -> bool {
+        matches!(self.status, Change::Unchanged(_))
+            && self
+                .headers
+                .iter()
+                .all(|(_, change)| matches!(change, Change::Unchanged(_)))
+            && self
+                .body
+                .lines
+                .iter()
+                .all(|change| matches!(change, Change::Unchanged(_)))
+    }
+
+    /// Generate a unified diff of both responses, suitable for `patch` or any
+    /// diff viewer. Each response is represented as its status line, followed
+    /// by its headers and body. Labels are used for the file header lines.
+    pub fn to_unified(&self, old_label: &str, new_label: &str) -> String {
+        let [old, new] = &self.documents;
+        unified_diff(old, new, old_label, new_label)
+    }
+}
+
+/// Parse a response body, if it hasn't been parsed already. Return `None` if
+/// it's already parsed, the content type is unknown, or parsing fails.
+fn parse_body(response: &ResponseRecord) -> Option<Box<dyn ResponseContent>> {
+    if response.body.parsed().is_some() {
+        None
+    } else {
+        response
+            .content_type()?
+            .parse_content(response.body.bytes())
+            .ok()
+    }
+}
+
+/// Get the text of an unparsed body. Binary bodies can't be diffed line by
+/// line, so they're summarized instead
+fn body_text(
+    response: &ResponseRecord,
+    content: Option<&dyn ResponseContent>,
+) -> String {
+    if let Some(content) = content {
+        content.prettify()
+    } else if let Some(text) = response.body.text() {
+        text.to_owned()
+    } else {
+        format!("<binary, {} bytes>", response.body.size())
+    }
+}
+
+/// Render a response as text, in roughly the same form as it appeared on the
+/// wire
+fn document(response: &ResponseRecord, body: &str) -> String {
+    let mut document = format!("{}\n", response.status);
+    for (name, value) in &response.headers {
+        let _ = writeln!(
+            document,
+            "{name}: {}",
+            String::from_utf8_lossy(value.as_bytes())
+        );
+    }
+    document.push('\n');
+    document.push_str(body);
+    if !body.is_empty() && !body.ends_with('\n') {
+        document.push('\n');
+    }
+    document
+}
+
+/// Compare headers by name. If a header has multiple values, they're compared
+/// in order.
+fn diff_headers(
+    old: &HeaderMap,
+    new: &HeaderMap,
+) -> Vec<(String, Change<String>)> {
+    let values = |headers: &HeaderMap, name| -> Vec<String> {
+        headers
+            .get_all(name)
+            .iter()
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+            .collect()
+    };
+
+    let mut changes = Vec::new();
+    for name in old.keys() {
+        let old_values = values(old, name);
+        let new_values = values(new, name);
+        pair_up(old_values, new_values, |change| {
+            changes.push((name.to_string(), change))
+        });
+    }
+    for name in new.keys().filter(|name| !old.contains_key(*name)) {
+        for value in values(new, name) {
+            changes.push((name.to_string(), Change::Added(value)));
+        }
+    }
+    changes
+}
+
+/// Compare two bodies line by line
+fn diff_lines(old: &str, new: &str) -> Vec<Change<String>> {
+    let diff = TextDiff::from_lines(old, new);
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+    let line = |line: &str| line.trim_end_matches(['\r', '\n']).to_owned();
+
+    let mut changes = Vec::new();
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        let old_lines = old_lines[old_range].iter().map(|l| line(l));
+        let new_lines = new_lines[new_range].iter().map(|l| line(l));
+        match tag {
+            DiffTag::Equal => changes.extend(old_lines.map(Change::Unchanged)),
+            DiffTag::Delete => changes.extend(old_lines.map(Change::Removed)),
+            DiffTag::Insert => changes.extend(new_lines.map(Change::Added)),
+            // Pair replaced lines up, so they appear next to each other
+            DiffTag::Replace => {
+                pair_up(old_lines.collect(), new_lines.collect(), |change| {
+                    changes.push(change)
+                })
+            }
+        }
+    }
+    changes
+}
+
+/// Pair up two lists of values positionally. Equal pairs are unchanged,
+/// unequal pairs are modified, and leftovers on either side are removed/added
+fn pair_up<T: PartialEq>(
+    old: Vec<T>,
+    new: Vec<T>,
+    mut push: impl FnMut(Change<T>),
+) {
+    let mut old = old.into_iter();
+    let mut new = new.into_iter();
+    loop {
+        match (old.next(), new.next()) {
+            (Some(old), Some(new)) if old == new => {
+                push(Change::Unchanged(old))
+            }
+            (Some(old), Some(new)) => push(Change::Modified(old, new)),
+            (Some(old), None) => push(Change::Removed(old)),
+            (None, Some(new)) => push(Change::Added(new)),
+            (None, None) => break,
+        }
+    }
+}
+
+/// Recursively compare two JSON values, recording every difference under its
+/// JSONPath. Objects are compared by key and arrays by index.
+fn diff_values(
+    path: String,
+    old: &Value,
+    new: &Value,
+    changes: &mut Vec<(String, Change<Value>)>,
+) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let path = format!("{path}{}", key_path(key));
+                match new.get(key) {
+                    Some(new_value) => {
+                        diff_values(path, old_value, new_value, changes)
+                    }
+                    None => {
+                        changes.push((path, Change::Removed(old_value.clone())))
+                    }
+                }
+            }
+            for (key, new_value) in new {
+                if !old.contains_key(key) {
+                    changes.push((
+                        format!("{path}{}", key_path(key)),
+                        Change::Added(new_value.clone()),
+                    ));
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for i in 0..old.len().max(new.len()) {
+                let path = format!("{path}[{i}]");
+                match (old.get(i), new.get(i)) {
+                    (Some(old), Some(new)) => {
+                        diff_values(path, old, new, changes)
+                    }
+                    (Some(old), None) => {
+                        changes.push((path, Change::Removed(old.clone())))
+                    }
+                    (None, Some(new)) => {
+                        changes.push((path, Change::Added(new.clone())))
+                    }
+                    (None, None) => unreachable!("Index is within both arrays"),
+                }
+            }
+        }
+        _ if old != new => {
+            changes.push((path, Change::Modified(old.clone(), new.clone())))
+        }
+        _ => {}
+    }
+}
+
+/// Get the JSONPath segment for an object key. Use dot notation when possible,
+/// and bracket notation otherwise
+fn key_path(key: &str) -> String {
+    let is_identifier = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_identifier {
+        format!(".{key}")
+    } else {
+        format!("['{}']", key.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+/// Reorder the keys of every object in `new` to match their order in `old`.
+/// Keys that only exist in `new` go at the end, in their original order.
+fn align_keys(old: &Value, new: &mut Value) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let mut remaining = std::mem::take(new);
+            for (key, old_value) in old {
+                if let Some(mut new_value) = remaining.remove(key) {
+                    align_keys(old_value, &mut new_value);
+                    new.insert(key.clone(), new_value);
+                }
+            }
+            new.extend(remaining);
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for (old, new) in old.iter().zip(new) {
+                align_keys(old, new);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::{RequestRecord, ResponseRecord},
+        test_util::{header_map, Factory},
+    };
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+
+    /// Create an exchange with the given response
+    fn exchange(status: u16, headers: &[(&str, &str)], body: &str) -> Exchange {
+        Exchange::factory((
+            RequestRecord::factory(()),
+            ResponseRecord {
+                status: StatusCode::from_u16(status).unwrap(),
+                headers: header_map(headers.iter().copied()),
+                body: body.into(),
+            },
+        ))
+    }
+
+    /// Status and headers are compared directly
+    #[rstest]
+    fn test_status_and_headers() {
+        let diff = ExchangeDiff::new(
+            &exchange(200, &[("x-a", "1"), ("x-b", "2")], ""),
+            &exchange(404, &[("x-a", "1"), ("x-b", "3"), ("x-c", "4")], ""),
+        );
+        assert_eq!(
+            diff.status,
+            Change::Modified(StatusCode::OK, StatusCode::NOT_FOUND)
+        );
+        assert_eq!(
+            diff.headers,
+            vec![
+                ("x-a".into(), Change::Unchanged("1".into())),
+                ("x-b".into(), Change::Modified("2".into(), "3".into())),
+                ("x-c".into(), Change::Added("4".into())),
+            ]
+        );
+        assert!(!diff.is_empty());
+    }
+
+    /// JSON bodies are compared structurally, so key order and formatting
+    /// don't matter
+    #[rstest]
+    fn test_json_body() {
+        let json = &[("content-type", "application/json")];
+        let diff = ExchangeDiff::new(
+            &exchange(
+                200,
+                json,
+                r#"{"name": "Ted", "age": 30, "tags": ["a", "b"], "a b": 1}"#,
+            ),
+            &exchange(
+                200,
+                json,
+                r#"{"tags":["a"],"age":31,"name":"Ted","admin":true,"a b":1}"#,
+            ),
+        );
+        assert_eq!(
+            diff.body.values,
+            Some(vec![
+                ("$.age".into(), Change::Modified(json!(30), json!(31))),
+                ("$.tags[1]".into(), Change::Removed(json!("b"))),
+                ("$.admin".into(), Change::Added(json!(true))),
+            ])
+        );
+        assert_eq!(
+            diff.body.lines,
+            vec![
+                Change::Unchanged("{".into()),
+                Change::Unchanged(r#"  "name": "Ted","#.into()),
+                Change::Modified(
+                    r#"  "age": 30,"#.into(),
+                    r#"  "age": 31,"#.into()
+                ),
+                Change::Unchanged(r#"  "tags": ["#.into()),
+                Change::Modified(r#"    "a","#.into(), r#"    "a""#.into()),
+                Change::Removed(r#"    "b""#.into()),
+                Change::Unchanged("  ],".into()),
+                Change::Modified(
+                    r#"  "a b": 1"#.into(),
+                    r#"  "a b": 1,"#.into()
+                ),
+                Change::Added(r#"  "admin": true"#.into()),
+                Change::Unchanged("}".into()),
+            ]
+        );
+    }
+
+    /// Identical bodies with different formatting produce no changes
+    #[rstest]
+    fn test_formatting_ignored() {
+        let json = &[("content-type", "application/json")];
+        let diff = ExchangeDiff::new(
+            &exchange(200, json, r#"{"a": 1, "b": 2}"#),
+            &exchange(200, json, "{\n  \"b\":2,\n  \"a\":1\n}"),
+        );
+        assert_eq!(diff.body.values, Some(vec![]));
+        assert!(diff.is_empty());
+    }
+
+    /// Bodies of unknown content types are compared as text
+    #[rstest]
+    fn test_text_body() {
+        let diff = ExchangeDiff::new(
+            &exchange(200, &[], "one\ntwo\n"),
+            &exchange(200, &[], "one\nthree\n"),
+        );
+        assert_eq!(diff.body.values, None);
+        assert_eq!(
+            diff.body.lines,
+            vec![
+                Change::Unchanged("one".into()),
+                Change::Modified("two".into(), "three".into()),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_to_unified() {
+        let diff = ExchangeDiff::new(
+            &exchange(200, &[("x-a", "1")], "one\ntwo\n"),
+            &exchange(500, &[("x-a", "1")], "one\nthree\n"),
+        );
+        assert_eq!(
+            diff.to_unified("old", "new"),
+            [
+                "--- old",
+                "+++ new",
+                "@@ -1,5 +1,5 @@",
+                "-200 OK",
+                "+500 Internal Server Error",
+                " x-a: 1",
+                " ",
+                " one",
+                "-two",
+                "+three",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[rstest]
+    #[case::identifier("name", ".name")]
+    #[case::underscore("_id", "._id")]
+    #[case::space("first name", "['first name']")]
+    #[case::leading_digit("1st", "['1st']")]
+    #[case::quote("it's", r"['it\'s']")]
+    fn test_key_path(#[case] key: &str, #[case] expected: &str) {
+        assert_eq!(key_path(key), expected);
+    }
+}
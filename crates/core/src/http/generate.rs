@@ -0,0 +1,849 @@
+//! Generate equivalent commands and code for third-party HTTP clients from a
+//! rendered request. Everything is driven by the [RequestRecord], so the
+//! output matches exactly what Slumber would send.
+//!
+//! - Text bodies are included as string literals. Binary bodies are included
+//!   as escaped bytes; for shell commands, they're piped in with `printf`
+//! - Multipart forms use each client's native form support, so the client
+//!   generates its own boundary. For shell commands, a form with binary values
+//!   is sent as the raw encoded body instead, because binary values can't be
+//!   passed as arguments. wget has no multipart support, so it always sends
+//!   the raw body
+
+use crate::http::RequestRecord;
+use anyhow::Context;
+use derive_more::Display;
+use itertools::Itertools;
+use reqwest::{header, Method};
+use std::fmt::Write;
+use strum::{EnumCount, EnumIter};
+
+/// A third-party client that a request can be converted for
+#[derive(
+    Copy, Clone, Debug, Default, Display, EnumCount, EnumIter, PartialEq,
+)]
+pub enum GenerateFormat {
+    #[default]
+    #[display("cURL")]
+    Curl,
+    #[display("HTTPie")]
+    Httpie,
+    #[display("wget")]
+    Wget,
+    #[display("Python (requests)")]
+    Python,
+    #[display("JavaScript (fetch)")]
+    Javascript,
+    #[display("Go (net/http)")]
+    Go,
+    #[display("Rust (reqwest)")]
+    Rust,
+}
+
+impl RequestRecord {
+    /// Generate a command or program that sends a request equivalent to this
+    /// one. This includes sensitive values verbatim; call [Self::redacted]
+    /// first to mask them.
+    ///
+    /// This only fails if one of the header values isn't valid UTF-8.
+    pub fn generate(&self, format: GenerateFormat) -> anyhow::Result<String> {
+        let request = GenerateRequest::new(self)?;
+        // These writes are all infallible because we're writing to a string,
+        // but use ? because it's shorter than unwrap()
+        let mut buf = String::new();
+        match format {
+            GenerateFormat::Curl => request.curl(&mut buf)?,
+            GenerateFormat::Httpie => request.httpie(&mut buf)?,
+            GenerateFormat::Wget => request.wget(&mut buf)?,
+            GenerateFormat::Python => request.python(&mut buf)?,
+            GenerateFormat::Javascript => request.javascript(&mut buf)?,
+            GenerateFormat::Go => request.go(&mut buf)?,
+            GenerateFormat::Rust => request.rust(&mut buf)?,
+        }
+        Ok(buf)
+    }
+}
+
+/// A request broken down into the pieces that each generator needs
+struct GenerateRequest<'a> {
+    method: &'a Method,
+    url: &'a str,
+    /// For multipart bodies, this excludes the `Content-Type` header. Clients
+    /// generate their own, with a new boundary
+    headers: Vec<(&'a str, &'a str)>,
+    body: Option<GenerateBody<'a>>,
+}
+
+enum GenerateBody<'a> {
+    Text(&'a str),
+    Binary(&'a [u8]),
+    Multipart {
+        fields: Vec<(String, &'a [u8])>,
+        /// The full encoded body, for clients that have to send it raw
+        raw: &'a [u8],
+        /// Original `Content-Type` header, with the boundary of the raw body
+        content_type: &'a str,
+    },
+}
+
+impl<'a> GenerateRequest<'a> {
+    fn new(record: &'a RequestRecord) -> anyhow::Result<Self> {
+        let mut headers = record
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = value.to_str().with_context(|| {
+                    format!("Error decoding value of header `{name}`")
+                })?;
+                Ok((name.as_str(), value))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let body = record.body().filter(|body| !body.is_empty()).map(|body| {
+            let content_type = record
+                .headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok());
+            if let Some((content_type, fields)) =
+                content_type.and_then(|content_type| {
+                    Some((content_type, parse_multipart(content_type, body)?))
+                })
+            {
+                headers.retain(|(name, _)| {
+                    !name.eq_ignore_ascii_case(header::CONTENT_TYPE.as_str())
+                });
+                GenerateBody::Multipart {
+                    fields,
+                    raw: body,
+                    content_type,
+                }
+            } else if let Ok(text) = std::str::from_utf8(body) {
+                GenerateBody::Text(text)
+            } else {
+                GenerateBody::Binary(body)
+            }
+        });
+
+        Ok(Self {
+            method: &record.method,
+            url: record.url.as_str(),
+            headers,
+            body,
+        })
+    }
+
+    /// Get the body as raw bytes to pipe into a shell command, if it can't be
+    /// passed as an argument. For multipart forms, this also returns the
+    /// `Content-Type` header that has to be sent with the body.
+    fn shell_stdin(
+        &self,
+        multipart: bool,
+    ) -> Option<(&'a [u8], Option<&'a str>)> {
+        match self.body {
+            Some(GenerateBody::Binary(body)) => Some((body, None)),
+            // Null bytes can't be passed in an argument
+            Some(GenerateBody::Text(text)) if text.contains('\0') => {
+                Some((text.as_bytes(), None))
+            }
+            Some(GenerateBody::Multipart {
+                ref fields,
+                raw,
+                content_type,
+            }) if !multipart || !is_shell_form(fields) => {
+                Some((raw, Some(content_type)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get headers for a shell command. If the body is piped in raw (see
+    /// [Self::shell_stdin]), this includes the body's original `Content-Type`
+    fn shell_headers(
+        &self,
+        stdin: Option<(&'a [u8], Option<&'a str>)>,
+    ) -> impl '_ + Iterator<Item = (&'a str, &'a str)> {
+        let content_type = stdin
+            .and_then(|(_, content_type)| content_type)
+            .map(|content_type| (header::CONTENT_TYPE.as_str(), content_type));
+        self.headers.iter().copied().chain(content_type)
+    }
+
+    /// Get headers with duplicate names joined into one value, for clients
+    /// that take headers as a map
+    fn joined_headers(&self) -> Vec<(&'a str, String)> {
+        self.headers
+            .iter()
+            .into_group_map_by(|(name, _)| *name)
+            .into_iter()
+            .map(|(name, values)| {
+                (name, values.into_iter().map(|(_, value)| value).join(", "))
+            })
+            .sorted_by_key(|(name, _)| {
+                self.headers.iter().position(|(other, _)| other == name)
+            })
+            .collect()
+    }
+
+    fn curl(&self, buf: &mut String) -> anyhow::Result<()> {
+        let stdin = self.shell_stdin(true);
+        if let Some((body, _)) = stdin {
+            write!(buf, "{} | ", shell_printf(body))?;
+        }
+        write!(
+            buf,
+            "curl -X{} --url {}",
+            self.method,
+            shell_quote(self.url)
+        )?;
+        for (name, value) in self.shell_headers(stdin) {
+            write!(
+                buf,
+                " --header {}",
+                shell_quote(&format!("{name}: {value}"))
+            )?;
+        }
+        match &self.body {
+            _ if stdin.is_some() => write!(buf, " --data-binary @-")?,
+            // A leading @ would make curl read the body from a file
+            Some(GenerateBody::Text(text)) if text.starts_with('@') => {
+                write!(buf, " --data-raw {}", shell_quote(text))?
+            }
+            Some(GenerateBody::Text(text)) => {
+                write!(buf, " --data {}", shell_quote(text))?
+            }
+            Some(GenerateBody::Multipart { fields, .. }) => {
+                for (name, value) in fields {
+                    // --form-string, so values aren't treated as file paths
+                    let field =
+                        format!("{name}={}", String::from_utf8_lossy(value));
+                    write!(buf, " --form-string {}", shell_quote(&field))?;
+                }
+            }
+            Some(GenerateBody::Binary(_)) | None => {}
+        }
+        Ok(())
+    }
+
+    fn httpie(&self, buf: &mut String) -> anyhow::Result<()> {
+        // HTTPie reads the body from stdin automatically
+        let stdin = self.shell_stdin(true);
+        if let Some((body, _)) = stdin {
+            write!(buf, "{} | ", shell_printf(body))?;
+        }
+        write!(buf, "http")?;
+        match &self.body {
+            _ if stdin.is_some() => {}
+            Some(GenerateBody::Text(text)) => {
+                write!(buf, " --raw {}", shell_quote(text))?
+            }
+            Some(GenerateBody::Multipart { .. }) => {
+                write!(buf, " --multipart")?
+            }
+            Some(GenerateBody::Binary(_)) | None => {}
+        }
+        write!(buf, " {} {}", self.method, shell_quote(self.url))?;
+        for (name, value) in self.shell_headers(stdin) {
+            // `Name:` with no value would *remove* the header
+            let item = if value.is_empty() {
+                format!("{name};")
+            } else {
+                format!("{name}:{value}")
+            };
+            write!(buf, " {}", shell_quote(&item))?;
+        }
+        if let (None, Some(GenerateBody::Multipart { fields, .. })) =
+            (stdin, &self.body)
+        {
+            for (name, value) in fields {
+                let item = format!("{name}={}", String::from_utf8_lossy(value));
+                write!(buf, " {}", shell_quote(&item))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn wget(&self, buf: &mut String) -> anyhow::Result<()> {
+        let stdin = self.shell_stdin(false);
+        if let Some((body, _)) = stdin {
+            write!(buf, "{} | ", shell_printf(body))?;
+        }
+        write!(
+            buf,
+            "wget --quiet --output-document=- --method={}",
+            self.method
+        )?;
+        for (name, value) in self.shell_headers(stdin) {
+            let header = format!("{name}: {value}");
+            write!(buf, " --header={}", shell_quote(&header))?;
+        }
+        match &self.body {
+            _ if stdin.is_some() => write!(buf, " --body-file=/dev/stdin")?,
+            Some(GenerateBody::Text(text)) => {
+                write!(buf, " --body-data={}", shell_quote(text))?
+            }
+            _ => {}
+        }
+        write!(buf, " {}", shell_quote(self.url))?;
+        Ok(())
+    }
+
+    fn python(&self, buf: &mut String) -> anyhow::Result<()> {
+        writeln!(buf, "import requests")?;
+        writeln!(buf)?;
+        writeln!(buf, "response = requests.request(")?;
+        writeln!(buf, "    {},", string_literal(self.method.as_str()))?;
+        writeln!(buf, "    {},", string_literal(self.url))?;
+        let headers = self.joined_headers();
+        if !headers.is_empty() {
+            writeln!(buf, "    headers={{")?;
+            for (name, value) in headers {
+                writeln!(
+                    buf,
+                    "        {}: {},",
+                    string_literal(name),
+                    string_literal(&value)
+                )?;
+            }
+            writeln!(buf, "    }},")?;
+        }
+        match &self.body {
+            Some(GenerateBody::Text(text)) => {
+                writeln!(buf, "    data={},", string_literal(text))?
+            }
+            Some(GenerateBody::Binary(body)) => {
+                writeln!(buf, "    data=b\"{}\",", escape_bytes(body))?
+            }
+            Some(GenerateBody::Multipart { fields, .. }) => {
+                // A list of tuples rather than a dict, to preserve order and
+                // duplicate fields. A `None` file name makes a plain field
+                writeln!(buf, "    files=[")?;
+                for (name, value) in fields {
+                    let value = match std::str::from_utf8(value) {
+                        Ok(text) => string_literal(text),
+                        Err(_) => format!("b\"{}\"", escape_bytes(value)),
+                    };
+                    writeln!(
+                        buf,
+                        "        ({}, (None, {value})),",
+                        string_literal(name)
+                    )?;
+                }
+                writeln!(buf, "    ],")?;
+            }
+            None => {}
+        }
+        writeln!(buf, ")")?;
+        write!(buf, "print(response.text)")?;
+        Ok(())
+    }
+
+    fn javascript(&self, buf: &mut String) -> anyhow::Result<()> {
+        if let Some(GenerateBody::Multipart { fields, .. }) = &self.body {
+            writeln!(buf, "const body = new FormData();")?;
+            for (name, value) in fields {
+                let value = match std::str::from_utf8(value) {
+                    Ok(text) => string_literal(text),
+                    Err(_) => format!("new Blob([{}])", uint8_array(value)),
+                };
+                writeln!(
+                    buf,
+                    "body.append({}, {value});",
+                    string_literal(name)
+                )?;
+            }
+            writeln!(buf)?;
+        }
+        writeln!(
+            buf,
+            "const response = await fetch({}, {{",
+            string_literal(self.url)
+        )?;
+        writeln!(buf, "  method: {},", string_literal(self.method.as_str()))?;
+        let headers = self.joined_headers();
+        if !headers.is_empty() {
+            writeln!(buf, "  headers: {{")?;
+            for (name, value) in headers {
+                writeln!(
+                    buf,
+                    "    {}: {},",
+                    string_literal(name),
+                    string_literal(&value)
+                )?;
+            }
+            writeln!(buf, "  }},")?;
+        }
+        match &self.body {
+            Some(GenerateBody::Text(text)) => {
+                writeln!(buf, "  body: {},", string_literal(text))?
+            }
+            Some(GenerateBody::Binary(body)) => {
+                writeln!(buf, "  body: {},", uint8_array(body))?
+            }
+            Some(GenerateBody::Multipart { .. }) => writeln!(buf, "  body,")?,
+            None => {}
+        }
+        writeln!(buf, "}});")?;
+        write!(buf, "console.log(await response.text());")?;
+        Ok(())
+    }
+
+    fn go(&self, buf: &mut String) -> anyhow::Result<()> {
+        let body_import = match self.body {
+            Some(GenerateBody::Text(_)) => &["strings"][..],
+            Some(GenerateBody::Binary(_)) => &["bytes"],
+            Some(GenerateBody::Multipart { .. }) => {
+                &["bytes", "mime/multipart"]
+            }
+            None => &[],
+        };
+        let imports = ["fmt", "io", "net/http"]
+            .into_iter()
+            .chain(body_import.iter().copied())
+            .sorted();
+        writeln!(buf, "package main")?;
+        writeln!(buf)?;
+        writeln!(buf, "import (")?;
+        for import in imports {
+            writeln!(buf, "\t\"{import}\"")?;
+        }
+        writeln!(buf, ")")?;
+        writeln!(buf)?;
+        writeln!(buf, "func main() {{")?;
+        let body = match &self.body {
+            Some(GenerateBody::Text(text)) => {
+                writeln!(
+                    buf,
+                    "\tbody := strings.NewReader({})",
+                    string_literal(text)
+                )?;
+                "body"
+            }
+            Some(GenerateBody::Binary(body)) => {
+                writeln!(
+                    buf,
+                    "\tbody := bytes.NewReader([]byte(\"{}\"))",
+                    escape_bytes(body)
+                )?;
+                "body"
+            }
+            Some(GenerateBody::Multipart { fields, .. }) => {
+                writeln!(buf, "\tbody := &bytes.Buffer{{}}")?;
+                writeln!(buf, "\tform := multipart.NewWriter(body)")?;
+                for (name, value) in fields {
+                    // Go strings can hold arbitrary bytes
+                    let value = match std::str::from_utf8(value) {
+                        Ok(text) => string_literal(text),
+                        Err(_) => format!("\"{}\"", escape_bytes(value)),
+                    };
+                    writeln!(
+                        buf,
+                        "\tform.WriteField({}, {value})",
+                        string_literal(name)
+                    )?;
+                }
+                writeln!(buf, "\tform.Close()")?;
+                "body"
+            }
+            None => "nil",
+        };
+        writeln!(
+            buf,
+            "\treq, err := http.NewRequest({}, {}, {body})",
+            string_literal(self.method.as_str()),
+            string_literal(self.url)
+        )?;
+        writeln!(buf, "\tif err != nil {{\n\t\tpanic(err)\n\t}}")?;
+        for (name, value) in &self.headers {
+            writeln!(
+                buf,
+                "\treq.Header.Add({}, {})",
+                string_literal(name),
+                string_literal(value)
+            )?;
+        }
+        if let Some(GenerateBody::Multipart { .. }) = self.body {
+            writeln!(
+                buf,
+                "\treq.Header.Set(\"Content-Type\", form.FormDataContentType())"
+            )?;
+        }
+        writeln!(buf, "\tresp, err := http.DefaultClient.Do(req)")?;
+        writeln!(buf, "\tif err != nil {{\n\t\tpanic(err)\n\t}}")?;
+        writeln!(buf, "\tdefer resp.Body.Close()")?;
+        writeln!(buf, "\trespBody, err := io.ReadAll(resp.Body)")?;
+        writeln!(buf, "\tif err != nil {{\n\t\tpanic(err)\n\t}}")?;
+        writeln!(buf, "\tfmt.Println(string(respBody))")?;
+        write!(buf, "}}")?;
+        Ok(())
+    }
+
+    fn rust(&self, buf: &mut String) -> anyhow::Result<()> {
+        writeln!(buf, "#[tokio::main]")?;
+        writeln!(
+            buf,
+            "async fn main() -> Result<(), Box<dyn std::error::Error>> {{"
+        )?;
+        if let Some(GenerateBody::Multipart { fields, .. }) = &self.body {
+            writeln!(buf, "    let form = reqwest::multipart::Form::new()")?;
+            for (name, value) in fields {
+                let name = rust_string_literal(name);
+                match std::str::from_utf8(value) {
+                    Ok(text) => writeln!(
+                        buf,
+                        "        .text({name}, {})",
+                        rust_string_literal(text)
+                    )?,
+                    Err(_) => writeln!(
+                        buf,
+                        "        .part({name}, reqwest::multipart::Part::bytes(\
+                        b\"{}\".as_slice()))",
+                        escape_bytes(value)
+                    )?,
+                }
+            }
+            // Replace the trailing newline with a semicolon
+            buf.pop();
+            writeln!(buf, ";")?;
+        }
+        // Standard methods have constants. Anything else has to be parsed
+        let method = if [
+            Method::CONNECT,
+            Method::DELETE,
+            Method::GET,
+            Method::HEAD,
+            Method::OPTIONS,
+            Method::PATCH,
+            Method::POST,
+            Method::PUT,
+            Method::TRACE,
+        ]
+        .contains(self.method)
+        {
+            format!("reqwest::Method::{}", self.method)
+        } else {
+            format!("reqwest::Method::from_bytes(b\"{}\")?", self.method)
+        };
+        writeln!(buf, "    let response = reqwest::Client::new()")?;
+        writeln!(
+            buf,
+            "        .request({method}, {})",
+            rust_string_literal(self.url)
+        )?;
+        for (name, value) in &self.headers {
+            writeln!(
+                buf,
+                "        .header({}, {})",
+                rust_string_literal(name),
+                rust_string_literal(value)
+            )?;
+        }
+        match &self.body {
+            Some(GenerateBody::Text(text)) => {
+                writeln!(buf, "        .body({})", rust_string_literal(text))?
+            }
+            Some(GenerateBody::Binary(body)) => writeln!(
+                buf,
+                "        .body(b\"{}\".as_slice())",
+                escape_bytes(body)
+            )?,
+            Some(GenerateBody::Multipart { .. }) => {
+                writeln!(buf, "        .multipart(form)")?
+            }
+            None => {}
+        }
+        writeln!(buf, "        .send()")?;
+        writeln!(buf, "        .await?;")?;
+        writeln!(buf, "    println!(\"{{}}\", response.text().await?);")?;
+        writeln!(buf, "    Ok(())")?;
+        write!(buf, "}}")?;
+        Ok(())
+    }
+}
+
+/// Split a `multipart/form-data` body into its fields. Return `None` if the
+/// body isn't a multipart form, or if any part has more than a field name
+/// (e.g. a file name), so it can be sent as raw bytes instead.
+fn parse_multipart<'a>(
+    content_type: &str,
+    body: &'a [u8],
+) -> Option<Vec<(String, &'a [u8])>> {
+    let mime: mime::Mime = content_type.parse().ok()?;
+    if mime.essence_str() != "multipart/form-data" {
+        return None;
+    }
+    let boundary = mime.get_param(mime::BOUNDARY)?.as_str();
+    let delimiter = format!("\r\n--{boundary}");
+
+    let body_start = format!("--{boundary}");
+    let mut rest = body.strip_prefix(body_start.as_bytes())?;
+    let mut fields = Vec::new();
+    loop {
+        if rest.starts_with(b"--") {
+            return Some(fields);
+        }
+        rest = rest.strip_prefix(b"\r\n")?;
+        let header_end = find(rest, b"\r\n\r\n")?;
+        let headers = std::str::from_utf8(&rest[..header_end]).ok()?;
+        // Only a Content-Disposition header with just the field name is
+        // supported
+        let (header_name, disposition) = headers.split_once(':')?;
+        if !header_name.eq_ignore_ascii_case("content-disposition")
+            || headers.contains("\r\n")
+        {
+            return None;
+        }
+        let name = disposition
+            .trim()
+            .strip_prefix("form-data; name=\"")?
+            .strip_suffix('"')?;
+        if name.contains('"') {
+            return None;
+        }
+        let name = name
+            .replace("%22", "\"")
+            .replace("%0D", "\r")
+            .replace("%0A", "\n");
+
+        rest = &rest[header_end + 4..];
+        let value_end = find(rest, delimiter.as_bytes())?;
+        fields.push((name, &rest[..value_end]));
+        rest = &rest[value_end + delimiter.len()..];
+    }
+}
+
+/// Find the first occurrence of a byte sequence
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Can every field in a form be passed as a shell argument?
+fn is_shell_form(fields: &[(String, &[u8])]) -> bool {
+    fields.iter().all(|(_, value)| {
+        !value.contains(&0) && std::str::from_utf8(value).is_ok()
+    })
+}
+
+/// Quote a value as a single shell argument
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Generate a `printf` command that writes the given bytes to stdout
+fn shell_printf(bytes: &[u8]) -> String {
+    let mut format = String::new();
+    for (i, &byte) in bytes.iter().enumerate() {
+        match byte {
+            // A leading dash would be parsed as an option
+            b'-' if i == 0 => format.push_str(r"\055"),
+            b'%' => format.push_str("%%"),
+            b'\\' => format.push_str(r"\\"),
+            b'\n' => format.push_str(r"\n"),
+            // Printable ASCII, minus the quote
+            b' '..=b'~' if byte != b'\'' => format.push(byte as char),
+            _ => {
+                // Octal escapes are the only numeric escapes that POSIX
+                // printf supports
+                let _ = write!(format, "\\{byte:03o}");
+            }
+        }
+    }
+    format!("printf '{format}'")
+}
+
+/// Quote a string as a literal. JSON string syntax is valid for Python,
+/// JavaScript and Go
+fn string_literal(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+/// Quote a string as a Rust literal. Rust's `Debug` output for strings is
+/// valid Rust syntax
+fn rust_string_literal(value: &str) -> String {
+    format!("{value:?}")
+}
+
+/// Escape bytes for the inside of a byte string literal. The escapes used are
+/// valid in Python `b""`, Go `""` and Rust `b""` literals.
+fn escape_bytes(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+    for &byte in bytes {
+        match byte {
+            b'"' => escaped.push_str("\\\""),
+            b'\\' => escaped.push_str(r"\\"),
+            b'\n' => escaped.push_str(r"\n"),
+            b'\r' => escaped.push_str(r"\r"),
+            b'\t' => escaped.push_str(r"\t"),
+            b' '..=b'~' => escaped.push(byte as char),
+            _ => {
+                let _ = write!(escaped, "\\x{byte:02x}");
+            }
+        }
+    }
+    escaped
+}
+
+/// Generate a JavaScript `Uint8Array` containing the given bytes
+fn uint8_array(bytes: &[u8]) -> String {
+    format!("new Uint8Array([{}])", bytes.iter().join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{header_map, test_data_dir, Factory};
+    use pretty_assertions::assert_eq;
+    use rstest::{fixture, rstest};
+    use std::{fs, path::PathBuf};
+
+    /// A request with a JSON body
+    #[fixture]
+    fn text_request() -> RequestRecord {
+        RequestRecord {
+            method: Method::POST,
+            url: "http://localhost/users?admin=true".parse().unwrap(),
+            headers: header_map([
+                ("content-type", "application/json"),
+                ("x-tag", "a"),
+                ("x-tag", "b"),
+            ]),
+            body: Some(br#"{"name":"Alice's"}"#.as_slice().into()),
+            ..RequestRecord::factory(())
+        }
+    }
+
+    /// A request with a binary body
+    #[fixture]
+    fn binary_request() -> RequestRecord {
+        RequestRecord {
+            method: Method::PUT,
+            url: "http://localhost/avatar".parse().unwrap(),
+            headers: header_map([("content-type", "image/png")]),
+            body: Some(b"\x89PNG\r\n\x00%'".as_slice().into()),
+            ..RequestRecord::factory(())
+        }
+    }
+
+    /// A request with a multipart body, with one binary field
+    #[fixture]
+    fn multipart_request() -> RequestRecord {
+        RequestRecord {
+            method: Method::POST,
+            url: "http://localhost/upload".parse().unwrap(),
+            headers: header_map([
+                ("content-type", "multipart/form-data; boundary=abc"),
+                ("accept", "application/json"),
+            ]),
+            body: Some(
+                b"--abc\r\n\
+                Content-Disposition: form-data; name=\"user\"\r\n\r\n\
+                Alice\r\n\
+                --abc\r\n\
+                Content-Disposition: form-data; name=\"data\"\r\n\r\n\
+                \xff\xfe\r\n\
+                --abc--\r\n"
+                    .as_slice()
+                    .into(),
+            ),
+            ..RequestRecord::factory(())
+        }
+    }
+
+    /// Test each format against each type of body. Expected output is stored
+    /// in separate files, because it's long and multi-line
+    #[rstest]
+    fn test_generate(
+        test_data_dir: PathBuf,
+        #[values(
+            GenerateFormat::Curl,
+            GenerateFormat::Httpie,
+            GenerateFormat::Wget,
+            GenerateFormat::Python,
+            GenerateFormat::Javascript,
+            GenerateFormat::Go,
+            GenerateFormat::Rust
+        )]
+        format: GenerateFormat,
+        #[values("text", "binary", "multipart")] body: &str,
+    ) {
+        let request = match body {
+            "text" => text_request(),
+            "binary" => binary_request(),
+            _ => multipart_request(),
+        };
+        let extension = match format {
+            GenerateFormat::Curl => "curl.sh",
+            GenerateFormat::Httpie => "httpie.sh",
+            GenerateFormat::Wget => "wget.sh",
+            GenerateFormat::Python => "py",
+            GenerateFormat::Javascript => "js",
+            GenerateFormat::Go => "go",
+            GenerateFormat::Rust => "rs",
+        };
+        let path = test_data_dir.join(format!("generate/{body}.{extension}"));
+        let expected = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            request.generate(format).unwrap(),
+            expected.trim_end(),
+            "{path:?}"
+        );
+    }
+
+    /// A multipart form with all text fields uses each client's native form
+    /// support
+    #[rstest]
+    #[case::curl(
+        GenerateFormat::Curl,
+        "curl -XPOST --url 'http://localhost/upload' \
+        --header 'accept: application/json' --form-string 'user=Alice'"
+    )]
+    #[case::httpie(
+        GenerateFormat::Httpie,
+        "http --multipart POST 'http://localhost/upload' \
+        'accept:application/json' 'user=Alice'"
+    )]
+    // wget doesn't support multipart, so the body is always raw
+    #[case::wget(
+        GenerateFormat::Wget,
+        "printf '\\055-abc\\015\\nContent-Disposition: form-data; \
+        name=\"user\"\\015\\n\\015\\nAlice\\015\\n--abc--\\015\\n' | \
+        wget --quiet --output-document=- --method=POST \
+        --header='accept: application/json' \
+        --header='content-type: multipart/form-data; boundary=abc' \
+        --body-file=/dev/stdin 'http://localhost/upload'"
+    )]
+    fn test_generate_text_form(
+        #[case] format: GenerateFormat,
+        #[case] expected: &str,
+    ) {
+        let request = RequestRecord {
+            body: Some(
+                b"--abc\r\n\
+                Content-Disposition: form-data; name=\"user\"\r\n\r\n\
+                Alice\r\n\
+                --abc--\r\n"
+                    .as_slice()
+                    .into(),
+            ),
+            ..multipart_request()
+        };
+        assert_eq!(request.generate(format).unwrap(), expected);
+    }
+
+    /// Parts with more than a field name can't be converted to fields, so the
+    /// body is treated as binary
+    #[test]
+    fn test_parse_multipart_unsupported() {
+        let body = b"--abc\r\n\
+            Content-Disposition: form-data; name=\"file\"; \
+            filename=\"a.txt\"\r\n\r\n\
+            hello\r\n\
+            --abc--\r\n";
+        assert_eq!(
+            parse_multipart("multipart/form-data; boundary=abc", body),
+            None
+        );
+        assert_eq!(parse_multipart("application/json", br#"{"a":1}"#), None);
+    }
+}
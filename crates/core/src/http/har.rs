@@ -0,0 +1,415 @@
+//! HTTP Archive (HAR) format, for sharing request history with other tools
+//! such as browser dev tools.
+//!
+//! Spec: http://www.softwareishard.com/blog/har-12-spec/
+
+use crate::http::{Exchange, RequestRecord, ResponseRecord};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use chrono::{DateTime, FixedOffset};
+use mime::Mime;
+use reqwest::header::{self, HeaderMap};
+use serde::{Deserialize, Serialize};
+use std::str;
+
+/// HAR spec version that we generate
+const HAR_VERSION: &str = "1.2";
+/// We don't record the negotiated HTTP version, so assume the most common one
+const HTTP_VERSION: &str = "HTTP/1.1";
+/// Value for unknown sizes and timings, as defined by the spec
+const UNKNOWN: i64 = -1;
+
+/// Root of a HAR file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Har {
+    pub log: Log,
+}
+
+impl Har {
+    /// Build a HAR document from a list of exchanges, in the given order
+    pub fn from_exchanges<'a>(
+        exchanges: impl IntoIterator<Item = &'a Exchange>,
+    ) -> Self {
+        Self {
+            log: Log {
+                version: HAR_VERSION.into(),
+                creator: Creator {
+                    name: "slumber".into(),
+                    version: env!("CARGO_PKG_VERSION").into(),
+                },
+                entries: exchanges.into_iter().map(Entry::from).collect(),
+            },
+        }
+    }
+
+    /// Serialize to pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        // All keys are strings and all values are plain data, so this can't
+        // fail
+        serde_json::to_string_pretty(self).expect("HAR serialization failed")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Log {
+    pub version: String,
+    pub creator: Creator,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Creator {
+    pub name: String,
+    pub version: String,
+}
+
+/// A single request/response pair
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub started_date_time: DateTime<FixedOffset>,
+    /// Total elapsed time, in milliseconds
+    pub time: f64,
+    pub request: Request,
+    pub response: Response,
+    #[serde(default)]
+    pub cache: Cache,
+    pub timings: Timings,
+}
+
+impl From<&Exchange> for Entry {
+    fn from(exchange: &Exchange) -> Self {
+        let time = exchange.duration().num_microseconds().unwrap_or_default()
+            as f64
+            / 1000.0;
+        Self {
+            started_date_time: exchange.start_time.fixed_offset(),
+            time,
+            request: exchange.request.as_ref().into(),
+            response: Response {
+                // The request's redactor knows what's sensitive in the
+                // response headers too (e.g. Set-Cookie)
+                headers: name_values(
+                    &exchange
+                        .request
+                        .redactor
+                        .redact_headers(&exchange.response.headers),
+                ),
+                ..(&exchange.response).into()
+            },
+            cache: Cache {},
+            // We only track start and end time, so attribute the entire
+            // duration to waiting on the server
+            timings: Timings {
+                blocked: UNKNOWN,
+                dns: UNKNOWN,
+                connect: UNKNOWN,
+                send: 0.0,
+                wait: time,
+                receive: 0.0,
+                ssl: UNKNOWN,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Request {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub http_version: String,
+    /// Cookies are included in the headers, so we never populate these
+    #[serde(default)]
+    pub cookies: Vec<Cookie>,
+    #[serde(default)]
+    pub headers: Vec<NameValue>,
+    #[serde(default)]
+    pub query_string: Vec<NameValue>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<PostData>,
+    #[serde(default = "unknown")]
+    pub headers_size: i64,
+    #[serde(default = "unknown")]
+    pub body_size: i64,
+}
+
+/// HAR files are meant to be shared, so sensitive values are always redacted
+impl From<&RequestRecord> for Request {
+    fn from(request: &RequestRecord) -> Self {
+        let request = &request.redacted();
+        Self {
+            method: request.method.to_string(),
+            url: request.url.to_string(),
+            http_version: HTTP_VERSION.into(),
+            cookies: Vec::new(),
+            headers: name_values(&request.headers),
+            query_string: request
+                .url
+                .query_pairs()
+                .map(|(name, value)| NameValue {
+                    name: name.into_owned(),
+                    value: value.into_owned(),
+                })
+                .collect(),
+            post_data: request.body.as_ref().map(|body| {
+                // The spec doesn't allow for base64 request bodies, so binary
+                // data has to be lossily converted
+                PostData {
+                    mime_type: mime_type(&request.headers),
+                    text: String::from_utf8_lossy(body).into_owned(),
+                }
+            }),
+            headers_size: UNKNOWN,
+            body_size: request
+                .body
+                .as_ref()
+                .map(|body| body.len() as i64)
+                .unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Response {
+    pub status: u16,
+    #[serde(default)]
+    pub status_text: String,
+    #[serde(default)]
+    pub http_version: String,
+    /// Cookies are included in the headers, so we never populate these
+    #[serde(default)]
+    pub cookies: Vec<Cookie>,
+    #[serde(default)]
+    pub headers: Vec<NameValue>,
+    pub content: Content,
+    #[serde(default, rename = "redirectURL")]
+    pub redirect_url: String,
+    #[serde(default = "unknown")]
+    pub headers_size: i64,
+    #[serde(default = "unknown")]
+    pub body_size: i64,
+}
+
+impl From<&ResponseRecord> for Response {
+    fn from(response: &ResponseRecord) -> Self {
+        let body = response.body.bytes();
+        // Binary bodies have to be base64 encoded
+        let (text, encoding) = match str::from_utf8(body) {
+            Ok(text) => (text.to_owned(), None),
+            Err(_) => (BASE64_STANDARD.encode(body), Some("base64".into())),
+        };
+        Self {
+            status: response.status.as_u16(),
+            status_text: response
+                .status
+                .canonical_reason()
+                .unwrap_or_default()
+                .into(),
+            http_version: HTTP_VERSION.into(),
+            cookies: Vec::new(),
+            headers: name_values(&response.headers),
+            content: Content {
+                size: body.len() as i64,
+                mime_type: mime_type(&response.headers),
+                text: Some(text),
+                encoding,
+            },
+            redirect_url: response
+                .headers
+                .get(header::LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default()
+                .into(),
+            headers_size: UNKNOWN,
+            body_size: body.len() as i64,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+}
+
+/// A header or query parameter
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NameValue {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostData {
+    #[serde(default)]
+    pub mime_type: String,
+    #[serde(default)]
+    pub text: String,
+}
+
+/// Response body
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Content {
+    #[serde(default = "unknown")]
+    pub size: i64,
+    #[serde(default)]
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// `base64` for binary bodies, otherwise omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+/// We don't track caching, but the field is required
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cache {}
+
+/// Breakdown of request time, in milliseconds. Unknown phases are `-1`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Timings {
+    #[serde(default = "unknown")]
+    pub blocked: i64,
+    #[serde(default = "unknown")]
+    pub dns: i64,
+    #[serde(default = "unknown")]
+    pub connect: i64,
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+    #[serde(default = "unknown")]
+    pub ssl: i64,
+}
+
+fn unknown() -> i64 {
+    UNKNOWN
+}
+
+fn name_values(headers: &HeaderMap) -> Vec<NameValue> {
+    headers
+        .iter()
+        .map(|(name, value)| NameValue {
+            name: name.to_string(),
+            value: String::from_utf8_lossy(value.as_bytes()).into_owned(),
+        })
+        .collect()
+}
+
+/// Get the MIME type from the Content-Type header, or an empty string if
+/// missing
+fn mime_type(headers: &HeaderMap) -> String {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok()?.parse::<Mime>().ok())
+        .map(|mime| mime.to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::redact::Redactor,
+        test_util::{header_map, Factory},
+    };
+    use chrono::{TimeZone, Utc};
+    use pretty_assertions::assert_eq;
+    use reqwest::{Method, StatusCode};
+    use serde_json::json;
+
+    #[test]
+    fn test_from_exchanges() {
+        let start_time = Utc.with_ymd_and_hms(2024, 10, 13, 9, 0, 0).unwrap();
+        let exchange = Exchange {
+            start_time,
+            end_time: start_time + chrono::Duration::milliseconds(250),
+            ..Exchange::factory((
+                RequestRecord {
+                    method: Method::POST,
+                    url: "http://localhost/users?admin=true".parse().unwrap(),
+                    headers: header_map([("content-type", "application/json")]),
+                    body: Some(br#"{"name":"Ted"}"#.as_slice().into()),
+                    redactor: Redactor::new(Default::default(), Vec::new()),
+                    ..RequestRecord::factory(())
+                },
+                ResponseRecord {
+                    status: StatusCode::CREATED,
+                    headers: header_map([
+                        ("content-type", "text/plain"),
+                        ("set-cookie", "session=abc123"),
+                    ]),
+                    body: b"\xc3\x28".as_slice().into(),
+                },
+            ))
+        };
+
+        let har = Har::from_exchanges([&exchange]);
+        assert_eq!(
+            serde_json::to_value(&har).unwrap(),
+            json!({
+                "log": {
+                    "version": "1.2",
+                    "creator": {
+                        "name": "slumber",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                    "entries": [{
+                        "startedDateTime": "2024-10-13T09:00:00Z",
+                        "time": 250.0,
+                        "request": {
+                            "method": "POST",
+                            "url": "http://localhost/users?admin=true",
+                            "httpVersion": "HTTP/1.1",
+                            "cookies": [],
+                            "headers": [
+                                {"name": "content-type", "value": "application/json"},
+                            ],
+                            "queryString": [
+                                {"name": "admin", "value": "true"},
+                            ],
+                            "postData": {
+                                "mimeType": "application/json",
+                                "text": r#"{"name":"Ted"}"#,
+                            },
+                            "headersSize": -1,
+                            "bodySize": 14,
+                        },
+                        "response": {
+                            "status": 201,
+                            "statusText": "Created",
+                            "httpVersion": "HTTP/1.1",
+                            "cookies": [],
+                            "headers": [
+                                {"name": "content-type", "value": "text/plain"},
+                                {"name": "set-cookie", "value": "REDACTED"},
+                            ],
+                            "content": {
+                                "size": 2,
+                                "mimeType": "text/plain",
+                                "text": "wyg=",
+                                "encoding": "base64",
+                            },
+                            "redirectURL": "",
+                            "headersSize": -1,
+                            "bodySize": 2,
+                        },
+                        "cache": {},
+                        "timings": {
+                            "blocked": -1,
+                            "dns": -1,
+                            "connect": -1,
+                            "send": 0.0,
+                            "wait": 250.0,
+                            "receive": 0.0,
+                            "ssl": -1,
+                        },
+                    }],
+                },
+            })
+        );
+    }
+}
@@ -8,6 +8,7 @@ use crate::{
     http::{
         cereal,
         content_type::{ContentType, ResponseContent},
+        redact::Redactor,
     },
     template::Template,
 };
@@ -21,11 +22,7 @@ use reqwest::{
     Body, Client, Method, Request, StatusCode, Url,
 };
 use serde::{Deserialize, Serialize};
-use std::{
-    collections::HashMap,
-    fmt::{Debug, Write},
-    sync::Arc,
-};
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
 use thiserror::Error;
 use tracing::error;
 use uuid::Uuid;
@@ -129,6 +126,13 @@ impl BuildFieldOverrides {
             None => Some(default),
         }
     }
+
+    /// Get the override for a particular field, keyed by index. Return `None`
+    /// if the field hasn't been modified. Use this instead of [Self::get] when
+    /// the field's default value isn't a plain template.
+    pub fn get_override(&self, index: usize) -> Option<&BuildFieldOverride> {
+        self.overrides.get(&index)
+    }
 }
 
 impl FromIterator<(usize, BuildFieldOverride)> for BuildFieldOverrides {
@@ -199,25 +203,44 @@ impl Exchange {
 
 /// Metadata about an exchange. Useful in lists where request/response content
 /// isn't needed.
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct ExchangeSummary {
     pub id: RequestId,
+    pub profile_id: Option<ProfileId>,
+    pub recipe_id: RecipeId,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
     pub status: StatusCode,
+    pub annotation: ExchangeAnnotation,
 }
 
 impl From<&Exchange> for ExchangeSummary {
     fn from(exchange: &Exchange) -> Self {
         Self {
             id: exchange.id,
+            profile_id: exchange.request.profile_id.clone(),
+            recipe_id: exchange.request.recipe_id.clone(),
             start_time: exchange.start_time,
             end_time: exchange.end_time,
             status: exchange.response.status,
+            // A fresh exchange hasn't been annotated yet
+            annotation: ExchangeAnnotation::default(),
         }
     }
 }
 
+/// User-provided metadata for an exchange in history. This is stored alongside
+/// the exchange, but is editable after the fact.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExchangeAnnotation {
+    /// Pinned exchanges are never pruned from history
+    pub pinned: bool,
+    /// Short name to find the exchange by, e.g. from a chain
+    pub label: Option<String>,
+    /// Free-form text
+    pub note: Option<String>,
+}
+
 /// Data for an HTTP request. This is similar to [reqwest::Request], but differs
 /// in some key ways:
 /// - Each [reqwest::Request] can only exist once (from creation to sending),
@@ -249,6 +272,10 @@ pub struct RequestRecord {
     /// Body content as bytes. This should be decoded as needed. This will
     /// **not** be populated for bodies that are above the "large" threshold.
     pub body: Option<Bytes>,
+    /// Masks sensitive data in this request. This isn't persisted, because
+    /// requests are redacted *before* being persisted.
+    #[serde(skip)]
+    pub redactor: Redactor,
 }
 
 impl RequestRecord {
@@ -266,6 +293,7 @@ impl RequestRecord {
         profile_id: Option<ProfileId>,
         request: &Request,
         max_body_size: usize,
+        redactor: Redactor,
     ) -> Self {
         Self {
             id: seed.id,
@@ -284,33 +312,27 @@ impl RequestRecord {
                 .and_then(Body::as_bytes)
                 .filter(|body| body.len() <= max_body_size)
                 .map(|body| body.to_owned().into()),
+            redactor,
         }
     }
 
-    /// Generate a cURL command equivalent to this request
-    ///
-    /// This only fails if one of the headers or body is binary and can't be
-    /// converted to UTF-8.
-    pub fn to_curl(&self) -> anyhow::Result<String> {
-        let mut buf = String::new();
-
-        // These writes are all infallible because we're writing to a string,
-        // but use ? because it's shorter than unwrap().
-        let method = &self.method;
-        let url = &self.url;
-        write!(&mut buf, "curl -X{method} --url '{url}'")?;
-
-        for (header, value) in &self.headers {
-            let value =
-                value.to_str().context("Error decoding header value")?;
-            write!(&mut buf, " --header '{header}: {value}'")?;
-        }
-
-        if let Some(body) = &self.body_str()? {
-            write!(&mut buf, " --data '{body}'")?;
+    /// Get a copy of this request with all sensitive values masked, according
+    /// to its redaction policy. The copy has no redactor of its own, because
+    /// there's nothing left to redact.
+    pub fn redacted(&self) -> Self {
+        Self {
+            id: self.id,
+            profile_id: self.profile_id.clone(),
+            recipe_id: self.recipe_id.clone(),
+            method: self.method.clone(),
+            url: self.redactor.redact_url(&self.url),
+            headers: self.redactor.redact_headers(&self.headers),
+            body: self
+                .body
+                .as_ref()
+                .map(|body| self.redactor.redact_body(body)),
+            redactor: Redactor::default(),
         }
-
-        Ok(buf)
     }
 
     pub fn body(&self) -> Option<&[u8]> {
@@ -341,6 +363,7 @@ impl crate::test_util::Factory for RequestRecord {
             url: "http://localhost/url".parse().unwrap(),
             headers: HeaderMap::new(),
             body: None,
+            redactor: Default::default(),
         }
     }
 }
@@ -364,6 +387,7 @@ impl crate::test_util::Factory<(Option<ProfileId>, RecipeId)>
                 ("User-Agent", "slumber"),
             ]),
             body: None,
+            redactor: Default::default(),
         }
     }
 }
@@ -685,7 +709,10 @@ impl PartialEq for RequestError {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_util::{header_map, Factory};
+    use crate::{
+        http::generate::GenerateFormat,
+        test_util::{header_map, Factory},
+    };
     use indexmap::indexmap;
     use rstest::rstest;
     use serde_json::json;
@@ -744,7 +771,7 @@ mod tests {
         };
 
         assert_eq!(
-            request.to_curl().unwrap(),
+            request.generate(GenerateFormat::Curl).unwrap(),
             "curl -XDELETE --url 'http://localhost/url' \
             --header 'accept: application/json' \
             --header 'content-type: application/json' \
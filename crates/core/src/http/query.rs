@@ -1,84 +1,210 @@
 //! Utilities for querying HTTP response data
 
-use crate::{collection::SelectorMode, http::content_type::ResponseContent};
-use derive_more::{Display, FromStr};
+use crate::{
+    collection::SelectorMode,
+    http::content_type::{ContentType, ResponseContent, XPathItem, Xml},
+};
+use anyhow::Context;
+use derive_more::Display;
 use serde::{Deserialize, Serialize};
 use serde_json_path::{ExactlyOneError, JsonPath};
+use std::str::FromStr;
 use thiserror::Error;
 
-/// A wrapper around a JSONPath. This combines some common behavior, and will
-/// make it easy to swap out the query language in the future if necessary.
-#[derive(Clone, Debug, Display, FromStr, PartialEq, Serialize, Deserialize)]
-#[serde(transparent)]
-pub struct Query(JsonPath);
+/// A query to extract data from content. JSONPath can be applied to any
+/// content type, by converting it to JSON. XPath can also be applied to any
+/// content type, but it's really only useful for XML. Other content types will
+/// be converted to XML first.
+///
+/// When parsing from a string, the query language is detected from the first
+/// character. JSONPath queries must start with `$`, and anything else is
+/// treated as XPath.
+#[derive(Clone, Debug, Display, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Query {
+    JsonPath(JsonPath),
+    XPath(XPath),
+}
 
 impl Query {
     /// Apply a query to some content, returning the result in the original
-    /// format. This will convert to a common format (JSON), apply the query,
-    /// then convert back.
+    /// format. This will convert to a common format (JSON or XML), apply the
+    /// query, then convert back.
     pub fn query_content(
         &self,
         value: &dyn ResponseContent,
-    ) -> Box<dyn ResponseContent> {
+    ) -> Result<Box<dyn ResponseContent>, QueryError> {
         let content_type = value.content_type();
-        let json_value = value.to_json();
-        // We have to clone all the elements to put them into a JSON array
-        let queried = serde_json::Value::Array(
-            self.0.query(&json_value).into_iter().cloned().collect(),
-        );
-        content_type.parse_json(queried)
+        match self {
+            Self::JsonPath(json_path) => {
+                let json_value = value.to_json();
+                // We have to clone all the elements to put them into a JSON
+                // array
+                let queried = serde_json::Value::Array(
+                    json_path.query(&json_value).into_iter().cloned().collect(),
+                );
+                Ok(content_type.parse_json(queried))
+            }
+            Self::XPath(xpath) => {
+                let items = xpath.query(value)?;
+                let queried = Xml::from_xpath_results(items);
+                Ok(match content_type {
+                    ContentType::Xml => Box::new(queried),
+                    _ => {
+                        content_type.parse_json(queried.to_json().into_owned())
+                    }
+                })
+            }
+        }
     }
 
     /// Apply a query to some content, returning a string. The query should
     /// return a single result. If it's a scalar, that will be stringified. If
     /// it's an array/object, it'll be converted back into its input format,
     /// then stringified.
+    ///
+    /// For XPath, a single result is stringified as its text value, or as XML
+    /// if it's an element. Multiple results are combined into an XML document.
     pub fn query_to_string(
         &self,
         mode: SelectorMode,
         value: &dyn ResponseContent,
     ) -> Result<String, QueryError> {
-        let content_type = value.content_type();
+        match self {
+            Self::JsonPath(json_path) => {
+                let content_type = value.content_type();
 
-        // All content types get converted to JSON for querying, then converted
-        // back. This is fucky but we need *some* common format
-        let json_value = value.to_json();
-        let node_list = self.0.query(&json_value);
-
-        let stringified = match mode {
-            SelectorMode::Auto => match node_list.len() {
-                0 => return Err(QueryError::NoResults),
-                1 => content_type.value_to_string(node_list.first().unwrap()),
-                2.. => content_type.vec_to_string(&node_list.all()),
-            },
-            SelectorMode::Single => {
-                content_type.value_to_string(node_list.exactly_one()?)
+                // All content types get converted to JSON for querying, then
+                // converted back. This is fucky but we need *some* common
+                // format
+                let json_value = value.to_json();
+                let node_list = json_path.query(&json_value);
+
+                let stringified = match mode {
+                    SelectorMode::Auto => match node_list.len() {
+                        0 => return Err(QueryError::NoResults),
+                        1 => content_type
+                            .value_to_string(node_list.first().unwrap()),
+                        2.. => content_type.vec_to_string(&node_list.all()),
+                    },
+                    SelectorMode::Single => {
+                        content_type.value_to_string(node_list.exactly_one()?)
+                    }
+                    SelectorMode::Array => {
+                        content_type.vec_to_string(&node_list.all())
+                    }
+                };
+                Ok(stringified)
+            }
+            Self::XPath(xpath) => {
+                let mut items = xpath.query(value)?;
+                let stringified = match (mode, items.len()) {
+                    (SelectorMode::Auto | SelectorMode::Single, 0) => {
+                        return Err(QueryError::NoXPathResults)
+                    }
+                    (SelectorMode::Auto | SelectorMode::Single, 1) => {
+                        items.remove(0).into_string()
+                    }
+                    (SelectorMode::Single, actual_count) => {
+                        return Err(QueryError::TooManyResults { actual_count })
+                    }
+                    (SelectorMode::Auto | SelectorMode::Array, _) => {
+                        Xml::from_xpath_results(items).to_string()
+                    }
+                };
+                Ok(stringified)
             }
-            SelectorMode::Array => content_type.vec_to_string(&node_list.all()),
-        };
+        }
+    }
+}
 
-        Ok(stringified)
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // JSONPath queries always start with `$`. In XPath, that can only be
+        // used for a variable reference, which we don't support anyway
+        if s.trim_start().starts_with('$') {
+            let json_path = JsonPath::parse(s)
+                .with_context(|| format!("Invalid JSONPath `{s}`"))?;
+            Ok(Self::JsonPath(json_path))
+        } else {
+            Ok(Self::XPath(s.parse()?))
+        }
+    }
+}
+
+/// For serialization
+impl From<Query> for String {
+    fn from(query: Query) -> Self {
+        query.to_string()
+    }
+}
+
+/// For deserialization
+impl TryFrom<String> for Query {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// An XPath 1.0 expression. We store the source text rather than the compiled
+/// expression, because the compiled form can't be cloned or compared. The
+/// expression is validated during parsing, so it's always valid.
+#[derive(Clone, Debug, Display, PartialEq)]
+pub struct XPath(String);
+
+impl XPath {
+    /// Apply this query to some content, converting it to XML if necessary
+    fn query(
+        &self,
+        value: &dyn ResponseContent,
+    ) -> Result<Vec<XPathItem>, QueryError> {
+        value.to_xml().evaluate_xpath(&Self::compile(&self.0)?)
+    }
+
+    fn compile(source: &str) -> Result<sxd_xpath::XPath, QueryError> {
+        sxd_xpath::Factory::new()
+            .build(source)
+            .map_err(|error| QueryError::XPath(error.to_string()))?
+            .ok_or_else(|| QueryError::XPath("Empty expression".into()))
+    }
+}
+
+impl FromStr for XPath {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::compile(s).with_context(|| format!("Invalid XPath `{s}`"))?;
+        Ok(Self(s.to_owned()))
     }
 }
 
 #[cfg(test)]
 impl From<&str> for Query {
     fn from(value: &str) -> Self {
-        Self(value.parse().unwrap())
+        value.parse().unwrap()
     }
 }
 
-/// A remapping of [serde_json_path::ExactlyOneError]. This is a simplified
-/// version that implements `Clone`, which makes it easier to use within
-/// template errors.
-#[derive(Copy, Clone, Debug, Error)]
+/// An error applying a query. This includes a simplified version of
+/// [serde_json_path::ExactlyOneError] that implements `Clone`, which makes it
+/// easier to use within template errors.
+#[derive(Clone, Debug, Error)]
 #[cfg_attr(test, derive(PartialEq))]
 pub enum QueryError {
     #[error("No results from JSONPath query")]
     NoResults,
+    #[error("No results from XPath query")]
+    NoXPathResults,
     /// Got either 0 or 2+ results for JSON path query
     #[error("Expected exactly one result from query, but got {actual_count}")]
     TooManyResults { actual_count: usize },
+    /// XPath compilation or evaluation failed
+    #[error("XPath error: {0}")]
+    XPath(String),
 }
 
 impl From<ExactlyOneError> for QueryError {
@@ -95,7 +221,10 @@ impl From<ExactlyOneError> for QueryError {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{assert_err, http::content_type::Json};
+    use crate::{
+        assert_err,
+        http::content_type::{Json, Xml},
+    };
     use rstest::rstest;
     use serde_json::json;
 
@@ -158,6 +287,24 @@ mod tests {
     }
 
     #[rstest]
+    #[case::xpath_too_many_results_single(
+        SelectorMode::Single,
+        "//item",
+        xml("<list><item/><item/></list>"),
+        "Expected exactly one result from query, but got 2"
+    )]
+    #[case::xpath_no_results_auto(
+        SelectorMode::Auto,
+        "//bogus",
+        xml("<list><item/></list>"),
+        "No results from XPath query"
+    )]
+    #[case::xpath_unknown_function(
+        SelectorMode::Auto,
+        "bogus()",
+        xml("<list><item/></list>"),
+        "XPath error"
+    )]
     #[case::too_many_results_single(
         SelectorMode::Single,
         "$[*]",
@@ -186,8 +333,100 @@ mod tests {
         assert_err!(query.query_to_string(mode, &*content), expected_err);
     }
 
+    /// Test XPath queries in each selector mode. Multiple results are
+    /// combined into a single XML document
+    #[rstest]
+    #[case::text_auto(SelectorMode::Auto, "//fruit[1]/@name", "apple")]
+    #[case::element_single(
+        SelectorMode::Single,
+        "//fruit[2]",
+        r#"<fruit name="guava"/>"#
+    )]
+    #[case::scalar(SelectorMode::Auto, "count(//fruit)", "3")]
+    #[case::multiple_auto(
+        SelectorMode::Auto,
+        "//fruit/@name",
+        "<results><result>apple</result><result>guava</result>\
+        <result>pear</result></results>"
+    )]
+    #[case::none_array(SelectorMode::Array, "//bogus", "<results></results>")]
+    fn test_query_to_string_xpath(
+        #[case] mode: SelectorMode,
+        #[case] query: &str,
+        #[case] expected: &str,
+    ) {
+        let content = xml(
+            r#"<fruits><fruit name="apple"/><fruit name="guava"/><fruit name="pear"/></fruits>"#,
+        );
+        let query = Query::from_str(query).unwrap();
+        assert!(matches!(query, Query::XPath(_)));
+        let out = query.query_to_string(mode, &*content).unwrap();
+        assert_eq!(out, expected);
+    }
+
+    /// XPath can be applied to non-XML content by converting it to XML, and
+    /// JSONPath can be applied to XML by converting it to JSON
+    #[rstest]
+    #[case::xpath_on_json(
+        "//name",
+        json(json!({"names": {"name": ["apple", "pear"]}})),
+        r#"{"results":{"name":["apple","pear"]}}"#,
+    )]
+    #[case::jsonpath_on_xml(
+        "$.names.name",
+        xml("<names><name>apple</name><name>pear</name></names>"),
+        "<root><item>apple</item><item>pear</item></root>"
+    )]
+    fn test_query_content_cross_format(
+        #[case] query: &str,
+        #[case] content: Box<dyn ResponseContent>,
+        #[case] expected: &str,
+    ) {
+        let query = Query::from_str(query).unwrap();
+        let out = query.query_content(&*content).unwrap();
+        assert_eq!(out.content_type(), content.content_type());
+        assert_eq!(out.to_string(), expected);
+    }
+
+    /// CSV columns and cells can be selected by header name
+    #[rstest]
+    #[case::cell(SelectorMode::Auto, "$[1].name", "pear")]
+    #[case::column(SelectorMode::Auto, "$[*].name", "apple\npear")]
+    #[case::rows(
+        SelectorMode::Auto,
+        "$[?@.name == 'pear']",
+        "name,count\npear,2"
+    )]
+    fn test_query_to_string_csv(
+        #[case] mode: SelectorMode,
+        #[case] query: &str,
+        #[case] expected: &str,
+    ) {
+        let content = ContentType::Csv
+            .parse_content(b"name,count\napple,3\npear,2\n")
+            .unwrap();
+        let query = Query::from_str(query).unwrap();
+        let out = query.query_to_string(mode, &*content).unwrap();
+        assert_eq!(out, expected);
+    }
+
+    /// Test query language detection and parse errors
+    #[rstest]
+    #[case::json_path("$.data", true)]
+    #[case::xpath("/data/item", true)]
+    #[case::invalid_json_path("$.][", false)]
+    #[case::invalid_xpath("/data/[", false)]
+    fn test_parse(#[case] query: &str, #[case] valid: bool) {
+        assert_eq!(query.parse::<Query>().is_ok(), valid);
+    }
+
     /// Helper to create JSON content
     fn json(value: serde_json::Value) -> Box<dyn ResponseContent> {
         Box::new(Json::from(value))
     }
+
+    /// Helper to create XML content
+    fn xml(document: &str) -> Box<dyn ResponseContent> {
+        Box::new(Xml::parse(document.as_bytes()).unwrap())
+    }
 }
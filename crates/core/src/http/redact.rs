@@ -0,0 +1,403 @@
+//! Redaction of sensitive request data. Secrets end up in requests via
+//! authentication headers, sensitive chains, or plain text in the collection.
+//! A [Redactor] masks them before the request is persisted to history or
+//! displayed. Response headers are masked before being persisted too, since
+//! servers can set secrets of their own (e.g. `Set-Cookie`).
+
+use bytes::Bytes;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fmt, sync::Arc};
+use url::Url;
+
+/// Replacement for redacted values. This is plain ASCII so it can be
+/// substituted into URLs and header values without breaking them.
+pub const REDACTED: &str = "REDACTED";
+
+/// Sensitive values shorter than this aren't redacted from URLs, header values
+/// and bodies. Replacing every occurrence of a one- or two-character value
+/// would mangle unrelated text. Values in sensitive headers are always
+/// redacted regardless of length.
+const MIN_VALUE_LENGTH: usize = 4;
+
+/// Characters percent-encoded when a URL path is parsed
+const PATH_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Characters percent-encoded when a URL query is parsed
+const QUERY_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'<')
+    .add(b'>')
+    .add(b'\'');
+
+/// Characters percent-encoded in a standalone URL component
+const COMPONENT_ENCODE_SET: &AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Configuration for which parts of a request are considered sensitive
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(PartialEq))]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactionConfig {
+    /// Disable to persist and display requests verbatim
+    pub enabled: bool,
+    /// Headers whose values are always redacted. Case-insensitive
+    pub headers: Vec<String>,
+    /// Additional patterns to redact from URLs, header values and bodies. If
+    /// a pattern contains a capture group, only the first group is redacted.
+    pub patterns: Vec<RedactionPattern>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            headers: [
+                "authorization",
+                "proxy-authorization",
+                "cookie",
+                "set-cookie",
+                "x-api-key",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// A regex used to find sensitive values in request content
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct RedactionPattern(Regex);
+
+impl PartialEq for RedactionPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl TryFrom<String> for RedactionPattern {
+    type Error = regex::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Regex::new(&value).map(Self)
+    }
+}
+
+impl From<RedactionPattern> for String {
+    fn from(pattern: RedactionPattern) -> Self {
+        pattern.0.as_str().to_owned()
+    }
+}
+
+/// Applies the redaction policy to a single request. In addition to the
+/// global [RedactionConfig], this knows the values of any sensitive chains
+/// that were rendered into the request, so they can be masked wherever they
+/// appear.
+///
+/// The default redactor does nothing. It's used for requests that have
+/// already been redacted (e.g. loaded from history).
+#[derive(Clone, Default)]
+pub struct Redactor {
+    /// `None` if redaction is disabled
+    config: Option<Arc<RedactionConfig>>,
+    /// Rendered values of sensitive chains, in every encoding they could
+    /// appear in. Sorted longest first
+    values: Arc<[String]>,
+}
+
+impl Redactor {
+    pub fn new(config: Arc<RedactionConfig>, values: Vec<String>) -> Self {
+        if config.enabled {
+            let mut values: Vec<String> = values
+                .into_iter()
+                .filter(|value| value.len() >= MIN_VALUE_LENGTH)
+                .flat_map(|value| encodings(&value))
+                .collect();
+            // Replace longer values first, so a value that contains another
+            // is masked whole
+            values.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+            values.dedup();
+            Self {
+                config: Some(config),
+                values: values.into(),
+            }
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Will this redactor modify anything?
+    pub fn is_enabled(&self) -> bool {
+        self.config.is_some()
+    }
+
+    /// Mask all sensitive values and pattern matches in a string
+    pub fn redact_str<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let Some(config) = &self.config else {
+            return text.into();
+        };
+        let mut text = Cow::Borrowed(text);
+        for value in self.values.iter() {
+            if text.contains(value.as_str()) {
+                text = text.replace(value.as_str(), REDACTED).into();
+            }
+        }
+        for RedactionPattern(regex) in &config.patterns {
+            if regex.is_match(&text) {
+                text = redact_pattern(regex, &text).into();
+            }
+        }
+        text
+    }
+
+    /// Mask sensitive values in a URL. If the redacted URL can't be parsed,
+    /// (e.g. because a pattern matched the scheme), everything after the host
+    /// is dropped instead.
+    pub fn redact_url(&self, url: &Url) -> Url {
+        match self.redact_str(url.as_str()) {
+            Cow::Borrowed(_) => url.clone(),
+            Cow::Owned(redacted) => redacted.parse().unwrap_or_else(|_| {
+                let mut url = url.clone();
+                url.set_path(REDACTED);
+                url.set_query(None);
+                url.set_fragment(None);
+                url
+            }),
+        }
+    }
+
+    /// Mask the values of sensitive headers entirely, and sensitive values
+    /// within all other headers
+    pub fn redact_headers(&self, headers: &HeaderMap) -> HeaderMap {
+        let Some(config) = &self.config else {
+            return headers.clone();
+        };
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let sensitive = config
+                    .headers
+                    .iter()
+                    .any(|header| header.eq_ignore_ascii_case(name.as_str()));
+                let value = if sensitive {
+                    HeaderValue::from_static(REDACTED)
+                } else {
+                    // Binary values are left alone, because sensitive values
+                    // and patterns are always text
+                    value
+                        .to_str()
+                        .ok()
+                        .and_then(|text| match self.redact_str(text) {
+                            Cow::Borrowed(_) => None,
+                            Cow::Owned(text) => {
+                                HeaderValue::from_str(&text).ok()
+                            }
+                        })
+                        .unwrap_or_else(|| value.clone())
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+
+    /// Mask sensitive values in a body. Non-UTF-8 bodies are returned as-is
+    pub fn redact_body(&self, body: &Bytes) -> Bytes {
+        match std::str::from_utf8(body).map(|text| self.redact_str(text)) {
+            Ok(Cow::Owned(text)) => text.into(),
+            Ok(Cow::Borrowed(_)) | Err(_) => body.clone(),
+        }
+    }
+}
+
+/// Don't leak the sensitive values into logs
+impl fmt::Debug for Redactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Redactor")
+            .field("enabled", &self.is_enabled())
+            .field("values", &format_args!("<{} values>", self.values.len()))
+            .finish()
+    }
+}
+
+/// The redaction config is global, so only the sensitive values distinguish
+/// one redactor from another
+#[cfg(any(test, feature = "test"))]
+impl PartialEq for Redactor {
+    fn eq(&self, other: &Self) -> bool {
+        self.values == other.values
+    }
+}
+
+/// Every form a sensitive value can take once it's in a request: as-is,
+/// percent-encoded by URL parsing or as a URL component, form-urlencoded (e.g.
+/// query parameters), and escaped within a JSON string
+fn encodings(value: &str) -> [String; 6] {
+    let json = serde_json::Value::from(value).to_string();
+    [
+        utf8_percent_encode(value, PATH_ENCODE_SET).to_string(),
+        utf8_percent_encode(value, QUERY_ENCODE_SET).to_string(),
+        utf8_percent_encode(value, COMPONENT_ENCODE_SET).to_string(),
+        url::form_urlencoded::byte_serialize(value.as_bytes()).collect(),
+        // Strip the surrounding quotes
+        json[1..json.len() - 1].to_owned(),
+        value.to_owned(),
+    ]
+}
+
+/// Replace matches of a pattern. If the pattern has a capture group, only the
+/// first group is replaced, so patterns can include surrounding context (e.g.
+/// `password=([^&]+)`) without it being masked.
+fn redact_pattern(regex: &Regex, text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut last = 0;
+    for captures in regex.captures_iter(text) {
+        let Some(matched) = captures.get(1).or_else(|| captures.get(0)) else {
+            continue;
+        };
+        output.push_str(&text[last..matched.start()]);
+        output.push_str(REDACTED);
+        last = matched.end();
+    }
+    output.push_str(&text[last..]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::header_map;
+    use rstest::rstest;
+
+    fn redactor(patterns: &[&str], values: &[&str]) -> Redactor {
+        let config = RedactionConfig {
+            patterns: patterns
+                .iter()
+                .map(|pattern| pattern.to_string().try_into().unwrap())
+                .collect(),
+            ..Default::default()
+        };
+        Redactor::new(
+            config.into(),
+            values.iter().map(|value| value.to_string()).collect(),
+        )
+    }
+
+    /// Sensitive values and pattern matches are masked
+    #[rstest]
+    #[case::none(&[], &[], "user=ted&pw=hunter2", "user=ted&pw=hunter2")]
+    #[case::value(
+        &[],
+        &["hunter2"],
+        "user=ted&pw=hunter2",
+        "user=ted&pw=REDACTED"
+    )]
+    #[case::short_value(&[], &["ted"], "user=ted", "user=ted")]
+    #[case::percent_encoded(
+        &[],
+        &["p@ss word"],
+        "/users/p@ss%20word?pw=p%40ss%20word",
+        "/users/REDACTED?pw=REDACTED"
+    )]
+    #[case::form_urlencoded(
+        &[],
+        &["a&b c"],
+        "user=ted&pw=a%26b+c",
+        "user=ted&pw=REDACTED"
+    )]
+    #[case::json_escaped(
+        &[],
+        &[r#"pa"ss\"#],
+        r#"{"pw": "pa\"ss\\"}"#,
+        r#"{"pw": "REDACTED"}"#
+    )]
+    #[case::pattern(&["hunter\\d"], &[], "pw=hunter2", "pw=REDACTED")]
+    #[case::pattern_group(
+        &["pw=([^&]+)"],
+        &[],
+        "pw=hunter2&x=pw=3",
+        "pw=REDACTED&x=pw=REDACTED"
+    )]
+    fn test_redact_str(
+        #[case] patterns: &[&str],
+        #[case] values: &[&str],
+        #[case] text: &str,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(redactor(patterns, values).redact_str(text), expected);
+    }
+
+    /// Sensitive headers are masked entirely, others only where they contain
+    /// sensitive values
+    #[test]
+    fn test_redact_headers() {
+        let headers = header_map([
+            ("Authorization", "Bearer abc123"),
+            ("X-Api-Key", "secret"),
+            ("Accept", "application/json"),
+            ("X-Token", "token=hunter2"),
+        ]);
+        assert_eq!(
+            redactor(&[], &["hunter2"]).redact_headers(&headers),
+            header_map([
+                ("Authorization", REDACTED),
+                ("X-Api-Key", REDACTED),
+                ("Accept", "application/json"),
+                ("X-Token", "token=REDACTED"),
+            ])
+        );
+    }
+
+    /// Values in the URL are masked and the URL remains valid
+    #[test]
+    fn test_redact_url() {
+        let url: Url = "https://example.com/users/hunter2?token=abc123"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            redactor(&["token=(\\w+)"], &["hunter2"])
+                .redact_url(&url)
+                .as_str(),
+            "https://example.com/users/REDACTED?token=REDACTED"
+        );
+    }
+
+    /// Disabling redaction turns the redactor into a no-op
+    #[test]
+    fn test_disabled() {
+        let config = RedactionConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        let redactor = Redactor::new(config.into(), vec!["hunter2".into()]);
+        assert!(!redactor.is_enabled());
+        assert_eq!(redactor.redact_str("hunter2"), "hunter2");
+        let headers = header_map([("Authorization", "Bearer abc123")]);
+        assert_eq!(redactor.redact_headers(&headers), headers);
+    }
+
+    /// Sensitive values never show up in debug output
+    #[test]
+    fn test_debug() {
+        let debug = format!("{:?}", redactor(&[], &["hunter2"]));
+        assert!(!debug.contains("hunter2"), "{debug}");
+    }
+}
@@ -6,7 +6,9 @@ mod parse;
 mod prompt;
 mod render;
 
-pub use error::{ChainError, TemplateError, TriggeredRequestError};
+pub use error::{
+    ChainError, TemplateError, TemplateParseError, TriggeredRequestError,
+};
 pub use prompt::{Prompt, PromptChannel, Prompter, Select};
 
 use crate::{
@@ -99,6 +101,49 @@ impl Template {
     pub fn is_empty(&self) -> bool {
         self.chunks.is_empty()
     }
+
+    /// Is the template exactly one key, with no surrounding text? E.g.
+    /// `{{count}}`, but not `{{count}} items`
+    pub fn is_single_key(&self) -> bool {
+        self.single_key().is_some()
+    }
+
+    /// If the template is exactly one key, with no surrounding text, get that
+    /// key
+    pub fn single_key(&self) -> Option<&TemplateKey> {
+        match self.chunks.as_slice() {
+            [TemplateInputChunk::Key(key)] => Some(key),
+            _ => None,
+        }
+    }
+
+    /// If the template starts with a key, split that key from the rest of the
+    /// template. E.g. `{{host}}/users` is split into `host` and `/users`
+    pub fn split_first_key(&self) -> Option<(&TemplateKey, Template)> {
+        match self.chunks.as_slice() {
+            [TemplateInputChunk::Key(key), rest @ ..] => Some((
+                key,
+                Self {
+                    chunks: rest.to_vec(),
+                },
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Concatenate templates. Useful for building a template piece by piece when
+/// importing from an external format with its own variable syntax
+impl FromIterator<Template> for Template {
+    fn from_iter<I: IntoIterator<Item = Template>>(iter: I) -> Self {
+        Self {
+            chunks: join_raw(
+                iter.into_iter()
+                    .flat_map(|template| template.chunks)
+                    .collect(),
+            ),
+        }
+    }
 }
 
 #[cfg(any(test, feature = "test"))]
@@ -229,7 +274,6 @@ impl crate::test_util::Factory for TemplateContext {
 }
 
 /// Join consecutive raw chunks in a generated template, to make it valid
-#[cfg(test)]
 fn join_raw(chunks: Vec<TemplateInputChunk>) -> Vec<TemplateInputChunk> {
     let len = chunks.len();
     chunks
@@ -261,22 +305,24 @@ mod tests {
     use crate::{
         assert_err,
         collection::{
-            Chain, ChainOutputTrim, ChainRequestSection, ChainRequestTrigger,
-            ChainSource, Profile, Recipe, RecipeId, SelectOptions,
+            Chain, ChainOutputTrim, ChainRequestHistory, ChainRequestSection,
+            ChainRequestTrigger, ChainSource, Profile, ProtobufSchema, Recipe,
+            RecipeId, SelectOptions,
         },
         http::{
-            content_type::ContentType, Exchange, RequestRecord, ResponseRecord,
+            content_type::ContentType, Exchange, ExchangeAnnotation,
+            RequestRecord, ResponseRecord,
         },
         test_util::{
             by_id, header_map, http_engine, invalid_utf8_chain, temp_dir,
-            Factory, TempDir, TestPrompter, TestSelectPrompter,
+            test_data_dir, Factory, TempDir, TestPrompter, TestSelectPrompter,
         },
     };
     use chrono::Utc;
     use indexmap::indexmap;
     use rstest::rstest;
     use serde_json::json;
-    use std::time::Duration;
+    use std::{path::PathBuf, time::Duration};
     use tokio::fs;
     use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
 
@@ -417,6 +463,7 @@ mod tests {
                 recipe: recipe.id.clone(),
                 trigger: Default::default(),
                 section,
+                history: Default::default(),
             },
             selector,
             content_type: Some(ContentType::Json),
@@ -467,6 +514,201 @@ mod tests {
         );
     }
 
+    /// Test selecting which response from history a chained request uses
+    #[rstest]
+    #[case::latest(ChainRequestHistory::Latest, "latest")]
+    #[case::pinned(ChainRequestHistory::Pinned, "pinned")]
+    #[case::label(ChainRequestHistory::Label("baseline".into()), "labelled")]
+    #[tokio::test]
+    async fn test_chain_request_history(
+        #[case] history: ChainRequestHistory,
+        #[case] expected_value: &str,
+    ) {
+        let recipe = Recipe::factory(());
+        let chain = Chain {
+            source: ChainSource::Request {
+                recipe: recipe.id.clone(),
+                trigger: Default::default(),
+                section: Default::default(),
+                history,
+            },
+            ..Chain::factory(())
+        };
+
+        // Oldest first
+        let database = CollectionDatabase::factory(());
+        let now = Utc::now();
+        for (i, (body, annotation)) in [
+            (
+                "pinned",
+                ExchangeAnnotation {
+                    pinned: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "labelled",
+                ExchangeAnnotation {
+                    label: Some("baseline".into()),
+                    ..Default::default()
+                },
+            ),
+            ("latest", ExchangeAnnotation::default()),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let start_time = now - chrono::Duration::minutes(3 - i as i64);
+            let exchange = Exchange {
+                start_time,
+                end_time: start_time,
+                ..Exchange::factory((
+                    RequestRecord {
+                        recipe_id: recipe.id.clone(),
+                        profile_id: None,
+                        ..RequestRecord::factory(())
+                    },
+                    ResponseRecord {
+                        body: body.as_bytes().to_vec().into(),
+                        ..ResponseRecord::factory(())
+                    },
+                ))
+            };
+            database.insert_exchange(&exchange).unwrap();
+            database.set_annotation(exchange.id, &annotation).unwrap();
+        }
+
+        let context = TemplateContext {
+            collection: Collection {
+                recipes: by_id([recipe]).into(),
+                chains: by_id([chain]),
+                ..Collection::factory(())
+            }
+            .into(),
+            database,
+            ..TemplateContext::factory(())
+        };
+
+        assert_eq!(
+            render!("{{chains.chain1}}", context).unwrap(),
+            expected_value
+        );
+    }
+
+    /// Test XPath and JSONPath selectors on an XML response. Content type is
+    /// detected from the response header
+    #[rstest]
+    #[case::xpath_attribute("/order/@id", "1234")]
+    #[case::xpath_text("//item[2]", "<item>pear</item>")]
+    #[case::xpath_namespace(
+        "//ns:note",
+        "<ns:note xmlns:ns=\"urn:notes\">fresh</ns:note>"
+    )]
+    #[case::json_path("$.order.item[0]", "apple")]
+    #[tokio::test]
+    async fn test_chain_request_xml(
+        #[case] selector: &str,
+        #[case] expected_value: &str,
+    ) {
+        let recipe = Recipe::factory(());
+        let chain = Chain {
+            source: ChainSource::Request {
+                recipe: recipe.id.clone(),
+                trigger: Default::default(),
+                section: Default::default(),
+                history: Default::default(),
+            },
+            selector: Some(selector.parse().unwrap()),
+            ..Chain::factory(())
+        };
+
+        let database = CollectionDatabase::factory(());
+        let request = RequestRecord {
+            recipe_id: recipe.id.clone(),
+            ..RequestRecord::factory(())
+        };
+        let response = ResponseRecord {
+            body: "<order id=\"1234\" xmlns:ns=\"urn:notes\">\
+                <item>apple</item><item>pear</item>\
+                <ns:note>fresh</ns:note></order>"
+                .into(),
+            headers: header_map([("content-type", "application/xml")]),
+            ..ResponseRecord::factory(())
+        };
+        database
+            .insert_exchange(&Exchange::factory((request, response)))
+            .unwrap();
+
+        let context = TemplateContext {
+            collection: Collection {
+                recipes: by_id([recipe]).into(),
+                chains: by_id([chain]),
+                ..Collection::factory(())
+            }
+            .into(),
+            database,
+            ..TemplateContext::factory(())
+        };
+
+        assert_eq!(
+            render!("{{chains.chain1}}", context).unwrap(),
+            expected_value
+        );
+    }
+
+    /// Test a selector on a protobuf response, which is decoded using the
+    /// schema from the upstream recipe
+    #[rstest]
+    #[tokio::test]
+    async fn test_chain_request_protobuf(test_data_dir: PathBuf) {
+        let recipe = Recipe {
+            protobuf: Some(ProtobufSchema {
+                file: test_data_dir.join("protobuf/user.proto"),
+                message: "users.v1.User".into(),
+                include: vec![],
+            }),
+            ..Recipe::factory(())
+        };
+        let chain = Chain {
+            source: ChainSource::Request {
+                recipe: recipe.id.clone(),
+                trigger: Default::default(),
+                section: Default::default(),
+                history: Default::default(),
+            },
+            selector: Some("$.name".parse().unwrap()),
+            ..Chain::factory(())
+        };
+
+        let database = CollectionDatabase::factory(());
+        let request = RequestRecord {
+            recipe_id: recipe.id.clone(),
+            ..RequestRecord::factory(())
+        };
+        let response = ResponseRecord {
+            // {id: 3, name: "Ted"}
+            body: b"\x08\x03\x12\x03Ted".as_slice().into(),
+            headers: header_map([("content-type", "application/x-protobuf")]),
+            ..ResponseRecord::factory(())
+        };
+        database
+            .insert_exchange(&Exchange::factory((request, response)))
+            .unwrap();
+
+        let context = TemplateContext {
+            collection: Collection {
+                recipes: by_id([recipe]).into(),
+                chains: by_id([chain]),
+                ..Collection::factory(())
+            }
+            .into(),
+            database,
+            ..TemplateContext::factory(())
+        };
+
+        assert_eq!(render!("{{chains.chain1}}", context).unwrap(), "Ted");
+    }
+
     /// Test all possible error cases for chained requests. This covers all
     /// chain-specific error variants
     #[rstest]
@@ -487,6 +729,7 @@ mod tests {
                 recipe: "unknown".into(),
                 trigger: Default::default(),
                 section: Default::default(),
+                history: Default::default(),
             },
             ..Chain::factory(())
         },
@@ -501,6 +744,7 @@ mod tests {
                 recipe: "recipe1".into(),
                 trigger: Default::default(),
                 section: Default::default(),
+                history: Default::default(),
             },
             ..Chain::factory(())
         },
@@ -515,6 +759,7 @@ mod tests {
                 recipe: "recipe1".into(),
                 trigger: ChainRequestTrigger::Always,
                 section: Default::default(),
+                history: Default::default(),
             },
             ..Chain::factory(())
         },
@@ -529,6 +774,7 @@ mod tests {
                 recipe: "recipe1".into(),
                 trigger: Default::default(),
                 section: Default::default(),
+                history: Default::default(),
             },
             selector: Some("$.message".parse().unwrap()),
             ..Chain::factory(())
@@ -550,6 +796,7 @@ mod tests {
                 recipe: "recipe1".into(),
                 trigger: Default::default(),
                 section: Default::default(),
+                history: Default::default(),
             },
             selector: Some("$.message".parse().unwrap()),
             content_type: Some(ContentType::Json),
@@ -571,7 +818,8 @@ mod tests {
             source: ChainSource::Request {
                 recipe: "recipe1".into(),
                 trigger: Default::default(),
-                section:Default::default()
+                section:Default::default(),
+                history: Default::default(),
             },
             selector: Some("$.bogus".parse().unwrap()),
             content_type: Some(ContentType::Json),
@@ -679,6 +927,7 @@ mod tests {
                 recipe: recipe.id.clone(),
                 trigger,
                 section: Default::default(),
+                history: Default::default(),
             },
             ..Chain::factory(())
         };
@@ -1025,6 +1274,7 @@ mod tests {
                 recipe: recipe.id.clone(),
                 trigger: Default::default(),
                 section: Default::default(),
+                history: Default::default(),
             },
             selector: None,
             content_type: Some(ContentType::Json),
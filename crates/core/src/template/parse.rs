@@ -49,6 +49,32 @@ impl Template {
         }
     }
 
+    /// Create a template that renders a single environment variable,
+    /// equivalent to `{{env.<variable>}}`
+    pub fn from_environment(variable: Identifier) -> Self {
+        Self {
+            chunks: vec![TemplateInputChunk::Key(TemplateKey::Environment(
+                variable,
+            ))],
+        }
+    }
+
+    /// Convert the template to a string for an external format with its own
+    /// variable syntax. Raw text is included verbatim, *without* escape
+    /// sequences, and each key is stringified by the given function.
+    pub fn display_with(
+        &self,
+        mut display_key: impl FnMut(&TemplateKey) -> String,
+    ) -> String {
+        self.chunks
+            .iter()
+            .map(|chunk| match chunk {
+                TemplateInputChunk::Raw(s) => s.as_str().to_owned(),
+                TemplateInputChunk::Key(key) => display_key(key),
+            })
+            .collect()
+    }
+
     /// Convert the template to a string. This will only allocate for escaped or
     /// keyed templates. This is guaranteed to return the exact string that was
     /// parsed to create the template, and therefore will parse back to the same
@@ -392,6 +418,27 @@ mod tests {
         assert_eq!(&template.chunks, &[key_chain("chain1")]);
     }
 
+    /// Test that [Template::from_environment] generates the correct template
+    #[test]
+    fn test_from_environment() {
+        let template = Template::from_environment("HOME".into());
+        assert_eq!(template.display(), "{{env.HOME}}");
+        assert_eq!(&template.chunks, &[key_env("HOME")]);
+    }
+
+    /// Test [Template::display_with]. Raw text should *not* be escaped
+    #[test]
+    fn test_display_with() {
+        let template: Template =
+            "{_{raw}} {{field1}} {{chains.chain1}} {{env.ENV}}".into();
+        let displayed = template.display_with(|key| match key {
+            TemplateKey::Field(field) => format!("<{field}>"),
+            TemplateKey::Chain(id) => format!("<chain {id}>"),
+            TemplateKey::Environment(variable) => format!("<env {variable}>"),
+        });
+        assert_eq!(displayed, "{{raw}} <field1> <chain chain1> <env ENV>");
+    }
+
     /// Test [Template::raw]. This should parse+stringify back to the same thing
     #[rstest]
     #[case::empty("", tmpl([]))]
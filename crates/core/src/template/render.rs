@@ -2,10 +2,14 @@
 
 use crate::{
     collection::{
-        ChainId, ChainOutputTrim, ChainRequestSection, ChainRequestTrigger,
-        ChainSource, RecipeId, SelectOptions,
+        ChainId, ChainOutputTrim, ChainRequestHistory, ChainRequestSection,
+        ChainRequestTrigger, ChainSource, RecipeId, SelectOptions,
+    },
+    db::HistoryFilter,
+    http::{
+        content_type::ContentType, redact::REDACTED, Exchange, RequestSeed,
+        ResponseRecord,
     },
-    http::{content_type::ContentType, Exchange, RequestSeed, ResponseRecord},
     template::{
         error::TriggeredRequestError, parse::TemplateInputChunk, ChainError,
         Prompt, Select, Template, TemplateChunk, TemplateContext,
@@ -16,7 +20,12 @@ use crate::{
 use async_trait::async_trait;
 use chrono::Utc;
 use futures::future;
-use std::{env, path::PathBuf, process::Stdio, sync::Arc};
+use std::{
+    env,
+    path::PathBuf,
+    process::Stdio,
+    sync::{Arc, Mutex},
+};
 use tokio::{fs, io::AsyncWriteExt, process::Command, sync::oneshot};
 use tracing::{debug, debug_span, error, instrument, trace, trace_span};
 
@@ -155,8 +164,20 @@ impl Template {
                     // Standard case - parse the key and render it
                     let result = key.to_source().render(context, stack).await;
                     stack.pop();
-                    if let Ok(value) = &result {
-                        trace!(?value, "Rendered template key to value");
+                    match &result {
+                        // Remember sensitive values so they can be redacted
+                        // from the request later. Don't log them either
+                        Ok(chunk) if chunk.sensitive => {
+                            context.state.add_sensitive_value(&chunk.value);
+                            trace!(
+                                value = REDACTED,
+                                "Rendered template key to value"
+                            );
+                        }
+                        Ok(value) => {
+                            trace!(?value, "Rendered template key to value");
+                        }
+                        Err(_) => {}
                     }
                     result
                 }
@@ -377,9 +398,11 @@ impl<'a> TemplateSource<'a> for ChainTemplateSource<'a> {
                     recipe,
                     trigger,
                     section,
+                    history,
                 } => {
-                    let response =
-                        self.get_response(context, recipe, *trigger).await?;
+                    let response = self
+                        .get_response(context, recipe, *trigger, history)
+                        .await?;
                     // Guess content type based on HTTP header
                     let content_type =
                         ContentType::from_headers(&response.headers).ok();
@@ -410,12 +433,21 @@ impl<'a> TemplateSource<'a> for ChainTemplateSource<'a> {
             let value = if let Some(selector) = &chain.selector {
                 let content_type =
                     content_type.ok_or(ChainError::UnknownContentType)?;
+                // Protobuf responses can only be decoded with the schema from
+                // the upstream recipe
+                let protobuf = match &chain.source {
+                    ChainSource::Request { recipe, .. } => context
+                        .collection
+                        .recipes
+                        .get_recipe(recipe)
+                        .and_then(|recipe| recipe.protobuf.as_ref()),
+                    _ => None,
+                };
                 // Parse according to detected content type
-                let value =
-                    content_type.parse_content(&value).map_err(|error| {
-                        ChainError::ParseResponse {
-                            error: error.into(),
-                        }
+                let value = content_type
+                    .parse_content_with_schema(&value, protobuf)
+                    .map_err(|error| ChainError::ParseResponse {
+                        error: error.into(),
                     })?;
                 selector
                     .query_to_string(chain.selector_mode, &*value)?
@@ -444,13 +476,14 @@ impl<'a> TemplateSource<'a> for ChainTemplateSource<'a> {
 
 impl<'a> ChainTemplateSource<'a> {
     /// Get an HTTP response for a recipe. This will either get the most recent
-    /// response from history or re-execute the request, depending on trigger
-    /// behavior.
+    /// matching response from history or re-execute the request, depending on
+    /// trigger behavior.
     async fn get_response(
         &self,
         context: &'a TemplateContext,
         recipe_id: &RecipeId,
         trigger: ChainRequestTrigger,
+        history: &ChainRequestHistory,
     ) -> Result<ResponseRecord, ChainError> {
         // Get the referenced recipe. We actually only need the whole recipe if
         // we're executing the request, but we want this to error out if the
@@ -466,12 +499,21 @@ impl<'a> ChainTemplateSource<'a> {
 
         // Defer loading the most recent exchange until we know we'll need it
         let get_most_recent = || -> Result<Option<Exchange>, ChainError> {
-            context
-                .database
-                .get_latest_request(
-                    context.selected_profile.as_ref(),
-                    recipe_id,
-                )
+            let database = &context.database;
+            let profile_id = context.selected_profile.as_ref();
+            let (pinned, label) = match history {
+                ChainRequestHistory::Latest => (false, None),
+                ChainRequestHistory::Pinned => (true, None),
+                ChainRequestHistory::Label(label) => (false, Some(label)),
+            };
+            database
+                .get_latest_matching(&HistoryFilter {
+                    recipe_id: Some(recipe_id.clone()),
+                    profile_id: Some(profile_id.cloned()),
+                    pinned,
+                    label: label.cloned(),
+                    ..Default::default()
+                })
                 .map_err(|error| ChainError::Database(error.into()))
         };
         // Helper to execute the request, if triggered
@@ -842,6 +884,27 @@ pub struct RenderGroupState {
     /// chain within a render group don't have to do the work multiple
     /// times.
     chain_results: FutureCache<ChainId, TemplateResult>,
+    /// Rendered values of all sensitive chains in the group, so they can be
+    /// redacted from the built request
+    sensitive_values: Mutex<Vec<String>>,
+}
+
+impl RenderGroupState {
+    /// Get the rendered values of all sensitive chains in this render group
+    pub fn sensitive_values(&self) -> Vec<String> {
+        self.sensitive_values.lock().unwrap().clone()
+    }
+
+    fn add_sensitive_value(&self, value: &[u8]) {
+        // Binary values can't be found in text anyway, so they're dropped
+        let Ok(value) = std::str::from_utf8(value) else {
+            return;
+        };
+        let mut values = self.sensitive_values.lock().unwrap();
+        if !values.iter().any(|existing| existing == value) {
+            values.push(value.to_owned());
+        }
+    }
 }
 
 /// Track the series of template keys that we've followed to get to the current
@@ -9,6 +9,7 @@ use chrono::{
 };
 use derive_more::{DerefMut, Display};
 use serde::de::DeserializeOwned;
+use similar::TextDiff;
 use std::{
     collections::{hash_map::Entry, HashMap},
     fmt::{self, Debug},
@@ -81,6 +82,20 @@ pub fn format_byte_size(size: usize) -> String {
     format!("{size:.1} {suffix}B")
 }
 
+/// Generate a line-by-line unified diff of two texts, suitable for `patch` or
+/// any diff viewer. Labels are used for the file header lines.
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_label: &str,
+    new_label: &str,
+) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(old_label, new_label)
+        .to_string()
+}
+
 /// Extension trait for [Result]
 pub trait ResultTraced<T, E>: Sized {
     /// If this is an error, trace it. Return the same result.
@@ -6,7 +6,7 @@ use chrono::{DateTime, TimeDelta, Utc};
 use itertools::Itertools;
 use reqwest::StatusCode;
 use slumber_core::{
-    collection::{ProfileId, RecipeId},
+    collection::{Collection, ProfileId, ProtobufSchema, RecipeId},
     db::CollectionDatabase,
     http::{
         content_type::ResponseContent, Exchange, ExchangeSummary,
@@ -41,6 +41,9 @@ pub struct RequestStore {
     requests: HashMap<RequestId, RequestState>,
     /// A helper to parse response bodies. See trait definition
     parser: Box<dyn ResponseParser>,
+    /// The current collection, used to look up recipe-specific parsing
+    /// config (protobuf schemas). Updated whenever the collection is reloaded
+    collection: Arc<Collection>,
 }
 
 impl RequestStore {
@@ -52,9 +55,16 @@ impl RequestStore {
             database,
             requests: Default::default(),
             parser: Box::new(parser),
+            collection: Default::default(),
         }
     }
 
+    /// Update the collection used to look up recipe parsing config. This only
+    /// applies to responses received/loaded after this call
+    pub fn set_collection(&mut self, collection: Arc<Collection>) {
+        self.collection = collection;
+    }
+
     /// Are any requests in flight?
     pub fn has_active_requests(&self) -> bool {
         self.requests
@@ -127,7 +137,9 @@ impl RequestStore {
 
     /// Mark a request as successful, i.e. we received a response
     pub fn response(&mut self, exchange: Exchange) {
-        let response_state = RequestState::response(exchange, &*self.parser);
+        let protobuf = protobuf_schema(&self.collection, &exchange);
+        let response_state =
+            RequestState::response(exchange, &*self.parser, protobuf);
         // Use replace just to help catch bugs
         self.replace(response_state.id(), |state| {
             // This indicates a bug or race condition (e.g. request cancelled as
@@ -219,8 +231,12 @@ impl RequestStore {
             Entry::Occupied(entry) => Some(entry.into_mut()),
             Entry::Vacant(entry) => {
                 self.database.get_request(id)?.map(|exchange| {
-                    entry
-                        .insert(RequestState::response(exchange, &*self.parser))
+                    let protobuf = protobuf_schema(&self.collection, &exchange);
+                    entry.insert(RequestState::response(
+                        exchange,
+                        &*self.parser,
+                        protobuf,
+                    ))
                 })
             }
         };
@@ -244,7 +260,8 @@ impl RequestStore {
                 // This is expensive because it parses the body, so avoid it if
                 // the record is already cached
                 .or_insert_with(|| {
-                    RequestState::response(exchange, &*self.parser)
+                    let protobuf = protobuf_schema(&self.collection, &exchange);
+                    RequestState::response(exchange, &*self.parser, protobuf)
                 });
         }
 
@@ -485,9 +502,13 @@ impl RequestState {
     /// Create a request state from a completed response. This will trigger
     /// parsing of the response in a background task, so the call is expensive
     /// but not blocking.
-    fn response(mut exchange: Exchange, parser: &dyn ResponseParser) -> Self {
+    fn response(
+        mut exchange: Exchange,
+        parser: &dyn ResponseParser,
+        protobuf: Option<&ProtobufSchema>,
+    ) -> Self {
         // Pre-parse the body so the view doesn't have to do it
-        parser.parse(exchange.id, &mut exchange.response);
+        parser.parse(exchange.id, &mut exchange.response, protobuf);
         Self::Response { exchange }
     }
 }
@@ -595,7 +616,7 @@ pub struct ResponseMetadata {
 /// A simplified version of [RequestState], which only stores metadata. This is
 /// useful when you want to show a list of requests and don't need the entire
 /// request/response data for each one.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum RequestStateSummary {
     Building {
         id: RequestId,
@@ -697,10 +718,29 @@ impl From<&RequestState> for RequestStateSummary {
     }
 }
 
+/// Get the protobuf schema for the recipe that generated an exchange, if any
+fn protobuf_schema<'a>(
+    collection: &'a Collection,
+    exchange: &Exchange,
+) -> Option<&'a ProtobufSchema> {
+    collection
+        .recipes
+        .get_recipe(&exchange.request.recipe_id)?
+        .protobuf
+        .as_ref()
+}
+
 /// An abstraction for defining how response bodies should be parsed. In the TUI
 /// we use background threads, but in tests we do it inline for simplicity.
 pub trait ResponseParser: Debug {
-    fn parse(&self, request_id: RequestId, response: &mut ResponseRecord);
+    /// Parse the response body. The protobuf schema comes from the recipe, and
+    /// is only needed if the body turns out to be protobuf
+    fn parse(
+        &self,
+        request_id: RequestId,
+        response: &mut ResponseRecord,
+        protobuf: Option<&ProtobufSchema>,
+    );
 }
 
 /// Parser response bodies in background threads, to prevent blocking the UI.
@@ -716,14 +756,20 @@ impl BackgroundResponseParser {
 }
 
 impl ResponseParser for BackgroundResponseParser {
-    fn parse(&self, request_id: RequestId, response: &mut ResponseRecord) {
+    fn parse(
+        &self,
+        request_id: RequestId,
+        response: &mut ResponseRecord,
+        protobuf: Option<&ProtobufSchema>,
+    ) {
         if let Some(content_type) = response.content_type() {
             // Bytes are cheaply clonable
             let body = response.body.bytes().clone();
+            let protobuf = protobuf.cloned();
             let messages_tx = self.messages_tx.clone();
             task::spawn_blocking(move || {
                 let Ok(parsed) = content_type
-                    .parse_content(&body)
+                    .parse_content_with_schema(&body, protobuf.as_ref())
                     .with_context(|| {
                         format!(
                             "Error parsing response body \
@@ -773,9 +819,10 @@ mod tests {
             RequestStore::new(CollectionDatabase::factory(()), PARSER);
         let exchange = Exchange::factory(());
         let id = exchange.id;
-        store
-            .requests
-            .insert(exchange.id, RequestState::response(exchange, &PARSER));
+        store.requests.insert(
+            exchange.id,
+            RequestState::response(exchange, &PARSER, None),
+        );
 
         // This is a bit jank, but since we can't clone exchanges, the only way
         // to get the value back for comparison is to access the map directly
@@ -938,7 +985,7 @@ mod tests {
         let present_id = present_exchange.id;
         store.requests.insert(
             present_id,
-            RequestState::response(present_exchange, &PARSER),
+            RequestState::response(present_exchange, &PARSER, None),
         );
 
         let missing_exchange = Exchange::factory(());
@@ -989,7 +1036,7 @@ mod tests {
 
         assert_eq!(
             store.load_latest(Some(&profile_id), &recipe_id).unwrap(),
-            Some(&RequestState::response(expected_exchange, &PARSER))
+            Some(&RequestState::response(expected_exchange, &PARSER, None))
         );
 
         // Non-match
@@ -1015,9 +1062,10 @@ mod tests {
         let request_id = exchange.id;
 
         let mut store = harness.request_store.borrow_mut();
-        store
-            .requests
-            .insert(exchange.id, RequestState::response(exchange, &PARSER));
+        store.requests.insert(
+            exchange.id,
+            RequestState::response(exchange, &PARSER, None),
+        );
         let loaded = store.load_latest(Some(&profile_id), &recipe_id).unwrap();
         assert_eq!(loaded.map(RequestState::id), Some(request_id));
     }
@@ -1042,9 +1090,10 @@ mod tests {
         // Pre-load one from the DB, to make sure it gets de-duped
         let exchange = exchanges.pop().unwrap();
         let response_id = exchange.id;
-        store
-            .requests
-            .insert(exchange.id, RequestState::response(exchange, &PARSER));
+        store.requests.insert(
+            exchange.id,
+            RequestState::response(exchange, &PARSER, None),
+        );
 
         let building_id = RequestId::new();
         store.start(
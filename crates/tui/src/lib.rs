@@ -38,8 +38,8 @@ use ratatui::{prelude::CrosstermBackend, Terminal};
 use slumber_config::{Action, Config};
 use slumber_core::{
     collection::{Collection, CollectionFile, ProfileId},
-    db::{CollectionDatabase, Database},
-    http::{RequestId, RequestSeed},
+    db::{CollectionDatabase, Database, TerminalPassphrasePrompter},
+    http::{generate::GenerateFormat, har::Har, RequestId, RequestSeed},
     template::{Prompter, Template, TemplateChunk, TemplateContext},
 };
 use std::{
@@ -99,8 +99,17 @@ impl Tui {
         // Load config file. Failure shouldn't be fatal since we can fall back
         // to default, just show an error to the user
         let config = Config::load().reported(&messages_tx).unwrap_or_default();
+        let database =
+            Database::load(&config.encryption, &TerminalPassphrasePrompter)?;
+        if config.retention.prune_on_startup {
+            // Skip the vacuum because it can be slow for large databases, and
+            // sqlite will reuse the freed pages anyway
+            database
+                .prune(&config.retention, false, false)
+                .reported(&messages_tx);
+        }
         // Load a database for this particular collection
-        let database = Database::load()?.into_collection(&collection_path)?;
+        let database = database.into_collection(&collection_path)?;
         // Initialize global view context
         TuiContext::init(config);
 
@@ -120,10 +129,11 @@ impl Tui {
         // `Tui`.
         let terminal = initialize_terminal()?;
 
-        let request_store = RequestStore::new(
+        let mut request_store = RequestStore::new(
             database.clone(),
             BackgroundResponseParser::new(messages_tx.clone()),
         );
+        request_store.set_collection(Arc::clone(&collection_file.collection));
 
         let app = Tui {
             terminal,
@@ -238,6 +248,7 @@ impl Tui {
                 let path = self.collection_file.path().to_owned();
                 self.edit_file(&path)?
             }
+            Message::CollectionPasteCurl => self.paste_curl()?,
 
             Message::CopyRequestUrl(request_config) => {
                 self.copy_request_url(request_config)?;
@@ -245,8 +256,12 @@ impl Tui {
             Message::CopyRequestBody(request_config) => {
                 self.copy_request_body(request_config)?;
             }
-            Message::CopyRequestCurl(request_config) => {
-                self.copy_request_curl(request_config)?;
+            Message::CopyRequestAs {
+                config,
+                format,
+                redact,
+            } => {
+                self.copy_request_as(config, format, redact)?;
             }
             Message::CopyText(text) => self.view.copy_text(text),
             Message::SaveResponseBody { request_id, data } => {
@@ -260,6 +275,12 @@ impl Tui {
                 )?;
             }
 
+            Message::SaveExchangeHar { request_id } => {
+                self.save_exchange_har(request_id).with_context(|| {
+                    format!("Error exporting request {request_id} as HAR")
+                })?;
+            }
+
             Message::EditFile { path, on_complete } => {
                 self.edit_file(&path)?;
                 on_complete(path);
@@ -399,9 +420,32 @@ impl Tui {
         Ok(watcher)
     }
 
+    /// Add recipes to the collection file from cURL commands in the
+    /// clipboard. The file watcher will pick up the change and reload the
+    /// collection.
+    fn paste_curl(&mut self) -> anyhow::Result<()> {
+        // Returned error doesn't impl 'static so we can't directly convert it
+        // to anyhow
+        let text = cli_clipboard::get_contents()
+            .map_err(|error| anyhow!("Error reading clipboard: {error}"))?;
+        let imported = Collection::from_curl(&text)?;
+        let ids = self.collection_file.append(&imported)?;
+        self.view.notify(format!(
+            "Added {} to {}",
+            ids.iter()
+                .map(|id| format!("`{id}`"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.collection_file.path().display()
+        ));
+        Ok(())
+    }
+
     /// Reload state with a new collection
     fn reload_collection(&mut self, collection: Collection) {
         self.collection_file.collection = collection.into();
+        self.request_store
+            .set_collection(Arc::clone(&self.collection_file.collection));
 
         // Rebuild the whole view, because tons of things can change
         let database = self.database.clone();
@@ -512,14 +556,18 @@ impl Tui {
         Ok(())
     }
 
-    /// Render a request, then copy the equivalent curl command to the clipboard
-    fn copy_request_curl(
+    /// Render a request, then copy the equivalent command/code for a
+    /// third-party client to the clipboard. Sensitive values are masked unless
+    /// `redact` is disabled
+    fn copy_request_as(
         &self,
         RequestConfig {
             profile_id,
             recipe_id,
             options,
         }: RequestConfig,
+        format: GenerateFormat,
+        redact: bool,
     ) -> anyhow::Result<()> {
         let seed = RequestSeed::new(recipe_id, options);
         let template_context = self.template_context(profile_id, false)?;
@@ -530,8 +578,13 @@ impl Tui {
                 .http_engine
                 .build(seed, &template_context)
                 .await?;
-            let command = ticket.record().to_curl()?;
-            messages_tx.send(Message::CopyText(command));
+            let record = ticket.record();
+            let output = if redact {
+                record.redacted().generate(format)?
+            } else {
+                record.generate(format)?
+            };
+            messages_tx.send(Message::CopyText(output));
             Ok(())
         });
         Ok(())
@@ -565,6 +618,24 @@ impl Tui {
         Ok(())
     }
 
+    /// Export a completed request/response as a HAR file
+    fn save_exchange_har(&self, request_id: RequestId) -> anyhow::Result<()> {
+        let Some(request_state) = self.request_store.get(request_id) else {
+            bail!("Request not in store")
+        };
+        let RequestState::Response { exchange } = request_state else {
+            bail!("Request is not complete")
+        };
+        let default_path = format!("{}.har", exchange.request.recipe_id);
+        let data = Har::from_exchanges([exchange]).to_json();
+        self.spawn(save_file(
+            self.messages_tx(),
+            Some(default_path),
+            data.into(),
+        ));
+        Ok(())
+    }
+
     /// Launch an HTTP request in a separate task
     fn send_request(
         &mut self,
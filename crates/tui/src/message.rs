@@ -8,8 +8,8 @@ use slumber_config::Action;
 use slumber_core::{
     collection::{Collection, ProfileId, RecipeId},
     http::{
-        content_type::ResponseContent, BuildOptions, Exchange,
-        RequestBuildError, RequestError, RequestId, RequestRecord,
+        content_type::ResponseContent, generate::GenerateFormat, BuildOptions,
+        Exchange, RequestBuildError, RequestError, RequestId, RequestRecord,
     },
     template::{Prompt, Prompter, Select, Template, TemplateChunk},
     util::ResultTraced,
@@ -64,6 +64,9 @@ pub enum Message {
     CollectionEndReload(Collection),
     /// Open the collection in the user's editor
     CollectionEdit,
+    /// Read cURL commands from the clipboard and add them to the collection
+    /// file as new recipes
+    CollectionPasteCurl,
 
     /// Show a yes/no confirmation to the user. Use the included channel to
     /// return the value.
@@ -73,8 +76,14 @@ pub enum Message {
     CopyRequestUrl(RequestConfig),
     /// Render request body from a recipe, then copy rendered text
     CopyRequestBody(RequestConfig),
-    /// Render request, then generate an equivalent cURL command and copy it
-    CopyRequestCurl(RequestConfig),
+    /// Render request, then generate an equivalent command/code for a
+    /// third-party client and copy it
+    CopyRequestAs {
+        config: RequestConfig,
+        format: GenerateFormat,
+        /// Mask sensitive values in the output?
+        redact: bool,
+    },
     /// Copy some text to the clipboard
     CopyText(String),
 
@@ -139,6 +148,10 @@ pub enum Message {
         data: Option<String>,
     },
 
+    /// Save a complete request/response to a HAR file. This will trigger a
+    /// process to prompt the user for a file name
+    SaveExchangeHar { request_id: RequestId },
+
     /// Show a select list to the user, asking them to choose an item
     /// Use the included channel to return the selection.
     SelectStart(Select),
@@ -14,7 +14,7 @@ use ratatui::{
 };
 use rstest::fixture;
 use slumber_core::{
-    collection::Collection,
+    collection::{Collection, ProtobufSchema},
     db::CollectionDatabase,
     http::{RequestId, ResponseRecord},
     test_util::Factory,
@@ -148,16 +148,22 @@ impl TestResponseParser {
     /// A helper for manually parsing a response body in tests
     pub fn parse_body(response: &mut ResponseRecord) {
         // Request ID is never used, so we can just pass a random one in
-        Self.parse(RequestId::new(), response);
+        Self.parse(RequestId::new(), response, None);
     }
 }
 
 impl ResponseParser for TestResponseParser {
-    fn parse(&self, _: RequestId, response: &mut ResponseRecord) {
+    fn parse(
+        &self,
+        _: RequestId,
+        response: &mut ResponseRecord,
+        protobuf: Option<&ProtobufSchema>,
+    ) {
         let Some(content_type) = response.content_type() else {
             return;
         };
-        let Ok(parsed) = content_type.parse_content(response.body.bytes())
+        let Ok(parsed) = content_type
+            .parse_content_with_schema(response.body.bytes(), protobuf)
         else {
             return;
         };
@@ -4,6 +4,7 @@
 pub mod actions;
 pub mod button;
 pub mod header_table;
+pub mod hex_view;
 pub mod list;
 pub mod modal;
 pub mod scrollbar;
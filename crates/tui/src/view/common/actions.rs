@@ -1,5 +1,8 @@
 use crate::view::{
-    common::{list::List, modal::Modal},
+    common::{
+        list::List,
+        modal::{Modal, ModalPriority},
+    },
     component::Component,
     draw::{Draw, DrawMetadata, Generate},
     event::{Child, Event, EventHandler},
@@ -53,6 +56,12 @@ where
     T: FixedSelect,
     ActionsModal<T>: Draw,
 {
+    /// Actions can be opened from within other modals (e.g. history), so this
+    /// needs to go in front of whatever is already open
+    fn priority(&self) -> ModalPriority {
+        ModalPriority::High
+    }
+
     fn title(&self) -> Line<'_> {
         "Actions".into()
     }
@@ -0,0 +1,464 @@
+//! Hex dump display for binary content
+
+use crate::{
+    context::TuiContext,
+    view::{
+        common::{scrollbar::Scrollbar, text_box::TextBox},
+        context::UpdateContext,
+        draw::{Draw, DrawMetadata},
+        event::{Child, Event, EventHandler, Update},
+        Component, ViewContext,
+    },
+};
+use anyhow::{anyhow, bail};
+use bytes::Bytes;
+use itertools::Itertools;
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+use slumber_config::Action;
+use std::{cell::Cell, cmp, fmt::Write, ops::Range, str::FromStr};
+
+/// Number of bytes shown on each line of the dump
+const BYTES_PER_LINE: usize = 16;
+
+/// A scrollable hex dump, in the style of `hexdump -C`. Each line shows the
+/// offset of its first byte, the bytes in hex, and the printable ASCII
+/// characters. The search box can find a byte sequence or jump to an offset.
+///
+/// Lines are generated on render, so only the visible portion of the content
+/// is ever formatted.
+#[derive(Debug)]
+pub struct HexView {
+    bytes: Bytes,
+    /// Index of the first visible line
+    offset_y: Cell<usize>,
+    /// How many lines are visible? Updated on render
+    window_height: Cell<usize>,
+    /// Are we currently typing in the search box?
+    search_focused: bool,
+    search_text_box: Component<TextBox>,
+    /// Bytes matched by the most recent search, highlighted in the dump
+    search_match: Option<Range<usize>>,
+}
+
+impl HexView {
+    pub fn new(bytes: Bytes) -> Self {
+        let input_engine = &TuiContext::get().input_engine;
+        let binding = input_engine.binding_display(Action::Search);
+
+        let text_box = TextBox::default()
+            .placeholder(format!(
+                "'{binding}' to search bytes (hex, \"text\" or @offset)"
+            ))
+            .validator(|text| text.parse::<SearchTarget>().is_ok())
+            .on_click(|| {
+                ViewContext::push_event(Event::new_local(SearchCallback::Focus))
+            })
+            .on_cancel(|| {
+                ViewContext::push_event(Event::new_local(
+                    SearchCallback::Cancel,
+                ))
+            })
+            .on_submit(|| {
+                ViewContext::push_event(Event::new_local(
+                    SearchCallback::Submit,
+                ))
+            });
+        Self {
+            bytes,
+            offset_y: Cell::new(0),
+            window_height: Cell::new(0),
+            search_focused: false,
+            search_text_box: text_box.into(),
+            search_match: None,
+        }
+    }
+
+    /// Get the full dump as text
+    pub fn dump(&self) -> String {
+        (0..self.line_count())
+            .map(|line| {
+                let start = line * BYTES_PER_LINE;
+                let end = cmp::min(start + BYTES_PER_LINE, self.bytes.len());
+                format_line(start, &self.bytes[start..end])
+            })
+            .join("\n")
+    }
+
+    fn line_count(&self) -> usize {
+        self.bytes.len().div_ceil(BYTES_PER_LINE)
+    }
+
+    fn max_scroll_line(&self) -> usize {
+        self.line_count().saturating_sub(self.window_height.get())
+    }
+
+    fn scroll_up(&mut self, lines: usize) {
+        *self.offset_y.get_mut() = self.offset_y.get().saturating_sub(lines);
+    }
+
+    fn scroll_down(&mut self, lines: usize) {
+        *self.offset_y.get_mut() = cmp::min(
+            self.offset_y.get().saturating_add(lines),
+            self.max_scroll_line(),
+        );
+    }
+
+    /// Scroll so the line containing the given byte is visible. If it's
+    /// already on screen, don't move
+    fn scroll_to_byte(&mut self, offset: usize) {
+        let line = offset / BYTES_PER_LINE;
+        let first = self.offset_y.get();
+        let height = self.window_height.get().max(1);
+        if line < first || line >= first + height {
+            *self.offset_y.get_mut() = cmp::min(line, self.max_scroll_line());
+        }
+    }
+
+    /// Execute whatever is in the search box. For a byte sequence, find the
+    /// next match after the current one, wrapping around to the start
+    fn search(&mut self) {
+        let text = self.search_text_box.data().text();
+        let Ok(target) = text.parse::<SearchTarget>() else {
+            return;
+        };
+        let found = match target {
+            SearchTarget::Offset(offset) if offset < self.bytes.len() => {
+                Some(offset..offset + 1)
+            }
+            SearchTarget::Offset(offset) => {
+                ViewContext::notify(format!(
+                    "Offset {offset:#x} is past the end of the body ({:#x})",
+                    self.bytes.len()
+                ));
+                return;
+            }
+            SearchTarget::Bytes(pattern) => {
+                let start = self
+                    .search_match
+                    .as_ref()
+                    .map(|range| range.start + 1)
+                    .unwrap_or(0);
+                find(&self.bytes, &pattern, start)
+                    .or_else(|| find(&self.bytes, &pattern, 0))
+                    .map(|index| index..index + pattern.len())
+            }
+        };
+        match found {
+            Some(range) => {
+                self.scroll_to_byte(range.start);
+                self.search_match = Some(range);
+            }
+            None => {
+                self.search_match = None;
+                ViewContext::notify(format!("No match for {text}"));
+            }
+        }
+    }
+
+    /// Generate a styled line of the dump
+    fn render_line(&self, line: usize) -> Line<'static> {
+        let styles = &TuiContext::get().styles;
+        let start = line * BYTES_PER_LINE;
+        let end = cmp::min(start + BYTES_PER_LINE, self.bytes.len());
+        let is_match = |offset: usize| {
+            self.search_match
+                .as_ref()
+                .is_some_and(|range| range.contains(&offset))
+        };
+
+        let mut spans = vec![
+            Span::styled(format!("{start:08x}"), styles.text_window.gutter),
+            "  ".into(),
+        ];
+        for i in 0..BYTES_PER_LINE {
+            let offset = start + i;
+            if offset < end {
+                let hex = format!("{:02x}", self.bytes[offset]);
+                if is_match(offset) {
+                    spans.push(Span::styled(hex, styles.text.highlight));
+                } else {
+                    spans.push(hex.into());
+                }
+                spans.push(" ".into());
+            } else {
+                spans.push("   ".into());
+            }
+            if i == BYTES_PER_LINE / 2 - 1 {
+                spans.push(" ".into());
+            }
+        }
+        spans.push(" |".into());
+        for offset in start..end {
+            let (c, printable) = to_ascii(self.bytes[offset]);
+            let style = if is_match(offset) {
+                styles.text.highlight
+            } else if printable {
+                Default::default()
+            } else {
+                styles.text.hint
+            };
+            spans.push(Span::styled(c.to_string(), style));
+        }
+        spans.push("|".into());
+        spans.into()
+    }
+}
+
+impl EventHandler for HexView {
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> Update {
+        if let Some(callback) = event.local::<SearchCallback>() {
+            match callback {
+                SearchCallback::Focus => self.search_focused = true,
+                SearchCallback::Cancel => self.search_focused = false,
+                SearchCallback::Submit => self.search(),
+            }
+            return Update::Consumed;
+        }
+
+        let Some(action) = event.action() else {
+            return Update::Propagate(event);
+        };
+        match action {
+            Action::Search => self.search_focused = true,
+            Action::Up | Action::ScrollUp => self.scroll_up(1),
+            Action::Down | Action::ScrollDown => self.scroll_down(1),
+            Action::PageUp => self.scroll_up(self.window_height.get()),
+            Action::PageDown => self.scroll_down(self.window_height.get()),
+            Action::Home => *self.offset_y.get_mut() = 0,
+            Action::End => *self.offset_y.get_mut() = self.max_scroll_line(),
+            _ => return Update::Propagate(event),
+        }
+        Update::Consumed
+    }
+
+    fn children(&mut self) -> Vec<Component<Child<'_>>> {
+        vec![self.search_text_box.to_child_mut()]
+    }
+}
+
+impl Draw for HexView {
+    fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
+        let [dump_area, search_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)])
+                .areas(metadata.area());
+
+        // Window size could have changed, so make sure scroll is still valid
+        self.window_height.set(dump_area.height as usize);
+        self.offset_y
+            .set(cmp::min(self.offset_y.get(), self.max_scroll_line()));
+
+        let first_line = self.offset_y.get();
+        let last_line =
+            cmp::min(first_line + dump_area.height as usize, self.line_count());
+        let lines = (first_line..last_line)
+            .map(|line| self.render_line(line))
+            .collect_vec();
+        frame.render_widget(Paragraph::new(lines), dump_area);
+        frame.render_widget(
+            Scrollbar {
+                content_length: self.line_count(),
+                offset: first_line,
+                ..Default::default()
+            },
+            dump_area,
+        );
+
+        self.search_text_box
+            .draw(frame, (), search_area, self.search_focused);
+    }
+}
+
+/// All callback events from the search text box
+#[derive(Debug)]
+enum SearchCallback {
+    Focus,
+    Cancel,
+    Submit,
+}
+
+/// What the user is searching for in the dump
+#[derive(Debug, PartialEq)]
+enum SearchTarget {
+    /// Jump to a byte offset: `@1024` or `@0x400`
+    Offset(usize),
+    /// Find a byte sequence, given as hex (`de ad be ef`) or quoted text
+    /// (`"PNG"`)
+    Bytes(Vec<u8>),
+}
+
+impl FromStr for SearchTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(offset) = s.strip_prefix('@') {
+            let offset = match offset.strip_prefix("0x") {
+                Some(hex) => usize::from_str_radix(hex, 16)?,
+                None => offset.parse()?,
+            };
+            Ok(Self::Offset(offset))
+        } else if let Some(text) =
+            s.strip_prefix('"').and_then(|s| s.strip_suffix('"'))
+        {
+            if text.is_empty() {
+                bail!("Search text is empty");
+            }
+            Ok(Self::Bytes(text.as_bytes().to_owned()))
+        } else {
+            let hex: String = s.split_whitespace().collect();
+            if hex.is_empty() || hex.len() % 2 != 0 {
+                bail!("Expected an even number of hex digits");
+            }
+            let bytes = (0..hex.len())
+                .step_by(2)
+                .map(|i| {
+                    let pair = hex.get(i..i + 2).ok_or_else(|| {
+                        anyhow!("Invalid hex sequence `{hex}`")
+                    })?;
+                    Ok(u8::from_str_radix(pair, 16)?)
+                })
+                .collect::<anyhow::Result<_>>()?;
+            Ok(Self::Bytes(bytes))
+        }
+    }
+}
+
+/// Find the first occurrence of a byte pattern, at or after `start`
+fn find(bytes: &[u8], pattern: &[u8], start: usize) -> Option<usize> {
+    bytes
+        .get(start..)?
+        .windows(pattern.len())
+        .position(|window| window == pattern)
+        .map(|index| index + start)
+}
+
+/// Format one line of the dump as plain text
+fn format_line(offset: usize, bytes: &[u8]) -> String {
+    let mut line = format!("{offset:08x}  ");
+    for i in 0..BYTES_PER_LINE {
+        match bytes.get(i) {
+            // Writing to a string can't fail
+            Some(byte) => write!(line, "{byte:02x} ").unwrap(),
+            None => line.push_str("   "),
+        }
+        if i == BYTES_PER_LINE / 2 - 1 {
+            line.push(' ');
+        }
+    }
+    line.push_str(" |");
+    line.extend(bytes.iter().map(|byte| to_ascii(*byte).0));
+    line.push('|');
+    line
+}
+
+/// Get the ASCII representation of a byte, and whether or not it's printable.
+/// Unprintable bytes are shown as `.`
+fn to_ascii(byte: u8) -> (char, bool) {
+    if byte.is_ascii_graphic() || byte == b' ' {
+        (byte as char, true)
+    } else {
+        ('.', false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{harness, terminal, TestHarness, TestTerminal},
+        view::test_util::TestComponent,
+    };
+    use crossterm::event::KeyCode;
+    use rstest::rstest;
+
+    #[test]
+    fn test_dump() {
+        let view = HexView::new(Bytes::from_static(
+            b"Hello world\n\x00\x01\x02\x03\xffmore",
+        ));
+        assert_eq!(
+            view.dump(),
+            "00000000  48 65 6c 6c 6f 20 77 6f  72 6c 64 0a 00 01 02 03  \
+            |Hello world.....|\n\
+            00000010  ff 6d 6f 72 65                                    \
+            |.more|"
+        );
+    }
+
+    #[rstest]
+    #[case::offset_decimal("@32", SearchTarget::Offset(32))]
+    #[case::offset_hex("@0x20", SearchTarget::Offset(32))]
+    #[case::hex("de ad BEEF", SearchTarget::Bytes(vec![0xde, 0xad, 0xbe, 0xef]))]
+    #[case::text("\"PNG\"", SearchTarget::Bytes(b"PNG".to_vec()))]
+    fn test_parse_search(#[case] input: &str, #[case] expected: SearchTarget) {
+        assert_eq!(input.parse::<SearchTarget>().unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::odd_hex("abc")]
+    #[case::bad_hex("zz")]
+    #[case::bad_offset("@0xzz")]
+    #[case::empty_text("\"\"")]
+    fn test_parse_search_error(#[case] input: &str) {
+        assert!(input.parse::<SearchTarget>().is_err());
+    }
+
+    /// Search for a byte sequence, then find the next match, wrapping around
+    #[rstest]
+    fn test_search(
+        harness: TestHarness,
+        #[with(80, 3)] terminal: TestTerminal,
+    ) {
+        // Put the matches on different lines, so we can check scrolling
+        let mut bytes = vec![0u8; 64];
+        bytes[4..6].copy_from_slice(b"\xca\xfe");
+        bytes[50..52].copy_from_slice(b"\xca\xfe");
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            HexView::new(bytes.into()),
+            (),
+        );
+
+        component.send_key(KeyCode::Char('/')).assert_empty();
+        component.send_text("ca fe").assert_empty();
+        component.send_key(KeyCode::Enter).assert_empty();
+        assert_eq!(component.data().search_match, Some(4..6));
+        assert_eq!(component.data().offset_y.get(), 0);
+
+        component.send_key(KeyCode::Enter).assert_empty();
+        assert_eq!(component.data().search_match, Some(50..52));
+        // Line 3 wasn't visible, so we scrolled as far as we can
+        assert_eq!(component.data().offset_y.get(), 2);
+
+        component.send_key(KeyCode::Enter).assert_empty();
+        assert_eq!(component.data().search_match, Some(4..6));
+        assert_eq!(component.data().offset_y.get(), 0);
+    }
+
+    /// Jump to an offset
+    #[rstest]
+    fn test_search_offset(
+        harness: TestHarness,
+        #[with(80, 3)] terminal: TestTerminal,
+    ) {
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            HexView::new(vec![0u8; 64].into()),
+            (),
+        );
+
+        component.send_key(KeyCode::Char('/')).assert_empty();
+        component.send_text("@0x30").assert_empty();
+        component.send_key(KeyCode::Enter).assert_empty();
+        assert_eq!(component.data().search_match, Some(48..49));
+        assert_eq!(component.data().offset_y.get(), 2);
+    }
+}
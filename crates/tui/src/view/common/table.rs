@@ -47,7 +47,8 @@ impl<'a, const COLS: usize, Cll> Generate for Table<'a, COLS, [Cll; COLS]>
 where
     Cll: Into<Cell<'a>>,
 {
-    type Output<'this> = ratatui::widgets::Table<'this>
+    type Output<'this>
+        = ratatui::widgets::Table<'this>
     where
         Self: 'this;
 
@@ -67,7 +68,8 @@ where
 }
 
 impl<'a, const COLS: usize> Generate for Table<'a, COLS, Row<'a>> {
-    type Output<'this> = ratatui::widgets::Table<'this>
+    type Output<'this>
+        = ratatui::widgets::Table<'this>
     where
         Self: 'this;
 
@@ -75,38 +77,91 @@ impl<'a, const COLS: usize> Generate for Table<'a, COLS, Row<'a>> {
     where
         Self: 'this,
     {
-        let styles = &TuiContext::get().styles;
-        let rows = self.rows.into_iter().enumerate().map(|(i, row)| {
-            // Apply theme styles, but let the row's individual styles override
-            let base_style = if self.alternate_row_style && i % 2 == 1 {
-                styles.table.alt
-            } else {
-                styles.table.text
-            };
-            let row_style = Styled::style(&row);
-            row.set_style(base_style.patch(row_style))
-        });
-        let mut table = ratatui::widgets::Table::new(rows, self.column_widths)
-            .highlight_style(styles.table.highlight);
-
-        // Add title
-        if let Some(title) = self.title {
-            table = table.block(
-                Block::default()
-                    .title(title)
-                    .title_style(styles.table.title),
-            );
-        }
+        build_table(
+            self.title,
+            self.header.map(Row::new),
+            self.rows,
+            self.column_widths,
+            self.alternate_row_style,
+        )
+    }
+}
 
-        // Add optional header if given
-        if let Some(header) = self.header {
-            table = table.header(Row::new(header).style(styles.table.header));
-        }
+/// Tabular data display with a dynamic number of columns. This is for data
+/// whose shape isn't known until runtime. Unlike [Table], the header is a
+/// full row so the caller can style individual header cells.
+#[derive(Debug, Default)]
+pub struct DynamicTable<'a> {
+    pub header: Option<Row<'a>>,
+    pub rows: Vec<Row<'a>>,
+    /// Use a different styling for alternating rows
+    pub alternate_row_style: bool,
+    /// One width per column
+    pub column_widths: Vec<Constraint>,
+}
 
-        table
+impl<'a> Generate for DynamicTable<'a> {
+    type Output<'this>
+        = ratatui::widgets::Table<'this>
+    where
+        Self: 'this;
+
+    fn generate<'this>(self) -> Self::Output<'this>
+    where
+        Self: 'this,
+    {
+        build_table(
+            None,
+            self.header,
+            self.rows,
+            self.column_widths,
+            self.alternate_row_style,
+        )
     }
 }
 
+/// Build a table widget with theme styling applied. Individual row styles
+/// override the theme.
+fn build_table<'a>(
+    title: Option<&'a str>,
+    header: Option<Row<'a>>,
+    rows: Vec<Row<'a>>,
+    column_widths: impl IntoIterator<Item = impl Into<Constraint>>,
+    alternate_row_style: bool,
+) -> ratatui::widgets::Table<'a> {
+    let styles = &TuiContext::get().styles;
+    let rows = rows.into_iter().enumerate().map(|(i, row)| {
+        // Apply theme styles, but let the row's individual styles override
+        let base_style = if alternate_row_style && i % 2 == 1 {
+            styles.table.alt
+        } else {
+            styles.table.text
+        };
+        let row_style = Styled::style(&row);
+        row.set_style(base_style.patch(row_style))
+    });
+    let mut table = ratatui::widgets::Table::new(rows, column_widths)
+        .highlight_style(styles.table.highlight);
+
+    // Add title
+    if let Some(title) = title {
+        table = table.block(
+            Block::default()
+                .title(title)
+                .title_style(styles.table.title),
+        );
+    }
+
+    // Add optional header if given
+    if let Some(header) = header {
+        let header_style = Styled::style(&header);
+        table = table
+            .header(header.set_style(styles.table.header.patch(header_style)));
+    }
+
+    table
+}
+
 /// A row in a table that can be toggled on/off. This will generate the checkbox
 /// column, and apply the appropriate row styling.
 #[derive(Debug)]
@@ -133,7 +188,8 @@ where
     Cells: IntoIterator,
     Cells::Item: Into<Text<'a>>,
 {
-    type Output<'this> = Row<'this>
+    type Output<'this>
+        = Row<'this>
     where
         Self: 'this;
 
@@ -1,3 +1,5 @@
+mod csv_table;
+mod exchange_diff;
 mod exchange_pane;
 mod help;
 mod history;
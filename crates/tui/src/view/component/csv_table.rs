@@ -0,0 +1,368 @@
+//! Tabular display for CSV/TSV bodies
+
+use crate::{
+    context::TuiContext,
+    view::{
+        common::{scrollbar::Scrollbar, table::DynamicTable},
+        context::UpdateContext,
+        draw::{Draw, DrawMetadata, Generate},
+        event::{Event, EventHandler, Update},
+    },
+};
+use ratatui::{
+    layout::Constraint,
+    text::{Line, Span},
+    widgets::{Cell as TableCell, Row, TableState},
+    Frame,
+};
+use slumber_config::Action;
+use slumber_core::http::content_type::CsvTable;
+use std::{
+    cell::{Cell, RefCell},
+    cmp::{self, Ordering},
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Don't let a single long cell take up the whole screen
+const MAX_COLUMN_WIDTH: usize = 40;
+/// Space between columns. This matches ratatui's default
+const COLUMN_SPACING: usize = 1;
+
+/// A scrollable table of delimited data. Rows can be selected with the
+/// standard up/down actions. The horizontal scroll actions select a column,
+/// and the toggle action sorts by the selected column.
+#[derive(Debug)]
+pub struct CsvTableView {
+    table: CsvTable,
+    /// Width of each column, based on its widest cell
+    column_widths: Vec<usize>,
+    /// Display order of the rows, as indexes into the table. This is the
+    /// identity order until the user sorts by a column
+    order: Vec<usize>,
+    sort: Option<ColumnSort>,
+    selected_column: usize,
+    /// Index of the leftmost visible column. This is updated during render,
+    /// to ensure the selected column is visible
+    column_offset: Cell<usize>,
+    /// Row selection and vertical scroll state. Needs interior mutability so
+    /// it can be updated during render
+    state: RefCell<TableState>,
+    /// Number of visible rows, for paging. Updated on render
+    window_height: Cell<usize>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct ColumnSort {
+    column: usize,
+    descending: bool,
+}
+
+impl CsvTableView {
+    pub fn new(table: CsvTable) -> Self {
+        let column_widths = table
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| {
+                // Leave room for the sort indicator
+                let header_width = header.width() + 2;
+                let cell_width = table
+                    .rows
+                    .iter()
+                    .map(|row| row[i].width())
+                    .max()
+                    .unwrap_or_default();
+                cmp::min(cmp::max(header_width, cell_width), MAX_COLUMN_WIDTH)
+            })
+            .collect();
+        let order = (0..table.rows.len()).collect();
+        let state = TableState::default()
+            .with_selected(if table.rows.is_empty() { None } else { Some(0) });
+        Self {
+            table,
+            column_widths,
+            order,
+            sort: None,
+            selected_column: 0,
+            column_offset: Cell::new(0),
+            state: RefCell::new(state),
+            window_height: Cell::new(0),
+        }
+    }
+
+    /// Move the selected row by some number of rows, clamping to the bounds
+    /// of the table
+    fn select_row(&mut self, delta: isize) {
+        let len = self.order.len();
+        if len == 0 {
+            return;
+        }
+        let state = self.state.get_mut();
+        let selected = state.selected().unwrap_or_default();
+        let selected = selected.saturating_add_signed(delta).min(len - 1);
+        state.select(Some(selected));
+    }
+
+    fn select_column(&mut self, delta: isize) {
+        let last = self.table.headers.len().saturating_sub(1);
+        self.selected_column =
+            self.selected_column.saturating_add_signed(delta).min(last);
+    }
+
+    /// Sort by the selected column. Repeatedly sorting by the same column
+    /// cycles between ascending, descending, and unsorted.
+    fn toggle_sort(&mut self) {
+        let column = self.selected_column;
+        self.sort = match self.sort {
+            Some(ColumnSort {
+                column: sorted,
+                descending: false,
+            }) if sorted == column => Some(ColumnSort {
+                column,
+                descending: true,
+            }),
+            Some(ColumnSort {
+                column: sorted,
+                descending: true,
+            }) if sorted == column => None,
+            _ => Some(ColumnSort {
+                column,
+                descending: false,
+            }),
+        };
+
+        // Sort is stable, so going back to the identity order always works
+        self.order = (0..self.table.rows.len()).collect();
+        if let Some(ColumnSort { column, descending }) = self.sort {
+            let rows = &self.table.rows;
+            self.order.sort_by(|a, b| {
+                let ordering =
+                    compare_cells(&rows[*a][column], &rows[*b][column]);
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+    }
+
+    /// Get the cells of each row, in display order
+    #[cfg(test)]
+    fn rows(&self) -> Vec<Vec<&str>> {
+        self.order
+            .iter()
+            .map(|i| self.table.rows[*i].iter().map(String::as_str).collect())
+            .collect()
+    }
+
+    /// Update the column offset so that the selected column is visible, then
+    /// return the range of visible columns
+    fn visible_columns(&self, width: usize) -> std::ops::Range<usize> {
+        let selected = self.selected_column;
+        let span_width = |start: usize, end: usize| -> usize {
+            self.column_widths[start..end]
+                .iter()
+                .map(|width| width + COLUMN_SPACING)
+                .sum()
+        };
+
+        let mut offset = cmp::min(self.column_offset.get(), selected);
+        while offset < selected && span_width(offset, selected + 1) > width {
+            offset += 1;
+        }
+        self.column_offset.set(offset);
+
+        // Show as many columns as fit, but always at least one
+        let mut end = offset + 1;
+        while end < self.column_widths.len()
+            && span_width(offset, end + 1) <= width
+        {
+            end += 1;
+        }
+        offset..cmp::min(end, self.column_widths.len())
+    }
+}
+
+impl EventHandler for CsvTableView {
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> Update {
+        let Some(action) = event.action() else {
+            return Update::Propagate(event);
+        };
+        let page = self.window_height.get().max(1) as isize;
+        match action {
+            Action::Up | Action::ScrollUp => self.select_row(-1),
+            Action::Down | Action::ScrollDown => self.select_row(1),
+            Action::PageUp => self.select_row(-page),
+            Action::PageDown => self.select_row(page),
+            Action::Home => self.select_row(isize::MIN),
+            Action::End => self.select_row(isize::MAX),
+            Action::ScrollLeft => self.select_column(-1),
+            Action::ScrollRight => self.select_column(1),
+            Action::Toggle => self.toggle_sort(),
+            _ => return Update::Propagate(event),
+        }
+        Update::Consumed
+    }
+}
+
+impl Draw for CsvTableView {
+    fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
+        let styles = &TuiContext::get().styles;
+        let area = metadata.area();
+        let columns = self.visible_columns(area.width as usize);
+
+        let header = self.table.headers[columns.clone()]
+            .iter()
+            .enumerate()
+            .map(|(i, header)| {
+                let column = columns.start + i;
+                let indicator = match self.sort {
+                    Some(ColumnSort {
+                        column: sorted,
+                        descending,
+                    }) if sorted == column => {
+                        if descending {
+                            " ▼"
+                        } else {
+                            " ▲"
+                        }
+                    }
+                    _ => "",
+                };
+                let line = Line::from(vec![
+                    Span::from(header.as_str()),
+                    Span::from(indicator),
+                ]);
+                if column == self.selected_column {
+                    TableCell::from(line).style(styles.table.highlight)
+                } else {
+                    TableCell::from(line)
+                }
+            });
+        let rows = self
+            .order
+            .iter()
+            .map(|i| {
+                Row::new(
+                    self.table.rows[*i][columns.clone()]
+                        .iter()
+                        .map(String::as_str),
+                )
+            })
+            .collect();
+        let table = DynamicTable {
+            header: Some(Row::new(header)),
+            rows,
+            alternate_row_style: true,
+            column_widths: self.column_widths[columns]
+                .iter()
+                .map(|width| Constraint::Length(*width as u16))
+                .collect(),
+        };
+
+        // Subtract one for the header
+        self.window_height
+            .set((area.height as usize).saturating_sub(1));
+        let mut state = self.state.borrow_mut();
+        frame.render_stateful_widget(table.generate(), area, &mut state);
+
+        frame.render_widget(
+            Scrollbar {
+                content_length: self.order.len(),
+                offset: state.offset(),
+                ..Default::default()
+            },
+            area,
+        );
+    }
+}
+
+/// Compare two cells for sorting. If both are numbers, compare numerically.
+/// Otherwise compare as strings.
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{harness, terminal, TestHarness, TestTerminal},
+        view::test_util::TestComponent,
+    };
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use rstest::rstest;
+
+    fn table() -> CsvTable {
+        CsvTable {
+            headers: vec!["name".into(), "count".into()],
+            rows: vec![
+                vec!["pear".into(), "10".into()],
+                vec!["apple".into(), "9".into()],
+                vec!["banana".into(), "100".into()],
+            ],
+        }
+    }
+
+    /// Sorting cycles through ascending, descending, and unsorted
+    #[rstest]
+    fn test_sort(harness: TestHarness, terminal: TestTerminal) {
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            CsvTableView::new(table()),
+            (),
+        );
+
+        // Sort by the first column as a string
+        component.send_key(KeyCode::Char(' ')).assert_empty();
+        assert_eq!(
+            component.data().rows(),
+            vec![
+                vec!["apple", "9"],
+                vec!["banana", "100"],
+                vec!["pear", "10"]
+            ]
+        );
+
+        // Move to the second column and sort numerically
+        component
+            .send_key_modifiers(KeyCode::Right, KeyModifiers::SHIFT)
+            .assert_empty();
+        component.send_key(KeyCode::Char(' ')).assert_empty();
+        assert_eq!(
+            component.data().rows(),
+            vec![
+                vec!["apple", "9"],
+                vec!["pear", "10"],
+                vec!["banana", "100"]
+            ]
+        );
+
+        // Descending
+        component.send_key(KeyCode::Char(' ')).assert_empty();
+        assert_eq!(
+            component.data().rows(),
+            vec![
+                vec!["banana", "100"],
+                vec!["pear", "10"],
+                vec!["apple", "9"]
+            ]
+        );
+
+        // Back to the original order
+        component.send_key(KeyCode::Char(' ')).assert_empty();
+        assert_eq!(
+            component.data().rows(),
+            vec![
+                vec!["pear", "10"],
+                vec!["apple", "9"],
+                vec!["banana", "100"]
+            ]
+        );
+    }
+}
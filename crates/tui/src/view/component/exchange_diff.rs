@@ -0,0 +1,259 @@
+use crate::{
+    context::TuiContext,
+    view::{
+        common::{
+            modal::{Modal, ModalPriority},
+            text_window::{TextWindow, TextWindowProps},
+        },
+        component::Component,
+        draw::{Draw, DrawMetadata},
+        event::{Child, EventHandler},
+        state::Identified,
+        ViewContext,
+    },
+};
+use ratatui::{
+    layout::Constraint,
+    style::Style,
+    text::{Line, Span, Text},
+    Frame,
+};
+use slumber_core::{
+    http::{
+        diff::{Change, ExchangeDiff},
+        Exchange,
+    },
+    util::format_time,
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Compare the responses of two exchanges side by side. The old exchange is
+/// on the left, the new one is on the right.
+#[derive(Debug)]
+pub struct ExchangeDiffModal {
+    title: String,
+    /// Pre-rendered diff. The diff is static, so we only need to generate it
+    /// once
+    text: Identified<Text<'static>>,
+    text_window: Component<TextWindow>,
+}
+
+impl ExchangeDiffModal {
+    pub fn new(old: &Exchange, new: &Exchange) -> Self {
+        let collection = ViewContext::collection();
+        let label = |exchange: &Exchange| {
+            let recipe_id = &exchange.request.recipe_id;
+            let recipe_name = collection
+                .recipes
+                .get_recipe(recipe_id)
+                .map(|recipe| recipe.name().to_owned())
+                .unwrap_or_else(|| recipe_id.to_string());
+            format!("{recipe_name} {}", format_time(&exchange.start_time))
+        };
+        let diff = ExchangeDiff::new(old, new);
+        Self {
+            title: format!("Diff {} / {}", label(old), label(new)),
+            text: Identified::new(render(&diff)),
+            text_window: Component::default(),
+        }
+    }
+}
+
+impl Modal for ExchangeDiffModal {
+    /// This is opened from the history modal, so it needs to go on top
+    fn priority(&self) -> ModalPriority {
+        ModalPriority::High
+    }
+
+    fn title(&self) -> Line<'_> {
+        self.title.as_str().into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        (Constraint::Percentage(80), Constraint::Percentage(80))
+    }
+}
+
+impl EventHandler for ExchangeDiffModal {
+    fn children(&mut self) -> Vec<Component<Child<'_>>> {
+        vec![self.text_window.to_child_mut()]
+    }
+}
+
+impl Draw for ExchangeDiffModal {
+    fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
+        self.text_window.draw(
+            frame,
+            TextWindowProps {
+                text: &self.text,
+                margins: Default::default(),
+                footer: None,
+            },
+            metadata.area(),
+            true,
+        );
+    }
+}
+
+/// Render the entire diff as two columns. Each row of the diff is one line,
+/// with the old side padded so the new side always starts in the same column.
+fn render(diff: &ExchangeDiff) -> Text<'static> {
+    let styles = &TuiContext::get().styles;
+
+    // Convert everything to strings up front, so we can measure the width of
+    // the left column
+    let status = map_change(&diff.status, |status| status.to_string());
+    let headers: Vec<Change<String>> = diff
+        .headers
+        .iter()
+        .map(|(name, change)| {
+            map_change(change, |value| format!("{name}: {value}"))
+        })
+        .collect();
+    let rows = [&status]
+        .into_iter()
+        .chain(&headers)
+        .chain(&diff.body.lines);
+    let width = rows
+        .map(|change| match change {
+            Change::Unchanged(old)
+            | Change::Removed(old)
+            | Change::Modified(old, _) => old.width(),
+            Change::Added(_) => 0,
+        })
+        .max()
+        .unwrap_or_default();
+
+    let row = |change: &Change<String>| -> Line<'static> {
+        let (old, new) = match change {
+            Change::Unchanged(value) => (
+                Span::raw(format!("  {value}")),
+                Span::raw(format!("  {value}")),
+            ),
+            Change::Added(value) => (
+                Span::raw(""),
+                Span::styled(format!("+ {value}"), styles.diff.added),
+            ),
+            Change::Removed(value) => (
+                Span::styled(format!("- {value}"), styles.diff.removed),
+                Span::raw(""),
+            ),
+            Change::Modified(old, new) => (
+                Span::styled(format!("- {old}"), styles.diff.removed),
+                Span::styled(format!("+ {new}"), styles.diff.added),
+            ),
+        };
+        // Pad with a separate span so the padding doesn't get styled
+        let padding = (width + 2).saturating_sub(old.content.width());
+        vec![old, " ".repeat(padding).into(), " │ ".into(), new].into()
+    };
+    let header = |text: String| Line::styled(text, styles.text.title);
+
+    let mut lines = vec![header("Status".into()), row(&status), "".into()];
+    lines.push(header("Headers".into()));
+    lines.extend(headers.iter().map(row));
+    lines.push("".into());
+
+    // If the bodies were parsed, list each changed value by path. This is
+    // easier to scan than the line diff for large bodies
+    if let Some(values) = &diff.body.values {
+        lines.push(header(format!("Changed Values ({})", values.len())));
+        lines.extend(values.iter().map(|(path, change)| {
+            let (text, style) = match change {
+                Change::Unchanged(value) => {
+                    (format!("  {path}: {value}"), Style::default())
+                }
+                Change::Added(value) => {
+                    (format!("+ {path}: {value}"), styles.diff.added)
+                }
+                Change::Removed(value) => {
+                    (format!("- {path}: {value}"), styles.diff.removed)
+                }
+                Change::Modified(old, new) => {
+                    (format!("~ {path}: {old} → {new}"), Style::default())
+                }
+            };
+            Line::styled(text, style)
+        }));
+        lines.push("".into());
+    }
+
+    lines.push(header("Body".into()));
+    lines.extend(diff.body.lines.iter().map(row));
+    lines.into()
+}
+
+/// Convert the values in a change to strings
+fn map_change<T>(
+    change: &Change<T>,
+    f: impl Fn(&T) -> String,
+) -> Change<String> {
+    match change {
+        Change::Unchanged(value) => Change::Unchanged(f(value)),
+        Change::Added(value) => Change::Added(f(value)),
+        Change::Removed(value) => Change::Removed(f(value)),
+        Change::Modified(old, new) => Change::Modified(f(old), f(new)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{harness, terminal, TestHarness, TestTerminal},
+        view::test_util::TestComponent,
+    };
+    use reqwest::StatusCode;
+    use rstest::rstest;
+    use slumber_core::{
+        http::{RequestRecord, ResponseRecord},
+        test_util::{header_map, Factory},
+    };
+
+    /// Both sides of the diff are rendered next to each other, with changes
+    /// marked
+    #[rstest]
+    fn test_render(harness: TestHarness, terminal: TestTerminal) {
+        let exchange = |status, body: &str| {
+            Exchange::factory((
+                RequestRecord::factory(()),
+                ResponseRecord {
+                    status,
+                    headers: header_map([("x-a", "1")]),
+                    body: body.as_bytes().to_vec().into(),
+                },
+            ))
+        };
+        let component = TestComponent::new(
+            &harness,
+            &terminal,
+            ExchangeDiffModal::new(
+                &exchange(StatusCode::OK, "one\ntwo\n"),
+                &exchange(StatusCode::NOT_FOUND, "one\nthree\nfour\n"),
+            ),
+            (),
+        );
+        let lines: Vec<String> = component
+            .data()
+            .text
+            .lines
+            .iter()
+            .map(|line| line.to_string().trim_end().to_owned())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "Status",
+                "- 200 OK │ + 404 Not Found",
+                "",
+                "Headers",
+                "  x-a: 1 │   x-a: 1",
+                "",
+                "Body",
+                "  one    │   one",
+                "- two    │ + three",
+                "         │ + four",
+            ]
+        );
+    }
+}
@@ -3,32 +3,71 @@ use crate::{
     http::RequestStateSummary,
     util::ResultReported,
     view::{
-        common::{list::List, modal::Modal},
-        component::Component,
-        draw::{Draw, DrawMetadata, Generate},
-        event::{Child, Event, EventHandler},
+        common::{
+            actions::ActionsModal, list::List, modal::Modal, text_box::TextBox,
+        },
+        component::{exchange_diff::ExchangeDiffModal, Component},
+        context::UpdateContext,
+        draw::{Draw, DrawMetadata, Generate, ToStringGenerate},
+        event::{Child, Event, EventHandler, Update},
         state::select::SelectState,
+        util::persistence::Persisted,
         ViewContext,
     },
 };
+use derive_more::Display;
+use persisted::PersistedKey;
 use ratatui::{
-    layout::Constraint,
+    layout::{Constraint, Layout},
     text::{Line, Span},
     Frame,
 };
-use slumber_core::{collection::RecipeId, http::RequestId};
+use serde::Serialize;
+use slumber_config::Action;
+use slumber_core::{
+    collection::{ProfileId, RecipeId},
+    db::HistoryFilter,
+    http::{Exchange, ExchangeAnnotation, RequestId},
+};
+use strum::{EnumCount, EnumIter};
 
-/// Browse request/response history for a recipe
+/// Browse request/response history for a recipe. History can be searched by
+/// text, which queries the full-text index in the DB. Two requests can be
+/// compared by toggling each one; the first toggled request is remembered
+/// across modals so requests from different recipes/profiles can be compared.
+/// Completed requests can be pinned, labelled and annotated with a note from
+/// the actions menu.
 #[derive(Debug)]
 pub struct History {
     recipe_name: String,
+    /// Full list of requests, shown whenever there's no search query. This
+    /// includes in-progress requests, which aren't in the DB
+    requests: Vec<RequestStateSummary>,
+    /// Search is scoped to the recipe+profile that the modal was opened for.
+    /// The query is set from the search box
+    filter: HistoryFilter,
+    /// Are we currently typing in the search box?
+    search_focused: bool,
+    search_text_box: Component<TextBox>,
+    /// Which annotation field, if any, is being edited. The annotation text
+    /// box replaces the search box while editing
+    editing: Option<AnnotationField>,
+    annotation_text_box: Component<TextBox>,
     select: Component<SelectState<RequestStateSummary>>,
+    /// Request that will be the old side of the next diff
+    diff_base: Persisted<DiffBaseKey>,
 }
 
+/// Persisted key for the ID of the request marked for diffing
+#[derive(Debug, Serialize, PersistedKey)]
+#[persisted(Option<RequestId>)]
+struct DiffBaseKey;
+
 impl History {
     /// Construct a new history modal with the given list of requests. Parent
     /// is responsible for loading the list from the request store.
     pub fn new(
+        profile_id: Option<&ProfileId>,
         recipe_id: &RecipeId,
         requests: Vec<RequestStateSummary>,
         selected_request_id: Option<RequestId>,
@@ -39,7 +78,48 @@ impl History {
             .reported(&ViewContext::messages_tx())
             .map(|recipe| recipe.name().to_owned())
             .unwrap_or_else(|| recipe_id.to_string());
-        let select = SelectState::builder(requests)
+        let select = Self::build_select(requests.clone(), selected_request_id);
+
+        let input_engine = &TuiContext::get().input_engine;
+        let binding = input_engine.binding_display(Action::Search);
+        let search_text_box = TextBox::default()
+            .placeholder(format!("'{binding}' to search"))
+            .on_click(|| {
+                ViewContext::push_event(Event::new_local(SearchCallback::Focus))
+            })
+            .on_cancel(|| {
+                ViewContext::push_event(Event::new_local(
+                    SearchCallback::Cancel,
+                ))
+            })
+            .on_submit(|| {
+                ViewContext::push_event(Event::new_local(
+                    SearchCallback::Submit,
+                ))
+            });
+
+        Self {
+            recipe_name,
+            requests,
+            filter: HistoryFilter {
+                recipe_id: Some(recipe_id.clone()),
+                profile_id: Some(profile_id.cloned()),
+                ..Default::default()
+            },
+            search_focused: false,
+            search_text_box: search_text_box.into(),
+            editing: None,
+            annotation_text_box: Component::default(),
+            select: select.into(),
+            diff_base: Persisted::new_default(DiffBaseKey),
+        }
+    }
+
+    fn build_select(
+        requests: Vec<RequestStateSummary>,
+        selected_request_id: Option<RequestId>,
+    ) -> SelectState<RequestStateSummary> {
+        SelectState::builder(requests)
             .preselect_opt(selected_request_id.as_ref())
             // When an item is selected, load it up
             .on_select(|exchange| {
@@ -47,15 +127,219 @@ impl History {
                     exchange.id(),
                 )))
             })
-            .build();
+            .on_toggle(|exchange| {
+                ViewContext::push_event(Event::new_local(DiffCallback(
+                    exchange.id(),
+                )))
+            })
+            .build()
+    }
 
-        Self {
-            recipe_name,
-            select: select.into(),
+    /// Run a search with the current text box contents, and replace the list
+    /// with the results. An empty query shows the full list again
+    fn search(&mut self) {
+        let text = self.search_text_box.data().text().trim();
+        let requests = if text.is_empty() {
+            self.filter.query = None;
+            self.requests.clone()
+        } else {
+            self.filter.query = Some(text.to_owned());
+            ViewContext::with_database(|database| {
+                database.search_requests(&self.filter)
+            })
+            .reported(&ViewContext::messages_tx())
+            .unwrap_or_default()
+            .into_iter()
+            .map(RequestStateSummary::Response)
+            .collect()
+        };
+        let selected_request_id =
+            self.select.data().selected().map(RequestStateSummary::id);
+        self.select = Self::build_select(requests, selected_request_id).into();
+    }
+
+    /// Get the annotation of the selected request. Only completed requests can
+    /// be annotated, so this returns `None` for anything else
+    fn selected_annotation(&self) -> Option<&ExchangeAnnotation> {
+        match self.select.data().selected()? {
+            RequestStateSummary::Response(exchange) => {
+                Some(&exchange.annotation)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the note of the selected request, if it has one
+    fn selected_note(&self) -> Option<&str> {
+        self.selected_annotation()?.note.as_deref()
+    }
+
+    /// Handle an action from the actions menu
+    fn handle_menu_action(&mut self, action: HistoryMenuAction) {
+        let Some(annotation) = self.selected_annotation() else {
+            return;
+        };
+        match action {
+            HistoryMenuAction::TogglePin => {
+                let annotation = ExchangeAnnotation {
+                    pinned: !annotation.pinned,
+                    ..annotation.clone()
+                };
+                self.set_annotation(annotation);
+            }
+            HistoryMenuAction::EditLabel => {
+                let text = annotation.label.clone().unwrap_or_default();
+                self.start_editing(AnnotationField::Label, text);
+            }
+            HistoryMenuAction::EditNote => {
+                let text = annotation.note.clone().unwrap_or_default();
+                self.start_editing(AnnotationField::Note, text);
+            }
+        }
+    }
+
+    /// Open the annotation text box to edit a field of the selected request
+    fn start_editing(&mut self, field: AnnotationField, text: String) {
+        // Build a new text box instead of calling set_text, because that
+        // would trigger the submit callback
+        self.annotation_text_box = TextBox::default()
+            .default_value(text)
+            .on_cancel(|| {
+                ViewContext::push_event(Event::new_local(
+                    AnnotationCallback::Cancel,
+                ))
+            })
+            .on_submit(|| {
+                ViewContext::push_event(Event::new_local(
+                    AnnotationCallback::Submit,
+                ))
+            })
+            .into();
+        self.editing = Some(field);
+    }
+
+    /// Save the contents of the annotation text box to the field being
+    /// edited. An empty value clears the field
+    fn submit_editing(&mut self) {
+        let Some(field) = self.editing.take() else {
+            return;
+        };
+        let Some(annotation) = self.selected_annotation() else {
+            return;
+        };
+        let text = self.annotation_text_box.data().text().trim();
+        let value = (!text.is_empty()).then(|| text.to_owned());
+        let annotation = match field {
+            AnnotationField::Label => ExchangeAnnotation {
+                label: value,
+                ..annotation.clone()
+            },
+            AnnotationField::Note => ExchangeAnnotation {
+                note: value,
+                ..annotation.clone()
+            },
+        };
+        self.set_annotation(annotation);
+    }
+
+    /// Save a new annotation for the selected request, and update our copies
+    /// of it to match
+    fn set_annotation(&mut self, annotation: ExchangeAnnotation) {
+        let Some(RequestStateSummary::Response(exchange)) =
+            self.select.data_mut().selected_mut()
+        else {
+            return;
+        };
+        let request_id = exchange.id;
+        let result = ViewContext::with_database(|database| {
+            database.set_annotation(request_id, &annotation)
+        });
+        if result.reported(&ViewContext::messages_tx()).is_none() {
+            return;
+        }
+        exchange.annotation = annotation.clone();
+        // The full list is separate from the visible list when searching
+        if let Some(RequestStateSummary::Response(exchange)) = self
+            .requests
+            .iter_mut()
+            .find(|request| request.id() == request_id)
+        {
+            exchange.annotation = annotation;
+        }
+    }
+
+    /// Mark a request for diffing. If another request is already marked,
+    /// compare the two instead. Toggling the marked request again unmarks it.
+    fn toggle_diff(&mut self, request_id: RequestId) {
+        let binding = TuiContext::get()
+            .input_engine
+            .binding_display(Action::Toggle);
+        match *self.diff_base {
+            Some(base_id) if base_id == request_id => {
+                *self.diff_base.get_mut() = None;
+                ViewContext::notify("Unmarked request for diff");
+            }
+            Some(base_id) => {
+                let messages_tx = ViewContext::messages_tx();
+                let Some(new) =
+                    load_exchange(request_id).reported(&messages_tx).flatten()
+                else {
+                    ViewContext::notify(
+                        "Only completed requests can be diffed",
+                    );
+                    return;
+                };
+                // If the marked request has since been deleted, start over
+                // with the new one
+                let Some(old) =
+                    load_exchange(base_id).reported(&messages_tx).flatten()
+                else {
+                    *self.diff_base.get_mut() = Some(request_id);
+                    ViewContext::notify(format!(
+                        "Marked request for diff; press {binding} on another \
+                        request to compare"
+                    ));
+                    return;
+                };
+                *self.diff_base.get_mut() = None;
+                ViewContext::open_modal(ExchangeDiffModal::new(&old, &new));
+            }
+            None => {
+                *self.diff_base.get_mut() = Some(request_id);
+                ViewContext::notify(format!(
+                    "Marked request for diff; press {binding} on another \
+                    request to compare"
+                ));
+            }
         }
     }
 }
 
+/// Load a completed exchange from the DB, and parse its body using the
+/// recipe's protobuf schema if it has one. Return `None` if the request isn't
+/// in the DB, i.e. it failed or is still in flight
+fn load_exchange(request_id: RequestId) -> anyhow::Result<Option<Exchange>> {
+    let Some(mut exchange) = ViewContext::with_database(|database| {
+        database.get_request(request_id)
+    })?
+    else {
+        return Ok(None);
+    };
+    let collection = ViewContext::collection();
+    let protobuf = collection
+        .recipes
+        .get_recipe(&exchange.request.recipe_id)
+        .and_then(|recipe| recipe.protobuf.as_ref());
+    if let Some(content_type) = exchange.response.content_type() {
+        if let Ok(body) = content_type
+            .parse_content_with_schema(exchange.response.body.bytes(), protobuf)
+        {
+            exchange.response.set_parsed_body(body);
+        }
+    }
+    Ok(Some(exchange))
+}
+
 impl Modal for History {
     fn title(&self) -> Line<'_> {
         vec![
@@ -69,27 +353,116 @@ impl Modal for History {
     }
 
     fn dimensions(&self) -> (Constraint, Constraint) {
+        // Extra line for the search box, plus one for the selected note
+        let note_height = self.selected_note().is_some() as u16;
         (
-            Constraint::Length(40),
-            Constraint::Length(self.select.data().len().min(20) as u16),
+            Constraint::Length(60),
+            Constraint::Length(
+                self.select.data().len().min(20) as u16 + 1 + note_height,
+            ),
         )
     }
 }
 
 impl EventHandler for History {
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> Update {
+        if let Some(Action::Search) = event.action() {
+            self.search_focused = true;
+        } else if let Some(Action::OpenActions) = event.action() {
+            // Annotations are stored in the DB, so in-progress and failed
+            // requests can't have them
+            let disabled: &[_] = if self.selected_annotation().is_some() {
+                &[]
+            } else {
+                &[
+                    HistoryMenuAction::TogglePin,
+                    HistoryMenuAction::EditLabel,
+                    HistoryMenuAction::EditNote,
+                ]
+            };
+            ViewContext::open_modal(ActionsModal::new(disabled));
+        } else if let Some(action) = event.local::<HistoryMenuAction>() {
+            self.handle_menu_action(*action);
+        } else if let Some(callback) = event.local::<AnnotationCallback>() {
+            match callback {
+                AnnotationCallback::Cancel => self.editing = None,
+                AnnotationCallback::Submit => self.submit_editing(),
+            }
+        } else if let Some(DiffCallback(request_id)) = event.local() {
+            self.toggle_diff(*request_id);
+        } else if let Some(callback) = event.local::<SearchCallback>() {
+            match callback {
+                SearchCallback::Focus => self.search_focused = true,
+                SearchCallback::Cancel => {
+                    // Reset text to whatever was submitted last
+                    self.search_text_box.data_mut().set_text(
+                        self.filter.query.clone().unwrap_or_default(),
+                    );
+                    self.search_focused = false;
+                }
+                SearchCallback::Submit => {
+                    self.search();
+                    self.search_focused = false;
+                }
+            }
+        } else {
+            return Update::Propagate(event);
+        }
+        Update::Consumed
+    }
+
     fn children(&mut self) -> Vec<Component<Child<'_>>> {
-        vec![self.select.to_child_mut()]
+        vec![
+            self.annotation_text_box.to_child_mut(),
+            self.search_text_box.to_child_mut(),
+            self.select.to_child_mut(),
+        ]
     }
 }
 
 impl Draw for History {
     fn draw(&self, frame: &mut Frame, _: (), metadata: DrawMetadata) {
+        let styles = &TuiContext::get().styles;
+        let note = self.selected_note();
+        let [list_area, note_area, search_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(note.is_some() as u16),
+            Constraint::Length(1),
+        ])
+        .areas(metadata.area());
+        let list_focused = !self.search_focused && self.editing.is_none();
         self.select.draw(
             frame,
             List::from(self.select.data()),
-            metadata.area(),
-            true,
+            list_area,
+            list_focused,
         );
+        if let Some(note) = note {
+            frame.render_widget(
+                Line::styled(format!("Note: {note}"), styles.text.hint),
+                note_area,
+            );
+        }
+        if let Some(field) = self.editing {
+            let [label_area, text_box_area] = Layout::horizontal([
+                Constraint::Length(field.to_string().len() as u16 + 2),
+                Constraint::Min(0),
+            ])
+            .areas(search_area);
+            frame.render_widget(
+                Span::styled(format!("{field}: "), styles.text.title),
+                label_area,
+            );
+            self.annotation_text_box
+                .draw(frame, (), text_box_area, true);
+        } else {
+            self.search_text_box.draw(
+                frame,
+                (),
+                search_area,
+                self.search_focused,
+            );
+        }
     }
 }
 
@@ -115,13 +488,262 @@ impl Generate for &RequestStateSummary {
                 Span::styled("Request error", styles.text.error)
             }
         };
-        vec![self.time().generate(), " ".into(), description].into()
+        let mut spans = vec![self.time().generate(), " ".into(), description];
+        if let RequestStateSummary::Response(exchange) = self {
+            let annotation = &exchange.annotation;
+            if annotation.pinned {
+                spans.push(Span::styled(" (pinned)", styles.text.primary));
+            }
+            if let Some(label) = &annotation.label {
+                spans.push(Span::styled(
+                    format!(" [{label}]"),
+                    styles.text.primary,
+                ));
+            }
+        }
+        spans.into()
     }
 }
 
+/// All callback events from the search text box
+#[derive(Debug)]
+enum SearchCallback {
+    Focus,
+    Cancel,
+    Submit,
+}
+
+/// All callback events from the annotation text box
+#[derive(Debug)]
+enum AnnotationCallback {
+    Cancel,
+    Submit,
+}
+
+/// Annotation fields that can be edited as text
+#[derive(Copy, Clone, Debug, Display)]
+enum AnnotationField {
+    Label,
+    Note,
+}
+
+/// Items in the actions popup menu for the History modal
+#[derive(
+    Copy, Clone, Debug, Default, Display, EnumCount, EnumIter, PartialEq,
+)]
+enum HistoryMenuAction {
+    #[default]
+    #[display("Pin/Unpin")]
+    TogglePin,
+    #[display("Edit Label")]
+    EditLabel,
+    #[display("Edit Note")]
+    EditNote,
+}
+
+impl ToStringGenerate for HistoryMenuAction {}
+
+/// Request was toggled in the list, to mark it for diffing
+#[derive(Debug)]
+struct DiffCallback(RequestId);
+
 /// Allow selection by ID
 impl PartialEq<RequestStateSummary> for RequestId {
     fn eq(&self, other: &RequestStateSummary) -> bool {
         self == &other.id()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{harness, terminal, TestHarness, TestTerminal};
+    use crate::view::test_util::TestComponent;
+    use crossterm::event::KeyCode;
+    use itertools::Itertools;
+    use rstest::rstest;
+    use slumber_core::{
+        assert_matches,
+        http::{Exchange, RequestRecord, ResponseRecord},
+        test_util::Factory,
+    };
+
+    /// Search history with the text box, then clear the search to get the
+    /// full list back
+    #[rstest]
+    fn test_search(harness: TestHarness, terminal: TestTerminal) {
+        let profile_id = harness.collection.first_profile_id().clone();
+        let recipe_id = harness.collection.first_recipe_id().clone();
+        let exchanges = ["Ted", "Fred"].map(|name| {
+            let exchange = Exchange::factory((
+                RequestRecord::factory((
+                    Some(profile_id.clone()),
+                    recipe_id.clone(),
+                )),
+                ResponseRecord {
+                    body: format!(r#"{{"name": "{name}"}}"#)
+                        .into_bytes()
+                        .into(),
+                    ..ResponseRecord::factory(())
+                },
+            ));
+            harness.database.insert_exchange(&exchange).unwrap();
+            exchange
+        });
+        let requests = exchanges
+            .iter()
+            .rev()
+            .map(|exchange| RequestStateSummary::Response(exchange.into()))
+            .collect();
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            History::new(Some(&profile_id), &recipe_id, requests, None),
+            (),
+        );
+        let ids = |component: &TestComponent<History, ()>| {
+            component
+                .data()
+                .select
+                .data()
+                .items()
+                .map(RequestStateSummary::id)
+                .collect_vec()
+        };
+        let all_ids = [exchanges[1].id, exchanges[0].id];
+        assert_eq!(ids(&component), all_ids);
+
+        component.send_key(KeyCode::Char('/')).assert_empty();
+        component.send_text("ted").assert_empty();
+        let _ = component.send_key(KeyCode::Enter);
+        assert_eq!(ids(&component), [exchanges[0].id]);
+
+        // Clearing the search shows everything again
+        component.send_key(KeyCode::Char('/')).assert_empty();
+        for _ in 0..3 {
+            component.send_key(KeyCode::Backspace).assert_empty();
+        }
+        let _ = component.send_key(KeyCode::Enter);
+        assert_eq!(ids(&component), all_ids);
+    }
+
+    /// Toggle one request to mark it, then toggle another to diff them
+    #[rstest]
+    fn test_diff(harness: TestHarness, terminal: TestTerminal) {
+        let profile_id = harness.collection.first_profile_id().clone();
+        let recipe_id = harness.collection.first_recipe_id().clone();
+        let exchanges = [(); 2].map(|_| {
+            let exchange = Exchange::factory((
+                Some(profile_id.clone()),
+                recipe_id.clone(),
+            ));
+            harness.database.insert_exchange(&exchange).unwrap();
+            exchange
+        });
+        let requests = exchanges
+            .iter()
+            .map(|exchange| RequestStateSummary::Response(exchange.into()))
+            .collect();
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            History::new(Some(&profile_id), &recipe_id, requests, None),
+            (),
+        );
+
+        assert_matches!(
+            component.send_key(KeyCode::Char(' ')).events(),
+            &[Event::Notify(_)]
+        );
+        assert_eq!(*component.data().diff_base, Some(exchanges[0].id));
+
+        // Toggling the same request again unmarks it
+        assert_matches!(
+            component.send_key(KeyCode::Char(' ')).events(),
+            &[Event::Notify(_)]
+        );
+        assert_eq!(*component.data().diff_base, None);
+
+        assert_matches!(
+            component.send_key(KeyCode::Char(' ')).events(),
+            &[Event::Notify(_)]
+        );
+        let _ = component.send_key(KeyCode::Down);
+        assert_matches!(
+            component.send_key(KeyCode::Char(' ')).events(),
+            &[Event::OpenModal(_)]
+        );
+        // Marker is cleared once the diff is opened
+        assert_eq!(*component.data().diff_base, None);
+    }
+
+    /// Pin, label and annotate a request from the actions menu. Changes should
+    /// be saved to the DB and reflected in the list
+    #[rstest]
+    fn test_annotate(harness: TestHarness, terminal: TestTerminal) {
+        let profile_id = harness.collection.first_profile_id().clone();
+        let recipe_id = harness.collection.first_recipe_id().clone();
+        let exchange =
+            Exchange::factory((Some(profile_id.clone()), recipe_id.clone()));
+        harness.database.insert_exchange(&exchange).unwrap();
+        let requests = vec![RequestStateSummary::Response((&exchange).into())];
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            History::new(Some(&profile_id), &recipe_id, requests, None),
+            (),
+        );
+        let assert_annotation =
+            |component: &TestComponent<History, ()>,
+             expected: &ExchangeAnnotation| {
+                assert_eq!(
+                    component.data().selected_annotation(),
+                    Some(expected)
+                );
+                assert_eq!(
+                    harness
+                        .database
+                        .get_annotation(exchange.id)
+                        .unwrap()
+                        .as_ref(),
+                    Some(expected)
+                );
+            };
+
+        component
+            .update_draw(Event::new_local(HistoryMenuAction::TogglePin))
+            .assert_empty();
+        assert_annotation(
+            &component,
+            &ExchangeAnnotation {
+                pinned: true,
+                ..Default::default()
+            },
+        );
+
+        component
+            .update_draw(Event::new_local(HistoryMenuAction::EditLabel))
+            .assert_empty();
+        component.send_text("baseline").assert_empty();
+        component.send_key(KeyCode::Enter).assert_empty();
+        component
+            .update_draw(Event::new_local(HistoryMenuAction::EditNote))
+            .assert_empty();
+        component.send_text("known good").assert_empty();
+        component.send_key(KeyCode::Enter).assert_empty();
+        let expected = ExchangeAnnotation {
+            pinned: true,
+            label: Some("baseline".into()),
+            note: Some("known good".into()),
+        };
+        assert_annotation(&component, &expected);
+
+        // Cancelling an edit leaves the field alone
+        component
+            .update_draw(Event::new_local(HistoryMenuAction::EditLabel))
+            .assert_empty();
+        component.send_text("-other").assert_empty();
+        component.send_key(KeyCode::Esc).assert_empty();
+        assert_annotation(&component, &expected);
+    }
+}
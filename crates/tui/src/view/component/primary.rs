@@ -30,8 +30,9 @@ use ratatui::{
 };
 use serde::{Deserialize, Serialize};
 use slumber_config::Action;
-use slumber_core::collection::{
-    Collection, ProfileId, RecipeId, RecipeNodeDiscriminants,
+use slumber_core::{
+    collection::{Collection, ProfileId, RecipeId, RecipeNodeDiscriminants},
+    http::generate::GenerateFormat,
 };
 use strum::{EnumCount, EnumIter};
 
@@ -42,6 +43,9 @@ pub struct PrimaryView {
     selected_pane:
         PersistedLazy<SingletonKey<PrimaryPane>, FixedSelectState<PrimaryPane>>,
     fullscreen_mode: Persisted<FullscreenModeKey>,
+    /// Should the request copied from the open "Copy as..." submenu be
+    /// redacted? Set when the submenu is opened
+    copy_as_redact: bool,
 
     // Children
     profile_pane: Component<ProfilePane>,
@@ -103,9 +107,14 @@ enum MenuAction {
     #[default]
     #[display("Edit Collection")]
     EditCollection,
+    #[display("Paste cURL as Recipe")]
+    PasteCurl,
 }
 impl ToStringGenerate for MenuAction {}
 
+/// Items in the "Copy as..." submenu of the recipe actions
+impl ToStringGenerate for GenerateFormat {}
+
 impl PrimaryView {
     pub fn new(collection: &Collection) -> Self {
         let profile_pane = ProfilePane::new(collection).into();
@@ -123,6 +132,7 @@ impl PrimaryView {
                 selected_pane,
             ),
             fullscreen_mode: Persisted::default(),
+            copy_as_redact: true,
 
             recipe_list_pane,
             profile_pane,
@@ -256,20 +266,47 @@ impl PrimaryView {
     /// Handle menu actions for recipe list or detail panes. We handle this here
     /// for code de-duplication, and because we have access to all the needed
     /// context.
-    fn handle_recipe_menu_action(&self, action: RecipeMenuAction) {
-        // If no recipes are available, we can't do anything
-        let Some(config) = self.recipe_pane.data().request_config() else {
-            return;
-        };
-
-        let message = match action {
-            RecipeMenuAction::EditCollection => Message::CollectionEdit,
-            RecipeMenuAction::CopyUrl => Message::CopyRequestUrl(config),
-            RecipeMenuAction::CopyBody => Message::CopyRequestBody(config),
-            RecipeMenuAction::CopyCurl => Message::CopyRequestCurl(config),
+    fn handle_recipe_menu_action(&mut self, action: RecipeMenuAction) {
+        let config = self.recipe_pane.data().request_config();
+        let message = match (action, config) {
+            (RecipeMenuAction::EditCollection, _) => Message::CollectionEdit,
+            (RecipeMenuAction::PasteCurl, _) => Message::CollectionPasteCurl,
+            (RecipeMenuAction::CopyUrl, Some(config)) => {
+                Message::CopyRequestUrl(config)
+            }
+            (RecipeMenuAction::CopyBody, Some(config)) => {
+                Message::CopyRequestBody(config)
+            }
+            (RecipeMenuAction::CopyAs, Some(_)) => {
+                self.copy_as_redact = true;
+                ViewContext::open_modal(
+                    ActionsModal::<GenerateFormat>::default(),
+                );
+                return;
+            }
+            (RecipeMenuAction::CopyAsUnredacted, Some(_)) => {
+                self.copy_as_redact = false;
+                ViewContext::open_modal(
+                    ActionsModal::<GenerateFormat>::default(),
+                );
+                return;
+            }
+            // If no recipes are available, we can't copy anything
+            (_, None) => return,
         };
         ViewContext::send_message(message);
     }
+
+    /// Handle a format picked from the "Copy as..." submenu
+    fn handle_copy_as(&self, format: GenerateFormat) {
+        if let Some(config) = self.recipe_pane.data().request_config() {
+            ViewContext::send_message(Message::CopyRequestAs {
+                config,
+                format,
+                redact: self.copy_as_redact,
+            });
+        }
+    }
 }
 
 impl EventHandler for PrimaryView {
@@ -347,12 +384,19 @@ impl EventHandler for PrimaryView {
                     local.downcast_ref::<RecipeMenuAction>()
                 {
                     self.handle_recipe_menu_action(*action);
+                } else if let Some(format) =
+                    local.downcast_ref::<GenerateFormat>()
+                {
+                    self.handle_copy_as(*format);
                 } else if let Some(action) = local.downcast_ref::<MenuAction>()
                 {
                     match action {
                         MenuAction::EditCollection => {
                             ViewContext::send_message(Message::CollectionEdit)
                         }
+                        MenuAction::PasteCurl => ViewContext::send_message(
+                            Message::CollectionPasteCurl,
+                        ),
                     }
                 } else {
                     return Update::Propagate(event);
@@ -519,24 +563,67 @@ mod tests {
         assert_eq!(request_config, expected_config);
     }
 
-    /// Test "Copy as cURL" action, which is available via the Recipe List or
-    /// Recipe panes
+    /// Test "Copy as..." action, which is available via the Recipe List or
+    /// Recipe panes. It opens a submenu of formats
     #[rstest]
-    fn test_copy_as_curl(mut harness: TestHarness, terminal: TestTerminal) {
+    fn test_copy_as(mut harness: TestHarness, terminal: TestTerminal) {
         let expected_config = RequestConfig {
             recipe_id: harness.collection.first_recipe_id().clone(),
             profile_id: Some(harness.collection.first_profile_id().clone()),
             options: BuildOptions::default(),
         };
         let mut component = create_component(&mut harness, &terminal);
+        assert_matches!(
+            component
+                .update_draw(Event::new_local(RecipeMenuAction::CopyAs))
+                .events(),
+            &[Event::OpenModal(_)]
+        );
+
+        // Pick a format from the submenu
         component
-            .update_draw(Event::new_local(RecipeMenuAction::CopyCurl))
+            .update_draw(Event::new_local(GenerateFormat::Python))
             .assert_empty();
-
-        let request_config = assert_matches!(
+        let (request_config, format, redact) = assert_matches!(
             harness.pop_message_now(),
-            Message::CopyRequestCurl(request_config) => request_config,
+            Message::CopyRequestAs { config, format, redact } =>
+                (config, format, redact),
         );
         assert_eq!(request_config, expected_config);
+        assert_eq!(format, GenerateFormat::Python);
+        assert!(redact);
+
+        // The unredacted variant opens the same submenu
+        assert_matches!(
+            component
+                .update_draw(Event::new_local(
+                    RecipeMenuAction::CopyAsUnredacted
+                ))
+                .events(),
+            &[Event::OpenModal(_)]
+        );
+        component
+            .update_draw(Event::new_local(GenerateFormat::Curl))
+            .assert_empty();
+        let (format, redact) = assert_matches!(
+            harness.pop_message_now(),
+            Message::CopyRequestAs { format, redact, .. } => (format, redact),
+        );
+        assert_eq!(format, GenerateFormat::Curl);
+        assert!(!redact);
+    }
+
+    /// Test "Paste cURL as Recipe" action, which is available even without a
+    /// recipe selected
+    #[rstest]
+    fn test_paste_curl(mut harness: TestHarness, terminal: TestTerminal) {
+        let mut component = create_component(&mut harness, &terminal);
+        component
+            .update_draw(Event::new_local(RecipeMenuAction::PasteCurl))
+            .assert_empty();
+        assert_matches!(
+            harness.pop_message_now(),
+            Message::CollectionPasteCurl
+        );
     }
 }
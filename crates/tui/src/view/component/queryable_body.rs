@@ -4,9 +4,11 @@ use crate::{
     context::TuiContext,
     view::{
         common::{
+            hex_view::HexView,
             text_box::TextBox,
             text_window::{ScrollbarMargins, TextWindow, TextWindowProps},
         },
+        component::csv_table::CsvTableView,
         context::UpdateContext,
         draw::{Draw, DrawMetadata},
         event::{Child, Event, EventHandler, Update},
@@ -22,17 +24,20 @@ use ratatui::{
     text::Text,
     Frame,
 };
-use serde_json_path::JsonPath;
 use slumber_config::Action;
 use slumber_core::{
-    http::{content_type::ContentType, query::Query, ResponseBody},
-    util::{MaybeStr, ResultTraced},
+    http::{
+        content_type::{ContentType, CsvTable, ResponseContent},
+        query::Query,
+        ResponseBody,
+    },
+    util::ResultTraced,
 };
 use std::cell::Cell;
 
 /// Display response body as text, with a query box to filter it if the body has
-/// been parsed. The query state can be persisted by persisting this entire
-/// container.
+/// been parsed. Tabular bodies (CSV/TSV) are displayed as a table instead. The
+/// query state can be persisted by persisting this entire container.
 #[derive(Debug)]
 pub struct QueryableBody {
     /// Visible text state. This needs to be in a cell because it's initialized
@@ -49,6 +54,9 @@ pub struct QueryableBody {
     query_text_box: Component<TextBox>,
     /// Filtered text display
     text_window: Component<TextWindow>,
+    /// Show the body as a hex dump, even if it's text. Binary bodies are
+    /// always shown as hex
+    force_hex: bool,
 }
 
 #[derive(Clone)]
@@ -74,10 +82,22 @@ struct StateKey {
 #[derive(Debug)]
 struct State {
     text: Identified<Text<'static>>,
+    /// Tabular content is shown in a table instead of as text. The text is
+    /// still generated, for copying
+    table: Option<Component<CsvTableView>>,
+    /// Hex dump of the raw body. Used for binary bodies, or when the user
+    /// forces hex view
+    hex_view: Component<HexView>,
     is_parsed: bool,
     is_binary: bool,
 }
 
+impl State {
+    fn show_hex(&self, force_hex: bool) -> bool {
+        force_hex || self.is_binary
+    }
+}
+
 impl QueryableBody {
     /// Create a new body, optionally loading the query text from the
     /// persistence DB. This is optional because not all callers use the query
@@ -87,8 +107,10 @@ impl QueryableBody {
         let binding = input_engine.binding_display(Action::Search);
 
         let text_box = TextBox::default()
-            .placeholder(format!("'{binding}' to filter body with JSONPath"))
-            .validator(|text| JsonPath::parse(text).is_ok())
+            .placeholder(format!(
+                "'{binding}' to filter body with JSONPath or XPath"
+            ))
+            .validator(|text| text.parse::<Query>().is_ok())
             // Callback trigger an events, so we can modify our own state
             .on_click(|| {
                 ViewContext::push_event(Event::new_local(QueryCallback::Focus))
@@ -106,13 +128,21 @@ impl QueryableBody {
             query: Default::default(),
             query_text_box: text_box.into(),
             text_window: Default::default(),
+            force_hex: false,
         }
     }
 
+    /// Switch between the hex dump and the regular text view. Binary bodies
+    /// are always shown as hex
+    pub fn toggle_hex(&mut self) {
+        self.force_hex = !self.force_hex;
+    }
+
     /// Get visible body text. Return an owned value because that's what all
     /// consumers need anyway, and it makes the API simpler. Return `None` if:
     /// - Text isn't initialized yet
     /// - Body is binary
+    /// - Body is being shown as hex
     /// - Body has not been parsed, either because it's too large or not a known
     ///   content type
     ///
@@ -121,7 +151,7 @@ impl QueryableBody {
     /// the clone by returning `None` instead.
     pub fn parsed_text(&self) -> Option<String> {
         let state = self.state.get()?;
-        if state.is_binary || !state.is_parsed {
+        if state.show_hex(self.force_hex) || !state.is_parsed {
             None
         } else {
             Some(state.text.to_string())
@@ -134,7 +164,13 @@ impl QueryableBody {
         // if it isn't then the user effectively sees nothing
         self.state
             .get()
-            .map(|state| state.text.to_string())
+            .map(|state| {
+                if state.show_hex(self.force_hex) {
+                    state.hex_view.data().dump()
+                } else {
+                    state.text.to_string()
+                }
+            })
             .unwrap_or_default()
     }
 }
@@ -186,10 +222,17 @@ impl EventHandler for QueryableBody {
     }
 
     fn children(&mut self) -> Vec<Component<Child<'_>>> {
-        vec![
-            self.query_text_box.to_child_mut(),
-            self.text_window.to_child_mut(),
-        ]
+        let force_hex = self.force_hex;
+        let body = match self.state.get_mut() {
+            Some(state) if state.show_hex(force_hex) => {
+                state.hex_view.to_child_mut()
+            }
+            Some(State {
+                table: Some(table), ..
+            }) => table.to_child_mut(),
+            _ => self.text_window.to_child_mut(),
+        };
+        vec![self.query_text_box.to_child_mut(), body]
     }
 }
 
@@ -200,6 +243,22 @@ impl<'a> Draw<QueryableBodyProps<'a>> for QueryableBody {
         props: QueryableBodyProps,
         metadata: DrawMetadata,
     ) {
+        let body = props.body;
+        let state_key = StateKey {
+            query: self.query.clone(),
+            is_parsed: props.body.parsed().is_some(),
+        };
+        let state = self.state.get_or_update(&state_key, || {
+            init_state(props.content_type, body, self.query.as_ref())
+        });
+
+        // Hex view has its own search box
+        if state.show_hex(self.force_hex) {
+            self.query_available.set(false);
+            state.hex_view.draw(frame, (), metadata.area(), true);
+            return;
+        }
+
         // Body can only be queried if it's been parsed
         let query_available = props.body.parsed().is_some();
         self.query_available.set(query_available);
@@ -211,27 +270,23 @@ impl<'a> Draw<QueryableBodyProps<'a>> for QueryableBody {
         .areas(metadata.area());
 
         // Draw the body
-        let body = props.body;
-        let state_key = StateKey {
-            query: self.query.clone(),
-            is_parsed: props.body.parsed().is_some(),
-        };
-        let state = self.state.get_or_update(&state_key, || {
-            init_state(props.content_type, body, self.query.as_ref())
-        });
-        self.text_window.draw(
-            frame,
-            TextWindowProps {
-                text: &state.text,
-                margins: ScrollbarMargins {
-                    bottom: 2, // Extra margin to jump over the search box
-                    ..Default::default()
+        if let Some(table) = &state.table {
+            table.draw(frame, (), body_area, true);
+        } else {
+            self.text_window.draw(
+                frame,
+                TextWindowProps {
+                    text: &state.text,
+                    margins: ScrollbarMargins {
+                        bottom: 2, // Extra margin to jump over the search box
+                        ..Default::default()
+                    },
+                    footer: None,
                 },
-                footer: None,
-            },
-            body_area,
-            true,
-        );
+                body_area,
+                true,
+            );
+        }
 
         if query_available {
             self.query_text_box
@@ -267,63 +322,76 @@ fn init_state(
     body: &ResponseBody,
     query: Option<&Query>,
 ) -> State {
-    if TuiContext::get().config.http.is_large(body.size()) {
-        // For bodies over the "large" size, skip prettification and
-        // highlighting because it's slow. We could try to push this work
-        // into a background thread instead, but there's way to kill those
-        // threads so we could end up piling up a lot of work. It also burns
-        // a lot of CPU, regardless of where it's run
-        //
-        // We don't show a hint to the user in this case because it's not
-        // worth the screen real estate
-        if let Some(text) = body.text() {
-            State {
-                text: str_to_text(text).into(),
-                is_parsed: false,
-                is_binary: false,
-            }
-        } else {
-            // Showing binary content is a bit of a novelty, there's not much
-            // value in it. For large bodies it's not worth the CPU cycles
-            let text: Text = "<binary>".into();
-            State {
-                text: text.into(),
-                is_parsed: false,
-                is_binary: true,
-            }
-        }
-    } else {
+    // The hex view is generated lazily on render, so this is cheap
+    let hex_view = HexView::new(body.bytes().clone()).into();
+
+    // For bodies over the "large" size, skip prettification and highlighting
+    // because it's slow. We could try to push this work into a background
+    // thread instead, but there's way to kill those threads so we could end up
+    // piling up a lot of work. It also burns a lot of CPU, regardless of where
+    // it's run
+    //
+    // We don't show a hint to the user in this case because it's not worth the
+    // screen real estate
+    let is_large = TuiContext::get().config.http.is_large(body.size());
+
+    // Check for a parsed body first, because binary formats (e.g. MessagePack)
+    // can be decoded into something readable
+    if let Some(parsed) = body.parsed().filter(|_| !is_large) {
         // Query and prettify text if possible. This involves a lot of cloning
         // because it makes stuff easier. If it becomes a bottleneck on large
         // responses it's fixable.
-        if let Some(parsed) = body.parsed() {
-            // Body is a known content type so we parsed it - apply a query
-            // if necessary and prettify the output
-            let text = query
-                .map(|query| query.query_content(parsed).prettify())
-                .unwrap_or_else(|| parsed.prettify());
-            let text = highlight::highlight_if(content_type, text.into());
-            State {
-                text: text.into(),
-                is_parsed: true,
-                is_binary: false,
-            }
-        } else if let Some(text) = body.text() {
-            // Body is textual but hasn't been parsed. Just show the plain text
-            State {
-                text: str_to_text(text).into(),
-                is_parsed: false,
-                is_binary: false,
-            }
-        } else {
-            // Content is binary, show a textual representation of it
-            let text: Text = format!("{:#}", MaybeStr(body.bytes())).into();
-            State {
-                text: text.into(),
-                is_parsed: false,
-                is_binary: true,
-            }
+        //
+        // Body is a known content type so we parsed it - apply a query if
+        // necessary and prettify the output
+        let (text, table) = match query {
+            Some(query) => match query.query_content(parsed) {
+                Ok(queried) => (queried.prettify(), to_table(&*queried)),
+                // Show the error in place of the body, so the user knows
+                // what's wrong with their query
+                Err(error) => (error.to_string(), None),
+            },
+            None => (parsed.prettify(), to_table(parsed)),
+        };
+        let text = highlight::highlight_if(content_type, text.into());
+        State {
+            text: text.into(),
+            table: table.map(CsvTableView::new).map(Component::new),
+            hex_view,
+            is_parsed: true,
+            is_binary: false,
+        }
+    } else if let Some(text) = body.text() {
+        // Body is textual but either large or hasn't been parsed. Just show
+        // the plain text
+        State {
+            text: str_to_text(text).into(),
+            table: None,
+            hex_view,
+            is_parsed: false,
+            is_binary: false,
+        }
+    } else {
+        // Content is binary, it'll be shown as a hex dump
+        State {
+            text: Text::default().into(),
+            table: None,
+            hex_view,
+            is_parsed: false,
+            is_binary: true,
+        }
+    }
+}
+
+/// Convert tabular content to a table for display. Return `None` if the
+/// content isn't tabular, or if a query has reduced it to something that isn't
+/// table-shaped
+fn to_table(content: &dyn ResponseContent) -> Option<CsvTable> {
+    match content.content_type() {
+        ContentType::Csv | ContentType::Tsv => {
+            CsvTable::from_json(&content.to_json())
         }
+        _ => None,
     }
 }
 
@@ -405,7 +473,7 @@ mod tests {
     #[rstest]
     fn test_parsed(
         harness: TestHarness,
-        #[with(32, 5)] terminal: TestTerminal,
+        #[with(41, 5)] terminal: TestTerminal,
         json_response: ResponseRecord,
     ) {
         let mut component = TestComponent::new(
@@ -433,7 +501,7 @@ mod tests {
             vec![gutter("3"), " }                        ".into()],
             vec![gutter(" "), "                          ".into()],
             vec![Span::styled(
-                "'/' to filter body with JSONPath",
+                "'/' to filter body with JSONPath or XPath",
                 styles.text.patch(styles.placeholder),
             )],
         ]);
@@ -463,12 +531,91 @@ mod tests {
             vec![gutter("3"), " ]                        ".into()],
             vec![gutter(" "), "                          ".into()],
             vec![Span::styled(
-                "$.greeting                      ",
+                "$.greeting                               ",
                 styles.text,
             )],
         ]);
     }
 
+    /// CSV bodies are displayed as a table, unless a query reduces them to
+    /// something that isn't table-shaped
+    #[rstest]
+    fn test_csv_table(
+        harness: TestHarness,
+        #[with(32, 5)] terminal: TestTerminal,
+    ) {
+        let mut response = ResponseRecord {
+            status: StatusCode::OK,
+            headers: header_map([("Content-Type", "text/csv")]),
+            body: ResponseBody::new(
+                b"name,count\napple,3\npear,2\n".as_slice().into(),
+            ),
+        };
+        TestResponseParser::parse_body(&mut response);
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            QueryableBody::new(),
+            QueryableBodyProps {
+                content_type: response.content_type(),
+                body: &response.body,
+            },
+        );
+
+        let data = component.data();
+        assert!(data.state.get().unwrap().table.is_some());
+        assert_eq!(
+            data.parsed_text().as_deref(),
+            Some("name,count\napple,3\npear,2")
+        );
+
+        // Selecting a single column falls back to text
+        component.send_key(KeyCode::Char('/')).assert_empty();
+        component.send_text("$[*].name").assert_empty();
+        component.send_key(KeyCode::Enter).assert_empty();
+        let data = component.data();
+        assert!(data.state.get().unwrap().table.is_none());
+        assert_eq!(data.parsed_text().as_deref(), Some("apple\npear"));
+    }
+
+    /// Binary formats that can be decoded are displayed as JSON, rather than
+    /// as a hex dump
+    #[rstest]
+    fn test_decoded_binary(harness: TestHarness, terminal: TestTerminal) {
+        let mut response = ResponseRecord {
+            status: StatusCode::OK,
+            headers: header_map([("Content-Type", "application/msgpack")]),
+            // {"a": [1, true]}
+            body: ResponseBody::new(b"\x81\xa1a\x92\x01\xc3".as_slice().into()),
+        };
+        TestResponseParser::parse_body(&mut response);
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            QueryableBody::new(),
+            QueryableBodyProps {
+                content_type: response.content_type(),
+                body: &response.body,
+            },
+        );
+
+        let data = component.data();
+        assert!(!data.state.get().unwrap().is_binary);
+        assert_eq!(
+            data.parsed_text().as_deref(),
+            Some("{\n  \"a\": [\n    1,\n    true\n  ]\n}")
+        );
+
+        // Queries apply to the decoded content
+        component.send_key(KeyCode::Char('/')).assert_empty();
+        component.send_text("$.a[1]").assert_empty();
+        component.send_key(KeyCode::Enter).assert_empty();
+        assert_eq!(
+            component.data().parsed_text().as_deref(),
+            Some("[\n  true\n]")
+        );
+    }
+
     /// Render a parsed body with query text box, and load initial query from
     /// the DB. This tests the `PersistedContainer` implementation
     #[rstest]
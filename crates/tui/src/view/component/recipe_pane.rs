@@ -183,12 +183,18 @@ pub enum RecipeMenuAction {
     #[default]
     #[display("Edit Collection")]
     EditCollection,
+    #[display("Paste cURL as Recipe")]
+    PasteCurl,
     #[display("Copy URL")]
     CopyUrl,
     #[display("Copy Body")]
     CopyBody,
-    #[display("Copy as cURL")]
-    CopyCurl,
+    /// Open a submenu to pick a third-party client
+    #[display("Copy as...")]
+    CopyAs,
+    /// Same as [Self::CopyAs], but sensitive values are included verbatim
+    #[display("Copy as (unredacted)...")]
+    CopyAsUnredacted,
 }
 
 impl RecipeMenuAction {
@@ -203,7 +209,12 @@ impl RecipeMenuAction {
                 &[Self::CopyBody]
             }
         } else {
-            &[Self::CopyUrl, Self::CopyBody, Self::CopyCurl]
+            &[
+                Self::CopyUrl,
+                Self::CopyBody,
+                Self::CopyAs,
+                Self::CopyAsUnredacted,
+            ]
         }
     }
 }
@@ -19,7 +19,7 @@ use ratatui::{style::Styled, Frame};
 use serde::Serialize;
 use slumber_config::Action;
 use slumber_core::{
-    collection::{RecipeBody, RecipeId},
+    collection::{JsonTemplate, RecipeBody, RecipeId},
     http::content_type::ContentType,
     template::Template,
     util::ResultTraced,
@@ -46,27 +46,62 @@ impl RecipeBodyDisplay {
             RecipeBody::Raw { body, content_type } => Self::Raw(
                 RawBody::new(recipe_id, body.clone(), *content_type).into(),
             ),
+            RecipeBody::Structured { body, content_type } => {
+                let template = display_template(
+                    content_type.parse_json(body.to_source()).prettify(),
+                );
+                Self::Raw(
+                    RawBody::new(recipe_id, template, Some(*content_type))
+                        .into(),
+                )
+            }
             RecipeBody::FormUrlencoded(fields)
-            | RecipeBody::FormMultipart(fields) => {
-                let inner = RecipeFieldTable::new(
-                    FormRowKey(recipe_id.clone()),
-                    fields.iter().enumerate().map(|(i, (field, value))| {
-                        (
-                            field.clone(),
-                            value.clone(),
-                            RecipeOverrideKey::form_field(recipe_id.clone(), i),
-                            FormRowToggleKey {
-                                recipe_id: recipe_id.clone(),
-                                field: field.clone(),
-                            },
-                        )
+            | RecipeBody::FormMultipart(fields) => Self::form(
+                recipe_id,
+                fields
+                    .iter()
+                    .map(|(field, value)| (field.clone(), value.clone())),
+            ),
+            RecipeBody::FormJson(fields) => {
+                Self::form(
+                    recipe_id,
+                    fields.iter().map(|(field, value)| {
+                        let template = match value {
+                            // Strings are sent as-is, so show them as-is
+                            JsonTemplate::String(template) => template.clone(),
+                            _ => display_template(
+                                ContentType::Json
+                                    .value_to_string(&value.to_source()),
+                            ),
+                        };
+                        (field.clone(), template)
                     }),
-                );
-                Self::Form(inner.into())
+                )
             }
         }
     }
 
+    fn form(
+        recipe_id: RecipeId,
+        fields: impl IntoIterator<Item = (String, Template)>,
+    ) -> Self {
+        let inner = RecipeFieldTable::new(
+            FormRowKey(recipe_id.clone()),
+            fields.into_iter().enumerate().map(|(i, (field, value))| {
+                (
+                    field.clone(),
+                    value,
+                    RecipeOverrideKey::form_field(recipe_id.clone(), i),
+                    FormRowToggleKey {
+                        recipe_id: recipe_id.clone(),
+                        field,
+                    },
+                )
+            }),
+        );
+        Self::Form(inner.into())
+    }
+
     /// If the user has applied a temporary edit to the body, get the override
     /// value. Return `None` to use the recipe's stock body.
     pub fn override_value(&self) -> Option<RecipeBody> {
@@ -113,6 +148,13 @@ impl Draw for RecipeBodyDisplay {
     }
 }
 
+/// Convert the source text of a structured body back to a template, so its
+/// templates can still be previewed. The source is generated from valid
+/// templates so it *should* parse, but fall back to raw text just in case
+fn display_template(source: String) -> Template {
+    source.parse().unwrap_or_else(|_| Template::raw(source))
+}
+
 #[derive(Debug)]
 pub struct RawBody {
     body: RecipeTemplate,
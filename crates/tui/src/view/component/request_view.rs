@@ -5,6 +5,7 @@ use crate::{
         common::{
             actions::ActionsModal,
             header_table::HeaderTable,
+            hex_view::HexView,
             text_window::{TextWindow, TextWindowProps},
         },
         context::UpdateContext,
@@ -29,10 +30,20 @@ use strum::{EnumCount, EnumIter};
 /// it just needs to have been built successfully.
 #[derive(Debug, Default)]
 pub struct RequestView {
-    state: StateCell<RequestId, State>,
+    /// State is rebuilt when the request changes or sensitive values are
+    /// revealed/hidden
+    state: StateCell<(RequestId, bool), State>,
     body_text_window: Component<TextWindow>,
+    /// Show the body as a hex dump, even if it's text. Binary bodies are
+    /// always shown as hex
+    force_hex: bool,
+    /// Show sensitive values instead of masking them. This only applies to
+    /// requests from the current session; history is redacted before it's
+    /// persisted, so there's nothing to reveal
+    reveal_sensitive: bool,
 }
 
+#[derive(Clone)]
 pub struct RequestViewProps {
     pub request: Arc<RequestRecord>,
 }
@@ -40,11 +51,27 @@ pub struct RequestViewProps {
 /// Inner state, which should be reset when request changes
 #[derive(Debug)]
 struct State {
-    /// Store pointer to the request, so we can access it in the update step
+    /// Store pointer to the request, so we can access it in the update step.
+    /// This is the *displayed* request, which is redacted unless the user
+    /// has asked to reveal sensitive values
     request: Arc<RequestRecord>,
+    /// Does the original request have anything to redact?
+    redactable: bool,
     /// Persist the visible body, because it may vary from the actual body.
     /// `None` iff the request has no body
     body: Option<Identified<Text<'static>>>,
+    /// Hex dump of the body. `None` iff the body isn't available
+    hex_view: Option<Component<HexView>>,
+    is_binary: bool,
+}
+
+impl State {
+    /// Get the hex view, if it should be shown instead of the body text
+    fn hex_view(&self, force_hex: bool) -> Option<&Component<HexView>> {
+        self.hex_view
+            .as_ref()
+            .filter(|_| force_hex || self.is_binary)
+    }
 }
 
 /// Items in the actions popup menu
@@ -59,6 +86,10 @@ enum MenuAction {
     CopyUrl,
     #[display("Copy Body")]
     CopyBody,
+    #[display("Toggle Hex View")]
+    ToggleHex,
+    #[display("Toggle Sensitive Values")]
+    ToggleSensitive,
 }
 
 impl ToStringGenerate for MenuAction {}
@@ -66,17 +97,19 @@ impl ToStringGenerate for MenuAction {}
 impl EventHandler for RequestView {
     fn update(&mut self, _: &mut UpdateContext, event: Event) -> Update {
         if let Some(Action::OpenActions) = event.action() {
-            let disabled = if self
-                .state
-                .get_mut()
-                .and_then(|state| state.body.as_ref())
-                .is_some()
-            {
-                [].as_slice()
-            } else {
-                &[MenuAction::CopyBody]
-            };
-            ViewContext::open_modal(ActionsModal::new(disabled));
+            let state = self.state.get();
+            let state = state.as_deref();
+            let mut disabled = Vec::new();
+            if state.and_then(|state| state.hex_view.as_ref()).is_none() {
+                if state.and_then(|state| state.body.as_ref()).is_none() {
+                    disabled.push(MenuAction::CopyBody);
+                }
+                disabled.push(MenuAction::ToggleHex);
+            }
+            if !state.is_some_and(|state| state.redactable) {
+                disabled.push(MenuAction::ToggleSensitive);
+            }
+            ViewContext::open_modal(ActionsModal::new(&disabled));
         } else if let Some(action) = event.local::<MenuAction>() {
             match action {
                 MenuAction::EditCollection => {
@@ -93,12 +126,19 @@ impl EventHandler for RequestView {
                     // Copy exactly what the user sees. Currently requests
                     // don't support formatting/querying but that could change
                     if let Some(body) = self.state.get().and_then(|state| {
+                        if let Some(hex_view) = state.hex_view(self.force_hex) {
+                            return Some(hex_view.data().dump());
+                        }
                         let body = state.body.as_ref()?;
                         Some(body.to_string())
                     }) {
                         ViewContext::send_message(Message::CopyText(body));
                     }
                 }
+                MenuAction::ToggleHex => self.force_hex = !self.force_hex,
+                MenuAction::ToggleSensitive => {
+                    self.reveal_sensitive = !self.reveal_sensitive
+                }
             }
         } else {
             return Update::Propagate(event);
@@ -107,7 +147,15 @@ impl EventHandler for RequestView {
     }
 
     fn children(&mut self) -> Vec<Component<Child<'_>>> {
-        vec![self.body_text_window.to_child_mut()]
+        let force_hex = self.force_hex;
+        match self.state.get_mut() {
+            Some(State {
+                hex_view: Some(hex_view),
+                is_binary,
+                ..
+            }) if force_hex || *is_binary => vec![hex_view.to_child_mut()],
+            _ => vec![self.body_text_window.to_child_mut()],
+        }
     }
 }
 
@@ -118,29 +166,49 @@ impl Draw<RequestViewProps> for RequestView {
         props: RequestViewProps,
         metadata: DrawMetadata,
     ) {
-        let state = self.state.get_or_update(&props.request.id, || State {
-            request: Arc::clone(&props.request),
-            body: init_body(&props.request),
+        let key = (props.request.id, self.reveal_sensitive);
+        let state = self.state.get_or_update(&key, || {
+            let redactable = props.request.redactor.is_enabled();
+            let request = if redactable && !self.reveal_sensitive {
+                Arc::new(props.request.redacted())
+            } else {
+                Arc::clone(&props.request)
+            };
+            State {
+                body: init_body(&request),
+                hex_view: request
+                    .body
+                    .clone()
+                    .map(|body| HexView::new(body).into()),
+                is_binary: request
+                    .body()
+                    .is_some_and(|body| std::str::from_utf8(body).is_err()),
+                request,
+                redactable,
+            }
         });
+        let request = &state.request;
 
         let [url_area, headers_area, body_area] = Layout::vertical([
             Constraint::Length(2),
-            Constraint::Length(props.request.headers.len() as u16 + 2),
+            Constraint::Length(request.headers.len() as u16 + 2),
             Constraint::Min(0),
         ])
         .areas(metadata.area());
 
         // This can get cut off which is jank but there isn't a good fix. User
         // can copy the URL to see the full thing
-        frame.render_widget(props.request.url.to_string(), url_area);
+        frame.render_widget(request.url.to_string(), url_area);
         frame.render_widget(
             HeaderTable {
-                headers: &props.request.headers,
+                headers: &request.headers,
             }
             .generate(),
             headers_area,
         );
-        if let Some(body) = &state.body {
+        if let Some(hex_view) = state.hex_view(self.force_hex) {
+            hex_view.draw(frame, (), body_area, true);
+        } else if let Some(body) = &state.body {
             self.body_text_window.draw(
                 frame,
                 TextWindowProps {
@@ -192,3 +260,65 @@ fn init_body(request: &RequestRecord) -> Option<Identified<Text<'static>>> {
             }
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{harness, terminal, TestHarness, TestTerminal},
+        view::test_util::TestComponent,
+    };
+    use rstest::rstest;
+    use slumber_core::{
+        assert_matches,
+        http::redact::{RedactionConfig, Redactor},
+        test_util::Factory,
+    };
+
+    /// Sensitive values are masked until the user reveals them
+    #[rstest]
+    #[tokio::test]
+    async fn test_toggle_sensitive(
+        mut harness: TestHarness,
+        terminal: TestTerminal,
+    ) {
+        let request = RequestRecord {
+            url: "http://localhost/users?token=hunter2".parse().unwrap(),
+            redactor: Redactor::new(
+                RedactionConfig::default().into(),
+                vec!["hunter2".into()],
+            ),
+            ..RequestRecord::factory(())
+        };
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            RequestView::default(),
+            RequestViewProps {
+                request: request.into(),
+            },
+        );
+
+        let mut copy_url = |component: &mut TestComponent<_, _>| {
+            component
+                .update_draw(Event::new_local(MenuAction::CopyUrl))
+                .assert_empty();
+            assert_matches!(
+                harness.pop_message_now(),
+                Message::CopyText(url) => url,
+            )
+        };
+
+        assert_eq!(
+            copy_url(&mut component),
+            "http://localhost/users?token=REDACTED"
+        );
+        component
+            .update_draw(Event::new_local(MenuAction::ToggleSensitive))
+            .assert_empty();
+        assert_eq!(
+            copy_url(&mut component),
+            "http://localhost/users?token=hunter2"
+        );
+    }
+}
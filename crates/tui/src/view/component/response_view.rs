@@ -51,6 +51,10 @@ enum BodyMenuAction {
     CopyBody,
     #[display("Save Body as File")]
     SaveBody,
+    #[display("Export as HAR")]
+    ExportHar,
+    #[display("Toggle Hex View")]
+    ToggleHex,
 }
 
 impl ToStringGenerate for BodyMenuAction {}
@@ -105,6 +109,19 @@ impl EventHandler for ResponseBodyView {
                         });
                     }
                 }
+                BodyMenuAction::ExportHar => {
+                    if let Some(state) = self.state.get() {
+                        // This will trigger a modal to ask the user for a path
+                        ViewContext::send_message(Message::SaveExchangeHar {
+                            request_id: state.request_id,
+                        });
+                    }
+                }
+                BodyMenuAction::ToggleHex => {
+                    if let Some(state) = self.state.get_mut() {
+                        state.body.data_mut().get_mut().toggle_hex();
+                    }
+                }
             }
         } else {
             return Update::Propagate(event);
@@ -214,9 +231,7 @@ mod tests {
             body: b"\x01\x02\x03\xff".to_vec().into(),
             ..ResponseRecord::factory(())
         },
-        // Remove newline after this fix:
-        // https://github.com/ratatui-org/ratatui/pull/1320
-        "01 02 03 ff\n"
+        "00000000  01 02 03 ff                                       |....|"
     )]
     #[tokio::test]
     async fn test_copy_body(
@@ -252,6 +267,62 @@ mod tests {
         assert_eq!(body, expected_body);
     }
 
+    /// Toggling hex view should change what gets copied
+    #[rstest]
+    #[tokio::test]
+    async fn test_toggle_hex(mut harness: TestHarness, terminal: TestTerminal) {
+        let mut exchange = Exchange {
+            response: ResponseRecord {
+                headers: header_map(
+                    indexmap! {"content-type" => "application/json"},
+                ),
+                body: br#"{"a":1}"#.to_vec().into(),
+                ..ResponseRecord::factory(())
+            },
+            ..Exchange::factory(())
+        };
+        TestResponseParser::parse_body(&mut exchange.response);
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            ResponseBodyView::default(),
+            ResponseBodyViewProps {
+                request_id: exchange.id,
+                recipe_id: &exchange.request.recipe_id,
+                response: &exchange.response,
+            },
+        );
+
+        component
+            .update_draw(Event::new_local(BodyMenuAction::ToggleHex))
+            .assert_empty();
+        component
+            .update_draw(Event::new_local(BodyMenuAction::CopyBody))
+            .assert_empty();
+        let body = assert_matches!(
+            harness.pop_message_now(),
+            Message::CopyText(body) => body,
+        );
+        assert_eq!(
+            body,
+            "00000000  7b 22 61 22 3a 31 7d                              \
+            |{\"a\":1}|"
+        );
+
+        // Toggle back to text
+        component
+            .update_draw(Event::new_local(BodyMenuAction::ToggleHex))
+            .assert_empty();
+        component
+            .update_draw(Event::new_local(BodyMenuAction::CopyBody))
+            .assert_empty();
+        let body = assert_matches!(
+            harness.pop_message_now(),
+            Message::CopyText(body) => body,
+        );
+        assert_eq!(body, "{\n  \"a\": 1\n}");
+    }
+
     /// Test "Save Body as File" menu action
     #[rstest]
     #[case::json_body(
@@ -311,4 +382,30 @@ mod tests {
         assert_eq!(request_id, exchange.id);
         assert_eq!(data.as_deref(), expected_body);
     }
+
+    /// Test "Export as HAR" menu action
+    #[rstest]
+    fn test_export_har(mut harness: TestHarness, terminal: TestTerminal) {
+        let exchange = Exchange::factory(());
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            ResponseBodyView::default(),
+            ResponseBodyViewProps {
+                request_id: exchange.id,
+                recipe_id: &exchange.request.recipe_id,
+                response: &exchange.response,
+            },
+        );
+
+        component
+            .update_draw(Event::new_local(BodyMenuAction::ExportHar))
+            .assert_empty();
+
+        let request_id = assert_matches!(
+            harness.pop_message_now(),
+            Message::SaveExchangeHar { request_id } => request_id,
+        );
+        assert_eq!(request_id, exchange.id);
+    }
 }
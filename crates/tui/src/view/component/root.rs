@@ -118,6 +118,7 @@ impl Root {
                 .collect();
 
             ViewContext::open_modal(History::new(
+                primary_view.selected_profile_id(),
                 recipe_id,
                 requests,
                 self.selected_request_id(),
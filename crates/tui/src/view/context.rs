@@ -5,6 +5,7 @@ use crate::{
         common::modal::Modal,
         component::RecipeOverrideStore,
         event::{Event, EventQueue},
+        state::Notification,
     },
 };
 use slumber_core::{collection::Collection, db::CollectionDatabase};
@@ -131,6 +132,12 @@ impl ViewContext {
         Self::push_event(Event::OpenModal(Box::new(modal)));
     }
 
+    /// Show an informational notification to the user
+    pub fn notify(message: impl ToString) {
+        let notification = Notification::new(message.to_string());
+        Self::push_event(Event::Notify(notification));
+    }
+
     /// Get a clone of the async message sender. Generally you should use
     /// [Self::send_message] instead, but in some contexts you need the whole
     /// sender.
@@ -10,6 +10,7 @@ use slumber_config::Theme;
 /// there. Styles are grouped into sub-structs generally by component.
 #[derive(Debug)]
 pub struct Styles {
+    pub diff: DiffStyles,
     pub list: ListStyles,
     pub modal: ModalStyles,
     pub pane: PaneStyles,
@@ -22,6 +23,15 @@ pub struct Styles {
     pub text_window: TextWindowStyle,
 }
 
+/// Styles for comparing two exchanges
+#[derive(Debug)]
+pub struct DiffStyles {
+    /// Lines that only appear in the new exchange
+    pub added: Style,
+    /// Lines that only appear in the old exchange
+    pub removed: Style,
+}
+
 /// Styles for List component
 #[derive(Debug)]
 pub struct ListStyles {
@@ -129,6 +139,10 @@ pub struct TextWindowStyle {
 impl Styles {
     pub fn new(theme: &Theme) -> Self {
         Self {
+            diff: DiffStyles {
+                added: Style::default().fg(theme.success_color),
+                removed: Style::default().fg(theme.error_color),
+            },
             list: ListStyles {
                 highlight: Style::default()
                     .bg(theme.primary_color)
@@ -24,17 +24,25 @@ thread_local! {
     /// per thread. The view is single threaded, which means we only create one
     static HIGHLIGHTER: RefCell<(
         Highlighter,
-        HashMap<ContentType, HighlightConfiguration>,
+        HashMap<ContentType, Option<HighlightConfiguration>>,
     )> = RefCell::default();
 }
 
 /// Apply syntax highlighting to some text. Syntax language will be determined
 /// from the content type.
 pub fn highlight(content_type: ContentType, mut text: Text<'_>) -> Text<'_> {
+    if content_type == ContentType::FormUrlencoded {
+        return highlight_form(text);
+    }
+
     HIGHLIGHTER.with_borrow_mut(|(highlighter, configs)| {
-        let config = configs
+        let Some(config) = configs
             .entry(content_type)
-            .or_insert_with(|| get_config(content_type));
+            .or_insert_with(|| get_config(content_type))
+        else {
+            // This content type has no highlighting
+            return text;
+        };
 
         // Each line in the input correponds to one line in the output, so we
         // can mutate each line inline
@@ -77,6 +85,72 @@ pub fn highlight(content_type: ContentType, mut text: Text<'_>) -> Text<'_> {
     })
 }
 
+/// Highlight a URL-encoded form body. There's no tree-sitter grammar for it,
+/// but the syntax is simple enough to tokenize by hand. Keys, values and
+/// escapes are styled, while the `=` and `&` separators are left alone.
+fn highlight_form(mut text: Text<'_>) -> Text<'_> {
+    for line in &mut text.lines {
+        let joined = join_line(line);
+        let bytes = joined.as_bytes();
+        let mut builder = LineBuilder::new(line);
+        let mut in_value = false;
+        // Start of the key/value text that hasn't been pushed yet
+        let mut start = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            let (len, style) = match bytes[i] {
+                b'&' => (1, None),
+                b'=' if !in_value => (1, None),
+                b'+' => (1, Some(HighlightName::Escape)),
+                b'%' if bytes.len() > i + 2
+                    && bytes[i + 1].is_ascii_hexdigit()
+                    && bytes[i + 2].is_ascii_hexdigit() =>
+                {
+                    (3, Some(HighlightName::Escape))
+                }
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            };
+
+            // Everything since the last token is part of a key or value
+            if start < i {
+                builder.set_style(form_text_style(in_value));
+                builder.push_span(&joined, start, i);
+            }
+            match bytes[i] {
+                b'&' => in_value = false,
+                b'=' => in_value = true,
+                _ => {}
+            }
+            match style {
+                Some(name) => builder.set_style(name.style()),
+                None => builder.reset_style(),
+            }
+            builder.push_span(&joined, i, i + len);
+            i += len;
+            start = i;
+        }
+        if start < bytes.len() {
+            builder.set_style(form_text_style(in_value));
+            builder.push_span(&joined, start, bytes.len());
+        }
+
+        *line = builder.build();
+    }
+    text
+}
+
+/// Style for the text of a form key or value
+fn form_text_style(in_value: bool) -> Style {
+    if in_value {
+        HighlightName::String.style()
+    } else {
+        HighlightName::Property.style()
+    }
+}
+
 /// Apply syntax highlighting if the content type is `Some`, otherwise just
 /// return the given text
 pub fn highlight_if(
@@ -90,10 +164,15 @@ pub fn highlight_if(
     }
 }
 
-/// Map [ContentType] to a syntax highlighting language
-fn get_config(content_type: ContentType) -> HighlightConfiguration {
+/// Map [ContentType] to a syntax highlighting language. Return `None` for
+/// content types that don't have a grammar
+fn get_config(content_type: ContentType) -> Option<HighlightConfiguration> {
     let mut config = match content_type {
-        ContentType::Json => HighlightConfiguration::new(
+        // Binary formats are decoded and displayed as JSON
+        ContentType::Json
+        | ContentType::MessagePack
+        | ContentType::Cbor
+        | ContentType::Protobuf => HighlightConfiguration::new(
             tree_sitter_json::language(),
             "json",
             tree_sitter_json::HIGHLIGHTS_QUERY,
@@ -101,6 +180,35 @@ fn get_config(content_type: ContentType) -> HighlightConfiguration {
             "",
         )
         .expect("Error initializing JSON syntax highlighter"),
+        ContentType::Xml => HighlightConfiguration::new(
+            tree_sitter_xml::language_xml(),
+            "xml",
+            tree_sitter_xml::XML_HIGHLIGHT_QUERY,
+            "",
+            "",
+        )
+        .expect("Error initializing XML syntax highlighter"),
+        ContentType::Yaml => HighlightConfiguration::new(
+            tree_sitter_yaml::language(),
+            "yaml",
+            tree_sitter_yaml::HIGHLIGHTS_QUERY,
+            "",
+            "",
+        )
+        .expect("Error initializing YAML syntax highlighter"),
+        ContentType::Toml => HighlightConfiguration::new(
+            tree_sitter_toml_ng::language(),
+            "toml",
+            tree_sitter_toml_ng::HIGHLIGHTS_QUERY,
+            "",
+            "",
+        )
+        .expect("Error initializing TOML syntax highlighter"),
+        // Form bodies are highlighted by hand, and CSV is displayed as a
+        // table instead
+        ContentType::FormUrlencoded | ContentType::Csv | ContentType::Tsv => {
+            return None
+        }
     };
     config.configure(
         HighlightName::iter()
@@ -108,7 +216,7 @@ fn get_config(content_type: ContentType) -> HighlightConfiguration {
             .collect_vec()
             .as_slice(),
     );
-    config
+    Some(config)
 }
 
 /// All highlight names that we support
@@ -117,27 +225,38 @@ fn get_config(content_type: ContentType) -> HighlightConfiguration {
 ///
 /// This enum should be the union of all highlight names in all supported langs:
 /// - https://github.com/tree-sitter/tree-sitter-json/blob/94f5c527b2965465956c2000ed6134dd24daf2a7/queries/highlights.scm
+/// - https://github.com/tree-sitter-grammars/tree-sitter-xml/blob/v0.6.4/queries/xml/highlights.scm
+/// - https://github.com/tree-sitter-grammars/tree-sitter-yaml/blob/v0.6.1/queries/highlights.scm
+/// - https://github.com/tree-sitter-grammars/tree-sitter-toml/blob/v0.6.0/queries/highlights.scm
 #[derive(Copy, Clone, Debug, EnumIter)]
 enum HighlightName {
+    Boolean,
     Comment,
     ConstantBuiltin,
     Escape,
+    Keyword,
     Number,
+    Property,
     String,
     StringSpecial,
+    Tag,
 }
 
 impl HighlightName {
     /// Map to a string name, to pass to tree-sitter
     fn to_str(self) -> &'static str {
         match self {
+            Self::Boolean => "boolean",
             Self::Comment => "comment",
             Self::ConstantBuiltin => "constant.builtin",
             Self::Escape => "escape",
+            Self::Keyword => "keyword",
             Self::Number => "number",
+            Self::Property => "property",
             Self::String => "string",
             // This doesn't seem to work??
             Self::StringSpecial => "string.special",
+            Self::Tag => "tag",
         }
     }
 
@@ -153,12 +272,16 @@ impl HighlightName {
     fn style(self) -> Style {
         // We only style by foreground for syntax
         let fg = match self {
+            Self::Boolean => Color::Blue,
             Self::Comment => Color::Gray,
             Self::ConstantBuiltin => Color::Blue,
             Self::Escape => Color::Green,
+            Self::Keyword => Color::Magenta,
             Self::Number => Color::Cyan,
+            Self::Property => Color::Yellow,
             Self::String => Color::LightGreen,
             Self::StringSpecial => Color::Green,
+            Self::Tag => Color::Blue,
         };
         Style::default().fg(fg)
     }
@@ -442,6 +565,91 @@ mod tests {
         assert_eq!(highlighted, expected);
     }
 
+    /// Test XML highlighting. Tags and attributes are styled, while text
+    /// content is left alone
+    #[test]
+    fn test_highlight_xml() {
+        fn fg(color: Color) -> Style {
+            Style::default().fg(color)
+        }
+
+        let text = vec![Line::from("<item id=\"1\">apple</item>")].into();
+        let highlighted = highlight(ContentType::Xml, text);
+        let expected = vec![Line::from(vec![
+            "<".into(),
+            Span::styled("item", fg(Color::Blue)),
+            " ".into(),
+            Span::styled("id", fg(Color::Yellow)),
+            "=".into(),
+            Span::styled("\"1\"", fg(Color::LightGreen)),
+            ">apple</".into(),
+            Span::styled("item", fg(Color::Blue)),
+            ">".into(),
+        ])]
+        .into();
+        assert_eq!(highlighted, expected);
+    }
+
+    #[test]
+    fn test_highlight_yaml() {
+        fn fg(color: Color) -> Style {
+            Style::default().fg(color)
+        }
+
+        let text = vec![Line::from("enabled: true")].into();
+        let highlighted = highlight(ContentType::Yaml, text);
+        let expected = vec![Line::from(vec![
+            Span::styled("enabled", fg(Color::Yellow)),
+            ": ".into(),
+            Span::styled("true", fg(Color::Blue)),
+        ])]
+        .into();
+        assert_eq!(highlighted, expected);
+    }
+
+    /// Form bodies are highlighted by hand. Existing styling is retained
+    #[test]
+    fn test_highlight_form() {
+        fn fg(color: Color) -> Style {
+            Style::default().fg(color)
+        }
+
+        let text = vec![Line::from(vec![
+            "name=good+bye%21&empty=&id=".into(),
+            Span::styled("12", fg(Color::Red)),
+            "%2".into(),
+        ])]
+        .into();
+        let highlighted = highlight(ContentType::FormUrlencoded, text);
+        let expected = vec![Line::from(vec![
+            Span::styled("name", fg(Color::Yellow)),
+            "=".into(),
+            Span::styled("good", fg(Color::LightGreen)),
+            Span::styled("+", fg(Color::Green)),
+            Span::styled("bye", fg(Color::LightGreen)),
+            Span::styled("%21", fg(Color::Green)),
+            "&".into(),
+            Span::styled("empty", fg(Color::Yellow)),
+            "=".into(),
+            "&".into(),
+            Span::styled("id", fg(Color::Yellow)),
+            "=".into(),
+            Span::styled("12", fg(Color::Red)),
+            // Incomplete escape is just text
+            Span::styled("%2", fg(Color::LightGreen)),
+        ])]
+        .into();
+        assert_eq!(highlighted, expected);
+    }
+
+    /// Content types without a grammar should be left untouched
+    #[test]
+    fn test_highlight_none() {
+        let text: Text = vec![Line::from("a,b")].into();
+        let highlighted = highlight(ContentType::Csv, text.clone());
+        assert_eq!(highlighted, text);
+    }
+
     /// Test [StylePatch::split]
     #[test]
     fn test_patch_split() {
@@ -0,0 +1,22 @@
+# Commands covering the supported options, for the cURL importer test
+
+# Copied from browser dev tools
+curl 'https://api.example.com/users?page=1&sort=name' \
+  -H 'accept: application/json' \
+  -H 'authorization: Bearer abc123' \
+  -H 'content-length: 0' \
+  -b 'session=xyz' \
+  --compressed
+
+curl 'https://api.example.com/users' \
+  -H 'Content-Type: application/json' \
+  --data-raw $'{"name":"Ted","bio":"It\'s me"}'
+
+# From API docs
+$ curl -sSL -u user:pass -XPOST https://api.example.com/login -d username=ted -d 'password=hunter2' | jq .
+
+curl -F name=avatar -F 'file=@/tmp/avatar.png;type=image/png' https://api.example.com/upload
+curl -G localhost:3000/search --data-urlencode 'q=hello world' -o out.json
+curl --json '{"a": 1}' http://localhost:3000/items && curl -I http://localhost:3000/items
+curl -X DELETE "https://api.example.com/users/1" -A slumber
+curl -X DELETE "https://api.example.com/users/1" -d 'text body'
@@ -0,0 +1 @@
+printf '\211PNG\015\n\000%%\047' | curl -XPUT --url 'http://localhost/avatar' --header 'content-type: image/png' --data-binary @-
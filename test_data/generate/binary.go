@@ -0,0 +1,27 @@
+package main
+
+import (
+	"bytes"
+	"fmt"
+	"io"
+	"net/http"
+)
+
+func main() {
+	body := bytes.NewReader([]byte("\x89PNG\r\n\x00%'"))
+	req, err := http.NewRequest("PUT", "http://localhost/avatar", body)
+	if err != nil {
+		panic(err)
+	}
+	req.Header.Add("content-type", "image/png")
+	resp, err := http.DefaultClient.Do(req)
+	if err != nil {
+		panic(err)
+	}
+	defer resp.Body.Close()
+	respBody, err := io.ReadAll(resp.Body)
+	if err != nil {
+		panic(err)
+	}
+	fmt.Println(string(respBody))
+}
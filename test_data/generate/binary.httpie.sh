@@ -0,0 +1 @@
+printf '\211PNG\015\n\000%%\047' | http PUT 'http://localhost/avatar' 'content-type:image/png'
@@ -0,0 +1,8 @@
+const response = await fetch("http://localhost/avatar", {
+  method: "PUT",
+  headers: {
+    "content-type": "image/png",
+  },
+  body: new Uint8Array([137, 80, 78, 71, 13, 10, 0, 37, 39]),
+});
+console.log(await response.text());
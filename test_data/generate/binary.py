@@ -0,0 +1,11 @@
+import requests
+
+response = requests.request(
+    "PUT",
+    "http://localhost/avatar",
+    headers={
+        "content-type": "image/png",
+    },
+    data=b"\x89PNG\r\n\x00%'",
+)
+print(response.text)
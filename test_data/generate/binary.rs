@@ -0,0 +1,11 @@
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let response = reqwest::Client::new()
+        .request(reqwest::Method::PUT, "http://localhost/avatar")
+        .header("content-type", "image/png")
+        .body(b"\x89PNG\r\n\x00%'".as_slice())
+        .send()
+        .await?;
+    println!("{}", response.text().await?);
+    Ok(())
+}
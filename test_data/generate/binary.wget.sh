@@ -0,0 +1 @@
+printf '\211PNG\015\n\000%%\047' | wget --quiet --output-document=- --method=PUT --header='content-type: image/png' --body-file=/dev/stdin 'http://localhost/avatar'
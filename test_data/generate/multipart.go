@@ -0,0 +1,33 @@
+package main
+
+import (
+	"bytes"
+	"fmt"
+	"io"
+	"mime/multipart"
+	"net/http"
+)
+
+func main() {
+	body := &bytes.Buffer{}
+	form := multipart.NewWriter(body)
+	form.WriteField("user", "Alice")
+	form.WriteField("data", "\xff\xfe")
+	form.Close()
+	req, err := http.NewRequest("POST", "http://localhost/upload", body)
+	if err != nil {
+		panic(err)
+	}
+	req.Header.Add("accept", "application/json")
+	req.Header.Set("Content-Type", form.FormDataContentType())
+	resp, err := http.DefaultClient.Do(req)
+	if err != nil {
+		panic(err)
+	}
+	defer resp.Body.Close()
+	respBody, err := io.ReadAll(resp.Body)
+	if err != nil {
+		panic(err)
+	}
+	fmt.Println(string(respBody))
+}
@@ -0,0 +1,12 @@
+const body = new FormData();
+body.append("user", "Alice");
+body.append("data", new Blob([new Uint8Array([255, 254])]));
+
+const response = await fetch("http://localhost/upload", {
+  method: "POST",
+  headers: {
+    "accept": "application/json",
+  },
+  body,
+});
+console.log(await response.text());
@@ -0,0 +1,14 @@
+import requests
+
+response = requests.request(
+    "POST",
+    "http://localhost/upload",
+    headers={
+        "accept": "application/json",
+    },
+    files=[
+        ("user", (None, "Alice")),
+        ("data", (None, b"\xff\xfe")),
+    ],
+)
+print(response.text)
@@ -0,0 +1,14 @@
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let form = reqwest::multipart::Form::new()
+        .text("user", "Alice")
+        .part("data", reqwest::multipart::Part::bytes(b"\xff\xfe".as_slice()));
+    let response = reqwest::Client::new()
+        .request(reqwest::Method::POST, "http://localhost/upload")
+        .header("accept", "application/json")
+        .multipart(form)
+        .send()
+        .await?;
+    println!("{}", response.text().await?);
+    Ok(())
+}
@@ -0,0 +1 @@
+printf '\055-abc\015\nContent-Disposition: form-data; name="user"\015\n\015\nAlice\015\n--abc\015\nContent-Disposition: form-data; name="data"\015\n\015\n\377\376\015\n--abc--\015\n' | wget --quiet --output-document=- --method=POST --header='accept: application/json' --header='content-type: multipart/form-data; boundary=abc' --body-file=/dev/stdin 'http://localhost/upload'
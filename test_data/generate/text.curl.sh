@@ -0,0 +1 @@
+curl -XPOST --url 'http://localhost/users?admin=true' --header 'content-type: application/json' --header 'x-tag: a' --header 'x-tag: b' --data '{"name":"Alice'\''s"}'
@@ -0,0 +1,29 @@
+package main
+
+import (
+	"fmt"
+	"io"
+	"net/http"
+	"strings"
+)
+
+func main() {
+	body := strings.NewReader("{\"name\":\"Alice's\"}")
+	req, err := http.NewRequest("POST", "http://localhost/users?admin=true", body)
+	if err != nil {
+		panic(err)
+	}
+	req.Header.Add("content-type", "application/json")
+	req.Header.Add("x-tag", "a")
+	req.Header.Add("x-tag", "b")
+	resp, err := http.DefaultClient.Do(req)
+	if err != nil {
+		panic(err)
+	}
+	defer resp.Body.Close()
+	respBody, err := io.ReadAll(resp.Body)
+	if err != nil {
+		panic(err)
+	}
+	fmt.Println(string(respBody))
+}
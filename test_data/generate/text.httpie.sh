@@ -0,0 +1 @@
+http --raw '{"name":"Alice'\''s"}' POST 'http://localhost/users?admin=true' 'content-type:application/json' 'x-tag:a' 'x-tag:b'
@@ -0,0 +1,9 @@
+const response = await fetch("http://localhost/users?admin=true", {
+  method: "POST",
+  headers: {
+    "content-type": "application/json",
+    "x-tag": "a, b",
+  },
+  body: "{\"name\":\"Alice's\"}",
+});
+console.log(await response.text());
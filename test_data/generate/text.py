@@ -0,0 +1,12 @@
+import requests
+
+response = requests.request(
+    "POST",
+    "http://localhost/users?admin=true",
+    headers={
+        "content-type": "application/json",
+        "x-tag": "a, b",
+    },
+    data="{\"name\":\"Alice's\"}",
+)
+print(response.text)
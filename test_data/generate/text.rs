@@ -0,0 +1,13 @@
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let response = reqwest::Client::new()
+        .request(reqwest::Method::POST, "http://localhost/users?admin=true")
+        .header("content-type", "application/json")
+        .header("x-tag", "a")
+        .header("x-tag", "b")
+        .body("{\"name\":\"Alice's\"}")
+        .send()
+        .await?;
+    println!("{}", response.text().await?);
+    Ok(())
+}
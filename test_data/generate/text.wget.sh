@@ -0,0 +1 @@
+wget --quiet --output-document=- --method=POST --header='content-type: application/json' --header='x-tag: a' --header='x-tag: b' --body-data='{"name":"Alice'\''s"}' 'http://localhost/users?admin=true'